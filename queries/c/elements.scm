@@ -0,0 +1,21 @@
+; C语言的函数定义、结构体、联合体、枚举和类型定义，C++的查询在此基础上扩展
+; 名称取自声明符，跳过指针、引用和函数参数等修饰
+
+(function_definition declarator: (_) @name) @definition.function
+
+; 只识别带有定义体的具名类型，忽略 `struct point p;` 这样的引用
+(struct_specifier name: (_) @name body: (_)) @definition.struct
+(union_specifier name: (_) @name body: (_)) @definition.struct
+(enum_specifier name: (_) @name body: (_)) @definition.enum
+
+; 匿名结构体的类型定义按结构体处理，如 `typedef struct { ... } Point;`
+(type_definition
+  type: (struct_specifier !name body: (_))
+  declarator: (_) @name) @definition.struct
+(type_definition
+  type: (union_specifier !name body: (_))
+  declarator: (_) @name) @definition.struct
+(type_definition
+  type: (enum_specifier !name body: (_))
+  declarator: (_) @name) @definition.enum
+(type_definition declarator: (_) @name) @definition.type
@@ -0,0 +1,5 @@
+; C++的类、命名空间和类型别名
+
+(class_specifier name: (_) @name body: (_)) @definition.class
+(namespace_definition name: (_) @name) @definition.module
+(alias_declaration name: (_) @name) @definition.type
@@ -0,0 +1,11 @@
+; Go的包、函数、方法和类型定义
+
+(package_clause (package_identifier) @name) @definition.module
+(function_declaration name: (_) @name) @definition.function
+(method_declaration name: (_) @name) @definition.function
+
+; 类型定义按底层类型区分，其他类型按类型别名处理
+(type_spec name: (_) @name type: (struct_type)) @definition.struct
+(type_spec name: (_) @name type: (interface_type)) @definition.interface
+(type_spec name: (_) @name) @definition.type
+(type_alias name: (_) @name) @definition.type
@@ -0,0 +1,10 @@
+; Java的包、类、接口、枚举、记录、方法和构造函数
+
+(package_declaration [(identifier) (scoped_identifier)] @name) @definition.module
+(class_declaration name: (_) @name) @definition.class
+(record_declaration name: (_) @name) @definition.class
+(interface_declaration name: (_) @name) @definition.interface
+(annotation_type_declaration name: (_) @name) @definition.interface
+(enum_declaration name: (_) @name) @definition.enum
+(method_declaration name: (_) @name) @definition.function
+(constructor_declaration name: (_) @name) @definition.function
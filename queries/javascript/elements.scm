@@ -0,0 +1,16 @@
+; JavaScript的函数、类和方法，TypeScript的查询在此基础上扩展
+
+(function_declaration name: (_) @name) @definition.function
+(generator_function_declaration name: (_) @name) @definition.function
+(class_declaration name: (_) @name) @definition.class
+(method_definition name: (_) @name) @definition.function
+
+; 赋值给变量的函数以变量命名
+(lexical_declaration
+  (variable_declarator
+    name: (_) @name
+    value: [(arrow_function) (function_expression)])) @definition.function
+(variable_declaration
+  (variable_declarator
+    name: (_) @name
+    value: [(arrow_function) (function_expression)])) @definition.function
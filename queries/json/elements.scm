@@ -0,0 +1,5 @@
+; JSON顶层对象中的键
+
+(document
+  (object
+    (pair key: (_) @name) @definition.key))
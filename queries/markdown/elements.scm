@@ -0,0 +1,7 @@
+; Markdown的标题章节和围栏代码块，代码块以信息字符串中的语言命名
+
+(section . (atx_heading heading_content: (_) @name)) @definition.section
+(section . (setext_heading heading_content: (_) @name)) @definition.section
+(fenced_code_block (info_string (language) @name)) @definition.code_block
+((fenced_code_block) @definition.code_block
+  (#set! name "code"))
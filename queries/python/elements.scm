@@ -0,0 +1,4 @@
+; Python的函数和类定义
+
+(function_definition name: (_) @name) @definition.function
+(class_definition name: (_) @name) @definition.class
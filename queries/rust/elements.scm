@@ -0,0 +1,17 @@
+; Rust的函数、类型、trait、实现块、模块、常量和宏
+
+(function_item name: (_) @name) @definition.function
+(function_signature_item name: (_) @name) @definition.function
+(struct_item name: (_) @name) @definition.struct
+(union_item name: (_) @name) @definition.struct
+(enum_item name: (_) @name) @definition.enum
+(trait_item name: (_) @name) @definition.trait
+(mod_item name: (_) @name) @definition.module
+(type_item name: (_) @name) @definition.type
+(const_item name: (_) @name) @definition.constant
+(static_item name: (_) @name) @definition.constant
+(macro_definition name: (_) @name) @definition.macro
+
+; 实现块以实现的类型命名，不含泛型参数
+(impl_item type: (generic_type type: (_) @name)) @definition.impl
+(impl_item type: (_) @name) @definition.impl
@@ -0,0 +1,8 @@
+; TOML的表和表数组（以点分键命名，如 `profile.release`），以及顶层和表中的键
+; 内联表中的键不单独提取
+
+(table . (_) @name) @definition.section
+(table_array_element . (_) @name) @definition.section
+(document (pair . (_) @name) @definition.key)
+(table (pair . (_) @name) @definition.key)
+(table_array_element (pair . (_) @name) @definition.key)
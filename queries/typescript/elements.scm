@@ -0,0 +1,10 @@
+; TypeScript的抽象类、方法签名、接口、类型别名、枚举和命名空间
+
+(abstract_class_declaration name: (_) @name) @definition.class
+(method_signature name: (_) @name) @definition.function
+(abstract_method_signature name: (_) @name) @definition.function
+(interface_declaration name: (_) @name) @definition.interface
+(type_alias_declaration name: (_) @name) @definition.type
+(enum_declaration name: (_) @name) @definition.enum
+(internal_module name: (_) @name) @definition.module
+(module name: (_) @name) @definition.module
@@ -0,0 +1,10 @@
+; YAML各文档中的顶层键
+
+(document
+  (block_node
+    (block_mapping
+      (block_mapping_pair key: (_) @name) @definition.key)))
+(document
+  (flow_node
+    (flow_mapping
+      (flow_pair key: (_) @name) @definition.key)))
@@ -1078,8 +1078,9 @@ impl AIClient {
     ) -> AppResult<AIResponse> {
         let model_name = model_name.unwrap_or(&self.default_model);
 
-        // 先检查缓存
-        if let Some(cached_response) = self.response_cache.get(prompt, model_name).await {
+        // 先检查缓存，固定的@引用内容参与缓存键，引用的文件修改后不会命中旧的回答
+        let cache_key = self.cache_key(prompt);
+        if let Some(cached_response) = self.response_cache.get(&cache_key, model_name).await {
             return Ok(cached_response);
         }
 
@@ -1119,12 +1120,26 @@ impl AIClient {
 
         // 保存到缓存
         self.response_cache
-            .set(prompt, model_name, &response)
+            .set(&cache_key, model_name, &response)
             .await?;
 
         Ok(response)
     }
 
+    /// 生成响应缓存的键：提示加上所有固定的上下文内容
+    fn cache_key(&self, prompt: &str) -> String {
+        let pinned = self
+            .context_manager
+            .read()
+            .expect("RwLock poisoned")
+            .pinned_items();
+        pinned.iter().fold(prompt.to_string(), |mut key, item| {
+            key.push('\0');
+            key.push_str(&item.content);
+            key
+        })
+    }
+
     /// 构建带有上下文的完整提示
     fn build_prompt_with_context(&self, prompt: &str, context_items: &[ContextItem]) -> String {
         let mut full_prompt = String::new();
@@ -1157,6 +1172,16 @@ impl AIClient {
         self.context_manager.write().unwrap().clear();
     }
 
+    /// 获取上下文管理器（用于持久化会话）
+    pub fn context_manager(&self) -> Arc<std::sync::RwLock<ContextManager>> {
+        self.context_manager.clone()
+    }
+
+    /// 用已保存的上下文替换当前上下文（用于恢复会话）
+    pub fn restore_context(&self, context: ContextManager) {
+        *self.context_manager.write().unwrap() = context;
+    }
+
     /// 设置上下文最大令牌数
     pub fn set_context_max_tokens(&self, max_tokens: usize) {
         self.context_manager
@@ -1,5 +1,11 @@
+use crate::config::app::AppConfig;
 use crate::error::AppResult;
 use crate::knowledge::base::KnowledgeBase;
+use crate::knowledge::bundle::{Bundle, ImportMode};
+use crate::knowledge::collection::{CollectionActions, CollectionFilter};
+use crate::knowledge::git::{GitRepository, GIT_COLLECTION};
+use crate::knowledge::pipeline::{FileStatus, IndexEvent};
+use crate::knowledge::query::{HighlightedLine, SearchQuery};
 use crate::plugins;
 use chrono;
 use dirs;
@@ -8,12 +14,130 @@ use std::path::Path;
 use std::sync::Arc;
 
 /// Handle interactive mode command
-pub fn handle_interactive(tab: Option<String>) -> Result<(), Box<dyn Error>> {
+pub fn handle_interactive(
+    tab: Option<String>,
+    session_id: Option<String>,
+    config: &AppConfig,
+) -> Result<(), Box<dyn Error>> {
     println!("Starting interactive mode...");
     println!("Tab: {:?}", tab);
 
-    // Initialize UI - TODO: 主人~ 这里需要实现UI初始化逻辑
-    // ui::run(tab)?;
+    // UI 内部会创建自己的 tokio 运行时，因此放到独立线程中运行，避免嵌套运行时
+    let config = config.clone();
+    let ui_thread = std::thread::spawn(move || {
+        crate::ui::run(tab, session_id, &config).map_err(|e| e.to_string())
+    });
+    ui_thread
+        .join()
+        .map_err(|_| "交互界面线程异常退出".to_string())??;
+
+    Ok(())
+}
+
+/// 获取当前工作目录下最近的会话ID（用于 `--continue`）
+pub fn latest_session_id(config: &AppConfig) -> Result<Option<String>, Box<dyn Error>> {
+    let store = crate::session::SessionStore::new(&config.app.data_dir);
+    let cwd = std::env::current_dir()?;
+    Ok(store.latest_for_dir(&cwd)?.map(|meta| meta.id))
+}
+
+/// Handle chat session commands
+pub fn handle_session(
+    action: crate::session::SessionActions,
+    config: &AppConfig,
+) -> Result<(), Box<dyn Error>> {
+    let store = crate::session::SessionStore::new(&config.app.data_dir);
+
+    match action {
+        crate::session::SessionActions::List { here } => {
+            let cwd = std::env::current_dir()?;
+            let sessions: Vec<_> = store
+                .list()?
+                .into_iter()
+                .filter(|meta| !here || meta.cwd == cwd)
+                .collect();
+
+            if sessions.is_empty() {
+                println!("没有已保存的会话。");
+                return Ok(());
+            }
+
+            println!("会话列表:");
+            for meta in sessions {
+                let updated = chrono::DateTime::from_timestamp(meta.updated_at, 0)
+                    .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default();
+                let fork_note = match (&meta.forked_from, meta.forked_at) {
+                    (Some(parent), Some(at)) => {
+                        format!(" [分叉自 {} #{}]", &parent[..parent.len().min(8)], at)
+                    }
+                    _ => String::new(),
+                };
+                println!(
+                    "- {} {} ({} 条消息, {}) {}{}",
+                    meta.short_id(),
+                    meta.title,
+                    meta.message_count,
+                    updated,
+                    meta.cwd.display(),
+                    fork_note
+                );
+            }
+        }
+
+        crate::session::SessionActions::Resume { id } => {
+            let meta = store.get(&id)?;
+            println!("恢复会话: {} - {}", meta.short_id(), meta.title);
+            handle_interactive(None, Some(meta.id), config)?;
+        }
+
+        crate::session::SessionActions::Delete { id } => {
+            let meta = store.delete(&id)?;
+            println!("会话已删除！标题: {}", meta.title);
+        }
+
+        crate::session::SessionActions::Rename { id, title } => {
+            let meta = store.rename(&id, &title)?;
+            println!(
+                "会话已重命名！ID: {}，新标题: {}",
+                meta.short_id(),
+                meta.title
+            );
+        }
+
+        crate::session::SessionActions::Fork { id, at } => {
+            let meta = store.fork(&id, at)?;
+            println!(
+                "已从第 {} 条消息分叉出新会话！ID: {}，标题: {}",
+                at,
+                meta.short_id(),
+                meta.title
+            );
+            println!(
+                "使用 'codex session resume {}' 继续该会话。",
+                meta.short_id()
+            );
+        }
+
+        crate::session::SessionActions::Export { id, format, output } => {
+            let format: crate::context::ContextExportFormat = format.parse()?;
+            let transcript = store.export(&id, format)?;
+            match output {
+                Some(path) => {
+                    // 输出到目录时使用会话ID作为文件名
+                    let path = if path.is_dir() {
+                        let meta = store.get(&id)?;
+                        path.join(format!("{}.{}", meta.short_id(), format.extension()))
+                    } else {
+                        path
+                    };
+                    std::fs::write(&path, transcript)?;
+                    println!("会话已导出到: {}", path.display());
+                }
+                None => print!("{}", transcript),
+            }
+        }
+    }
 
     Ok(())
 }
@@ -52,10 +176,1075 @@ pub fn handle_code(
     Ok(())
 }
 
-/// Handle knowledge base commands
-pub fn handle_knowledge(action: String) -> Result<(), Box<dyn Error>> {
-    // TODO: 主人~ 这里需要实现知识库命令处理逻辑
-    println!("Handling knowledge command: {}", action);
+/// 创建用于解析 @ 引用的解析器，符号查找使用知识库索引缓存
+pub fn build_mention_resolver(
+    config: &AppConfig,
+) -> Result<crate::mentions::MentionResolver, Box<dyn Error>> {
+    let cwd = std::env::current_dir()?;
+    crate::parsers::initialize_parsers()?;
+
+    let mut indexer = crate::knowledge::indexer::CodeIndexer::new(config.knowledge.clone())?;
+    let resolver = crate::mentions::MentionResolver::new(&cwd);
+    Ok(match indexer.load_cache() {
+        Ok(()) => resolver.with_indexer(indexer),
+        Err(_) => resolver,
+    })
+}
+
+/// Handle ask command: answer a question with @-mentioned files and symbols pinned into context
+pub async fn handle_ask(prompt: &str, config: &AppConfig) -> Result<(), Box<dyn Error>> {
+    let resolver = build_mention_resolver(config)?;
+    let ai_client = crate::ai::AIClient::new().await?;
+
+    // 解析 @ 引用并固定到上下文
+    let (mentions, errors) = resolver.resolve_all(prompt);
+    for error in &errors {
+        println!("⚠️  无法解析引用 {}", error);
+    }
+    if !mentions.is_empty() {
+        let context = ai_client.context_manager();
+        let attached =
+            crate::mentions::MentionResolver::pin_into(&mentions, &mut context.write().unwrap());
+        println!("已附加 {} 项上下文:", attached.len());
+        for (label, tokens) in attached {
+            println!("- {} ({} tokens)", label, tokens);
+        }
+        println!();
+    }
+
+    let response = ai_client.generate_response(prompt, None).await?;
+    println!("{}", response.content());
+
+    Ok(())
+}
+
+/// 打开统一知识库，当前目录存在旧版知识库时自动迁移
+pub fn open_knowledge_base(
+    config: &AppConfig,
+) -> Result<crate::knowledge::local::LocalKnowledgeBase, Box<dyn Error>> {
+    let mut kb = crate::knowledge::local::LocalKnowledgeBase::new(config.knowledge.clone())?;
+
+    let cwd = std::env::current_dir()?;
+    if crate::knowledge::migrate::find_legacy_store(&cwd).is_some() {
+        println!("检测到旧版知识库，正在迁移...");
+        let report = crate::knowledge::migrate::migrate_legacy_store(&mut kb, &cwd)?;
+        println!(
+            "迁移完成！导入 {} 个文件，{} 个网页，跳过 {} 个无效条目",
+            report.files, report.pages, report.skipped
+        );
+    }
+
+    kb.init()?;
+    Ok(kb)
+}
+
+/// Handle knowledge base commands, `config_path` is forwarded to background watchers
+pub fn handle_knowledge(
+    action: crate::knowledge::base::KnowledgeActions,
+    config: &AppConfig,
+    config_path: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    use crate::knowledge::base::KnowledgeActions;
+
+    match action {
+        KnowledgeActions::Migrate { from } => {
+            let mut kb =
+                crate::knowledge::local::LocalKnowledgeBase::new(config.knowledge.clone())?;
+            let dir = match from {
+                Some(dir) => dir,
+                None => std::env::current_dir()?,
+            };
+            let report = crate::knowledge::migrate::migrate_legacy_store(&mut kb, &dir)?;
+            println!(
+                "迁移完成！导入 {} 个文件，{} 个网页，跳过 {} 个无效条目",
+                report.files, report.pages, report.skipped
+            );
+        }
+
+        KnowledgeActions::Add {
+            mut paths,
+            recursive,
+            collection,
+            jobs,
+            quiet,
+        } => {
+            let mut kb = open_knowledge_base(config)?;
+            kb.set_quiet(quiet);
+            if let Some(jobs) = jobs {
+                kb.set_index_workers(jobs);
+            }
+            if let Some(name) = &collection {
+                kb.set_collection(name)?;
+                if paths.is_empty() {
+                    paths = kb.collection_paths(name);
+                }
+            }
+            if paths.is_empty() {
+                println!("请指定要添加的文件或目录，或在集合配置中设置 paths。");
+                return Ok(());
+            }
+
+            let mut progress = ProgressBar::new(quiet);
+            let stats =
+                kb.add_files_with_progress(&paths, recursive, &mut |event| progress.update(&event))?;
+            let added = stats.processed();
+            match collection {
+                Some(name) => println!("已添加 {} 个文件到集合 {}", added, name),
+                None => println!("已添加 {} 个文件到知识库", added),
+            }
+            if !quiet {
+                println!(
+                    "重新索引 {} 个，未修改 {} 个，跳过 {} 个，失败 {} 个；{}",
+                    stats.indexed, stats.unchanged, stats.skipped, stats.failed, stats.timings
+                );
+            }
+        }
+
+        KnowledgeActions::AddGit {
+            path,
+            max_count,
+            full,
+            collection,
+        } => {
+            let mut kb = open_knowledge_base(config)?;
+            if let Some(name) = &collection {
+                kb.set_collection(name)?;
+            }
+            let repo = match path {
+                Some(path) => path,
+                None => std::env::current_dir()?,
+            };
+            let summary = kb.add_git_history(&repo, max_count, full)?;
+            let collection = collection.as_deref().unwrap_or(GIT_COLLECTION);
+            match (summary.commits, &summary.head) {
+                (_, None) => println!("仓库中还没有提交。"),
+                (0, Some(head)) => println!("提交历史已是最新（{}）", &head[..head.len().min(8)]),
+                (commits, Some(head)) => println!(
+                    "已索引 {} 个提交（{} 个文件差异）到集合 {}，最新提交 {}",
+                    commits,
+                    summary.files,
+                    collection,
+                    &head[..head.len().min(8)]
+                ),
+            }
+        }
+
+        KnowledgeActions::Blame { file, lines } => {
+            let lines = lines.as_deref().map(parse_line_range).transpose()?;
+            let file = std::fs::canonicalize(&file)?;
+            let repository = GitRepository::open(file.parent().unwrap_or(&file))?;
+            match repository.blame(&file, lines)? {
+                Some(commit) => println!("{}", commit.describe()),
+                None => println!("这些行还没有提交。"),
+            }
+        }
+
+        KnowledgeActions::Search {
+            query,
+            limit,
+            semantic,
+            collections,
+            explain,
+        } => {
+            let kb = open_knowledge_base(config)?;
+            let filter = collections
+                .as_deref()
+                .map(CollectionFilter::parse)
+                .transpose()?;
+
+            let highlighter = SearchQuery::parse(&query)?.highlighter();
+            let symbols = kb.search_symbols_in(&query, limit, filter.as_ref())?;
+            if !symbols.is_empty() {
+                println!("符号:");
+                for symbol in &symbols {
+                    let element = &symbol.element;
+                    println!(
+                        "  [{:.2}] {} {} - {}:{}",
+                        symbol.score,
+                        element.element_type.as_str(),
+                        element.qualified_name(),
+                        element.definition.file_path,
+                        element.definition.line
+                    );
+                    if !symbol.signature.is_empty() {
+                        let signature = HighlightedLine {
+                            line: element.definition.line as usize,
+                            ranges: highlighter.ranges(&symbol.signature),
+                            text: symbol.signature.clone(),
+                        };
+                        println!("         {}", render_highlighted(&signature));
+                    }
+                    if explain {
+                        println!("         排序依据: {}", symbol.explanation);
+                    }
+                }
+            }
+
+            let hits = match (semantic, &filter) {
+                (true, _) => kb.search_hybrid_in(&query, limit, filter.as_ref())?,
+                (false, Some(filter)) => kb.search_documents_in(&query, limit, filter)?,
+                (false, None) => kb.search_documents(&query, limit)?,
+            };
+            if !hits.is_empty() {
+                println!("文档:");
+                for hit in &hits {
+                    let location = match hit.lines {
+                        Some((start, end)) => format!("{}:{}-{}", hit.path, start, end),
+                        None => hit.path.clone(),
+                    };
+                    println!(
+                        "  [{:.3}] {} - {} ({}, {}, {})",
+                        hit.score,
+                        hit.title,
+                        location,
+                        hit.language,
+                        hit.source.as_str(),
+                        hit.collection
+                    );
+                    if hit.highlights.is_empty() {
+                        for line in hit.snippet.lines().filter(|line| !line.trim().is_empty()) {
+                            println!("         {}", line.trim());
+                        }
+                    }
+                    for line in &hit.highlights {
+                        println!("   {:>5} │ {}", line.line, render_highlighted(line));
+                    }
+                    if explain {
+                        println!("         排序依据: {}", hit.explanation);
+                    }
+                }
+            }
+
+            if symbols.is_empty() && hits.is_empty() {
+                println!("没有找到匹配的结果。");
+            }
+            kb.record_search(&query)?;
+        }
+
+        KnowledgeActions::List {
+            details,
+            collections,
+        } => {
+            let kb = open_knowledge_base(config)?;
+            let filter = collections
+                .as_deref()
+                .map(CollectionFilter::parse)
+                .transpose()?;
+            let documents: Vec<_> = kb
+                .list()?
+                .into_iter()
+                .filter(|meta| filter.as_ref().is_none_or(|f| f.matches(&meta.collection)))
+                .collect();
+            if documents.is_empty() {
+                println!("知识库为空。");
+                return Ok(());
+            }
+
+            println!("知识库文档 ({}):", documents.len());
+            for meta in documents {
+                if details {
+                    let indexed = chrono::DateTime::from_timestamp(meta.indexed_at, 0)
+                        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+                        .unwrap_or_default();
+                    println!(
+                        "- {}\n  集合: {}，来源: {}，语言: {}，大小: {} 字节，行数: {}，索引时间: {}",
+                        meta.path,
+                        meta.collection,
+                        meta.source.as_str(),
+                        meta.language,
+                        meta.size,
+                        meta.lines,
+                        indexed
+                    );
+                } else {
+                    println!("- {}", meta.path);
+                }
+            }
+        }
+
+        KnowledgeActions::Stats => {
+            let mut kb = open_knowledge_base(config)?;
+            let stats = kb.stats()?;
+            println!("文档: {}", stats.documents);
+            print_counts("按来源", &stats.by_source);
+            print_counts("按语言", &stats.by_language);
+            print_counts("按集合", &stats.by_collection);
+            println!("符号: {}", stats.symbols);
+            print_counts("按类型", &stats.symbols_by_kind);
+            println!("向量: {}", stats.vectors);
+            println!("交叉引用文件: {}", stats.xref_files);
+            println!("索引缓存文件: {}", stats.cached_files);
+            println!("索引段: {}", stats.segments);
+            println!("索引大小: {}", format_size(stats.index_size));
+            println!("元数据大小: {}", format_size(stats.metadata_size));
+            let updated = stats
+                .last_updated
+                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            println!("最近更新: {}", updated);
+        }
+
+        KnowledgeActions::Verify => {
+            let mut kb = open_knowledge_base(config)?;
+            let report = kb.verify()?;
+            if report.is_healthy() {
+                println!("已检查 {} 个文档，未发现问题。", report.checked);
+                return Ok(());
+            }
+            println!(
+                "已检查 {} 个文档，发现 {} 个问题:",
+                report.checked,
+                report.issues.len()
+            );
+            for issue in &report.issues {
+                println!("- {}", issue);
+            }
+            println!("运行 `codex knowledge repair` 修复这些问题。");
+        }
+
+        KnowledgeActions::Repair { compact } => {
+            let mut kb = open_knowledge_base(config)?;
+            let report = kb.verify()?;
+            if report.is_healthy() {
+                println!("已检查 {} 个文档，未发现问题。", report.checked);
+            } else {
+                for issue in &report.issues {
+                    println!("- {}", issue);
+                }
+                let summary = kb.repair(&report)?;
+                println!(
+                    "已修复 {} 个问题：删除 {} 个文档，重新索引 {} 个文件，清理 {} 项孤立数据",
+                    report.issues.len(),
+                    summary.removed,
+                    summary.reindexed,
+                    summary.cleaned
+                );
+                let remaining = kb.verify()?.issues.len();
+                if remaining > 0 {
+                    println!(
+                        "仍有 {} 个问题未能修复，请运行 `codex knowledge verify` 查看。",
+                        remaining
+                    );
+                }
+            }
+            if compact {
+                print_compact_summary(&kb.compact()?);
+            }
+        }
+
+        KnowledgeActions::Compact => {
+            let mut kb = open_knowledge_base(config)?;
+            print_compact_summary(&kb.compact()?);
+        }
+
+        KnowledgeActions::Clear { confirm } => {
+            if !confirm {
+                println!("清空知识库将删除所有索引数据，请使用 --confirm 确认。");
+                return Ok(());
+            }
+            let mut kb = open_knowledge_base(config)?;
+            kb.clear()?;
+            println!("知识库已清空！");
+        }
+
+        KnowledgeActions::Watch {
+            path,
+            debounce,
+            background,
+            stop,
+        } => {
+            handle_watch(path, debounce, background, stop, config, config_path)?;
+        }
+
+        KnowledgeActions::Export { file, root } => {
+            let kb = open_knowledge_base(config)?;
+            let root = match root {
+                Some(root) => root,
+                None => std::env::current_dir()?,
+            };
+            let bundle = kb.export_bundle(&root)?;
+            bundle.write(&file)?;
+            println!(
+                "已导出 {} 个文档、{} 个代码元素、{} 个向量到 {}",
+                bundle.manifest.documents,
+                bundle.manifest.symbols,
+                bundle.manifest.vectors,
+                file.display()
+            );
+        }
+
+        KnowledgeActions::Import {
+            file,
+            root,
+            replace,
+        } => {
+            let bundle = Bundle::read(&file)?;
+            let root = match root {
+                Some(root) => root,
+                None => std::env::current_dir()?,
+            };
+            let mode = if replace {
+                ImportMode::Replace
+            } else {
+                ImportMode::Merge
+            };
+
+            println!(
+                "导入知识库归档（格式版本 {}，由 codex {} 创建）",
+                bundle.manifest.format_version, bundle.manifest.codex_version
+            );
+            let mut kb = open_knowledge_base(config)?;
+            kb.set_quiet(true);
+            let summary = kb.import_bundle(bundle, &root, mode)?;
+            println!(
+                "已导入 {} 个文档、{} 个代码元素、{} 个向量",
+                summary.documents, summary.symbols, summary.vectors
+            );
+            if summary.reembedded > 0 {
+                println!("已使用当前嵌入模型为 {} 个文档重新计算向量", summary.reembedded);
+            }
+        }
+
+        KnowledgeActions::Collection { action } => {
+            handle_collection(action, config)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理知识库集合命令
+fn handle_collection(
+    action: CollectionActions,
+    config: &AppConfig,
+) -> Result<(), Box<dyn Error>> {
+    match action {
+        CollectionActions::List => {
+            let kb = open_knowledge_base(config)?;
+            let stats = kb.collection_stats()?;
+            if stats.is_empty() {
+                println!("知识库中没有集合。");
+                return Ok(());
+            }
+
+            println!("知识库集合 ({}):", stats.len());
+            for collection in &stats {
+                println!(
+                    "- {}: {} 个文档，{} 个向量{}",
+                    collection.name,
+                    collection.documents(),
+                    collection.vectors,
+                    if collection.description.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" - {}", collection.description)
+                    }
+                );
+            }
+        }
+
+        CollectionActions::Stats { name } => {
+            let kb = open_knowledge_base(config)?;
+            let Some(collection) = kb
+                .collection_stats()?
+                .into_iter()
+                .find(|collection| collection.name == name)
+            else {
+                println!("集合不存在: {}", name);
+                return Ok(());
+            };
+
+            println!("集合: {}", collection.name);
+            if !collection.description.is_empty() {
+                println!("描述: {}", collection.description);
+            }
+            println!("文件: {}", collection.files);
+            println!("网页: {}", collection.pages);
+            println!("提交: {}", collection.commits);
+            println!("大小: {} 字节", collection.size);
+            println!("行数: {}", collection.lines);
+            println!("向量: {}", collection.vectors);
+            let indexed = collection
+                .last_indexed
+                .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            println!("最近索引: {}", indexed);
+        }
+
+        CollectionActions::Delete { name, confirm } => {
+            if !confirm {
+                println!("删除集合将移除其中所有文档的索引数据，请使用 --confirm 确认。");
+                return Ok(());
+            }
+            let mut kb = open_knowledge_base(config)?;
+            let removed = kb.delete_collection(&name)?;
+            println!("已从集合 {} 删除 {} 个文档", name, removed);
+        }
+    }
+
+    Ok(())
+}
+
+/// 解析行范围，如 `42` 或 `10-20`
+fn parse_line_range(range: &str) -> Result<(usize, usize), Box<dyn Error>> {
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let start: usize = start.trim().parse()?;
+    let end: usize = end.trim().parse()?;
+    if start == 0 || end < start {
+        return Err(format!("无效的行范围: {}", range).into());
+    }
+    Ok((start, end))
+}
+
+/// 以终端粗体黄色显示搜索结果行中的匹配部分
+fn render_highlighted(line: &HighlightedLine) -> String {
+    line.segments()
+        .into_iter()
+        .map(|(text, matched)| {
+            if matched {
+                format!("\x1b[1;33m{}\x1b[0m", text)
+            } else {
+                text.to_string()
+            }
+        })
+        .collect()
+}
+
+/// 终端索引进度条，输出到标准错误，非终端时只输出失败的文件
+struct ProgressBar {
+    /// 不显示进度
+    quiet: bool,
+    /// 标准错误是否为终端
+    interactive: bool,
+    /// 上次绘制时间，限制刷新频率
+    last_draw: Option<std::time::Instant>,
+}
+
+impl ProgressBar {
+    /// 进度条宽度
+    const WIDTH: usize = 30;
+
+    fn new(quiet: bool) -> Self {
+        use std::io::IsTerminal;
+        Self {
+            quiet,
+            interactive: std::io::stderr().is_terminal(),
+            last_draw: None,
+        }
+    }
+
+    /// 根据索引事件更新进度
+    fn update(&mut self, event: &IndexEvent) {
+        if self.quiet {
+            return;
+        }
+        match event {
+            IndexEvent::File {
+                status: FileStatus::Failed(_),
+                ..
+            } => {
+                self.clear();
+                eprintln!("{}", event);
+            }
+            IndexEvent::File {
+                path, done, total, ..
+            } if self.interactive => {
+                let redraw = self
+                    .last_draw
+                    .is_none_or(|last| last.elapsed().as_millis() >= 50);
+                if redraw || done == total {
+                    self.draw(*done, *total, path);
+                }
+            }
+            IndexEvent::Finished(_) => self.clear(),
+            _ => {}
+        }
+    }
+
+    /// 绘制进度条和当前文件名
+    fn draw(&mut self, done: usize, total: usize, path: &Path) {
+        let filled = (done * Self::WIDTH).checked_div(total).unwrap_or(Self::WIDTH);
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        eprint!(
+            "\r\x1b[2K索引中 [{}{}] {}/{} {}",
+            "#".repeat(filled),
+            ".".repeat(Self::WIDTH - filled),
+            done,
+            total,
+            name
+        );
+        self.last_draw = Some(std::time::Instant::now());
+    }
+
+    /// 清除已绘制的进度条
+    fn clear(&mut self) {
+        if self.last_draw.take().is_some() {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+/// 输出分组计数，按数量从多到少排列
+fn print_counts(title: &str, counts: &std::collections::BTreeMap<String, usize>) {
+    let mut counts: Vec<_> = counts.iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+    for (name, count) in counts {
+        let name = if name.is_empty() { "-" } else { name.as_str() };
+        println!("  {} {}: {}", title, name, count);
+    }
+}
+
+/// 将字节数格式化为便于阅读的大小
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// 输出索引压缩结果
+fn print_compact_summary(summary: &crate::knowledge::health::CompactSummary) {
+    println!(
+        "已合并索引段: {} -> {}，索引大小: {} -> {}",
+        summary.segments_before,
+        summary.segments_after,
+        format_size(summary.size_before),
+        format_size(summary.size_after)
+    );
+}
+
+/// 后台监听服务的PID文件名
+const WATCH_PID_FILE: &str = "watch.pid";
+
+/// 处理知识库监听命令
+fn handle_watch(
+    path: Option<std::path::PathBuf>,
+    debounce: u64,
+    background: bool,
+    stop: bool,
+    config: &AppConfig,
+    config_path: Option<&str>,
+) -> Result<(), Box<dyn Error>> {
+    let pid_file = config.knowledge.metadata_dir.join(WATCH_PID_FILE);
+
+    if stop {
+        let Some(pid) = running_watch_pid(&pid_file)? else {
+            println!("没有正在运行的后台监听服务");
+            return Ok(());
+        };
+        #[cfg(unix)]
+        let status = std::process::Command::new("kill")
+            .arg(pid.to_string())
+            .status()?;
+        #[cfg(windows)]
+        let status = std::process::Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/F"])
+            .status()?;
+        if !status.success() {
+            return Err(format!("无法停止后台监听服务 (PID {})", pid).into());
+        }
+        std::fs::remove_file(&pid_file)?;
+        println!("已停止后台监听服务 (PID {})", pid);
+        return Ok(());
+    }
+
+    let root = match path {
+        Some(path) => path,
+        None => std::env::current_dir()?,
+    };
+
+    if background {
+        if config.knowledge.metadata_dir.as_os_str().is_empty() {
+            return Err("知识库未配置存储目录，无法在后台监听".into());
+        }
+        if let Some(pid) = running_watch_pid(&pid_file)? {
+            return Err(format!(
+                "后台监听服务已在运行 (PID {})，请先执行 `codex knowledge watch --stop`",
+                pid
+            )
+            .into());
+        }
+
+        let log_dir = config.app.data_dir.join("logs");
+        std::fs::create_dir_all(&log_dir)?;
+        let log_path = log_dir.join("watch.log");
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)?;
+
+        let mut command = std::process::Command::new(std::env::current_exe()?);
+        if let Some(path) = config_path {
+            command.arg("--config").arg(std::fs::canonicalize(path)?);
+        }
+        command
+            .args(["knowledge", "watch", "--debounce", &debounce.to_string()])
+            .arg(root.canonicalize()?)
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        #[cfg(unix)]
+        {
+            // 脱离当前终端的进程组，关闭终端时不随之退出
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let child = command.spawn()?;
+
+        std::fs::create_dir_all(&config.knowledge.metadata_dir)?;
+        std::fs::write(&pid_file, child.id().to_string())?;
+        println!("后台监听服务已启动 (PID {})", child.id());
+        println!("日志: {:?}", log_path);
+        return Ok(());
+    }
+
+    let watcher = crate::knowledge::watcher::KnowledgeWatcher::new(
+        config.knowledge.clone(),
+        &root,
+        std::time::Duration::from_millis(debounce),
+    )?;
+
+    // Ctrl+C 时处理完当前批次后退出
+    let stop_flag = Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let signal_flag = stop_flag.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            signal_flag.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+    });
+
+    watcher.run(&stop_flag, &mut |event| {
+        let time = chrono::Local::now().format("%H:%M:%S");
+        println!("[{}] {}", time, event);
+    })?;
+    Ok(())
+}
+
+/// 读取后台监听服务的PID，进程已退出或PID被其他程序复用时删除过期的PID文件
+fn running_watch_pid(pid_file: &Path) -> Result<Option<u32>, Box<dyn Error>> {
+    let Ok(content) = std::fs::read_to_string(pid_file) else {
+        return Ok(None);
+    };
+    match content.trim().parse::<u32>() {
+        Ok(pid) if is_watch_process(pid) => Ok(Some(pid)),
+        _ => {
+            std::fs::remove_file(pid_file)?;
+            Ok(None)
+        }
+    }
+}
+
+/// 检查进程是否仍在运行且为当前程序的监听服务
+fn is_watch_process(pid: u32) -> bool {
+    let Some(name) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.file_name().map(|name| name.to_string_lossy().to_string()))
+    else {
+        return false;
+    };
+
+    #[cfg(unix)]
+    {
+        let output = std::process::Command::new("ps")
+            .args(["-p", &pid.to_string(), "-o", "args="])
+            .output();
+        let Ok(output) = output else {
+            return false;
+        };
+        let args = String::from_utf8_lossy(&output.stdout);
+        let mut args = args.split_whitespace();
+        output.status.success()
+            && args
+                .next()
+                .and_then(|program| Path::new(program).file_name())
+                .is_some_and(|program| program.to_string_lossy() == name)
+            && args.any(|arg| arg == "watch")
+    }
+    #[cfg(windows)]
+    {
+        let output = std::process::Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH", "/FO", "CSV"])
+            .output();
+        output.is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout).contains(&format!("\"{}\"", name))
+        })
+    }
+}
+
+/// Handle cross-reference queries
+pub fn handle_xref(
+    action: crate::knowledge::xref::XrefActions,
+    config: &AppConfig,
+) -> Result<(), Box<dyn Error>> {
+    use crate::knowledge::xref::XrefActions;
+
+    let kb = open_knowledge_base(config)?;
+    let xref = kb.xref().ok_or("当前知识库不支持交叉引用")?;
+    if xref.file_count() == 0 {
+        println!("交叉引用索引为空，请先使用 `codex knowledge add` 添加代码。");
+        return Ok(());
+    }
+
+    // 读取引用所在行，便于在终端中查看上下文
+    let source_line = |path: &str, line: usize| {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| content.lines().nth(line - 1).map(|l| l.trim().to_string()))
+            .unwrap_or_default()
+    };
+
+    match action {
+        XrefActions::Def { symbol } => {
+            let definitions = xref.definitions(&symbol)?;
+            if definitions.is_empty() {
+                println!("没有找到 {} 的定义。", symbol);
+            }
+            for location in definitions {
+                let definition = &location.item;
+                println!(
+                    "{} {} - {}:{}-{}",
+                    definition.kind.as_str(),
+                    definition.qualified_name,
+                    location.path,
+                    definition.line,
+                    definition.end_line
+                );
+                println!("    {}", source_line(&location.path, definition.line));
+            }
+        }
+
+        XrefActions::Refs { symbol } => {
+            let references = xref.references(&symbol)?;
+            if references.is_empty() {
+                println!("没有找到 {} 的引用。", symbol);
+                return Ok(());
+            }
+            println!("引用 ({}):", references.len());
+            for location in references {
+                let reference = &location.item;
+                println!(
+                    "  {}:{}:{} [{}] {}",
+                    location.path,
+                    reference.line,
+                    reference.column,
+                    reference.kind.as_str(),
+                    source_line(&location.path, reference.line)
+                );
+            }
+        }
+
+        XrefActions::Callers { symbol } => {
+            let callers = xref.callers(&symbol)?;
+            if callers.is_empty() {
+                println!("没有找到 {} 的调用者。", symbol);
+                return Ok(());
+            }
+            println!("调用者 ({}):", callers.len());
+            for location in callers {
+                let reference = &location.item;
+                println!(
+                    "  {} - {}:{}:{}",
+                    reference.caller.as_deref().unwrap_or("<顶层>"),
+                    location.path,
+                    reference.line,
+                    reference.column
+                );
+                println!("      {}", source_line(&location.path, reference.line));
+            }
+        }
+
+        XrefActions::Callees { symbol } => {
+            let callees = xref.callees(&symbol)?;
+            if callees.is_empty() {
+                println!("没有找到 {} 调用的函数。", symbol);
+                return Ok(());
+            }
+            println!("被调用函数 ({}):", callees.len());
+            for location in callees {
+                let reference = &location.item;
+                let name = match &reference.qualifier {
+                    Some(qualifier) => format!("{}::{}", qualifier, reference.name),
+                    None => reference.name.clone(),
+                };
+                println!(
+                    "  {} - {}:{}:{}",
+                    name, location.path, reference.line, reference.column
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle dependency graph commands
+pub fn handle_deps(
+    action: crate::knowledge::deps::DepsActions,
+    config: &AppConfig,
+) -> Result<(), Box<dyn Error>> {
+    use crate::knowledge::deps::{DependencyGraph, DepsActions, GraphFormat};
+    use crate::knowledge::walker::ProjectWalker;
+
+    // 依赖图需要所有源文件，只沿用知识库的排除规则，不按扩展名过滤
+    let walker = ProjectWalker::from_config(&config.knowledge).extensions(&[]);
+    let cwd = std::env::current_dir()?;
+
+    match action {
+        DepsActions::Graph {
+            path,
+            format,
+            external,
+        } => {
+            let format: GraphFormat = format.parse()?;
+            let graph = DependencyGraph::build(path.as_deref().unwrap_or(&cwd), &walker)?;
+            match format {
+                GraphFormat::Dot => print!("{}", graph.to_dot(external)),
+                GraphFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&graph.to_json(external))?)
+                }
+            }
+        }
+
+        DepsActions::Cycles { path } => {
+            let graph = DependencyGraph::build(path.as_deref().unwrap_or(&cwd), &walker)?;
+            let cycles = graph.cycles();
+            if cycles.is_empty() {
+                println!("没有发现循环依赖。");
+                return Ok(());
+            }
+            println!("发现 {} 个循环依赖:", cycles.len());
+            for cycle in cycles {
+                println!("  {} -> {}", cycle.join(" -> "), cycle[0]);
+            }
+        }
+
+        DepsActions::Dependents {
+            file,
+            root,
+            transitive,
+        } => {
+            let graph = DependencyGraph::build(root.as_deref().unwrap_or(&cwd), &walker)?;
+            let target = graph.relative(&file).ok_or_else(|| {
+                format!(
+                    "{} 不在项目目录 {} 中",
+                    file.display(),
+                    graph.root().display()
+                )
+            })?;
+            let dependents = graph.dependents(&target, transitive);
+            if dependents.is_empty() {
+                println!("没有文件依赖 {}。", target);
+                return Ok(());
+            }
+            let scope = if transitive { "直接或间接" } else { "直接" };
+            println!("{}依赖 {} 的文件 ({}):", scope, target, dependents.len());
+            for dependent in dependents {
+                println!("  {}", dependent);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// 检查文件语法，输出每个错误的位置和所在行；有语法错误时返回错误
+pub fn handle_check_syntax(files: &[std::path::PathBuf]) -> Result<(), Box<dyn Error>> {
+    use crate::parsers::{check_file_syntax, initialize_parsers};
+
+    initialize_parsers()?;
+    let mut failed = 0;
+    for file in files {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| format!("无法读取 {}: {}", file.display(), e))?;
+        let Some(diagnostics) = check_file_syntax(&file.to_string_lossy(), &content)? else {
+            println!("{}: 跳过，不支持的语言", file.display());
+            continue;
+        };
+        if diagnostics.is_empty() {
+            println!("{}: 语法正确", file.display());
+            continue;
+        }
+        failed += 1;
+        for diagnostic in &diagnostics {
+            println!("{}:{}", file.display(), diagnostic);
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} 个文件存在语法错误", failed).into());
+    }
+    Ok(())
+}
+
+/// 根据命令行参数构建重构目标，提取的表达式转换为文件中的字节范围
+pub fn refactor_target(
+    path: &std::path::Path,
+    name: Option<String>,
+    new_name: Option<String>,
+    expression: Option<&str>,
+    line: Option<u32>,
+) -> Result<crate::refactor::RefactorTarget, Box<dyn Error>> {
+    let selection = match expression {
+        Some(expression) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("无法读取 {}: {}", path.display(), e))?;
+            Some(crate::refactor::find_selection(&content, expression, line)?)
+        }
+        None => None,
+    };
+    Ok(crate::refactor::RefactorTarget {
+        name,
+        new_name,
+        selection,
+        line,
+    })
+}
+
+/// 执行重构并输出差异，`apply` 为真时写入文件
+pub async fn handle_refactor(
+    kind: &str,
+    path: &std::path::Path,
+    target: &crate::refactor::RefactorTarget,
+    apply: bool,
+) -> Result<(), Box<dyn Error>> {
+    use crate::code::{CodeProgrammer, RefactorType};
+
+    let refactor_type = RefactorType::from(kind);
+    // 只有需要 AI 的重构才创建 AI 客户端
+    let refactoring = match CodeProgrammer::refactor_locally(path, &refactor_type, target)? {
+        Some(refactoring) => refactoring,
+        None => {
+            let ai_client = crate::ai::AIClient::new().await?;
+            CodeProgrammer::with_ai_client(ai_client)
+                .refactor_code(path, &refactor_type, target)
+                .await?
+        }
+    };
+
+    println!("{}", refactoring.description);
+    if refactoring.is_empty() {
+        println!("没有需要修改的内容");
+        return Ok(());
+    }
+    print!("{}", refactoring.diff());
+    if apply {
+        refactoring.apply()?;
+        println!("已修改 {} 个文件", refactoring.changes.len());
+    } else {
+        println!("使用 --apply 写入修改");
+    }
     Ok(())
 }
 
@@ -64,17 +1253,16 @@ pub async fn handle_scrape(
     urls: &[String],
     depth: u32,
     add_to_kb: bool,
+    collection: Option<&str>,
+    config: &AppConfig,
 ) -> Result<(), Box<dyn Error>> {
     println!("Scraping URLs: {:?}", urls);
     println!("Depth: {}", depth);
     println!("Add to KB: {}", add_to_kb);
 
-    // 创建配置加载器
-    let config_loader = crate::config::loader::ConfigLoader::new();
-    let config = config_loader.load(None)?;
-
     // 创建远程知识库实例
-    let mut remote_kb = crate::knowledge::remote::RemoteKnowledgeBase::new(config.knowledge)?;
+    let mut remote_kb =
+        crate::knowledge::remote::RemoteKnowledgeBase::new(config.knowledge.clone())?;
 
     // 初始化远程知识库
     remote_kb.init()?;
@@ -82,6 +1270,19 @@ pub async fn handle_scrape(
     // 抓取远程内容
     let scraped_content = remote_kb.scrape(urls).await?;
 
+    // 将抓取的网页添加到知识库
+    if add_to_kb && !scraped_content.is_empty() {
+        let mut kb = open_knowledge_base(config)?;
+        if let Some(name) = collection {
+            kb.set_collection(name)?;
+        }
+        for content in &scraped_content {
+            kb.add_web_page(&content.meta.url, &content.meta.title, &content.content)?;
+        }
+        kb.commit()?;
+        println!("已将 {} 个页面添加到知识库", scraped_content.len());
+    }
+
     // 显示抓取结果
     println!("\n抓取完成，共抓取到 {} 个页面:", scraped_content.len());
     for content in &scraped_content {
@@ -1,5 +1,7 @@
 use crate::ai::AIClient;
 use crate::error::{AppError, AppResult};
+use crate::parsers::{check_file_syntax, initialize_parsers, SyntaxDiagnostic};
+use crate::refactor::{self, FileChange, RefactorTarget, Refactoring};
 use std::error::Error;
 use std::fs;
 use std::path::Path;
@@ -36,10 +38,11 @@ impl From<&str> for CodeLanguage {
             "js" => CodeLanguage::JavaScript,
             "ts" => CodeLanguage::TypeScript,
             "java" => CodeLanguage::Java,
-            "c" => CodeLanguage::C,
+            "c" | "h" => CodeLanguage::C,
             "cpp" => CodeLanguage::Cpp,
             "cc" => CodeLanguage::Cpp,
             "cxx" => CodeLanguage::Cpp,
+            "hpp" | "hh" | "hxx" => CodeLanguage::Cpp,
             "go" => CodeLanguage::Go,
             "rb" => CodeLanguage::Ruby,
             "php" => CodeLanguage::PHP,
@@ -94,7 +97,11 @@ impl Default for CodeProgrammer {
 impl CodeProgrammer {
     /// Create a new code programmer instance
     pub fn new() -> Self {
-        let ai_client = AIClient::default();
+        Self::with_ai_client(AIClient::default())
+    }
+
+    /// Create a code programmer that uses an existing AI client
+    pub fn with_ai_client(ai_client: AIClient) -> Self {
         let mut parsers = std::collections::HashMap::new();
 
         // Initialize parsers for supported languages
@@ -110,6 +117,20 @@ impl CodeProgrammer {
         if let Ok(parser) = Self::create_parser(CodeLanguage::TypeScript) {
             parsers.insert(CodeLanguage::TypeScript, parser);
         }
+        for language in [
+            CodeLanguage::Go,
+            CodeLanguage::Java,
+            CodeLanguage::C,
+            CodeLanguage::Cpp,
+            CodeLanguage::Markdown,
+            CodeLanguage::Toml,
+            CodeLanguage::YAML,
+            CodeLanguage::JSON,
+        ] {
+            if let Ok(parser) = Self::create_parser(language) {
+                parsers.insert(language, parser);
+            }
+        }
 
         Self { ai_client, parsers }
     }
@@ -122,6 +143,14 @@ impl CodeProgrammer {
             CodeLanguage::Python => tree_sitter_python::language(),
             CodeLanguage::JavaScript => tree_sitter_javascript::language(),
             CodeLanguage::TypeScript => tree_sitter_typescript::language_typescript(),
+            CodeLanguage::Go => tree_sitter_go::language(),
+            CodeLanguage::Java => tree_sitter_java::language(),
+            CodeLanguage::C => tree_sitter_c::language(),
+            CodeLanguage::Cpp => tree_sitter_cpp::language(),
+            CodeLanguage::Markdown => tree_sitter_md::language(),
+            CodeLanguage::Toml => tree_sitter_toml::language(),
+            CodeLanguage::YAML => tree_sitter_yaml::language(),
+            CodeLanguage::JSON => tree_sitter_json::language(),
             _ => return Err(AppError::Other("Unsupported language".to_string())),
         };
 
@@ -202,20 +231,80 @@ impl CodeProgrammer {
         Ok(response.content().to_string())
     }
 
-    /// Refactor code
+    /// Apply a refactoring that does not need the AI, returning `None` when the
+    /// refactoring kind or the file's language has to be delegated to the AI
+    pub fn refactor_locally(
+        path: &Path,
+        refactor_type: &RefactorType,
+        target: &RefactorTarget,
+    ) -> AppResult<Option<Refactoring>> {
+        initialize_parsers()?;
+        let required = |value: &Option<String>, what: &str| {
+            value
+                .clone()
+                .ok_or_else(|| AppError::Other(format!("{} requires {}", refactor_type, what)))
+        };
+
+        match refactor_type {
+            RefactorType::RenameVariable => {
+                let name = required(&target.name, "a name")?;
+                let new_name = required(&target.new_name, "a new name")?;
+                refactor::rename_symbol(path, &name, &new_name).map(Some)
+            }
+            RefactorType::ExtractVariable if refactor::supports_variables(path) => {
+                let name = required(&target.name, "a variable name")?;
+                let selection = target.selection.clone().ok_or_else(|| {
+                    AppError::Other(format!("{} requires a selection", refactor_type))
+                })?;
+                let code = Self::read_code_from_file(path)?;
+                refactor::extract_variable(path, &code, selection, &name).map(Some)
+            }
+            RefactorType::InlineVariable if refactor::supports_variables(path) => {
+                let name = required(&target.name, "a variable name")?;
+                let code = Self::read_code_from_file(path)?;
+                refactor::inline_variable(path, &code, &name, target.line).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Refactor a file. Renames, extract-variable and inline-variable are applied
+    /// deterministically with tree-sitter; other kinds, and languages without
+    /// variable refactorings, are delegated to the AI. Nothing is written: show
+    /// `Refactoring::diff` and call `Refactoring::apply` to write the result.
+    /// For renames `path` may be a project directory
     pub async fn refactor_code(
         &mut self,
-        code: &str,
-        language: CodeLanguage,
-        refactor_type: &str,
-    ) -> AppResult<String> {
-        let language_str: &str = language.into();
+        path: &Path,
+        refactor_type: &RefactorType,
+        target: &RefactorTarget,
+    ) -> AppResult<Refactoring> {
+        if let Some(refactoring) = Self::refactor_locally(path, refactor_type, target)? {
+            return Ok(refactoring);
+        }
+
+        let code = Self::read_code_from_file(path)?;
+        let language_str: &str = Self::get_language_from_path(path).into();
+        let mut goal = refactor_type.to_string();
+        if let Some(name) = &target.name {
+            goal.push_str(&format!(" (name: `{}`)", name));
+        }
+        if let Some(selection) = target.selection.clone().and_then(|range| code.get(range)) {
+            goal.push_str(&format!(" for the expression `{}`", selection));
+        }
         let prompt = format!(
-            "Refactor the following {language_str} code to {refactor_type}:\n\n{code}\n\nProvide the refactored code along with explanations of the changes made and how they address the refactoring goal."
+            "Refactor the following {language_str} code to {goal}:\n\n{code}\n\nReturn ONLY the complete refactored file, no additional explanation."
         );
-
         let response = self.ai_client.generate_response(&prompt, None).await?;
-        Ok(response.content().to_string())
+        let modified = Self::repair_syntax(&self.ai_client, path, response.content()).await?;
+        Refactoring::new(
+            format!("{} (AI)", refactor_type),
+            vec![FileChange {
+                path: path.to_path_buf(),
+                original: code,
+                modified,
+            }],
+        )
     }
 
     /// Analyze codebase structure
@@ -327,10 +416,75 @@ impl CodeProgrammer {
         fs::read_to_string(path).map_err(|e| e.into())
     }
 
-    /// Write code to file
+    /// Write code to file, refusing code that does not parse
     pub fn write_code_to_file(path: &Path, code: &str) -> AppResult<()> {
+        let diagnostics = Self::check_syntax(path, code)?;
+        if !diagnostics.is_empty() {
+            return Err(AppError::Other(format!(
+                "Refusing to write {}: {}",
+                path.display(),
+                format_diagnostics(&diagnostics)
+            )));
+        }
         fs::write(path, code).map_err(|e| e.into())
     }
+
+    /// Check code for syntax errors, languages without a parser always pass
+    pub fn check_syntax(path: &Path, code: &str) -> AppResult<Vec<SyntaxDiagnostic>> {
+        initialize_parsers()?;
+        Ok(check_file_syntax(&path.to_string_lossy(), code)?.unwrap_or_default())
+    }
+
+    /// Repair AI-generated code before it is written: strip a surrounding Markdown
+    /// code fence, then ask the AI to fix remaining syntax errors. Code that still
+    /// fails to parse is returned as is and rejected by `write_code_to_file`
+    pub async fn repair_syntax(ai_client: &AIClient, path: &Path, code: &str) -> AppResult<String> {
+        let mut code = strip_code_fence(code).to_string();
+        for _ in 0..MAX_SYNTAX_REPAIRS {
+            let diagnostics = Self::check_syntax(path, &code)?;
+            if diagnostics.is_empty() {
+                return Ok(code);
+            }
+            let prompt = format!(
+                "The following code for {} does not parse:\n{}\n\nCode:\n{}\n\nFix the syntax errors without changing behavior. Return ONLY the corrected code, no additional explanation.",
+                path.display(),
+                format_diagnostics(&diagnostics),
+                code
+            );
+            let response = ai_client.generate_response(&prompt, None).await?;
+            code = strip_code_fence(response.content()).to_string();
+        }
+        Ok(code)
+    }
+}
+
+/// Maximum number of times the AI is asked to fix syntax errors in generated code
+const MAX_SYNTAX_REPAIRS: usize = 2;
+
+/// Format syntax diagnostics as one error per line
+fn format_diagnostics(diagnostics: &[SyntaxDiagnostic]) -> String {
+    let lines: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+    format!(
+        "{} syntax error(s)\n{}",
+        diagnostics.len(),
+        lines.join("\n")
+    )
+}
+
+/// Strip a Markdown code fence wrapping the whole response, e.g. ```rust ... ```
+fn strip_code_fence(code: &str) -> &str {
+    let trimmed = code.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return code;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return code;
+    };
+    // The first line holds the optional language tag
+    match body.split_once('\n') {
+        Some((_, body)) => body,
+        None => code,
+    }
 }
 
 /// Code refactoring types
@@ -338,6 +492,7 @@ pub enum RefactorType {
     ExtractFunction,
     InlineFunction,
     ExtractVariable,
+    InlineVariable,
     RenameVariable,
     ExtractClass,
     ExtractInterface,
@@ -350,11 +505,13 @@ pub enum RefactorType {
 
 impl From<&str> for RefactorType {
     fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
+        match s.to_lowercase().replace('-', "_").as_str() {
             "extract_function" | "extract function" => RefactorType::ExtractFunction,
             "inline_function" | "inline function" => RefactorType::InlineFunction,
             "extract_variable" | "extract variable" => RefactorType::ExtractVariable,
-            "rename_variable" | "rename variable" => RefactorType::RenameVariable,
+            "inline_variable" | "inline variable" => RefactorType::InlineVariable,
+            "rename_variable" | "rename variable" | "rename_symbol" | "rename symbol"
+            | "rename" => RefactorType::RenameVariable,
             "extract_class" | "extract class" => RefactorType::ExtractClass,
             "extract_interface" | "extract interface" => RefactorType::ExtractInterface,
             "convert_to_generic" | "convert to generic" => RefactorType::ConvertToGeneric,
@@ -365,3 +522,23 @@ impl From<&str> for RefactorType {
         }
     }
 }
+
+impl std::fmt::Display for RefactorType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let goal = match self {
+            RefactorType::ExtractFunction => "extract a function",
+            RefactorType::InlineFunction => "inline a function",
+            RefactorType::ExtractVariable => "extract a variable",
+            RefactorType::InlineVariable => "inline a variable",
+            RefactorType::RenameVariable => "rename a symbol",
+            RefactorType::ExtractClass => "extract a class",
+            RefactorType::ExtractInterface => "extract an interface",
+            RefactorType::ConvertToGeneric => "convert to generic code",
+            RefactorType::ReplaceMagicNumbers => "replace magic numbers with named constants",
+            RefactorType::SimplifyConditionals => "simplify conditionals",
+            RefactorType::RemoveDuplication => "remove duplication",
+            RefactorType::Other(goal) => goal,
+        };
+        f.write_str(goal)
+    }
+}
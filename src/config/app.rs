@@ -2,7 +2,9 @@
 //!
 //! 定义应用程序的配置结构和默认值
 
+use crate::knowledge::search_result_optimizer::SearchResultOptimizerConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 /// 应用配置结构体
@@ -139,4 +141,44 @@ pub struct KnowledgeConfig {
     pub remote_depth: Option<u32>,
     /// 远程内容存储目录
     pub remote_dir: Option<PathBuf>,
+    /// 索引文件的最大大小（字节），为0时使用默认值
+    pub max_file_size: u64,
+    /// 遍历目录时是否跟随符号链接
+    pub follow_symlinks: bool,
+    /// 并行读取和解析文件的线程数量，为0时使用CPU核心数
+    pub index_workers: usize,
+    /// 语义搜索嵌入模型配置
+    pub embedding: EmbeddingConfig,
+    /// 命名集合配置，键为集合名称
+    pub collections: BTreeMap<String, CollectionConfig>,
+    /// 搜索结果排序配置
+    pub search: SearchResultOptimizerConfig,
+}
+
+/// 知识库集合配置，未设置的字段使用知识库的全局配置
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct CollectionConfig {
+    /// 集合描述
+    pub description: String,
+    /// 未指定路径时添加到集合的默认路径
+    pub paths: Vec<String>,
+    /// 额外排除的文件模式
+    pub exclude_patterns: Vec<String>,
+    /// 支持的文件类型，为空时使用全局配置
+    pub supported_extensions: Vec<String>,
+}
+
+/// 嵌入模型配置
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct EmbeddingConfig {
+    /// 嵌入模型提供者：ollama、local，为空或 none 时不启用语义搜索
+    pub provider: String,
+    /// 模型名称
+    pub model: String,
+    /// API基础URL
+    pub base_url: String,
+    /// 本地嵌入的向量维度
+    pub dimensions: usize,
 }
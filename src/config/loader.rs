@@ -285,6 +285,40 @@ impl ConfigLoader {
             config.knowledge.supported_extensions =
                 value.split(',').map(|s| s.trim().to_string()).collect();
         }
+
+        // 索引文件的最大大小
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_MAX_FILE_SIZE") {
+            if let Ok(size) = value.parse() {
+                config.knowledge.max_file_size = size;
+            }
+        }
+
+        // 是否跟随符号链接
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_FOLLOW_SYMLINKS") {
+            config.knowledge.follow_symlinks = value == "1" || value.eq_ignore_ascii_case("true");
+        }
+
+        // 并行索引的线程数量
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_INDEX_WORKERS") {
+            if let Ok(workers) = value.parse() {
+                config.knowledge.index_workers = workers;
+            }
+        }
+
+        // 嵌入模型提供者
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_EMBEDDING_PROVIDER") {
+            config.knowledge.embedding.provider = value;
+        }
+
+        // 嵌入模型名称
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_EMBEDDING_MODEL") {
+            config.knowledge.embedding.model = value;
+        }
+
+        // 嵌入模型API地址
+        if let Ok(value) = env::var("CODEX_KNOWLEDGE_EMBEDDING_BASE_URL") {
+            config.knowledge.embedding.base_url = value;
+        }
     }
 
     /// 检查是否是首次启动（配置文件不存在）
@@ -360,12 +394,21 @@ impl ConfigLoader {
                     .iter()
                     .map(|s| s.to_string())
                     .collect(),
-                supported_extensions: vec!["rs", "py", "js", "ts", "json", "yaml", "toml", "md"]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect(),
+                supported_extensions: vec![
+                    "rs", "py", "js", "ts", "go", "java", "c", "h", "cpp", "hpp", "json", "yaml",
+                    "toml", "md",
+                ]
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
                 remote_depth: Some(2),
                 remote_dir: None,
+                max_file_size: 1024 * 1024,
+                follow_symlinks: false,
+                index_workers: 0,
+                embedding: super::app::EmbeddingConfig::default(),
+                collections: Default::default(),
+                search: Default::default(),
             },
         }
     }
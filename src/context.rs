@@ -1,6 +1,6 @@
 use chrono::{TimeZone, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::error::Error;
 use std::sync::Arc;
 
@@ -25,6 +25,16 @@ pub struct ContextItem {
     pub token_count: usize,
     /// Tags for categorization
     pub tags: Vec<String>,
+    /// Pinned items are never evicted by compression
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+impl ContextItem {
+    /// Get the source label recorded for this item (e.g. `src/main.rs:10-40`)
+    pub fn source(&self) -> Option<&str> {
+        self.tags.iter().find_map(|tag| tag.strip_prefix("source:"))
+    }
 }
 
 /// Context item type
@@ -169,6 +179,49 @@ impl ContextManager {
         self.add_item(item);
     }
 
+    /// Add the result of a tool call to the context
+    pub fn add_tool_result(&mut self, tool: &str, content: &str) {
+        let item = self.create_context_item(
+            content,
+            ContextItemType::ToolResult,
+            50,
+            vec!["tool".to_string(), tool.to_string()],
+        );
+        self.add_item(item);
+    }
+
+    /// Add a pinned code snippet that compression never evicts, returning its ID
+    pub fn add_pinned_snippet(&mut self, content: &str, language: &str, source: &str) -> String {
+        let mut item = self.create_context_item(
+            content,
+            ContextItemType::CodeSnippet,
+            100,
+            vec![
+                "code".to_string(),
+                language.to_string(),
+                format!("source:{}", source),
+            ],
+        );
+        item.pinned = true;
+        let id = item.id.clone();
+        self.add_item(item);
+        id
+    }
+
+    /// Get all pinned items in chronological order
+    pub fn pinned_items(&self) -> Vec<ContextItem> {
+        self.context
+            .iter()
+            .filter(|item| item.pinned)
+            .cloned()
+            .collect()
+    }
+
+    /// Unpin and remove a pinned item by ID
+    pub fn unpin(&mut self, id: &str) {
+        self.context.retain(|item| !(item.pinned && item.id == id));
+    }
+
     /// Add a knowledge base entry to the context
     pub fn add_knowledge_entry(&mut self, content: &str, source: &str) {
         let item = self.create_context_item(
@@ -201,6 +254,7 @@ impl ContextManager {
             ref_count: 0,
             token_count,
             tags,
+            pinned: false,
         }
     }
 
@@ -233,6 +287,21 @@ impl ContextManager {
             total_tokens, target_tokens
         );
 
+        // Pinned items are always kept, so only the unpinned items compete for
+        // the remaining budget
+        let original = std::mem::take(&mut self.context);
+        let pinned_tokens: usize = original
+            .iter()
+            .filter(|item| item.pinned)
+            .map(|item| item.token_count)
+            .sum();
+        let target_tokens = target_tokens.saturating_sub(pinned_tokens);
+        self.context = original
+            .iter()
+            .filter(|item| !item.pinned)
+            .cloned()
+            .collect();
+
         match self.strategy {
             CompressionStrategy::RecentFirst => {
                 self.compress_recent_first(target_tokens);
@@ -251,6 +320,13 @@ impl ContextManager {
             }
         }
 
+        // Restore pinned items and keep the original chronological order
+        let kept: HashSet<String> = self.context.iter().map(|item| item.id.clone()).collect();
+        self.context = original
+            .into_iter()
+            .filter(|item| item.pinned || kept.contains(&item.id))
+            .collect();
+
         println!("Compressed to {} tokens", self.total_tokens());
     }
 
@@ -407,6 +483,21 @@ impl ContextManager {
         self.context.retain(|item| item.id != id);
     }
 
+    /// Keep only the first `len` items, dropping everything after them
+    pub fn truncate(&mut self, len: usize) {
+        self.context.truncate(len);
+    }
+
+    /// Get the number of items in the context
+    pub fn len(&self) -> usize {
+        self.context.len()
+    }
+
+    /// Check whether the context is empty
+    pub fn is_empty(&self) -> bool {
+        self.context.is_empty()
+    }
+
     /// Update importance of an item
     pub fn update_importance(&mut self, id: &str, importance: u8) {
         for item in self.context.iter_mut() {
@@ -446,7 +537,96 @@ impl ContextManager {
                 serde_json::to_string_pretty(&self.context).map_err(|e| e.into())
             }
             ContextExportFormat::Yaml => serde_yaml::to_string(&self.context).map_err(|e| e.into()),
+            ContextExportFormat::Markdown => Ok(self.render_markdown(DEFAULT_TRANSCRIPT_TITLE)),
+            ContextExportFormat::Html => Ok(self.render_html(DEFAULT_TRANSCRIPT_TITLE)),
+        }
+    }
+
+    /// Render the conversation as a Markdown transcript
+    pub fn render_markdown(&self, title: &str) -> String {
+        let mut output = format!("# {}\n\n", title);
+
+        for item in &self.context {
+            output.push_str(&format!(
+                "## {} · {}\n\n",
+                transcript_role(item),
+                format_timestamp(item.created_at)
+            ));
+
+            for segment in transcript_segments(item) {
+                match segment {
+                    TranscriptSegment::Text(text) => {
+                        output.push_str(text.trim());
+                        output.push_str("\n\n");
+                    }
+                    TranscriptSegment::Code { language, code } => {
+                        let fence = code_fence(&code);
+                        output.push_str(&format!("{}{}\n{}\n{}\n\n", fence, language, code, fence));
+                    }
+                }
+            }
+
+            output.push_str(&format!("*{} tokens*\n\n", item.token_count));
+        }
+
+        output.push_str(&format!(
+            "---\n\n**Total:** {} messages, {} tokens\n",
+            self.context.len(),
+            self.total_tokens()
+        ));
+        output
+    }
+
+    /// Render the conversation as a standalone HTML page
+    pub fn render_html(&self, title: &str) -> String {
+        let mut output = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            escape_html(title),
+            TRANSCRIPT_CSS,
+            escape_html(title)
+        );
+
+        for item in &self.context {
+            let role: &str = item.item_type.into();
+            output.push_str(&format!(
+                "<section class=\"message {}\">\n<header><strong>{}</strong> <time>{}</time></header>\n",
+                role,
+                escape_html(&transcript_role(item)),
+                format_timestamp(item.created_at)
+            ));
+
+            for segment in transcript_segments(item) {
+                match segment {
+                    TranscriptSegment::Text(text) => {
+                        for paragraph in text.split("\n\n").filter(|p| !p.trim().is_empty()) {
+                            output.push_str(&format!(
+                                "<p>{}</p>\n",
+                                escape_html(paragraph.trim()).replace('\n', "<br>\n")
+                            ));
+                        }
+                    }
+                    TranscriptSegment::Code { language, code } => {
+                        output.push_str(&format!(
+                            "<pre><code class=\"language-{}\">{}</code></pre>\n",
+                            escape_html(&language),
+                            escape_html(&code)
+                        ));
+                    }
+                }
+            }
+
+            output.push_str(&format!(
+                "<footer>{} tokens</footer>\n</section>\n",
+                item.token_count
+            ));
         }
+
+        output.push_str(&format!(
+            "<p class=\"total\">Total: {} messages, {} tokens</p>\n</body>\n</html>\n",
+            self.context.len(),
+            self.total_tokens()
+        ));
+        output
     }
 
     /// Import context from a string
@@ -472,6 +652,9 @@ impl ContextManager {
                 // Text format import not implemented yet
                 Err("Text format import not implemented".into())
             }
+            ContextExportFormat::Markdown | ContextExportFormat::Html => {
+                Err("Markdown and HTML transcripts cannot be imported".into())
+            }
         }
     }
 
@@ -508,6 +691,166 @@ pub enum ContextExportFormat {
     Text,
     Json,
     Yaml,
+    Markdown,
+    Html,
+}
+
+impl ContextExportFormat {
+    /// Get the file extension for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ContextExportFormat::Text => "txt",
+            ContextExportFormat::Json => "json",
+            ContextExportFormat::Yaml => "yaml",
+            ContextExportFormat::Markdown => "md",
+            ContextExportFormat::Html => "html",
+        }
+    }
+}
+
+impl std::str::FromStr for ContextExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(ContextExportFormat::Text),
+            "json" => Ok(ContextExportFormat::Json),
+            "yaml" | "yml" => Ok(ContextExportFormat::Yaml),
+            "markdown" | "md" => Ok(ContextExportFormat::Markdown),
+            "html" | "htm" => Ok(ContextExportFormat::Html),
+            _ => Err(format!("Unsupported export format: {}", s)),
+        }
+    }
+}
+
+/// Title used for transcripts exported without a session title
+const DEFAULT_TRANSCRIPT_TITLE: &str = "Conversation transcript";
+
+/// Inline stylesheet for standalone HTML transcripts
+const TRANSCRIPT_CSS: &str =
+    "body{font-family:sans-serif;max-width:860px;margin:2em auto;padding:0 1em;color:#222}\
+section{border:1px solid #ddd;border-radius:6px;margin:1em 0;padding:.5em 1em}\
+section.user{background:#f3f7ff}section.ai{background:#f8f8f8}section.tool{background:#fffbea}\
+header time,footer{color:#888;font-size:.85em}\
+pre{background:#272822;color:#f8f8f2;padding:.75em;overflow-x:auto;border-radius:4px}\
+.total{font-weight:bold}";
+
+/// A piece of message content, either prose or a fenced code block
+enum TranscriptSegment {
+    Text(String),
+    Code { language: String, code: String },
+}
+
+/// Get the display role of a context item
+fn transcript_role(item: &ContextItem) -> String {
+    match item.item_type {
+        ContextItemType::UserMessage => "User".to_string(),
+        ContextItemType::AIMessage => "Assistant".to_string(),
+        ContextItemType::SystemPrompt => "System".to_string(),
+        ContextItemType::ToolResult => match item.tags.get(1) {
+            Some(tool) => format!("Tool call: {}", tool),
+            None => "Tool call".to_string(),
+        },
+        ContextItemType::CodeSnippet => match item.source() {
+            Some(source) => format!("Code: {}", source),
+            None => "Code".to_string(),
+        },
+        ContextItemType::KnowledgeBaseEntry => "Knowledge base".to_string(),
+        ContextItemType::Other => "Other".to_string(),
+    }
+}
+
+/// Split a context item into prose and code segments
+fn transcript_segments(item: &ContextItem) -> Vec<TranscriptSegment> {
+    let has_fence = item
+        .content
+        .lines()
+        .any(|line| line.trim_start().starts_with("```"));
+    match item.item_type {
+        // Bare code snippets are wrapped using the language recorded in their tags
+        ContextItemType::CodeSnippet if !has_fence => vec![TranscriptSegment::Code {
+            language: item.tags.get(1).cloned().unwrap_or_default(),
+            code: item.content.trim_end().to_string(),
+        }],
+        ContextItemType::ToolResult if !has_fence => vec![TranscriptSegment::Code {
+            language: "text".to_string(),
+            code: item.content.trim_end().to_string(),
+        }],
+        _ => split_fenced_blocks(&item.content),
+    }
+}
+
+/// Split Markdown content into prose and fenced code blocks
+fn split_fenced_blocks(content: &str) -> Vec<TranscriptSegment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut code: Option<(usize, String, String)> = None;
+
+    for line in content.lines() {
+        let trimmed = line.trim_start();
+        let ticks = trimmed.len() - trimmed.trim_start_matches('`').len();
+        match code.take() {
+            // A closing fence is at least as long as the opening one and has no info string
+            Some((fence, language, body))
+                if ticks >= fence && trimmed[ticks..].trim().is_empty() =>
+            {
+                segments.push(TranscriptSegment::Code {
+                    language,
+                    code: body.trim_end_matches('\n').to_string(),
+                });
+            }
+            Some((fence, language, mut body)) => {
+                body.push_str(line);
+                body.push('\n');
+                code = Some((fence, language, body));
+            }
+            None if ticks >= 3 => {
+                if !text.trim().is_empty() {
+                    segments.push(TranscriptSegment::Text(std::mem::take(&mut text)));
+                }
+                let language = trimmed[ticks..].trim().to_string();
+                code = Some((ticks, language, String::new()));
+            }
+            None => {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+
+    // An unterminated fence keeps its content as code
+    if let Some((_, language, body)) = code {
+        segments.push(TranscriptSegment::Code {
+            language,
+            code: body.trim_end_matches('\n').to_string(),
+        });
+    }
+    if !text.trim().is_empty() {
+        segments.push(TranscriptSegment::Text(text));
+    }
+    segments
+}
+
+/// Choose a code fence longer than any backtick run in the code
+fn code_fence(code: &str) -> String {
+    let longest = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+/// Format a Unix timestamp for transcripts
+fn format_timestamp(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default()
+}
+
+/// Escape text for inclusion in HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Context summary structure
@@ -590,6 +933,8 @@ pub struct ContextCollector {
     code_depth: u32,
     /// 相关信息收集深度
     info_depth: u32,
+    /// 只从这些集合中检索相关代码，为空时检索整个知识库
+    collections: Option<crate::knowledge::collection::CollectionFilter>,
 }
 
 impl ContextCollector {
@@ -599,6 +944,7 @@ impl ContextCollector {
             knowledge_base: None,
             code_depth: 2,
             info_depth: 1,
+            collections: None,
         }
     }
 
@@ -623,14 +969,149 @@ impl ContextCollector {
         self
     }
 
+    /// 限定检索相关代码的集合
+    pub fn with_collections(
+        mut self,
+        filter: crate::knowledge::collection::CollectionFilter,
+    ) -> Self {
+        self.collections = Some(filter);
+        self
+    }
+
     /// 基于查询收集相关代码
+    ///
+    /// 在知识库（或指定集合）中全文检索，每个命中的片段生成一个代码片段，
+    /// 片段内容优先从文件中按行范围读取，读取失败时使用检索摘要
     pub fn collect_related_code(&self, query: &str) -> Vec<ContextItem> {
-        // TODO: 主人~ 这里需要实现基于查询的相关代码收集逻辑
-        // 1. 使用知识库搜索相关代码
-        // 2. 提取相关代码元素
-        // 3. 为每个代码元素创建ContextItem
-        // 4. 返回相关代码的ContextItem列表
-        Vec::new()
+        let Some(kb) = self.knowledge_base.as_ref() else {
+            return Vec::new();
+        };
+
+        let limit = self.code_depth.max(1) as usize * 5;
+        let hits = match &self.collections {
+            Some(filter) => kb.search_documents_in(query, limit, filter),
+            None => kb.search_documents(query, limit),
+        };
+        let hits = match hits {
+            Ok(hits) => hits,
+            Err(e) => {
+                log::warn!("检索相关代码失败: {}", e);
+                return Vec::new();
+            }
+        };
+
+        let mut items = Vec::new();
+        for hit in hits {
+            let mut tags = vec!["code".to_string(), format!("collection:{}", hit.collection)];
+            let (content, source) = match hit.lines {
+                Some((start, end)) => {
+                    let content = std::fs::read_to_string(&hit.path)
+                        .ok()
+                        .map(|text| {
+                            text.lines()
+                                .skip(start.saturating_sub(1))
+                                .take(end + 1 - start.max(1))
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        })
+                        .filter(|text| !text.is_empty())
+                        .unwrap_or_else(|| hit.snippet.clone());
+                    (content, format!("source:{}:{}-{}", hit.path, start, end))
+                }
+                None => (hit.snippet.clone(), format!("source:{}", hit.path)),
+            };
+            if content.trim().is_empty() {
+                continue;
+            }
+            tags.push(source);
+            let content = match hit.lines {
+                Some(lines) if hit.source == crate::knowledge::base::DocumentSource::File => {
+                    attach_last_commit(&hit.path, lines, content, &mut tags)
+                }
+                _ => content,
+            };
+
+            let now = chrono::Utc::now().timestamp();
+            items.push(ContextItem {
+                id: format!("{}-{}", now, uuid::Uuid::new_v4()),
+                token_count: DefaultTokenizer.count_tokens(&content),
+                content,
+                item_type: ContextItemType::CodeSnippet,
+                importance: 60,
+                created_at: now,
+                last_accessed: now,
+                ref_count: 0,
+                tags,
+                pinned: false,
+            });
+        }
+        items
+    }
+
+    /// 收集调用指定函数的代码，用于修改函数时让AI了解其调用方
+    ///
+    /// 每个调用者生成一个代码片段：调用发生在函数内时取整个函数体，顶层调用取调用行附近的代码
+    pub fn collect_callers(&self, functions: &[&str]) -> Vec<ContextItem> {
+        let Some(xref) = self.knowledge_base.as_ref().and_then(|kb| kb.xref()) else {
+            return Vec::new();
+        };
+
+        let mut seen = std::collections::HashSet::new();
+        let mut items = Vec::new();
+        for function in functions {
+            let Ok(callers) = xref.callers(function) else {
+                continue;
+            };
+            for location in callers {
+                let reference = &location.item;
+                // 调用者所在函数的定义范围，找不到时取调用行前后各3行
+                let span = reference
+                    .caller
+                    .as_deref()
+                    .and_then(|caller| {
+                        xref.definitions(caller).ok()?.into_iter().find(|def| {
+                            def.path == location.path
+                                && def.item.line <= reference.line
+                                && reference.line <= def.item.end_line
+                        })
+                    })
+                    .map(|def| (def.item.line, def.item.end_line))
+                    .unwrap_or((reference.line.saturating_sub(3).max(1), reference.line + 3));
+                if !seen.insert((location.path.clone(), span)) {
+                    continue;
+                }
+
+                let Ok(content) = std::fs::read_to_string(&location.path) else {
+                    continue;
+                };
+                let snippet = content
+                    .lines()
+                    .skip(span.0 - 1)
+                    .take(span.1 + 1 - span.0)
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let mut tags = vec![
+                    "code".to_string(),
+                    format!("caller:{}", function),
+                    format!("source:{}:{}-{}", location.path, span.0, span.1),
+                ];
+                let snippet = attach_last_commit(&location.path, span, snippet, &mut tags);
+                let now = chrono::Utc::now().timestamp();
+                items.push(ContextItem {
+                    id: format!("{}-{}", now, uuid::Uuid::new_v4()),
+                    token_count: DefaultTokenizer.count_tokens(&snippet),
+                    content: snippet,
+                    item_type: ContextItemType::CodeSnippet,
+                    importance: 70,
+                    created_at: now,
+                    last_accessed: now,
+                    ref_count: 0,
+                    tags,
+                    pinned: false,
+                });
+            }
+        }
+        items
     }
 
     /// 基于任务收集相关信息
@@ -674,6 +1155,22 @@ impl ContextCollector {
     }
 }
 
+/// 在代码片段后附加这些行最近一次修改的提交说明，文件不在 Git 仓库中时保持原样
+fn attach_last_commit(
+    path: &str,
+    (start, end): (usize, usize),
+    content: String,
+    tags: &mut Vec<String>,
+) -> String {
+    match crate::knowledge::git::blame_lines(std::path::Path::new(path), start, end) {
+        Some(commit) => {
+            tags.push(format!("commit:{}", commit.short_hash()));
+            format!("{}\n\n{}", content, commit.describe())
+        }
+        None => content,
+    }
+}
+
 /// Context manager builder for easy configuration
 pub struct ContextManagerBuilder {
     max_tokens: usize,
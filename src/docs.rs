@@ -1,10 +1,10 @@
+use crate::knowledge::walker::{read_text_file, ProjectWalker, DEFAULT_MAX_FILE_SIZE};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 /// Documentation generation format
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -137,14 +137,10 @@ impl DocsGenerator {
     fn generate_dir_docs(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
         println!("Generating documentation for directory: {}", path.display());
 
-        // Walk directory and generate docs for each file
-        for entry in WalkDir::new(path) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                let file_path = entry.path();
-                if self.should_process_file(file_path) {
-                    self.generate_file_docs(file_path)?;
-                }
+        // Walk directory (honoring ignore files) and generate docs for each file
+        for file_path in ProjectWalker::new().walk(path)? {
+            if self.should_process_file(&file_path) {
+                self.generate_file_docs(&file_path)?;
             }
         }
 
@@ -158,8 +154,11 @@ impl DocsGenerator {
     fn generate_file_docs(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
         println!("Generating documentation for file: {}", path.display());
 
-        // Read file content
-        let content = fs::read_to_string(path)?;
+        // Read file content, skipping binary and oversized files
+        let Some(content) = read_text_file(path, DEFAULT_MAX_FILE_SIZE)? else {
+            println!("Skipping binary or oversized file: {}", path.display());
+            return Ok(());
+        };
 
         // Determine file type
         let file_type = self.get_file_type(path);
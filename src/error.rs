@@ -324,6 +324,30 @@ impl From<walkdir::Error> for AppError {
     }
 }
 
+/// 从tantivy::TantivyError转换为AppError
+impl From<tantivy::TantivyError> for AppError {
+    fn from(err: tantivy::TantivyError) -> Self {
+        AppError::Knowledge {
+            operation: "index".to_string(),
+            description: err.to_string(),
+            index_path: None,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
+/// 从sled::Error转换为AppError
+impl From<sled::Error> for AppError {
+    fn from(err: sled::Error) -> Self {
+        AppError::Knowledge {
+            operation: "metadata".to_string(),
+            description: err.to_string(),
+            index_path: None,
+            source: Some(Box::new(err)),
+        }
+    }
+}
+
 /// 从reqwest::Error转换为AppError
 impl From<reqwest::Error> for AppError {
     fn from(err: reqwest::Error) -> Self {
@@ -3,7 +3,12 @@
 //! 定义知识库的基本结构和操作
 
 use crate::error::AppResult;
-use crate::parsers::{CodeElement, CodeElementType};
+use crate::knowledge::collection::{default_collection, CollectionActions, CollectionFilter};
+use crate::knowledge::query::HighlightedLine;
+use crate::knowledge::xref::XrefIndex;
+use crate::parsers::CodeElement;
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -22,6 +27,242 @@ pub struct CodeFile {
     pub modified_at: u64,
 }
 
+/// 知识库文档来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentSource {
+    /// 本地文件
+    File,
+    /// 抓取的网页
+    Web,
+    /// Git 提交
+    Git,
+}
+
+impl DocumentSource {
+    /// 获取来源名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentSource::File => "file",
+            DocumentSource::Web => "web",
+            DocumentSource::Git => "git",
+        }
+    }
+}
+
+/// 知识库文档元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentMeta {
+    /// 文件路径或网页URL
+    pub path: String,
+    /// 标题（文件名或网页标题）
+    pub title: String,
+    /// 内容语言
+    pub language: String,
+    /// 文档来源
+    pub source: DocumentSource,
+    /// 内容大小（字节）
+    pub size: u64,
+    /// 内容行数
+    pub lines: usize,
+    /// 索引时间（Unix秒），Git 提交为提交时间
+    pub indexed_at: i64,
+    /// 所属集合
+    #[serde(default = "default_collection")]
+    pub collection: String,
+}
+
+/// 知识库文档搜索结果
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// 文件路径或网页URL
+    pub path: String,
+    /// 标题
+    pub title: String,
+    /// 内容语言
+    pub language: String,
+    /// 文档来源
+    pub source: DocumentSource,
+    /// 匹配内容片段
+    pub snippet: String,
+    /// 匹配片段所在的行范围
+    pub lines: Option<(usize, usize)>,
+    /// 片段中高亮的匹配行
+    pub highlights: Vec<HighlightedLine>,
+    /// 所属集合
+    pub collection: String,
+    /// 相关性评分
+    pub score: f32,
+    /// 排序依据说明，由搜索结果优化器生成
+    pub explanation: String,
+}
+
+/// 知识库操作枚举
+#[derive(Debug, Clone, Subcommand)]
+pub enum KnowledgeActions {
+    /// 添加文件或目录到知识库
+    Add {
+        /// 文件或目录路径
+        paths: Vec<String>,
+
+        /// 递归添加子目录中的文件
+        #[arg(long, short)]
+        recursive: bool,
+
+        /// 添加到指定集合，未指定路径时使用集合配置中的路径
+        #[arg(long, short)]
+        collection: Option<String>,
+
+        /// 并行读取和解析文件的线程数量，默认使用配置或CPU核心数
+        #[arg(long, short)]
+        jobs: Option<usize>,
+
+        /// 不显示索引进度和耗时
+        #[arg(long, short)]
+        quiet: bool,
+    },
+
+    /// 索引 Git 提交历史（提交说明、作者、日期和差异），从上次索引的提交继续
+    AddGit {
+        /// 仓库目录，默认为当前目录
+        path: Option<PathBuf>,
+
+        /// 最多索引的提交数量，从最新的提交开始
+        #[arg(long, short = 'n')]
+        max_count: Option<usize>,
+
+        /// 忽略上次索引的位置，重新索引全部历史
+        #[arg(long)]
+        full: bool,
+
+        /// 添加到指定集合，默认为 git
+        #[arg(long, short)]
+        collection: Option<String>,
+    },
+
+    /// 查看代码最近一次修改的提交
+    Blame {
+        /// 文件路径
+        file: PathBuf,
+
+        /// 行范围，如 42 或 10-20，默认为整个文件
+        #[arg(long, short = 'L')]
+        lines: Option<String>,
+    },
+
+    /// 搜索知识库
+    Search {
+        /// 搜索查询，支持 lang:、path:、kind:、name:、after:、before: 过滤条件，
+        /// "短语"、/正则/ 和 模糊词~
+        query: String,
+
+        /// 返回结果数量
+        #[arg(long, short, default_value_t = 10)]
+        limit: usize,
+
+        /// 结合语义检索，按关键词和向量相似度的融合排名排序
+        #[arg(long)]
+        semantic: bool,
+
+        /// 只搜索指定集合，逗号分隔，支持通配符（如 repo,docs:*）
+        #[arg(long = "in", value_name = "COLLECTIONS")]
+        collections: Option<String>,
+
+        /// 显示每个结果的排序依据
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// 列出知识库中的文档
+    List {
+        /// 显示详细信息
+        #[arg(long, short)]
+        details: bool,
+
+        /// 只列出指定集合的文档，逗号分隔，支持通配符
+        #[arg(long = "in", value_name = "COLLECTIONS")]
+        collections: Option<String>,
+    },
+
+    /// 管理知识库集合
+    Collection {
+        #[command(subcommand)]
+        action: CollectionActions,
+    },
+
+    /// 显示知识库统计信息：各语言和集合的文档数、各类型的符号数、索引大小和更新时间
+    Stats,
+
+    /// 检查索引一致性：已删除的文件、孤立文档和符号、索引缓存不一致
+    Verify,
+
+    /// 检查并修复索引一致性问题
+    Repair {
+        /// 修复后合并索引段
+        #[arg(long)]
+        compact: bool,
+    },
+
+    /// 合并索引段并清理不再使用的文件
+    Compact,
+
+    /// 清空知识库
+    Clear {
+        /// 确认清空，不再询问
+        #[arg(long, short = 'y')]
+        confirm: bool,
+    },
+
+    /// 从旧版知识库（.codex_kb）导入数据
+    Migrate {
+        /// 旧版知识库所在目录，默认为当前目录
+        #[arg(long)]
+        from: Option<PathBuf>,
+    },
+
+    /// 导出知识库为可共享的归档文件
+    Export {
+        /// 归档文件路径
+        file: PathBuf,
+
+        /// 项目根目录，其下的文件路径以相对路径保存，默认为当前目录
+        #[arg(long)]
+        root: Option<PathBuf>,
+    },
+
+    /// 从归档文件导入知识库
+    Import {
+        /// 归档文件路径
+        file: PathBuf,
+
+        /// 项目根目录，归档中的相对路径以此还原，默认为当前目录
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// 清空现有知识库后导入，默认合并到现有知识库
+        #[arg(long)]
+        replace: bool,
+    },
+
+    /// 监听目录变化并增量更新索引
+    Watch {
+        /// 监听的目录，默认为当前目录
+        path: Option<PathBuf>,
+
+        /// 去抖时间（毫秒）
+        #[arg(long, default_value_t = crate::knowledge::watcher::DEFAULT_DEBOUNCE_MS)]
+        debounce: u64,
+
+        /// 作为后台服务运行
+        #[arg(long, conflicts_with = "stop")]
+        background: bool,
+
+        /// 停止后台监听服务
+        #[arg(long)]
+        stop: bool,
+    },
+}
+
 /// 知识库基础接口
 pub trait KnowledgeBase {
     /// 初始化知识库
@@ -34,11 +275,35 @@ pub trait KnowledgeBase {
     fn remove_file(&mut self, path: &PathBuf) -> AppResult<()>;
 
     /// 搜索代码元素
-    fn search(&mut self, query: &str) -> AppResult<Vec<Arc<CodeElement>>>;
+    fn search(&self, query: &str) -> AppResult<Vec<Arc<CodeElement>>>;
+
+    /// 搜索文档内容，默认不支持全文搜索
+    fn search_documents(&self, _query: &str, _limit: usize) -> AppResult<Vec<SearchHit>> {
+        Ok(Vec::new())
+    }
+
+    /// 在指定集合中搜索文档内容，默认过滤全局搜索的结果
+    fn search_documents_in(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &CollectionFilter,
+    ) -> AppResult<Vec<SearchHit>> {
+        Ok(self
+            .search_documents(query, limit)?
+            .into_iter()
+            .filter(|hit| filter.matches(&hit.collection))
+            .collect())
+    }
 
     /// 获取文件列表
     fn list_files(&self) -> AppResult<Vec<PathBuf>>;
 
     /// 清除知识库
     fn clear(&mut self) -> AppResult<()>;
+
+    /// 获取交叉引用索引，默认不支持
+    fn xref(&self) -> Option<&XrefIndex> {
+        None
+    }
 }
@@ -0,0 +1,343 @@
+//! 知识库归档
+//!
+//! 将知识库导出为带版本号的 tar.gz 归档，包含文档内容和元数据（含抓取的网页）、
+//! 索引缓存中的代码元素、交叉引用以及嵌入向量。导出时项目根目录下的文件路径改写为
+//! 相对路径，导入时以目标项目根目录还原，使索引可以在不同机器之间共享
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::{DocumentMeta, DocumentSource};
+use crate::knowledge::indexer::IndexCache;
+use crate::knowledge::vector_index::VectorEntry;
+use crate::knowledge::xref::FileXrefs;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Component, Path};
+
+/// 当前的归档格式版本
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// 归档清单文件名
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// 文档文件名，每行一个文档
+const DOCUMENTS_FILE: &str = "documents.jsonl";
+
+/// 索引缓存文件名
+const INDEX_CACHE_FILE: &str = "index_cache.json";
+
+/// 交叉引用文件名，每行一个文件
+const XREFS_FILE: &str = "xrefs.jsonl";
+
+/// 嵌入向量文件名
+const VECTORS_FILE: &str = "vectors.json";
+
+/// 归档清单
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// 归档格式版本
+    pub format_version: u32,
+    /// 创建归档的 codex 版本
+    pub codex_version: String,
+    /// 创建时间（Unix秒）
+    pub created_at: i64,
+    /// 文档数量
+    pub documents: usize,
+    /// 代码元素数量
+    pub symbols: usize,
+    /// 片段向量数量
+    pub vectors: usize,
+    /// 生成向量的嵌入模型标识，没有向量时为空
+    pub embedding_provider: Option<String>,
+}
+
+/// 归档中的文档
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleDocument {
+    /// 文档元数据
+    pub meta: DocumentMeta,
+    /// 文档内容
+    pub content: String,
+}
+
+/// 归档中的嵌入向量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleVectors {
+    /// 嵌入模型标识
+    pub provider: String,
+    /// 片段向量
+    pub entries: Vec<VectorEntry>,
+}
+
+/// 导入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// 合并到现有知识库，同路径的文档被覆盖
+    Merge,
+    /// 清空现有知识库后导入
+    Replace,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default)]
+pub struct ImportSummary {
+    /// 导入的文档数量
+    pub documents: usize,
+    /// 导入的代码元素数量
+    pub symbols: usize,
+    /// 直接导入的片段向量数量
+    pub vectors: usize,
+    /// 使用当前嵌入模型重新计算向量的文档数量
+    pub reembedded: usize,
+}
+
+/// 知识库归档
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    /// 归档清单
+    pub manifest: BundleManifest,
+    /// 文档
+    pub documents: Vec<BundleDocument>,
+    /// 索引缓存
+    pub index_cache: IndexCache,
+    /// 交叉引用
+    pub xrefs: Vec<FileXrefs>,
+    /// 嵌入向量
+    pub vectors: Option<BundleVectors>,
+}
+
+impl Bundle {
+    /// 由知识库数据创建归档，自动生成清单
+    pub fn new(
+        documents: Vec<BundleDocument>,
+        index_cache: IndexCache,
+        xrefs: Vec<FileXrefs>,
+        vectors: Option<BundleVectors>,
+    ) -> Self {
+        let vectors = vectors.filter(|vectors| !vectors.entries.is_empty());
+        let manifest = BundleManifest {
+            format_version: BUNDLE_FORMAT_VERSION,
+            codex_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now().timestamp(),
+            documents: documents.len(),
+            symbols: index_cache.total_elements,
+            vectors: vectors.as_ref().map_or(0, |vectors| vectors.entries.len()),
+            embedding_provider: vectors.as_ref().map(|vectors| vectors.provider.clone()),
+        };
+        Self {
+            manifest,
+            documents,
+            index_cache,
+            xrefs,
+            vectors,
+        }
+    }
+
+    /// 将项目根目录下的文件路径改写为相对路径
+    pub fn relativize(&mut self, root: &Path) {
+        self.rewrite_paths(|path| relativize_path(path, root));
+    }
+
+    /// 以项目根目录还原相对路径
+    pub fn absolutize(&mut self, root: &Path) {
+        self.rewrite_paths(|path| absolutize_path(path, root));
+    }
+
+    /// 改写归档中所有文件路径，网页URL保持不变
+    fn rewrite_paths(&mut self, rewrite: impl Fn(&str) -> String) {
+        for document in &mut self.documents {
+            if document.meta.source == DocumentSource::File {
+                document.meta.path = rewrite(&document.meta.path);
+            }
+        }
+
+        let files = std::mem::take(&mut self.index_cache.files);
+        self.index_cache.files = files
+            .into_iter()
+            .map(|(key, mut item)| {
+                item.path = rewrite(&item.path.to_string_lossy()).into();
+                for element in &mut item.elements {
+                    element.definition.file_path = rewrite(&element.definition.file_path);
+                }
+                (rewrite(&key), item)
+            })
+            .collect();
+
+        for xrefs in &mut self.xrefs {
+            xrefs.path = rewrite(&xrefs.path);
+        }
+        if let Some(vectors) = &mut self.vectors {
+            for entry in &mut vectors.entries {
+                if !is_url(&entry.path) {
+                    entry.path = rewrite(&entry.path);
+                }
+            }
+        }
+    }
+
+    /// 写入归档文件
+    pub fn write(&self, file: &Path) -> AppResult<()> {
+        let encoder = GzEncoder::new(File::create(file)?, Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+
+        append_entry(
+            &mut archive,
+            MANIFEST_FILE,
+            &serde_json::to_vec_pretty(&self.manifest)?,
+        )?;
+        append_entry(&mut archive, DOCUMENTS_FILE, &json_lines(&self.documents)?)?;
+        append_entry(
+            &mut archive,
+            INDEX_CACHE_FILE,
+            &serde_json::to_vec(&self.index_cache)?,
+        )?;
+        append_entry(&mut archive, XREFS_FILE, &json_lines(&self.xrefs)?)?;
+        if let Some(vectors) = &self.vectors {
+            append_entry(&mut archive, VECTORS_FILE, &serde_json::to_vec(vectors)?)?;
+        }
+
+        archive.into_inner()?.finish()?;
+        Ok(())
+    }
+
+    /// 读取归档文件并校验格式版本
+    pub fn read(file: &Path) -> AppResult<Self> {
+        let mut archive = tar::Archive::new(GzDecoder::new(File::open(file)?));
+        let mut manifest = None;
+        let mut documents = Vec::new();
+        let mut index_cache = None;
+        let mut xrefs = Vec::new();
+        let mut vectors = None;
+
+        let entries = archive
+            .entries()
+            .map_err(|e| AppError::knowledge(&format!("无法读取知识库归档: {}", e)))?;
+        for entry in entries {
+            let mut entry = entry?;
+            let name = entry.path()?.to_string_lossy().to_string();
+            // 清单最先写入，读取其他文件前必须已通过版本校验
+            if manifest.is_none() && name != MANIFEST_FILE {
+                return Err(AppError::knowledge(
+                    "不是有效的知识库归档：缺少 manifest.json",
+                ));
+            }
+            match name.as_str() {
+                MANIFEST_FILE => {
+                    let value: BundleManifest = serde_json::from_reader(&mut entry)?;
+                    check_format_version(value.format_version)?;
+                    manifest = Some(value);
+                }
+                DOCUMENTS_FILE => documents = read_json_lines(&mut entry)?,
+                INDEX_CACHE_FILE => {
+                    let cache: IndexCache = serde_json::from_reader(&mut entry)?;
+                    let expected = IndexCache::default().version;
+                    if cache.version != expected {
+                        return Err(AppError::knowledge(&format!(
+                            "归档中的索引缓存版本 {} 与当前版本 {} 不兼容",
+                            cache.version, expected
+                        )));
+                    }
+                    index_cache = Some(cache);
+                }
+                XREFS_FILE => xrefs = read_json_lines(&mut entry)?,
+                VECTORS_FILE => vectors = Some(serde_json::from_reader(&mut entry)?),
+                _ => log::warn!("忽略知识库归档中的未知文件: {}", name),
+            }
+        }
+
+        let manifest = manifest
+            .ok_or_else(|| AppError::knowledge("不是有效的知识库归档：缺少 manifest.json"))?;
+        Ok(Self {
+            manifest,
+            documents,
+            index_cache: index_cache.unwrap_or_default(),
+            xrefs,
+            vectors,
+        })
+    }
+}
+
+/// 检查归档格式版本是否受支持
+fn check_format_version(version: u32) -> AppResult<()> {
+    if version == 0 || version > BUNDLE_FORMAT_VERSION {
+        return Err(AppError::knowledge(&format!(
+            "不支持的知识库归档版本 {}（当前支持 {}），请升级 codex 后再导入",
+            version, BUNDLE_FORMAT_VERSION
+        )));
+    }
+    Ok(())
+}
+
+/// 向归档追加一个文件
+fn append_entry<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> AppResult<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// 序列化为每行一个对象的 JSON
+fn json_lines<T: Serialize>(items: &[T]) -> AppResult<Vec<u8>> {
+    let mut data = Vec::new();
+    for item in items {
+        serde_json::to_writer(&mut data, item)?;
+        data.push(b'\n');
+    }
+    Ok(data)
+}
+
+/// 读取每行一个对象的 JSON
+fn read_json_lines<T: for<'de> Deserialize<'de>>(reader: impl Read) -> AppResult<Vec<T>> {
+    let mut items = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            items.push(serde_json::from_str(&line)?);
+        }
+    }
+    Ok(items)
+}
+
+/// 是否为网页URL
+fn is_url(path: &str) -> bool {
+    path.contains("://")
+}
+
+/// 将项目根目录下的绝对路径改写为以 `/` 分隔的相对路径，其他路径保持不变
+pub fn relativize_path(path: &str, root: &Path) -> String {
+    match Path::new(path).strip_prefix(root) {
+        Ok(relative) if !is_url(path) => relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        _ => path.to_string(),
+    }
+}
+
+/// 以项目根目录还原相对路径，绝对路径和网页URL保持不变
+pub fn absolutize_path(path: &str, root: &Path) -> String {
+    let relative = Path::new(path);
+    if is_url(path) || relative.is_absolute() {
+        return path.to_string();
+    }
+    // 拒绝跳出项目根目录的路径
+    if relative
+        .components()
+        .any(|component| matches!(component, Component::ParentDir))
+    {
+        log::warn!("归档中的路径跳出了项目根目录，保持原样: {}", path);
+        return path.to_string();
+    }
+    root.join(relative).to_string_lossy().to_string()
+}
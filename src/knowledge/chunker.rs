@@ -0,0 +1,133 @@
+//! 文档分段
+//!
+//! 将文件切分为带行号范围的片段，供全文索引和向量索引使用。有解析器的语言
+//! 沿函数、impl、类等代码元素的边界切分，过长的元素在内部语句处切分并保留重叠；
+//! 其他文件按固定行数切分
+
+use crate::parsers::PARSER_REGISTRY;
+use tree_sitter::{Node as TsNode, Parser as TsParser};
+
+/// 每个片段的最大行数
+pub const CHUNK_LINES: usize = 40;
+
+/// 在元素内部切分时相邻片段重叠的行数
+pub const CHUNK_OVERLAP: usize = 10;
+
+/// 文本片段
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextChunk {
+    /// 起始行（从1开始）
+    pub start_line: usize,
+    /// 结束行（包含）
+    pub end_line: usize,
+    /// 片段内容
+    pub text: String,
+}
+
+/// 可切分的位置：某个代码元素的起始行
+#[derive(Debug, Clone, Copy)]
+struct Cut {
+    /// 起始行（从0开始）
+    row: usize,
+    /// 元素的嵌套深度，顶层元素为0
+    depth: usize,
+}
+
+/// 按代码元素边界切分文档，没有解析器的文件按行切分
+pub fn chunk_document(path: &str, content: &str) -> Vec<TextChunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= CHUNK_LINES {
+        return split_at_cuts(&lines, &[]);
+    }
+    let cuts = element_cuts(path, content).unwrap_or_default();
+    split_at_cuts(&lines, &cuts)
+}
+
+/// 解析文件并收集所有代码元素的起始行，每行只保留最浅的深度
+fn element_cuts(path: &str, content: &str) -> Option<Vec<Cut>> {
+    let parser = PARSER_REGISTRY
+        .read()
+        .unwrap()
+        .get_parser_by_filename(path)?;
+    let mut ts_parser = TsParser::new();
+    ts_parser.set_language(parser.tree_sitter_language()).ok()?;
+    let tree = ts_parser.parse(content, None)?;
+
+    let mut cuts = Vec::new();
+    collect_cuts(tree.root_node(), 0, &mut cuts);
+    cuts.sort_by_key(|cut| (cut.row, cut.depth));
+    cuts.dedup_by_key(|cut| cut.row);
+    Some(cuts)
+}
+
+/// 收集节点下各子元素的起始行，独占一行的注释和属性归入其后的元素
+fn collect_cuts(node: TsNode, depth: usize, cuts: &mut Vec<Cut>) {
+    let mut leading: Option<usize> = None;
+    let mut previous_end: Option<usize> = None;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        let row = child.start_position().row;
+        let own_line = previous_end.is_none_or(|end| row > end);
+        previous_end = Some(child.end_position().row);
+
+        if own_line && is_leading(child.kind()) {
+            leading.get_or_insert(row);
+            continue;
+        }
+        cuts.push(Cut {
+            row: leading.take().unwrap_or(row),
+            depth,
+        });
+        if child.end_position().row > row {
+            collect_cuts(child, depth + 1, cuts);
+        }
+    }
+}
+
+/// 是否为附着在后续元素上的节点（注释、属性、装饰器）
+fn is_leading(kind: &str) -> bool {
+    kind.contains("comment") || kind == "attribute_item" || kind == "decorator"
+}
+
+/// 按切分位置生成片段
+///
+/// 每个片段不超过 `CHUNK_LINES` 行，优先在嵌套最浅的元素边界处切分，同一深度下
+/// 选择最远的位置；在元素内部切分或没有可用位置时，下一个片段与当前片段重叠
+fn split_at_cuts(lines: &[&str], cuts: &[Cut]) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    // 下一个切分位置必须越过的行，避免重叠时原地切分
+    let mut floor = 0;
+
+    while start < lines.len() {
+        let limit = start + CHUNK_LINES;
+        let (end, depth) = if limit >= lines.len() {
+            (lines.len(), 0)
+        } else {
+            cuts.iter()
+                .filter(|cut| cut.row > floor && cut.row <= limit)
+                .min_by(|a, b| a.depth.cmp(&b.depth).then(b.row.cmp(&a.row)))
+                .map_or((limit, usize::MAX), |cut| (cut.row, cut.depth))
+        };
+
+        let text = lines[start..end].join("\n");
+        if !text.trim().is_empty() {
+            chunks.push(TextChunk {
+                start_line: start + 1,
+                end_line: end,
+                text,
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+
+        start = if depth > 0 {
+            end.saturating_sub(CHUNK_OVERLAP).max(start + 1)
+        } else {
+            end
+        };
+        floor = end;
+    }
+    chunks
+}
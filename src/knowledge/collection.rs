@@ -0,0 +1,163 @@
+//! 知识库集合
+//!
+//! 将知识库文档划分为命名集合（如 `repo`、`docs:axum`、`notes`），
+//! 支持按集合添加、检索、统计和删除，每个集合可在配置中覆盖遍历规则
+
+use crate::config::app::KnowledgeConfig;
+use crate::error::{AppError, AppResult};
+use clap::Subcommand;
+use regex::Regex;
+
+/// 未指定集合时使用的默认集合
+pub const DEFAULT_COLLECTION: &str = "default";
+
+/// 集合管理命令
+#[derive(Debug, Clone, Subcommand)]
+pub enum CollectionActions {
+    /// 列出所有集合及其统计信息
+    List,
+
+    /// 显示集合的统计信息
+    Stats {
+        /// 集合名称
+        name: String,
+    },
+
+    /// 删除集合及其中的所有文档
+    Delete {
+        /// 集合名称
+        name: String,
+
+        /// 确认删除，不再询问
+        #[arg(long, short = 'y')]
+        confirm: bool,
+    },
+}
+
+/// 默认集合名称，用于反序列化旧数据
+pub fn default_collection() -> String {
+    DEFAULT_COLLECTION.to_string()
+}
+
+/// 检查集合名称：只允许字母、数字和 `_ - . : /`
+pub fn validate_collection_name(name: &str) -> AppResult<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::knowledge(&format!(
+            "无效的集合名称 '{}'：只能包含字母、数字和 _ - . : /",
+            name
+        )))
+    }
+}
+
+/// 获取集合生效的配置：合并集合的排除模式，集合指定文件类型时覆盖全局配置
+pub fn collection_config(config: &KnowledgeConfig, name: &str) -> KnowledgeConfig {
+    let mut effective = config.clone();
+    if let Some(collection) = config.collections.get(name) {
+        effective
+            .exclude_patterns
+            .extend(collection.exclude_patterns.iter().cloned());
+        if !collection.supported_extensions.is_empty() {
+            effective.supported_extensions = collection.supported_extensions.clone();
+        }
+    }
+    effective
+}
+
+/// 集合过滤条件，由逗号分隔的集合名称组成，支持 `*` 通配符（如 `repo,docs:*`）
+#[derive(Debug, Clone)]
+pub struct CollectionFilter {
+    /// 原始模式
+    patterns: Vec<String>,
+    /// 模式对应的正则表达式（不含首尾锚点）
+    expressions: Vec<String>,
+    /// 编译后的匹配器
+    matchers: Vec<Regex>,
+}
+
+impl CollectionFilter {
+    /// 解析逗号分隔的集合模式
+    pub fn parse(spec: &str) -> AppResult<Self> {
+        let patterns: Vec<String> = spec
+            .split(',')
+            .map(|pattern| pattern.trim().to_string())
+            .filter(|pattern| !pattern.is_empty())
+            .collect();
+        if patterns.is_empty() {
+            return Err(AppError::knowledge("集合过滤条件不能为空"));
+        }
+
+        let mut expressions = Vec::new();
+        let mut matchers = Vec::new();
+        for pattern in &patterns {
+            validate_collection_name(&pattern.replace('*', "_"))?;
+            let expression = pattern
+                .split('*')
+                .map(regex::escape)
+                .collect::<Vec<_>>()
+                .join(".*");
+            matchers.push(
+                Regex::new(&format!("^{}$", expression))
+                    .map_err(|e| AppError::knowledge(&format!("无效的集合模式: {}", e)))?,
+            );
+            expressions.push(expression);
+        }
+        Ok(Self {
+            patterns,
+            expressions,
+            matchers,
+        })
+    }
+
+    /// 获取原始模式
+    pub fn patterns(&self) -> &[String] {
+        &self.patterns
+    }
+
+    /// 获取用于索引查询的正则表达式，需完整匹配集合名称
+    pub fn expressions(&self) -> &[String] {
+        &self.expressions
+    }
+
+    /// 判断集合是否匹配任一模式
+    pub fn matches(&self, collection: &str) -> bool {
+        self.matchers
+            .iter()
+            .any(|matcher| matcher.is_match(collection))
+    }
+}
+
+/// 集合统计信息
+#[derive(Debug, Clone, Default)]
+pub struct CollectionStats {
+    /// 集合名称
+    pub name: String,
+    /// 配置中的集合描述
+    pub description: String,
+    /// 文件数量
+    pub files: usize,
+    /// 网页数量
+    pub pages: usize,
+    /// Git 提交数量
+    pub commits: usize,
+    /// 内容总大小（字节）
+    pub size: u64,
+    /// 内容总行数
+    pub lines: usize,
+    /// 已生成向量的片段数量
+    pub vectors: usize,
+    /// 最近一次索引时间（Unix秒）
+    pub last_indexed: Option<i64>,
+}
+
+impl CollectionStats {
+    /// 文档总数
+    pub fn documents(&self) -> usize {
+        self.files + self.pages + self.commits
+    }
+}
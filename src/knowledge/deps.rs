@@ -0,0 +1,845 @@
+//! 项目依赖图
+//!
+//! 使用各语言解析器提取的导入语句，将 Rust 模块路径、Python 包、JS/TS 相对导入和
+//! tsconfig 路径别名、Go 包、Java 类以及 C/C++ 头文件解析为项目内的文件，
+//! 构建文件依赖图，支持循环依赖检测和反向依赖查询
+
+use crate::error::AppResult;
+use crate::knowledge::walker::{read_text_file, ProjectWalker};
+use crate::parsers::{initialize_parsers, is_document_language, Import, PARSER_REGISTRY};
+use clap::Subcommand;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// JavaScript和TypeScript导入省略的扩展名，按查找顺序排列
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "tsx", "d.ts", "js", "jsx", "mjs", "cjs"];
+
+/// 依赖图操作枚举
+#[derive(Debug, Clone, Subcommand)]
+pub enum DepsActions {
+    /// 输出项目的文件依赖图
+    Graph {
+        /// 项目根目录，默认为当前目录
+        path: Option<PathBuf>,
+
+        /// 输出格式：dot 或 json
+        #[arg(long, short, default_value = "dot")]
+        format: String,
+
+        /// 包含项目外部的依赖（标准库和第三方包）
+        #[arg(long)]
+        external: bool,
+    },
+
+    /// 检测文件之间的循环依赖
+    Cycles {
+        /// 项目根目录，默认为当前目录
+        path: Option<PathBuf>,
+    },
+
+    /// 查询依赖指定文件的文件
+    Dependents {
+        /// 文件路径
+        file: PathBuf,
+
+        /// 项目根目录，默认为当前目录
+        #[arg(long)]
+        root: Option<PathBuf>,
+
+        /// 包含间接依赖该文件的文件
+        #[arg(long, short)]
+        transitive: bool,
+    },
+}
+
+/// 依赖图输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// JSON
+    Json,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" => Ok(GraphFormat::Dot),
+            "json" => Ok(GraphFormat::Json),
+            _ => Err(format!("未知的依赖图格式: {}，可选 dot 或 json", s)),
+        }
+    }
+}
+
+/// 单条依赖
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependency {
+    /// 源代码中的导入路径
+    pub import: String,
+    /// 解析得到的项目内文件（相对项目根目录），位于项目外部或无法解析时为空
+    pub target: Option<String>,
+    /// 导入所在行
+    pub line: u32,
+}
+
+/// 文件的依赖
+#[derive(Debug, Clone, Serialize)]
+pub struct FileDependencies {
+    /// 文件路径（相对项目根目录）
+    pub path: String,
+    /// 语言
+    pub language: String,
+    /// 文件中的依赖，按导入顺序排列
+    pub dependencies: Vec<Dependency>,
+}
+
+/// 项目依赖图
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    /// 项目根目录
+    root: PathBuf,
+    /// 按路径排序的文件依赖
+    files: BTreeMap<String, FileDependencies>,
+}
+
+impl DependencyGraph {
+    /// 遍历项目目录，解析所有受支持文件的导入并构建依赖图
+    pub fn build(root: &Path, walker: &ProjectWalker) -> AppResult<Self> {
+        initialize_parsers()?;
+        let root = fs::canonicalize(root)?;
+        let sources: BTreeMap<String, PathBuf> = walker
+            .walk(&root)?
+            .into_iter()
+            .filter_map(|path| Some((relative_path(&root, &path)?, path)))
+            .collect();
+        let resolver = Resolver::new(&root, sources.keys().cloned().collect());
+
+        let mut files = BTreeMap::new();
+        for (relative, path) in &sources {
+            let parser = PARSER_REGISTRY
+                .read()
+                .unwrap()
+                .get_parser_by_filename(relative);
+            let Some(parser) =
+                parser.filter(|parser| !is_document_language(&parser.language_name()))
+            else {
+                continue;
+            };
+            let Some(content) = read_text_file(path, walker.file_size_limit())? else {
+                continue;
+            };
+            let imports = match parser.parse_imports(&content) {
+                Ok(imports) => imports,
+                Err(e) => {
+                    log::warn!("解析 {} 的导入失败: {}", relative, e);
+                    continue;
+                }
+            };
+
+            let language = parser.language_name();
+            let dependencies = imports
+                .iter()
+                .flat_map(|import| resolver.resolve(&language, relative, import))
+                .collect();
+            files.insert(
+                relative.clone(),
+                FileDependencies {
+                    path: relative.clone(),
+                    language,
+                    dependencies,
+                },
+            );
+        }
+        Ok(Self { root, files })
+    }
+
+    /// 获取项目根目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 获取所有文件的依赖，按路径排序
+    pub fn files(&self) -> impl Iterator<Item = &FileDependencies> {
+        self.files.values()
+    }
+
+    /// 获取文件的依赖
+    pub fn file(&self, path: &str) -> Option<&FileDependencies> {
+        self.files.get(path)
+    }
+
+    /// 将路径转换为相对项目根目录的路径，不在项目中时返回 `None`
+    pub fn relative(&self, path: &Path) -> Option<String> {
+        relative_path(&self.root, &fs::canonicalize(path).ok()?)
+    }
+
+    /// 项目内文件之间的依赖，去重后按路径排序
+    pub fn edges(&self) -> BTreeSet<(&str, &str)> {
+        self.files
+            .values()
+            .flat_map(|file| {
+                file.dependencies.iter().filter_map(|dependency| {
+                    Some((file.path.as_str(), dependency.target.as_deref()?))
+                })
+            })
+            .collect()
+    }
+
+    /// 项目外部的依赖：文件路径和外部包名称，去重后排序
+    pub fn external(&self) -> BTreeSet<(&str, String)> {
+        self.files
+            .values()
+            .flat_map(|file| {
+                file.dependencies
+                    .iter()
+                    .filter(|dependency| dependency.target.is_none())
+                    .map(|dependency| {
+                        (
+                            file.path.as_str(),
+                            external_name(&file.language, &dependency.import),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// 依赖指定文件的文件，`transitive` 为真时包括间接依赖，结果按路径排序
+    pub fn dependents(&self, path: &str, transitive: bool) -> Vec<String> {
+        let mut reverse: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (from, to) in self.edges() {
+            reverse.entry(to).or_default().push(from);
+        }
+
+        let mut found = BTreeSet::new();
+        let mut queue = VecDeque::from([path]);
+        while let Some(current) = queue.pop_front() {
+            for &dependent in reverse.get(current).into_iter().flatten() {
+                if dependent != path && found.insert(dependent) && transitive {
+                    queue.push_back(dependent);
+                }
+            }
+        }
+        found.into_iter().map(str::to_string).collect()
+    }
+
+    /// 检测循环依赖，每组相互依赖的文件返回一条从路径最小的文件出发的环
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut successors: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (from, to) in self.edges() {
+            successors.entry(from).or_default().push(to);
+        }
+
+        let mut cycles = Vec::new();
+        for component in strongly_connected(&successors) {
+            if component.len() < 2 {
+                continue;
+            }
+            let members: BTreeSet<&str> = component.iter().copied().collect();
+            let start = *members.iter().next().unwrap();
+            if let Some(cycle) = find_cycle(start, &members, &successors) {
+                cycles.push(cycle.into_iter().map(str::to_string).collect());
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// 生成 Graphviz DOT 格式的依赖图，外部依赖以虚线表示
+    pub fn to_dot(&self, external: bool) -> String {
+        let mut dot =
+            String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+        for path in self.files.keys() {
+            dot.push_str(&format!("    {};\n", dot_id(path)));
+        }
+        for (from, to) in self.edges() {
+            dot.push_str(&format!("    {} -> {};\n", dot_id(from), dot_id(to)));
+        }
+        if external {
+            let external = self.external();
+            let packages: BTreeSet<&str> = external.iter().map(|(_, name)| name.as_str()).collect();
+            for package in packages {
+                dot.push_str(&format!(
+                    "    {} [shape=ellipse, style=dashed];\n",
+                    dot_id(package)
+                ));
+            }
+            for (from, package) in &external {
+                dot.push_str(&format!(
+                    "    {} -> {} [style=dashed];\n",
+                    dot_id(from),
+                    dot_id(package)
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 生成JSON格式的依赖图，包含每个文件的导入、项目内的依赖边和循环依赖
+    pub fn to_json(&self, external: bool) -> serde_json::Value {
+        let files: Vec<serde_json::Value> = self
+            .files
+            .values()
+            .map(|file| {
+                let dependencies: Vec<&Dependency> = file
+                    .dependencies
+                    .iter()
+                    .filter(|dependency| external || dependency.target.is_some())
+                    .collect();
+                json!({
+                    "path": file.path,
+                    "language": file.language,
+                    "dependencies": dependencies,
+                })
+            })
+            .collect();
+        let edges: Vec<serde_json::Value> = self
+            .edges()
+            .into_iter()
+            .map(|(from, to)| json!({ "from": from, "to": to }))
+            .collect();
+
+        let mut graph = json!({
+            "root": self.root.to_string_lossy(),
+            "files": files,
+            "edges": edges,
+            "cycles": self.cycles(),
+        });
+        if external {
+            let external: Vec<serde_json::Value> = self
+                .external()
+                .into_iter()
+                .map(|(from, package)| json!({ "from": from, "package": package }))
+                .collect();
+            graph["external"] = json!(external);
+        }
+        graph
+    }
+}
+
+/// tsconfig.json 或 jsconfig.json 中的模块解析配置
+#[derive(Debug, Default)]
+struct ScriptPaths {
+    /// 非相对导入的基准目录（相对项目根目录）
+    base_url: Option<String>,
+    /// 路径别名及其目标，目标已相对项目根目录
+    aliases: Vec<(String, Vec<String>)>,
+}
+
+impl ScriptPaths {
+    /// 读取项目根目录下的 tsconfig.json 或 jsconfig.json
+    fn load(root: &Path) -> Self {
+        for name in ["tsconfig.json", "jsconfig.json"] {
+            let Ok(text) = fs::read_to_string(root.join(name)) else {
+                continue;
+            };
+            let Some(config) = parse_json_with_comments(&text) else {
+                log::warn!("无法解析 {}，忽略其中的路径别名", name);
+                continue;
+            };
+            let options = &config["compilerOptions"];
+            let base_url = options["baseUrl"].as_str().and_then(normalize);
+            let alias_base = base_url.clone().unwrap_or_default();
+            let aliases = options["paths"]
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|target| target.as_str())
+                        .map(|target| join(&alias_base, target))
+                        .collect();
+                    (pattern.clone(), targets)
+                })
+                .collect();
+            return Self { base_url, aliases };
+        }
+        Self::default()
+    }
+}
+
+/// 将导入解析为项目内的文件
+struct Resolver {
+    /// 项目中的所有文件（相对项目根目录）
+    files: BTreeSet<String>,
+    /// JavaScript和TypeScript的路径配置
+    script_paths: ScriptPaths,
+    /// go.mod 中声明的模块路径
+    go_module: Option<String>,
+}
+
+impl Resolver {
+    /// 读取项目的模块配置并创建解析器
+    fn new(root: &Path, files: BTreeSet<String>) -> Self {
+        let go_module = fs::read_to_string(root.join("go.mod"))
+            .ok()
+            .and_then(|text| {
+                text.lines()
+                    .find_map(|line| line.trim().strip_prefix("module "))
+                    .map(|module| module.trim().to_string())
+            });
+        Self {
+            files,
+            script_paths: ScriptPaths::load(root),
+            go_module,
+        }
+    }
+
+    /// 解析文件中的一条导入，可能对应多个文件；无法解析时返回一条外部依赖，
+    /// 导入文件自身时不产生依赖
+    fn resolve(&self, language: &str, file: &str, import: &Import) -> Vec<Dependency> {
+        let targets = match language {
+            "rust" => self
+                .resolve_rust(file, &import.module)
+                .into_iter()
+                .collect(),
+            "python" => self.resolve_python(file, import),
+            "javascript" | "typescript" => self
+                .resolve_script(file, &import.module)
+                .into_iter()
+                .collect(),
+            "go" => self.resolve_go(&import.module),
+            "java" => self.resolve_java(import),
+            "c" | "cpp" => self
+                .resolve_include(file, &import.module)
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        if targets.is_empty() {
+            return vec![Dependency {
+                import: import.module.clone(),
+                target: None,
+                line: import.line,
+            }];
+        }
+
+        let mut seen = BTreeSet::new();
+        targets
+            .into_iter()
+            .filter(|target| target != file && seen.insert(target.clone()))
+            .map(|target| Dependency {
+                import: import.module.clone(),
+                target: Some(target),
+                line: import.line,
+            })
+            .collect()
+    }
+
+    /// 解析Rust模块路径：`crate::`、`self::` 和 `super::` 按模块目录解析，
+    /// 其他路径先在当前模块中查找，找不到时视为外部 crate
+    fn resolve_rust(&self, file: &str, module: &str) -> Option<String> {
+        let segments: Vec<&str> = module
+            .split("::")
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect();
+        let (first, rest) = segments.split_first()?;
+
+        let (dir, mut rest) = match *first {
+            "crate" => (self.rust_crate_dir(file)?, rest),
+            "self" => (rust_module_dir(file), rest),
+            "super" => (rust_module_dir(file), &segments[..]),
+            _ => {
+                return self
+                    .rust_module_file(&rust_module_dir(file), &segments)
+                    .or_else(|| self.rust_module_file(&self.rust_crate_dir(file)?, &segments));
+            }
+        };
+        let mut dir = dir;
+        while let Some((&"super", tail)) = rest.split_first() {
+            dir = parent_dir(&dir).to_string();
+            rest = tail;
+        }
+        // 找不到子模块时，导入的是模块自身中定义的项
+        self.rust_module_file(&dir, rest)
+            .or_else(|| self.rust_dir_module(&dir))
+    }
+
+    /// crate 的源代码目录：包含 lib.rs 或 main.rs 的最近上级目录
+    fn rust_crate_dir(&self, file: &str) -> Option<String> {
+        let mut dir = parent_dir(file).to_string();
+        loop {
+            if ["lib.rs", "main.rs"]
+                .iter()
+                .any(|root| self.files.contains(&join(&dir, root)))
+            {
+                return Some(dir);
+            }
+            if dir.is_empty() {
+                return None;
+            }
+            dir = parent_dir(&dir).to_string();
+        }
+    }
+
+    /// 在模块目录下查找路径对应的最长模块文件，如 `a::b::Item` 对应 `a/b.rs` 或 `a/b/mod.rs`
+    fn rust_module_file(&self, dir: &str, segments: &[&str]) -> Option<String> {
+        (1..=segments.len()).rev().find_map(|end| {
+            let path = join(dir, &segments[..end].join("/"));
+            [format!("{}.rs", path), format!("{}/mod.rs", path)]
+                .into_iter()
+                .find(|candidate| self.files.contains(candidate))
+        })
+    }
+
+    /// 模块目录对应的模块文件
+    fn rust_dir_module(&self, dir: &str) -> Option<String> {
+        [
+            join(dir, "mod.rs"),
+            join(dir, "lib.rs"),
+            join(dir, "main.rs"),
+            format!("{}.rs", dir),
+        ]
+        .into_iter()
+        .find(|candidate| self.files.contains(candidate))
+    }
+
+    /// 解析Python模块：相对导入从当前包开始，绝对导入从项目根目录或 `src` 开始；
+    /// `from pkg import name` 中的名称是子模块时依赖子模块，否则依赖包本身
+    fn resolve_python(&self, file: &str, import: &Import) -> Vec<String> {
+        let module = import.module.as_str();
+        let dots = module.chars().take_while(|c| *c == '.').count();
+        let module_path = module[dots..].replace('.', "/");
+        let bases = if dots > 0 {
+            let mut dir = parent_dir(file).to_string();
+            for _ in 1..dots {
+                dir = parent_dir(&dir).to_string();
+            }
+            vec![dir]
+        } else {
+            vec![String::new(), "src".to_string()]
+        };
+
+        for base in bases {
+            let path = join(&base, &module_path);
+            let mut targets = Vec::new();
+            let mut imports_items = import.names.is_empty();
+            for name in &import.names {
+                match self.python_module(&join(&path, name)) {
+                    Some(submodule) if name != "*" => targets.push(submodule),
+                    _ => imports_items = true,
+                }
+            }
+            if imports_items {
+                targets.extend(self.python_module(&path));
+            }
+            if !targets.is_empty() {
+                return targets;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Python模块路径对应的文件：模块文件或包的 `__init__.py`
+    fn python_module(&self, path: &str) -> Option<String> {
+        let file = (!path.is_empty()).then(|| format!("{}.py", path));
+        file.into_iter()
+            .chain([join(path, "__init__.py")])
+            .find(|candidate| self.files.contains(candidate))
+    }
+
+    /// 解析JavaScript和TypeScript导入：相对路径、tsconfig 路径别名和 baseUrl，
+    /// 依次尝试省略的扩展名和目录的 index 文件
+    fn resolve_script(&self, file: &str, module: &str) -> Option<String> {
+        if module == "." || module == ".." || module.starts_with("./") || module.starts_with("../")
+        {
+            return self.script_file(&normalize(&join(parent_dir(file), module))?);
+        }
+        for (pattern, targets) in &self.script_paths.aliases {
+            let rest = match pattern.strip_suffix('*') {
+                Some(prefix) => module.strip_prefix(prefix),
+                None => (module == pattern).then_some(""),
+            };
+            let Some(rest) = rest else {
+                continue;
+            };
+            let found = targets
+                .iter()
+                .find_map(|target| self.script_file(&normalize(&target.replacen('*', rest, 1))?));
+            if found.is_some() {
+                return found;
+            }
+        }
+        let base_url = self.script_paths.base_url.as_deref()?;
+        self.script_file(&normalize(&join(base_url, module))?)
+    }
+
+    /// 查找脚本模块对应的文件
+    fn script_file(&self, path: &str) -> Option<String> {
+        if self.files.contains(path) {
+            return Some(path.to_string());
+        }
+        // TypeScript 的 ESM 导入以 .js 结尾，指向同名的 .ts 文件
+        let stem = path.strip_suffix(".js").unwrap_or(path);
+        SCRIPT_EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}.{}", stem, ext))
+            .chain(
+                SCRIPT_EXTENSIONS
+                    .iter()
+                    .map(|ext| join(path, &format!("index.{}", ext))),
+            )
+            .find(|candidate| self.files.contains(candidate))
+    }
+
+    /// 解析Go导入：go.mod 模块下的包依赖包目录中的所有非测试文件
+    fn resolve_go(&self, module: &str) -> Vec<String> {
+        let Some(prefix) = self.go_module.as_deref() else {
+            return Vec::new();
+        };
+        let dir = match module.strip_prefix(prefix) {
+            Some("") => "",
+            Some(rest) if rest.starts_with('/') => &rest[1..],
+            _ => return Vec::new(),
+        };
+        self.files
+            .iter()
+            .filter(|path| {
+                parent_dir(path) == dir && path.ends_with(".go") && !path.ends_with("_test.go")
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// 解析Java导入：按包路径后缀查找类文件，包导入依赖包中的所有类，
+    /// 静态导入逐级去掉末尾的成员名
+    fn resolve_java(&self, import: &Import) -> Vec<String> {
+        let path = import.module.replace('.', "/");
+        let in_package = |file: &&String, package: &str| {
+            let dir = parent_dir(file);
+            dir == package || dir.ends_with(&format!("/{}", package))
+        };
+        if import.names.iter().any(|name| name == "*") {
+            let classes: Vec<String> = self
+                .files
+                .iter()
+                .filter(|file| file.ends_with(".java") && in_package(file, &path))
+                .cloned()
+                .collect();
+            if !classes.is_empty() {
+                return classes;
+            }
+        }
+
+        let mut class = path.as_str();
+        loop {
+            let file = format!("{}.java", class);
+            let suffix = format!("/{}", file);
+            if let Some(found) = self
+                .files
+                .iter()
+                .find(|candidate| **candidate == file || candidate.ends_with(&suffix))
+            {
+                return vec![found.clone()];
+            }
+            match class.rsplit_once('/') {
+                Some((parent, _)) => class = parent,
+                None => return Vec::new(),
+            }
+        }
+    }
+
+    /// 解析C/C++头文件：引号形式先查找当前目录，再查找项目根目录和 include 目录；
+    /// 尖括号形式只查找项目的 include 目录
+    fn resolve_include(&self, file: &str, module: &str) -> Option<String> {
+        if let Some(header) = module
+            .strip_prefix('<')
+            .and_then(|module| module.strip_suffix('>'))
+        {
+            return normalize(&join("include", header))
+                .filter(|candidate| self.files.contains(candidate));
+        }
+        [
+            join(parent_dir(file), module),
+            module.to_string(),
+            join("include", module),
+        ]
+        .iter()
+        .filter_map(|candidate| normalize(candidate))
+        .find(|candidate| self.files.contains(candidate))
+    }
+}
+
+/// Tarjan 算法求强连通分量
+fn strongly_connected<'a>(successors: &BTreeMap<&'a str, Vec<&'a str>>) -> Vec<Vec<&'a str>> {
+    /// 遍历状态
+    struct State<'a> {
+        index: HashMap<&'a str, usize>,
+        low: HashMap<&'a str, usize>,
+        stack: Vec<&'a str>,
+        on_stack: BTreeSet<&'a str>,
+        components: Vec<Vec<&'a str>>,
+    }
+
+    fn visit<'a>(
+        node: &'a str,
+        successors: &BTreeMap<&'a str, Vec<&'a str>>,
+        state: &mut State<'a>,
+    ) {
+        let index = state.index.len();
+        state.index.insert(node, index);
+        state.low.insert(node, index);
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        for &next in successors.get(node).into_iter().flatten() {
+            if !state.index.contains_key(next) {
+                visit(next, successors, state);
+                let low = state.low[node].min(state.low[next]);
+                state.low.insert(node, low);
+            } else if state.on_stack.contains(next) {
+                let low = state.low[node].min(state.index[next]);
+                state.low.insert(node, low);
+            }
+        }
+
+        if state.low[node] == state.index[node] {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+
+    let mut state = State {
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: BTreeSet::new(),
+        components: Vec::new(),
+    };
+    for &node in successors.keys() {
+        if !state.index.contains_key(node) {
+            visit(node, successors, &mut state);
+        }
+    }
+    state.components
+}
+
+/// 在强连通分量中用广度优先搜索找出从起点出发回到起点的最短环
+fn find_cycle<'a>(
+    start: &'a str,
+    members: &BTreeSet<&'a str>,
+    successors: &BTreeMap<&'a str, Vec<&'a str>>,
+) -> Option<Vec<&'a str>> {
+    let mut previous: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        for &next in successors.get(node).into_iter().flatten() {
+            if next == start {
+                let mut cycle = vec![node];
+                let mut current = node;
+                while let Some(&before) = previous.get(current) {
+                    cycle.push(before);
+                    current = before;
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if members.contains(next) && next != start && !previous.contains_key(next) {
+                previous.insert(next, node);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+/// 项目外部依赖的名称：Rust crate、Python 顶层包、npm 包、Java 包，其他语言使用导入路径
+fn external_name(language: &str, import: &str) -> String {
+    let name = match language {
+        "rust" => import.split("::").next(),
+        "python" => import.trim_start_matches('.').split('.').next(),
+        "javascript" | "typescript" => {
+            // 作用域包的名称包含两段，如 `@scope/pkg`
+            let segments = if import.starts_with('@') { 2 } else { 1 };
+            return import
+                .splitn(segments + 1, '/')
+                .take(segments)
+                .collect::<Vec<_>>()
+                .join("/");
+        }
+        "java" => import.rsplit_once('.').map(|(package, _)| package),
+        _ => None,
+    };
+    name.unwrap_or(import).to_string()
+}
+
+/// 转换为DOT标识符，转义引号
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// 解析可能带有 `//` 行注释的 tsconfig.json
+fn parse_json_with_comments(text: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(text).ok().or_else(|| {
+        let stripped: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .collect();
+        serde_json::from_str(&stripped.join("\n")).ok()
+    })
+}
+
+/// 获取相对项目根目录的路径，使用 `/` 分隔
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// 获取路径所在的目录，根目录下的文件返回空字符串
+fn parent_dir(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// 拼接目录和相对路径
+fn join(dir: &str, path: &str) -> String {
+    match (dir, path) {
+        ("", _) => path.to_string(),
+        (_, "") => dir.to_string(),
+        _ => format!("{}/{}", dir, path),
+    }
+}
+
+/// 规范化相对路径，处理 `.` 和 `..`，超出项目根目录时返回 `None`
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            _ => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// Rust文件对应模块的目录：`lib.rs`、`main.rs` 和 `mod.rs` 为所在目录，其他文件为同名子目录
+fn rust_module_dir(file: &str) -> String {
+    let dir = parent_dir(file);
+    let name = file.rsplit('/').next().unwrap_or(file);
+    match name {
+        "lib.rs" | "main.rs" | "mod.rs" => dir.to_string(),
+        _ => join(dir, name.trim_end_matches(".rs")),
+    }
+}
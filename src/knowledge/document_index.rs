@@ -0,0 +1,553 @@
+//! 文档全文索引
+//!
+//! 将文件和网页内容写入tantivy索引，支持全文搜索和匹配片段提取。
+//! 每个文档按片段分别索引，搜索结果返回匹配片段的行号范围；
+//! 完整内容单独保存，仅用于读取
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::{DocumentMeta, DocumentSource, SearchHit};
+use crate::knowledge::chunker::{chunk_document, TextChunk};
+use crate::knowledge::collection::{default_collection, CollectionFilter};
+use crate::knowledge::query::{QueryTerm, SearchQuery};
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Bound;
+use std::path::Path;
+use tantivy::collector::TopDocs;
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{
+    AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, RangeQuery, RegexQuery,
+    TermQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption, Schema, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{
+    doc, Document, Index, IndexReader, IndexWriter, ReloadPolicy, SnippetGenerator, Term,
+};
+
+/// 索引写入器的内存缓冲大小
+const WRITER_HEAP_SIZE: usize = 50_000_000;
+
+/// 匹配片段的最大字符数
+const SNIPPET_MAX_CHARS: usize = 200;
+
+/// 保存完整内容的文档记录
+const KIND_DOCUMENT: &str = "document";
+
+/// 参与搜索的片段记录
+const KIND_CHUNK: &str = "chunk";
+
+/// 文档索引字段
+#[derive(Clone, Copy)]
+struct DocumentFields {
+    path: Field,
+    title: Field,
+    content: Field,
+    language: Field,
+    source: Field,
+    timestamp: Field,
+    kind: Field,
+    start_line: Field,
+    end_line: Field,
+    body: Field,
+    collection: Field,
+}
+
+/// 文档全文索引
+pub struct DocumentIndex {
+    /// tantivy索引
+    index: Index,
+    /// 索引读取器
+    reader: IndexReader,
+    /// 索引写入器，首次写入时创建，避免只读时占用索引锁
+    writer: Option<IndexWriter>,
+    /// 索引字段
+    fields: DocumentFields,
+}
+
+impl DocumentIndex {
+    /// 构建文档索引的模式
+    fn schema() -> (Schema, DocumentFields) {
+        let mut builder = Schema::builder();
+        let fields = DocumentFields {
+            path: builder.add_text_field("path", STRING | STORED),
+            title: builder.add_text_field("title", TEXT | STORED),
+            content: builder.add_text_field("content", TEXT | STORED),
+            language: builder.add_text_field("language", STRING | STORED),
+            source: builder.add_text_field("source", STRING | STORED),
+            timestamp: builder.add_i64_field("timestamp", INDEXED | STORED | FAST),
+            kind: builder.add_text_field("kind", STRING),
+            start_line: builder.add_u64_field("start_line", STORED),
+            end_line: builder.add_u64_field("end_line", STORED),
+            body: builder.add_text_field("body", STORED),
+            collection: builder.add_text_field("collection", STRING | STORED),
+        };
+        (builder.build(), fields)
+    }
+
+    /// 打开或创建指定目录下的文档索引，旧格式的整文件索引会被重建为分段索引
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        let (schema, fields) = Self::schema();
+        std::fs::create_dir_all(dir)?;
+
+        let mut legacy = None;
+        if Index::exists(&open_directory(dir)?).map_err(tantivy::TantivyError::from)? {
+            let index = Index::open(open_directory(dir)?)?;
+            if index.schema() != schema {
+                legacy = Some(read_legacy_documents(&index)?);
+                drop(index);
+                std::fs::remove_dir_all(dir)?;
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+
+        let index = Index::open_or_create(open_directory(dir)?, schema)?;
+        let mut documents = Self::with_index(index, fields)?;
+        if let Some(legacy) = legacy {
+            log::info!("正在将 {} 个文档重建为分段索引", legacy.len());
+            for (meta, content) in &legacy {
+                documents.upsert(meta, content, &chunk_document(&meta.path, content))?;
+            }
+            documents.commit()?;
+        }
+        Ok(documents)
+    }
+
+    /// 创建内存中的文档索引
+    pub fn in_memory() -> AppResult<Self> {
+        let (schema, fields) = Self::schema();
+        Self::with_index(Index::create_in_ram(schema), fields)
+    }
+
+    /// 使用已打开的索引创建实例
+    fn with_index(index: Index, fields: DocumentFields) -> AppResult<Self> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            index,
+            reader,
+            writer: None,
+            fields,
+        })
+    }
+
+    /// 获取索引写入器
+    fn writer(&mut self) -> AppResult<&mut IndexWriter> {
+        if self.writer.is_none() {
+            self.writer = Some(self.index.writer(WRITER_HEAP_SIZE)?);
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    /// 写入文档及其片段，替换相同路径的旧文档
+    pub fn upsert(
+        &mut self,
+        meta: &DocumentMeta,
+        content: &str,
+        chunks: &[TextChunk],
+    ) -> AppResult<()> {
+        let fields = self.fields;
+        let writer = self.writer()?;
+        writer.delete_term(Term::from_field_text(fields.path, &meta.path));
+        writer.add_document(doc!(
+            fields.path => meta.path.clone(),
+            fields.kind => KIND_DOCUMENT,
+            fields.body => content,
+        ))?;
+        for chunk in chunks {
+            writer.add_document(doc!(
+                fields.path => meta.path.clone(),
+                fields.title => meta.title.clone(),
+                fields.content => chunk.text.clone(),
+                fields.language => meta.language.clone(),
+                fields.source => meta.source.as_str(),
+                fields.timestamp => meta.indexed_at,
+                fields.kind => KIND_CHUNK,
+                fields.start_line => chunk.start_line as u64,
+                fields.end_line => chunk.end_line as u64,
+                fields.collection => meta.collection.clone(),
+            ))?;
+        }
+        Ok(())
+    }
+
+    /// 删除指定路径的文档
+    pub fn remove(&mut self, path: &str) -> AppResult<()> {
+        let field = self.fields.path;
+        self.writer()?
+            .delete_term(Term::from_field_text(field, path));
+        Ok(())
+    }
+
+    /// 清空索引
+    pub fn clear(&mut self) -> AppResult<()> {
+        self.writer()?.delete_all_documents()?;
+        Ok(())
+    }
+
+    /// 提交挂起的修改并刷新读取器
+    pub fn commit(&mut self) -> AppResult<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.commit()?;
+            self.reader.reload()?;
+        }
+        Ok(())
+    }
+
+    /// 获取指定路径的文档内容
+    pub fn content(&self, path: &str) -> AppResult<Option<String>> {
+        let term_query = |field: Field, text: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, text),
+                IndexRecordOption::Basic,
+            ))
+        };
+        let query = BooleanQuery::new(vec![
+            (Occur::Must, term_query(self.fields.path, path)),
+            (Occur::Must, term_query(self.fields.kind, KIND_DOCUMENT)),
+        ]);
+        let searcher = self.reader.searcher();
+        let Some((_, address)) = searcher
+            .search(&query, &TopDocs::with_limit(1))?
+            .into_iter()
+            .next()
+        else {
+            return Ok(None);
+        };
+        let document = searcher.doc(address)?;
+        Ok(document
+            .get_first(self.fields.body)
+            .and_then(|value| value.as_text())
+            .map(|text| text.to_string()))
+    }
+
+    /// 获取索引中所有文档的路径
+    pub fn paths(&self) -> AppResult<BTreeSet<String>> {
+        let query = TermQuery::new(
+            Term::from_field_text(self.fields.kind, KIND_DOCUMENT),
+            IndexRecordOption::Basic,
+        );
+        let searcher = self.reader.searcher();
+        let limit = (searcher.num_docs() as usize).max(1);
+        let mut paths = BTreeSet::new();
+        for (_, address) in searcher.search(&query, &TopDocs::with_limit(limit))? {
+            let document = searcher.doc(address)?;
+            if let Some(path) = document
+                .get_first(self.fields.path)
+                .and_then(|value| value.as_text())
+            {
+                paths.insert(path.to_string());
+            }
+        }
+        Ok(paths)
+    }
+
+    /// 获取索引的段数量
+    pub fn segment_count(&self) -> usize {
+        self.reader.searcher().segment_readers().len()
+    }
+
+    /// 合并所有索引段并清理不再使用的文件，返回合并前的段数量
+    pub fn compact(&mut self) -> AppResult<usize> {
+        self.commit()?;
+        let segments = self.index.searchable_segment_ids()?;
+        let writer = self.writer()?;
+        if !segments.is_empty() {
+            writer.merge(&segments).wait()?;
+        }
+        writer.garbage_collect_files().wait()?;
+        self.reader.reload()?;
+        Ok(segments.len())
+    }
+
+    /// 解析全文查询，语法错误时退化为关键词查询
+    fn parse_text_query(&self, text: &str) -> AppResult<Box<dyn Query>> {
+        let parser =
+            QueryParser::for_index(&self.index, vec![self.fields.title, self.fields.content]);
+        if let Ok(parsed) = parser.parse_query(text) {
+            return Ok(parsed);
+        }
+
+        let keywords: Vec<&str> = text
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|term| !term.is_empty())
+            .collect();
+        parser
+            .parse_query(&keywords.join(" "))
+            .map_err(|e| AppError::knowledge(&format!("无效的搜索查询: {}", e)))
+    }
+
+    /// 构建查询词部分：关键词、短语、正则和模糊词任一匹配即可，没有查询词时返回 `None`
+    fn terms_query(&self, query: &SearchQuery) -> AppResult<Option<Box<dyn Query>>> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        let text = query.text();
+        if !text.trim().is_empty() {
+            clauses.push((Occur::Should, self.parse_text_query(&text)?));
+        }
+        for term in &query.terms {
+            for field in [self.fields.title, self.fields.content] {
+                let clause: Box<dyn Query> = match term {
+                    QueryTerm::Regex(pattern) => {
+                        Box::new(RegexQuery::from_pattern(pattern, field).map_err(|e| {
+                            AppError::knowledge(&format!("无效的正则表达式: {}", e))
+                        })?)
+                    }
+                    QueryTerm::Fuzzy { term, distance } => Box::new(FuzzyTermQuery::new(
+                        Term::from_field_text(field, term),
+                        *distance,
+                        true,
+                    )),
+                    _ => continue,
+                };
+                clauses.push((Occur::Should, clause));
+            }
+        }
+        Ok(match clauses.len() {
+            0 => None,
+            1 => clauses.pop().map(|(_, clause)| clause),
+            _ => Some(Box::new(BooleanQuery::new(clauses))),
+        })
+    }
+
+    /// 全文搜索，返回按相关性排序的匹配片段，可限定在指定集合中
+    ///
+    /// 查询中的语言、路径和时间条件在索引中过滤；含元素类型或名称条件时不返回文档
+    pub fn search(
+        &self,
+        query: &SearchQuery,
+        limit: usize,
+        filter: Option<&CollectionFilter>,
+    ) -> AppResult<Vec<SearchHit>> {
+        if query.is_empty() || query.has_symbol_filters() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let term_query = |field: Field, text: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, text),
+                IndexRecordOption::Basic,
+            ))
+        };
+        let any_of = |clauses: Vec<Box<dyn Query>>| -> Box<dyn Query> {
+            Box::new(BooleanQuery::new(
+                clauses
+                    .into_iter()
+                    .map(|clause| (Occur::Should, clause))
+                    .collect(),
+            ))
+        };
+
+        let text_query = self.terms_query(query)?;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> =
+            vec![(Occur::Must, term_query(self.fields.kind, KIND_CHUNK))];
+        if let Some(text_query) = &text_query {
+            clauses.push((Occur::Must, text_query.box_clone()));
+        }
+        if !query.languages.is_empty() {
+            let languages = query
+                .languages
+                .iter()
+                .map(|language| term_query(self.fields.language, language))
+                .collect();
+            clauses.push((Occur::Must, any_of(languages)));
+        }
+        if !query.paths.is_empty() {
+            let mut paths = Vec::new();
+            for expression in query.path_expressions() {
+                paths.push(
+                    Box::new(RegexQuery::from_pattern(&expression, self.fields.path)?)
+                        as Box<dyn Query>,
+                );
+            }
+            clauses.push((Occur::Must, any_of(paths)));
+        }
+        if query.has_date_filters() {
+            let lower = query.after.map_or(Bound::Unbounded, Bound::Included);
+            let upper = query.before.map_or(Bound::Unbounded, Bound::Excluded);
+            clauses.push((
+                Occur::Must,
+                Box::new(RangeQuery::new_i64_bounds(
+                    self.fields.timestamp,
+                    lower,
+                    upper,
+                )),
+            ));
+        }
+        if let Some(filter) = filter {
+            let mut collections = Vec::new();
+            for expression in filter.expressions() {
+                collections.push(Box::new(RegexQuery::from_pattern(
+                    expression,
+                    self.fields.collection,
+                )?) as Box<dyn Query>);
+            }
+            clauses.push((Occur::Must, any_of(collections)));
+        }
+
+        let searcher = self.reader.searcher();
+        let top_docs = searcher.search(&BooleanQuery::new(clauses), &TopDocs::with_limit(limit))?;
+
+        // 摘要只根据查询词生成
+        let snippets = match &text_query {
+            Some(text_query) => {
+                let mut snippets =
+                    SnippetGenerator::create(&searcher, &**text_query, self.fields.content)?;
+                snippets.set_max_num_chars(SNIPPET_MAX_CHARS);
+                Some(snippets)
+            }
+            None => None,
+        };
+        let highlighter = query.highlighter();
+
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let document = searcher.doc(address)?;
+            let text = |field: Field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_text())
+                    .unwrap_or("")
+                    .to_string()
+            };
+            let line = |field: Field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_u64())
+                    .unwrap_or(0) as usize
+            };
+
+            let content = text(self.fields.content);
+            let mut snippet = snippets
+                .as_ref()
+                .map(|snippets| snippets.snippet_from_doc(&document).fragment().to_string())
+                .unwrap_or_default();
+            if snippet.is_empty() {
+                // 只匹配标题或没有查询词时使用内容开头作为片段
+                snippet = content.chars().take(SNIPPET_MAX_CHARS).collect();
+            }
+            let start_line = line(self.fields.start_line);
+
+            hits.push(SearchHit {
+                path: text(self.fields.path),
+                title: text(self.fields.title),
+                language: text(self.fields.language),
+                source: parse_source(&text(self.fields.source)),
+                snippet,
+                lines: Some((start_line, line(self.fields.end_line))),
+                highlights: highlighter.highlight(&content, start_line.max(1)),
+                collection: text(self.fields.collection),
+                score,
+                explanation: String::new(),
+            });
+        }
+        Ok(hits)
+    }
+}
+
+/// 打开索引目录
+fn open_directory(dir: &Path) -> AppResult<MmapDirectory> {
+    MmapDirectory::open(dir).map_err(|e| AppError::Knowledge {
+        operation: "open".to_string(),
+        description: e.to_string(),
+        index_path: Some(dir.to_path_buf()),
+        source: Some(Box::new(e)),
+    })
+}
+
+/// 解析文档来源
+fn parse_source(source: &str) -> DocumentSource {
+    if source == DocumentSource::Web.as_str() {
+        DocumentSource::Web
+    } else if source == DocumentSource::Git.as_str() {
+        DocumentSource::Git
+    } else {
+        DocumentSource::File
+    }
+}
+
+/// 旧格式索引中读取出的文档
+#[derive(Default)]
+struct LegacyDocument {
+    /// 元数据字段所在的记录已读取
+    has_meta: bool,
+    title: String,
+    language: String,
+    source: String,
+    collection: String,
+    indexed_at: i64,
+    content: Option<String>,
+}
+
+/// 读取旧格式索引中的所有文档，兼容整文件记录和分段记录两种格式
+fn read_legacy_documents(index: &Index) -> AppResult<Vec<(DocumentMeta, String)>> {
+    let schema = index.schema();
+    let reader = index.reader()?;
+    let searcher = reader.searcher();
+    let limit = (searcher.num_docs() as usize).max(1);
+
+    let mut legacy: BTreeMap<String, LegacyDocument> = BTreeMap::new();
+    for (_, address) in searcher.search(&AllQuery, &TopDocs::with_limit(limit))? {
+        let document: Document = searcher.doc(address)?;
+        let value = |name: &str| {
+            schema
+                .get_field(name)
+                .and_then(|field| document.get_first(field))
+        };
+        let text = |name: &str| {
+            value(name)
+                .and_then(|value| value.as_text())
+                .map(|text| text.to_string())
+        };
+
+        let entry = legacy.entry(text("path").unwrap_or_default()).or_default();
+        if let Some(body) = text("body") {
+            // 分段格式中保存完整内容的记录
+            entry.content = Some(body);
+            continue;
+        }
+        if value("start_line").is_none() {
+            // 整文件格式的记录
+            entry.content = text("content");
+        }
+        if !entry.has_meta {
+            entry.has_meta = true;
+            entry.title = text("title").unwrap_or_default();
+            entry.language = text("language").unwrap_or_default();
+            entry.source = text("source").unwrap_or_default();
+            entry.collection = text("collection").unwrap_or_else(default_collection);
+            entry.indexed_at = value("timestamp")
+                .and_then(|value| value.as_i64())
+                .unwrap_or_default();
+        }
+    }
+
+    Ok(legacy
+        .into_iter()
+        .filter_map(|(path, document)| {
+            let content = document.content?;
+            let title = if document.title.is_empty() {
+                Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone())
+            } else {
+                document.title
+            };
+            let meta = DocumentMeta {
+                path,
+                title,
+                language: document.language,
+                source: parse_source(&document.source),
+                size: content.len() as u64,
+                lines: content.lines().count(),
+                indexed_at: document.indexed_at,
+                collection: if document.has_meta {
+                    document.collection
+                } else {
+                    default_collection()
+                },
+            };
+            Some((meta, content))
+        })
+        .collect())
+}
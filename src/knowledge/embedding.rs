@@ -0,0 +1,280 @@
+//! 文本嵌入
+//!
+//! 将代码片段转换为向量，支持 Ollama 兼容的嵌入接口和不依赖外部服务的本地哈希嵌入
+
+use crate::config::app::EmbeddingConfig;
+use crate::error::{AppError, AppResult};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Ollama 默认API地址
+const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Ollama 默认嵌入模型
+const DEFAULT_OLLAMA_MODEL: &str = "nomic-embed-text";
+
+/// 本地嵌入的默认维度
+const DEFAULT_LOCAL_DIMENSIONS: usize = 256;
+
+/// 本地嵌入算法的版本，哈希或特征变化时递增，使已保存的向量重建
+const LOCAL_EMBEDDING_VERSION: u32 = 2;
+
+/// FNV-1a 64位哈希的初始值
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64位哈希的乘数
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 嵌入模型提供者
+pub trait EmbeddingProvider: Send + Sync {
+    /// 提供者标识，包含模型名称，模型变化时已有向量需要重建
+    fn id(&self) -> String;
+
+    /// 批量计算文本的嵌入向量
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>>;
+}
+
+/// 根据配置创建嵌入模型提供者，未启用时返回 `None`
+pub fn create_provider(config: &EmbeddingConfig) -> AppResult<Option<Box<dyn EmbeddingProvider>>> {
+    match config.provider.to_lowercase().as_str() {
+        "" | "none" => Ok(None),
+        "ollama" => Ok(Some(Box::new(OllamaEmbeddingProvider::new(
+            &config.base_url,
+            &config.model,
+        )))),
+        "local" => Ok(Some(Box::new(LocalEmbeddingProvider::new(
+            config.dimensions,
+        )))),
+        other => Err(AppError::knowledge(&format!(
+            "不支持的嵌入模型提供者: {}（可选: ollama, local）",
+            other
+        ))),
+    }
+}
+
+/// Ollama `/api/embed` 请求
+#[derive(Serialize)]
+struct OllamaEmbedRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Ollama `/api/embed` 响应
+#[derive(Deserialize)]
+struct OllamaEmbedResponse {
+    embeddings: Vec<Vec<f32>>,
+}
+
+/// Ollama 兼容的嵌入接口
+pub struct OllamaEmbeddingProvider {
+    /// HTTP客户端
+    client: reqwest::Client,
+    /// API基础URL
+    base_url: String,
+    /// 模型名称
+    model: String,
+}
+
+impl OllamaEmbeddingProvider {
+    /// 创建新的 Ollama 嵌入提供者，参数为空时使用默认值
+    pub fn new(base_url: &str, model: &str) -> Self {
+        let base_url = if base_url.is_empty() {
+            DEFAULT_OLLAMA_URL
+        } else {
+            base_url
+        };
+        let model = if model.is_empty() {
+            DEFAULT_OLLAMA_MODEL
+        } else {
+            model
+        };
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    /// 发送嵌入请求
+    async fn request(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        let url = format!("{}/api/embed", self.base_url);
+        let response = self
+            .client
+            .post(&url)
+            .json(&OllamaEmbedRequest {
+                model: &self.model,
+                input: texts,
+            })
+            .send()
+            .await
+            .map_err(|e| AppError::knowledge(&format!("请求嵌入接口失败 {}: {}", url, e)))?;
+
+        if !response.status().is_success() {
+            return Err(AppError::knowledge(&format!(
+                "嵌入接口返回错误 {}: {}",
+                response.status(),
+                response.text().await.unwrap_or_default()
+            )));
+        }
+
+        let body: OllamaEmbedResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::knowledge(&format!("无法解析嵌入接口响应: {}", e)))?;
+        if body.embeddings.len() != texts.len() {
+            return Err(AppError::knowledge(&format!(
+                "嵌入接口返回 {} 个向量，预期 {} 个",
+                body.embeddings.len(),
+                texts.len()
+            )));
+        }
+        Ok(body.embeddings)
+    }
+}
+
+impl EmbeddingProvider for OllamaEmbeddingProvider {
+    fn id(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        block_on(self.request(texts))?
+    }
+}
+
+/// 本地哈希嵌入：将词和子词哈希到固定维度，无需外部服务
+pub struct LocalEmbeddingProvider {
+    /// 向量维度
+    dimensions: usize,
+}
+
+impl LocalEmbeddingProvider {
+    /// 创建新的本地嵌入提供者，维度为0时使用默认值
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: if dimensions == 0 {
+                DEFAULT_LOCAL_DIMENSIONS
+            } else {
+                dimensions
+            },
+        }
+    }
+
+    /// 计算单个文本的向量
+    fn embed_text(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; self.dimensions];
+        for word in split_words(text) {
+            // 完整词权重较高，三字符子词用于匹配词形变化
+            add_feature(&mut vector, &word, 1.0);
+            let chars: Vec<char> = format!("<{}>", word).chars().collect();
+            for gram in chars.windows(3) {
+                add_feature(&mut vector, &gram.iter().collect::<String>(), 0.5);
+            }
+        }
+        normalize(&mut vector);
+        vector
+    }
+}
+
+impl EmbeddingProvider for LocalEmbeddingProvider {
+    fn id(&self) -> String {
+        format!("local-v{}:{}", LOCAL_EMBEDDING_VERSION, self.dimensions)
+    }
+
+    fn embed(&self, texts: &[String]) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| self.embed_text(text)).collect())
+    }
+}
+
+/// 将文本拆分为小写词，标识符按驼峰和下划线拆分
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for token in text.split(|c: char| !c.is_alphanumeric()) {
+        let mut current = String::new();
+        let mut prev_lower = false;
+        for c in token.chars() {
+            if c.is_uppercase() && prev_lower && !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+            current.extend(c.to_lowercase());
+        }
+        if !current.is_empty() {
+            words.push(current);
+        }
+    }
+    words
+}
+
+/// 计算特征的 FNV-1a 哈希，结果不随 Rust 版本变化，保证保存的向量可以复用
+fn feature_hash(feature: &str) -> u64 {
+    feature.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+/// 将特征哈希到向量中
+fn add_feature(vector: &mut [f32], feature: &str, weight: f32) {
+    let hash = feature_hash(feature);
+    let index = (hash % vector.len() as u64) as usize;
+    // 使用哈希的最高位决定符号，减少哈希冲突的影响
+    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    vector[index] += sign * weight;
+}
+
+/// 归一化向量
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+/// 计算两个向量的余弦相似度
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+/// 在同步代码中执行异步请求，兼容已在 tokio 运行时中调用的情况
+///
+/// 多线程运行时中使用 `block_in_place`；单线程运行时不支持阻塞当前线程，改为在独立线程中创建运行时执行
+fn block_on<F>(future: F) -> AppResult<F::Output>
+where
+    F: Future + Send,
+    F::Output: Send,
+{
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            Ok(tokio::task::block_in_place(|| handle.block_on(future)))
+        }
+        Ok(_) => std::thread::scope(|scope| {
+            scope
+                .spawn(|| current_thread_block_on(future))
+                .join()
+                .map_err(|_| AppError::knowledge("嵌入请求线程异常退出"))?
+        }),
+        Err(_) => current_thread_block_on(future),
+    }
+}
+
+/// 创建单线程运行时执行异步请求
+fn current_thread_block_on<F: Future>(future: F) -> AppResult<F::Output> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future))
+}
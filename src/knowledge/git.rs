@@ -0,0 +1,400 @@
+//! Git 历史
+//!
+//! 通过本地 `git` 命令读取提交历史和 blame 信息。提交说明、作者、日期和各文件的
+//! 差异片段作为文档写入知识库的 `git` 集合，blame 结果用于为放入AI上下文的代码片段
+//! 附加最近一次修改的提交说明
+
+use crate::error::{AppError, AppResult};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 提交历史默认写入的集合
+pub const GIT_COLLECTION: &str = "git";
+
+/// 提交文档路径的前缀，后接提交哈希
+pub const GIT_DOCUMENT_PREFIX: &str = "git://";
+
+/// 每个文件保留的差异行数上限，超出部分截断
+const MAX_DIFF_LINES_PER_FILE: usize = 200;
+
+/// 附加到代码片段的提交说明行数上限
+const MAX_BLAME_MESSAGE_LINES: usize = 10;
+
+/// 短哈希长度
+const SHORT_HASH_LEN: usize = 8;
+
+/// 提交记录分隔符
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// 提交字段分隔符
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// 单个文件的差异
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileDiff {
+    /// 文件路径（相对于仓库根目录）
+    pub path: String,
+    /// 差异片段，每个片段以 `@@` 行开头
+    pub hunks: Vec<String>,
+}
+
+/// 提交记录
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitCommit {
+    /// 提交哈希
+    pub hash: String,
+    /// 作者名称
+    pub author: String,
+    /// 作者邮箱
+    pub email: String,
+    /// 提交时间（Unix秒）
+    pub timestamp: i64,
+    /// 提交说明标题
+    pub subject: String,
+    /// 完整的提交说明
+    pub message: String,
+    /// 修改的文件
+    pub files: Vec<FileDiff>,
+}
+
+impl GitCommit {
+    /// 提交在知识库中的文档路径
+    pub fn document_path(&self) -> String {
+        format!("{}{}", GIT_DOCUMENT_PREFIX, self.hash)
+    }
+
+    /// 短哈希
+    pub fn short_hash(&self) -> &str {
+        short_hash(&self.hash)
+    }
+
+    /// 生成写入知识库的文档内容，格式与 `git show` 相近
+    pub fn to_document(&self) -> String {
+        let mut document = format!(
+            "commit {}\nAuthor: {} <{}>\nDate:   {}\n\n{}\n",
+            self.hash,
+            self.author,
+            self.email,
+            format_time(self.timestamp),
+            self.message.trim_end()
+        );
+        for file in &self.files {
+            document.push_str(&format!("\ndiff {}\n", file.path));
+            for hunk in &file.hunks {
+                document.push_str(hunk);
+                document.push('\n');
+            }
+        }
+        document
+    }
+}
+
+/// 代码行最近一次修改的提交
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlameCommit {
+    /// 提交哈希
+    pub hash: String,
+    /// 作者名称
+    pub author: String,
+    /// 提交时间（Unix秒）
+    pub timestamp: i64,
+    /// 提交说明标题
+    pub summary: String,
+    /// 完整的提交说明
+    pub message: String,
+}
+
+impl BlameCommit {
+    /// 短哈希
+    pub fn short_hash(&self) -> &str {
+        short_hash(&self.hash)
+    }
+
+    /// 生成附加到代码片段的说明：提交、作者、日期和截断后的提交说明
+    pub fn describe(&self) -> String {
+        let message = self.message.trim();
+        let message = if message.is_empty() {
+            self.summary.as_str()
+        } else {
+            message
+        };
+        let mut lines: Vec<&str> = message.lines().take(MAX_BLAME_MESSAGE_LINES).collect();
+        if message.lines().count() > MAX_BLAME_MESSAGE_LINES {
+            lines.push("...");
+        }
+        format!(
+            "最近修改: {} {} {}\n{}",
+            self.short_hash(),
+            self.author,
+            format_time(self.timestamp),
+            lines
+                .iter()
+                .map(|line| format!("    {}", line).trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        )
+    }
+}
+
+/// 提交历史索引结果
+#[derive(Debug, Clone, Default)]
+pub struct GitIndexSummary {
+    /// 新索引的提交数量
+    pub commits: usize,
+    /// 新索引的文件差异数量
+    pub files: usize,
+    /// 索引到的最新提交
+    pub head: Option<String>,
+}
+
+/// 本地 Git 仓库
+#[derive(Debug, Clone)]
+pub struct GitRepository {
+    /// 仓库根目录
+    root: PathBuf,
+}
+
+impl GitRepository {
+    /// 打开包含指定路径的仓库
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let root = run_git(path, &["rev-parse", "--show-toplevel"]).map_err(|e| {
+            AppError::knowledge(&format!("{} 不在 Git 仓库中: {}", path.display(), e))
+        })?;
+        Ok(Self {
+            root: PathBuf::from(root.trim()),
+        })
+    }
+
+    /// 仓库根目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 当前 HEAD 提交，仓库还没有提交时返回 `None`
+    pub fn head(&self) -> AppResult<Option<String>> {
+        match run_git(&self.root, &["rev-parse", "--verify", "--quiet", "HEAD"]) {
+            Ok(head) => Ok(Some(head.trim().to_string())),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// 提交是否在当前 HEAD 的历史中
+    pub fn is_ancestor(&self, commit: &str) -> bool {
+        run_git(&self.root, &["merge-base", "--is-ancestor", commit, "HEAD"]).is_ok()
+    }
+
+    /// 读取 `since` 之后（不含）到 `until` 的提交，按时间从旧到新排序；
+    /// 指定 `max_count` 时只保留最新的若干个提交
+    pub fn commits(
+        &self,
+        since: Option<&str>,
+        until: &str,
+        max_count: Option<usize>,
+    ) -> AppResult<Vec<GitCommit>> {
+        let format = format!(
+            "--format={}%H{}%an{}%ae{}%at{}%B{}",
+            RECORD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR,
+            FIELD_SEPARATOR
+        );
+        let range = match since {
+            Some(since) => format!("{}..{}", since, until),
+            None => until.to_string(),
+        };
+        let mut args = vec![
+            "log".to_string(),
+            "--reverse".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--patch".to_string(),
+            "--unified=3".to_string(),
+            format,
+        ];
+        if let Some(max_count) = max_count {
+            args.push(format!("--max-count={}", max_count));
+        }
+        args.push(range);
+        args.push("--".to_string());
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        Ok(parse_log(&run_git(&self.root, &args)?))
+    }
+
+    /// 查找指定行范围内最近一次修改的提交，行号从1开始；未提交的修改不计入
+    pub fn blame(
+        &self,
+        file: &Path,
+        lines: Option<(usize, usize)>,
+    ) -> AppResult<Option<BlameCommit>> {
+        let file = file.to_string_lossy();
+        let mut args = vec!["blame".to_string(), "--porcelain".to_string()];
+        if let Some((start, end)) = lines {
+            args.push(format!("-L{},{}", start.max(1), end.max(start.max(1))));
+        }
+        args.push("--".to_string());
+        args.push(file.to_string());
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let Some(mut commit) = parse_blame(&run_git(&self.root, &args)?) else {
+            return Ok(None);
+        };
+        commit.message = run_git(&self.root, &["log", "-1", "--format=%B", &commit.hash])?
+            .trim_end()
+            .to_string();
+        Ok(Some(commit))
+    }
+}
+
+/// 查找文件中指定行范围最近一次修改的提交，文件不在仓库中或未提交时返回 `None`
+pub fn blame_lines(file: &Path, start: usize, end: usize) -> Option<BlameCommit> {
+    let dir = file.parent()?;
+    let repository = GitRepository::open(dir).ok()?;
+    match repository.blame(file, Some((start, end))) {
+        Ok(commit) => commit,
+        Err(e) => {
+            log::debug!("无法获取 {} 的 blame 信息: {}", file.display(), e);
+            None
+        }
+    }
+}
+
+/// 解析 `git log --patch` 的输出
+pub fn parse_log(output: &str) -> Vec<GitCommit> {
+    let mut commits = Vec::new();
+    for record in output.split(RECORD_SEPARATOR) {
+        let fields: Vec<&str> = record.splitn(6, FIELD_SEPARATOR).collect();
+        let [hash, author, email, timestamp, message, patch] = fields[..] else {
+            continue;
+        };
+        let message = message.trim().to_string();
+        commits.push(GitCommit {
+            hash: hash.trim().to_string(),
+            author: author.to_string(),
+            email: email.to_string(),
+            timestamp: timestamp.trim().parse().unwrap_or_default(),
+            subject: message.lines().next().unwrap_or_default().to_string(),
+            message,
+            files: parse_patch(patch),
+        });
+    }
+    commits
+}
+
+/// 将补丁按文件拆分为差异片段
+fn parse_patch(patch: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    // 当前文件已保留的差异行数
+    let mut kept = 0;
+    for line in patch.lines() {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            let path = header
+                .rfind(" b/")
+                .map(|index| &header[index + 3..])
+                .unwrap_or(header);
+            files.push(FileDiff {
+                path: path.to_string(),
+                hunks: Vec::new(),
+            });
+            kept = 0;
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            if kept >= MAX_DIFF_LINES_PER_FILE {
+                continue;
+            }
+            file.hunks.push(line.to_string());
+            kept += 1;
+            continue;
+        }
+        let Some(hunk) = file.hunks.last_mut() else {
+            // 片段之前的 index、---、+++ 等文件头
+            continue;
+        };
+        if kept < MAX_DIFF_LINES_PER_FILE {
+            hunk.push('\n');
+            hunk.push_str(line);
+        } else if kept == MAX_DIFF_LINES_PER_FILE {
+            hunk.push_str("\n... (差异过长，已截断)");
+        }
+        kept += 1;
+    }
+    files
+}
+
+/// 解析 `git blame --porcelain` 的输出，返回时间最新的已提交修改
+fn parse_blame(output: &str) -> Option<BlameCommit> {
+    let mut commits: HashMap<String, BlameCommit> = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in output.lines() {
+        if line.starts_with('\t') {
+            current = None;
+            continue;
+        }
+        let Some(hash) = &current else {
+            let hash = line.split(' ').next().unwrap_or_default();
+            if hash.len() >= 40 && hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                commits
+                    .entry(hash.to_string())
+                    .or_insert_with(|| BlameCommit {
+                        hash: hash.to_string(),
+                        ..Default::default()
+                    });
+                current = Some(hash.to_string());
+            }
+            continue;
+        };
+        let Some(commit) = commits.get_mut(hash) else {
+            continue;
+        };
+        if let Some(author) = line.strip_prefix("author ") {
+            commit.author = author.to_string();
+        } else if let Some(time) = line.strip_prefix("author-time ") {
+            commit.timestamp = time.parse().unwrap_or_default();
+        } else if let Some(summary) = line.strip_prefix("summary ") {
+            commit.summary = summary.to_string();
+        }
+    }
+
+    commits
+        .into_values()
+        // 未提交的修改使用全零哈希
+        .filter(|commit| commit.hash.chars().any(|c| c != '0'))
+        .max_by_key(|commit| commit.timestamp)
+}
+
+/// 在指定目录执行 git 命令，返回标准输出
+fn run_git(dir: &Path, args: &[&str]) -> AppResult<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| AppError::knowledge(&format!("无法执行 git 命令: {}", e)))?;
+    if !output.status.success() {
+        return Err(AppError::knowledge(&format!(
+            "git {} 执行失败: {}",
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 取哈希的前几位
+fn short_hash(hash: &str) -> &str {
+    &hash[..hash.len().min(SHORT_HASH_LEN)]
+}
+
+/// 格式化提交时间
+fn format_time(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
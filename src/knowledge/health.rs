@@ -0,0 +1,147 @@
+//! 知识库健康检查
+//!
+//! 统计知识库内容，检查元数据、全文索引、符号索引、向量索引和索引缓存之间的一致性，
+//! 并描述修复和压缩的结果
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// 知识库统计信息
+#[derive(Debug, Clone, Default)]
+pub struct KnowledgeStats {
+    /// 文档总数
+    pub documents: usize,
+    /// 各来源的文档数量
+    pub by_source: BTreeMap<String, usize>,
+    /// 各语言的文档数量
+    pub by_language: BTreeMap<String, usize>,
+    /// 各集合的文档数量
+    pub by_collection: BTreeMap<String, usize>,
+    /// 符号总数
+    pub symbols: usize,
+    /// 各元素类型的符号数量
+    pub symbols_by_kind: BTreeMap<String, usize>,
+    /// 已生成向量的片段数量
+    pub vectors: usize,
+    /// 已建立交叉引用的文件数量
+    pub xref_files: usize,
+    /// 索引缓存中的文件数量
+    pub cached_files: usize,
+    /// 全文索引和符号索引的段数量
+    pub segments: usize,
+    /// 索引目录大小（字节）
+    pub index_size: u64,
+    /// 元数据目录大小（字节）
+    pub metadata_size: u64,
+    /// 最近一次更新时间（Unix秒），不含 Git 提交时间
+    pub last_updated: Option<i64>,
+}
+
+/// 一致性检查发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HealthIssue {
+    /// 已索引的本地文件不存在
+    MissingFile(String),
+    /// 有元数据但全文索引中没有内容
+    MissingContent(String),
+    /// 全文索引中的文档没有元数据
+    OrphanedDocument(String),
+    /// 符号索引中的符号没有对应文档
+    OrphanedSymbols { path: String, count: usize },
+    /// 向量索引中的片段没有对应文档
+    OrphanedVectors { path: String, count: usize },
+    /// 交叉引用没有对应文档
+    OrphanedXrefs(String),
+    /// 索引缓存中的文件没有对应文档
+    StaleCacheEntry(String),
+    /// 索引缓存记录的代码元素数量与符号索引不一致
+    CacheMismatch {
+        path: String,
+        cached: usize,
+        indexed: usize,
+    },
+}
+
+impl fmt::Display for HealthIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthIssue::MissingFile(path) => write!(f, "文件已不存在: {}", path),
+            HealthIssue::MissingContent(path) => write!(f, "全文索引缺少文档: {}", path),
+            HealthIssue::OrphanedDocument(path) => write!(f, "全文索引中有孤立文档: {}", path),
+            HealthIssue::OrphanedSymbols { path, count } => {
+                write!(f, "符号索引中有 {} 个孤立符号: {}", count, path)
+            }
+            HealthIssue::OrphanedVectors { path, count } => {
+                write!(f, "向量索引中有 {} 个孤立片段: {}", count, path)
+            }
+            HealthIssue::OrphanedXrefs(path) => write!(f, "交叉引用没有对应文档: {}", path),
+            HealthIssue::StaleCacheEntry(path) => write!(f, "索引缓存中有过期条目: {}", path),
+            HealthIssue::CacheMismatch {
+                path,
+                cached,
+                indexed,
+            } => write!(
+                f,
+                "索引缓存与符号索引不一致: {}（缓存 {} 个元素，索引 {} 个）",
+                path, cached, indexed
+            ),
+        }
+    }
+}
+
+/// 一致性检查报告
+#[derive(Debug, Clone, Default)]
+pub struct VerifyReport {
+    /// 检查的文档数量
+    pub checked: usize,
+    /// 发现的问题
+    pub issues: Vec<HealthIssue>,
+}
+
+impl VerifyReport {
+    /// 是否没有发现问题
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// 修复结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairSummary {
+    /// 删除的文档数量（文件已不存在或内容无法恢复）
+    pub removed: usize,
+    /// 重新索引的文件数量
+    pub reindexed: usize,
+    /// 清理的孤立数据和过期缓存数量
+    pub cleaned: usize,
+}
+
+/// 压缩结果
+#[derive(Debug, Clone, Default)]
+pub struct CompactSummary {
+    /// 压缩前的段数量
+    pub segments_before: usize,
+    /// 压缩后的段数量
+    pub segments_after: usize,
+    /// 压缩前的索引目录大小（字节）
+    pub size_before: u64,
+    /// 压缩后的索引目录大小（字节）
+    pub size_after: u64,
+}
+
+/// 计算目录中所有文件的总大小，目录不存在时返回0
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
@@ -0,0 +1,35 @@
+//! 混合检索排序
+//!
+//! 使用倒数排名融合（RRF）合并关键词检索和语义检索的结果
+
+use std::collections::HashMap;
+
+/// RRF 平滑常数，降低排名靠前结果之间的得分差距
+pub const RRF_K: f32 = 60.0;
+
+/// 融合多个按相关性排序的结果列表，返回按融合得分降序排列的键
+///
+/// 每个结果的得分为其在各列表中 `1 / (k + rank)` 之和，rank 从1开始
+pub fn reciprocal_rank_fusion(rankings: &[Vec<String>], k: f32) -> Vec<(String, f32)> {
+    let mut scores: HashMap<&str, f32> = HashMap::new();
+    // 记录首次出现的位置，得分相同时保持稳定顺序
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+
+    for ranking in rankings {
+        for (rank, key) in ranking.iter().enumerate() {
+            *scores.entry(key).or_default() += 1.0 / (k + rank as f32 + 1.0);
+            let order = first_seen.len();
+            first_seen.entry(key).or_insert(order);
+        }
+    }
+
+    let mut fused: Vec<(String, f32)> = scores
+        .into_iter()
+        .map(|(key, score)| (key.to_string(), score))
+        .collect();
+    fused.sort_by(|a, b| {
+        b.1.total_cmp(&a.1)
+            .then_with(|| first_seen[a.0.as_str()].cmp(&first_seen[b.0.as_str()]))
+    });
+    fused
+}
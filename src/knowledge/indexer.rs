@@ -5,6 +5,10 @@
 use crate::config::app::KnowledgeConfig;
 use crate::error::AppResult;
 use crate::knowledge::base::CodeFile;
+use crate::knowledge::pipeline::{IndexEvent, IndexPipeline, IndexSink, IndexStats, ParsedFile};
+use crate::knowledge::query::SearchQuery;
+use crate::knowledge::symbol_index::{SymbolCounts, SymbolIndex, SymbolMatch};
+use crate::parse_cache::{diff_elements, ElementChanges};
 use crate::parsers::{initialize_parsers, CodeElement, CodeElementType, PARSER_REGISTRY};
 use hashbrown::HashMap;
 use serde::{Deserialize, Serialize};
@@ -13,6 +17,9 @@ use std::io::{Read, Write};
 use std::path::PathBuf;
 use std::time::SystemTime;
 
+/// 默认返回的搜索结果数量
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
 /// 索引缓存项，存储单个文件的索引信息
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct IndexCacheItem {
@@ -71,6 +78,10 @@ pub struct CodeIndexer {
     cache: IndexCache,
     /// 缓存是否已加载
     cache_loaded: bool,
+    /// 代码符号索引
+    symbols: Option<SymbolIndex>,
+    /// 静默模式，不输出索引进度
+    quiet: bool,
 }
 
 impl CodeIndexer {
@@ -82,9 +93,28 @@ impl CodeIndexer {
             parsers_initialized: false,
             cache: IndexCache::default(),
             cache_loaded: false,
+            symbols: None,
+            quiet: false,
         })
     }
 
+    /// 设置静默模式，开启后不再输出索引进度
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
+    /// 索引器是否已初始化
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// 输出索引进度
+    fn log(&self, message: &str) {
+        if !self.quiet {
+            println!("{}", message);
+        }
+    }
+
     /// 获取缓存文件路径
     fn get_cache_path(&self) -> PathBuf {
         self.config.metadata_dir.join("index_cache.json")
@@ -109,6 +139,22 @@ impl CodeIndexer {
         self.cache.total_elements
     }
 
+    /// 在索引缓存中按名称查找代码元素
+    pub fn find_symbol(&self, name: &str) -> Vec<CodeElement> {
+        let mut found = Vec::new();
+        for item in self.cache.files.values() {
+            for element in item.elements.iter().filter(|e| e.name == name) {
+                let mut element = element.clone();
+                // 旧缓存中的元素可能没有记录文件路径，使用缓存项的路径补全
+                if element.definition.file_path.is_empty() {
+                    element.definition.file_path = item.path.to_string_lossy().to_string();
+                }
+                found.push(element);
+            }
+        }
+        found
+    }
+
     /// 从文件加载索引缓存
     pub fn load_cache(&mut self) -> AppResult<()> {
         let cache_path = self.get_cache_path();
@@ -118,15 +164,15 @@ impl CodeIndexer {
             file.read_to_string(&mut cache_json)?;
             self.cache = serde_json::from_str(&cache_json)?;
             self.cache_loaded = true;
-            println!(
+            self.log(&format!(
                 "加载缓存成功，包含 {} 个文件，{} 个代码元素",
                 self.cache.file_count, self.cache.total_elements
-            );
+            ));
         } else {
             // 缓存文件不存在，使用默认缓存
             self.cache = IndexCache::default();
             self.cache_loaded = true;
-            println!("未找到缓存文件，使用新缓存");
+            self.log("未找到缓存文件，使用新缓存");
         }
         Ok(())
     }
@@ -134,17 +180,24 @@ impl CodeIndexer {
     /// 更新索引缓存
     pub fn update_cache(&mut self, file: &CodeFile, elements: &[CodeElement]) -> AppResult<()> {
         let file_key = file.path.to_str().unwrap_or("").to_string();
-        let now = SystemTime::now();
 
         // 创建缓存项
         let cache_item = IndexCacheItem {
             path: file.path.clone(),
-            modified_at: SystemTime::now(),
+            modified_at: fs::metadata(&file.path)
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or_else(|_| SystemTime::now()),
             size: file.size,
             element_count: elements.len(),
             elements: elements.to_vec(),
         };
+        self.insert_cache_item(file_key, cache_item);
 
+        Ok(())
+    }
+
+    /// 写入缓存项，替换同一文件的旧缓存项并更新统计信息
+    fn insert_cache_item(&mut self, file_key: String, cache_item: IndexCacheItem) {
         // 移除旧缓存项（如果存在）
         if let Some(old_item) = self.cache.files.remove(&file_key) {
             self.cache.total_elements = self
@@ -153,14 +206,40 @@ impl CodeIndexer {
                 .saturating_sub(old_item.element_count);
         }
 
-        // 添加新缓存项
-        self.cache.files.insert(file_key.clone(), cache_item);
-
         // 更新缓存统计信息
+        self.cache.total_elements += cache_item.elements.len();
+        self.cache.files.insert(file_key, cache_item);
         self.cache.file_count = self.cache.files.len();
-        self.cache.total_elements += elements.len();
-        self.cache.updated_at = now;
+        self.cache.updated_at = SystemTime::now();
+    }
 
+    /// 获取索引缓存
+    pub fn cache(&self) -> &IndexCache {
+        &self.cache
+    }
+
+    /// 使用已解析的缓存项恢复文件的符号索引，不重新解析文件；需调用 `commit` 提交
+    ///
+    /// 本地文件内容与缓存的内容一致时使用本地文件的修改时间，避免下次添加时重新索引
+    pub fn restore_file(&mut self, mut cache_item: IndexCacheItem, content: &str) -> AppResult<()> {
+        if !self.initialized {
+            self.init()?;
+        }
+
+        let file_key = cache_item.path.to_string_lossy().to_string();
+        if let Some(symbols) = self.symbols.as_mut() {
+            symbols.replace_file(&file_key, content, &cache_item.elements)?;
+        }
+
+        let unchanged = fs::read_to_string(&cache_item.path).is_ok_and(|local| local == content);
+        if let (true, Ok(modified)) = (
+            unchanged,
+            fs::metadata(&cache_item.path).and_then(|metadata| metadata.modified()),
+        ) {
+            cache_item.modified_at = modified;
+        }
+        cache_item.element_count = cache_item.elements.len();
+        self.insert_cache_item(file_key, cache_item);
         Ok(())
     }
 
@@ -178,6 +257,18 @@ impl CodeIndexer {
         // 加载索引缓存
         self.load_cache()?;
 
+        // 打开符号索引，未配置索引目录时使用内存索引
+        let symbols = if self.config.index_dir.as_os_str().is_empty() {
+            SymbolIndex::in_memory()?
+        } else {
+            SymbolIndex::open(&self.config.index_dir.join("symbols"))?
+        };
+        // 符号索引为空时缓存已失效，需要重新索引所有文件
+        if symbols.num_symbols() == 0 && self.cache.total_elements > 0 {
+            self.cache = IndexCache::default();
+        }
+        self.symbols = Some(symbols);
+
         self.initialized = true;
         Ok(())
     }
@@ -193,139 +284,271 @@ impl CodeIndexer {
 
     /// 索引单个文件，实现增量索引
     pub fn index_file(&mut self, file: CodeFile) -> AppResult<()> {
+        self.index_file_uncommitted(file)?;
+        self.commit()
+    }
+
+    /// 提交符号索引的挂起修改
+    pub fn commit(&mut self) -> AppResult<()> {
+        if let Some(symbols) = self.symbols.as_mut() {
+            symbols.commit()?;
+        }
+        Ok(())
+    }
+
+    /// 按文件和元素类型统计符号索引中的符号
+    pub fn symbol_counts(&mut self) -> AppResult<SymbolCounts> {
+        if !self.initialized {
+            self.init()?;
+        }
+        match self.symbols.as_ref() {
+            Some(symbols) => symbols.counts(),
+            None => Ok(SymbolCounts::default()),
+        }
+    }
+
+    /// 获取符号索引的段数量
+    pub fn segment_count(&self) -> usize {
+        self.symbols
+            .as_ref()
+            .map(SymbolIndex::segment_count)
+            .unwrap_or(0)
+    }
+
+    /// 合并符号索引的所有段，返回合并前的段数量
+    pub fn compact(&mut self) -> AppResult<usize> {
+        if !self.initialized {
+            self.init()?;
+        }
+        match self.symbols.as_mut() {
+            Some(symbols) => symbols.compact(),
+            None => Ok(0),
+        }
+    }
+
+    /// 从索引和缓存中移除文件
+    pub fn remove_file(&mut self, path: &std::path::Path) -> AppResult<()> {
+        if !self.initialized {
+            self.init()?;
+        }
+
+        let file_key = path.to_str().unwrap_or("").to_string();
+        if let Some(old_item) = self.cache.files.remove(&file_key) {
+            self.cache.file_count = self.cache.files.len();
+            self.cache.total_elements = self
+                .cache
+                .total_elements
+                .saturating_sub(old_item.element_count);
+            self.cache.updated_at = SystemTime::now();
+        }
+        if let Some(symbols) = self.symbols.as_mut() {
+            symbols.remove_file(&file_key)?;
+            symbols.commit()?;
+        }
+        Ok(())
+    }
+
+    /// 清空符号索引和索引缓存
+    pub fn clear(&mut self) -> AppResult<()> {
+        if !self.initialized {
+            self.init()?;
+        }
+
+        if let Some(symbols) = self.symbols.as_mut() {
+            symbols.clear()?;
+            symbols.commit()?;
+        }
+        self.cache = IndexCache::default();
+        self.save_cache()
+    }
+
+    /// 索引单个文件但不提交符号索引，用于批量索引，完成后需调用 `commit`
+    pub fn index_file_uncommitted(&mut self, file: CodeFile) -> AppResult<()> {
         // 确保索引器已初始化
         if !self.initialized {
             self.init()?;
         }
 
         let file_key = file.path.to_str().unwrap_or("").to_string();
-        let file_metadata = std::fs::metadata(&file.path)?;
-        let file_modified = file_metadata.modified()?;
+        let file_modified = std::fs::metadata(&file.path)?.modified()?;
 
         // 检查文件是否已经在缓存中，且未修改
-        let needs_reindex = if let Some(cache_item) = self.cache.files.get(&file_key) {
-            // 比较修改时间和大小
-            cache_item.modified_at != file_modified || cache_item.size != file.size
-        } else {
-            // 文件不在缓存中，需要索引
-            true
-        };
-
-        if !needs_reindex {
+        if self.is_unchanged(&file_key, file_modified, file.size) {
             // 文件未修改，跳过索引
-            println!("文件未修改，跳过索引: {:?}", file.path);
+            self.log(&format!("文件未修改，跳过索引: {:?}", file.path));
             return Ok(());
         }
 
-        println!("开始索引文件: {:?}", file.path);
-
         // 使用解析器解析代码元素
-        let registry = PARSER_REGISTRY.read().unwrap();
-        if let Some(parser) = registry.get_parser_by_filename(file.path.to_str().unwrap_or("")) {
-            // 解析文件内容，生成代码元素
-            let code_elements = parser.parse_file(file.path.to_str().unwrap(), &file.content)?;
-
-            // TODO: 主人~ 这里需要实现将代码元素添加到tantivy索引的逻辑
-            // 提示：遍历code_elements，为每个元素创建索引文档
-            println!(
-                "索引文件: {:?}，找到 {} 个代码元素",
-                file.path,
-                code_elements.len()
-            );
-
-            // 简单打印找到的元素
-            for element in &code_elements {
-                println!("  - {:?}: {}", element.element_type, element.name);
+        match Self::parse_elements(&file_key, &file.content)? {
+            Some(code_elements) => {
+                self.store_elements(&file, &code_elements, None)?;
+                self.log(&format!(
+                    "索引文件: {:?}，找到 {} 个代码元素",
+                    file.path,
+                    code_elements.len()
+                ));
             }
-
-            // 更新缓存
-            self.update_cache(&file, &code_elements)?;
-        } else {
-            println!("警告：未找到适合文件 {:?} 的解析器", file.path);
+            None => self.log(&format!("警告：未找到适合文件 {:?} 的解析器", file.path)),
         }
 
         Ok(())
     }
 
-    /// 索引目录
-    pub fn index_directory(&mut self, path: &std::path::Path) -> AppResult<()> {
-        use std::fs;
-        use std::io::Read;
-        use walkdir::WalkDir;
+    /// 判断文件与索引缓存中的记录是否一致（修改时间和大小都相同）
+    pub fn is_unchanged(&self, file_key: &str, modified_at: SystemTime, size: u64) -> bool {
+        self.cache
+            .files
+            .get(file_key)
+            .is_some_and(|item| item.modified_at == modified_at && item.size == size)
+    }
 
-        // 确保索引器已初始化
+    /// 获取索引缓存中各文件的修改时间和大小，供工作线程判断文件是否需要重新索引
+    pub fn cache_snapshot(&self) -> std::collections::HashMap<String, (SystemTime, u64)> {
+        self.cache
+            .files
+            .iter()
+            .map(|(key, item)| (key.clone(), (item.modified_at, item.size)))
+            .collect()
+    }
+
+    /// 使用文件对应的解析器解析代码元素，没有对应解析器时返回空
+    pub fn parse_elements(file_key: &str, content: &str) -> AppResult<Option<Vec<CodeElement>>> {
+        let Some(parser) = PARSER_REGISTRY
+            .read()
+            .unwrap()
+            .get_parser_by_filename(file_key)
+        else {
+            return Ok(None);
+        };
+        let mut code_elements = parser.parse_file(file_key, content)?;
+
+        // 解析器不记录文件路径，在此补全
+        for element in code_elements.iter_mut() {
+            if element.definition.file_path.is_empty() {
+                element.definition.file_path = file_key.to_string();
+            }
+        }
+        Ok(Some(code_elements))
+    }
+
+    /// 写入已解析的代码元素并更新缓存；需调用 `commit` 提交
+    ///
+    /// 索引中该文件的符号与缓存一致时只更新变化的符号，否则替换该文件的所有符号；
+    /// `changes` 为增量解析已得到的元素差异，为空时与缓存中的元素比较
+    pub fn store_elements(
+        &mut self,
+        file: &CodeFile,
+        elements: &[CodeElement],
+        changes: Option<&ElementChanges>,
+    ) -> AppResult<()> {
         if !self.initialized {
             self.init()?;
         }
 
-        // 遍历目录
-        for entry in WalkDir::new(path)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.file_type().is_file())
-        {
-            let file_path = entry.path();
-
-            // 检查文件扩展名是否在支持列表中
-            if let Some(ext) = file_path.extension() {
-                let ext_str = ext.to_str().unwrap_or("");
-                if self
-                    .config
-                    .supported_extensions
-                    .contains(&ext_str.to_lowercase())
-                {
-                    // 检查是否在排除列表中
-                    let path_str = file_path.to_str().unwrap_or("");
-                    let mut should_exclude = false;
-                    for pattern in &self.config.exclude_patterns {
-                        if path_str.contains(pattern) {
-                            should_exclude = true;
-                            break;
+        let file_key = file.path.to_str().unwrap_or("").to_string();
+        if let Some(symbols) = self.symbols.as_mut() {
+            let cached = self.cache.files.get(&file_key).map(|item| &item.elements);
+            match cached {
+                Some(cached) if symbols.file_symbol_count(&file_key)? == cached.len() => {
+                    match changes {
+                        Some(changes) => symbols.update_file(&file_key, &file.content, changes)?,
+                        None => {
+                            let changes = diff_elements(cached, elements);
+                            symbols.update_file(&file_key, &file.content, &changes)?;
                         }
                     }
-
-                    if !should_exclude {
-                        // 读取文件内容
-                        let mut file = fs::File::open(file_path)?;
-                        let mut content = String::new();
-                        file.read_to_string(&mut content)?;
-
-                        // 获取文件元数据
-                        let metadata = fs::metadata(file_path)?;
-                        let modified_at = metadata
-                            .modified()
-                            .map_err(|e| crate::error::AppError::Other(e.to_string()))?
-                            .elapsed()
-                            .map_err(|e| crate::error::AppError::Other(e.to_string()))?
-                            .as_secs();
-
-                        // 创建CodeFile对象
-                        let code_file = CodeFile {
-                            path: file_path.to_path_buf(),
-                            content,
-                            language: ext_str.to_lowercase(),
-                            size: metadata.len(),
-                            modified_at,
-                        };
-
-                        // 索引文件
-                        self.index_file(code_file)?;
-                    }
                 }
+                _ => symbols.replace_file(&file_key, &file.content, elements)?,
             }
         }
+        self.update_cache(file, elements)
+    }
 
-        // 索引完成后保存缓存
-        self.save_cache()?;
-        println!("索引完成，已保存缓存");
-
+    /// 索引目录，遵循忽略文件并跳过二进制和过大的文件
+    pub fn index_directory(&mut self, path: &std::path::Path) -> AppResult<()> {
+        let stats = self.index_directory_with_progress(path, &mut |_| {})?;
+        self.log(&format!("索引完成，已保存缓存，{}", stats.timings));
         Ok(())
     }
 
+    /// 使用并行流水线索引目录，通过 `on_event` 报告进度；中断后再次索引时跳过已索引的文件
+    pub fn index_directory_with_progress(
+        &mut self,
+        path: &std::path::Path,
+        on_event: &mut dyn FnMut(IndexEvent),
+    ) -> AppResult<IndexStats> {
+        use crate::knowledge::walker::ProjectWalker;
+
+        // 确保索引器已初始化
+        if !self.initialized {
+            self.init()?;
+        }
+
+        let walker = ProjectWalker::from_config(&self.config);
+        let pipeline =
+            IndexPipeline::new(self.config.index_workers).max_file_size(walker.file_size_limit());
+        let cached = self.cache_snapshot();
+        let is_unchanged = |path: &std::path::Path, metadata: &fs::Metadata| {
+            let key = path.to_string_lossy();
+            match (cached.get(key.as_ref()), metadata.modified()) {
+                (Some((modified_at, size)), Ok(modified)) => {
+                    *modified_at == modified && *size == metadata.len()
+                }
+                _ => false,
+            }
+        };
+        pipeline.run(|| walker.walk(path), &is_unchanged, self, on_event)
+    }
+
     /// 搜索索引
     pub fn search(&self, query: &str) -> AppResult<Vec<crate::parsers::CodeElement>> {
-        // TODO: 主人~ 这里需要实现索引搜索逻辑
-        // 提示：使用tantivy搜索索引，返回匹配的代码元素
-        Ok(Vec::new())
+        Ok(self
+            .search_symbols(query, None, DEFAULT_SEARCH_LIMIT)?
+            .into_iter()
+            .map(|symbol| symbol.element)
+            .collect())
+    }
+
+    /// 搜索符号索引，可按元素类型过滤，返回带评分的结果
+    pub fn search_symbols(
+        &self,
+        query: &str,
+        element_type: Option<&CodeElementType>,
+        limit: usize,
+    ) -> AppResult<Vec<SymbolMatch>> {
+        match &self.symbols {
+            Some(symbols) => symbols.search(&SearchQuery::parse(query)?, element_type, limit),
+            // 索引器尚未初始化，没有可搜索的内容
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl IndexSink for CodeIndexer {
+    fn write_parsed(&mut self, parsed: ParsedFile) -> AppResult<usize> {
+        let Some(elements) = parsed.elements else {
+            return Ok(0);
+        };
+        let language = parsed
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        let file = CodeFile {
+            size: fs::metadata(&parsed.path)?.len(),
+            path: parsed.path,
+            content: parsed.content,
+            language,
+            modified_at: chrono::Utc::now().timestamp() as u64,
+        };
+        self.store_elements(&file, &elements, parsed.changes.as_ref())?;
+        Ok(elements.len())
+    }
+
+    fn checkpoint(&mut self) -> AppResult<()> {
+        self.commit()?;
+        self.save_cache()
     }
 }
@@ -0,0 +1,1328 @@
+//! 本地知识库
+//!
+//! 统一存储本地文件、代码符号和抓取的网页，索引和元数据分别保存在
+//! `KnowledgeConfig` 配置的 `index_dir` 和 `metadata_dir` 目录中
+
+use crate::config::app::KnowledgeConfig;
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::{CodeFile, DocumentMeta, DocumentSource, KnowledgeBase, SearchHit};
+use crate::knowledge::bundle::{Bundle, BundleDocument, BundleVectors, ImportMode, ImportSummary};
+use crate::knowledge::chunker::{chunk_document, TextChunk};
+use crate::knowledge::collection::{
+    collection_config, validate_collection_name, CollectionFilter, CollectionStats,
+    DEFAULT_COLLECTION,
+};
+use crate::knowledge::document_index::DocumentIndex;
+use crate::knowledge::embedding::{create_provider, EmbeddingProvider};
+use crate::knowledge::git::{GitIndexSummary, GitRepository, GIT_COLLECTION, GIT_DOCUMENT_PREFIX};
+use crate::knowledge::health::{
+    dir_size, CompactSummary, HealthIssue, KnowledgeStats, RepairSummary, VerifyReport,
+};
+use crate::knowledge::hybrid::{reciprocal_rank_fusion, RRF_K};
+use crate::knowledge::indexer::CodeIndexer;
+use crate::knowledge::pipeline::{IndexEvent, IndexPipeline, IndexSink, IndexStats, ParsedFile};
+use crate::knowledge::query::SearchQuery;
+use crate::knowledge::search_result_optimizer::{
+    RuleBasedSearchResultOptimizer, SearchCandidate, SearchHistoryStore, SearchResultItem,
+    SearchResultOptimizer,
+};
+use crate::knowledge::symbol_index::SymbolMatch;
+use crate::knowledge::vector_index::{VectorEntry, VectorIndex};
+use crate::knowledge::walker::{read_text_file, ProjectWalker};
+use crate::knowledge::xref::{FileXrefs, XrefIndex};
+use crate::parse_cache::ParseCache;
+use crate::parsers::{CodeElement, PARSER_REGISTRY};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 混合检索时每路召回的候选数量相对于结果数量的倍数
+const HYBRID_CANDIDATES: usize = 3;
+
+/// 未指定数量时代码搜索返回的结果数量
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+/// 语义匹配摘要的最大字符数
+const SNIPPET_CHARS: usize = 200;
+
+/// 本地知识库实现
+pub struct LocalKnowledgeBase {
+    /// 知识库配置
+    config: KnowledgeConfig,
+    /// 文档全文索引
+    documents: DocumentIndex,
+    /// 片段向量索引
+    vectors: VectorIndex,
+    /// 嵌入模型提供者，未配置时不生成向量
+    embedder: Option<Box<dyn EmbeddingProvider>>,
+    /// 文档元数据存储，键为文件路径或网页URL
+    metadata: sled::Db,
+    /// 交叉引用索引，与元数据存储在同一数据库中
+    xrefs: XrefIndex,
+    /// 各仓库上次索引到的提交，键为仓库根目录
+    git_state: sled::Tree,
+    /// 用户搜索历史，用于个性化排序
+    search_history: SearchHistoryStore,
+    /// 代码符号索引器
+    indexer: CodeIndexer,
+    /// 新添加文档所属的集合，未设置时沿用文档原有集合或默认集合
+    collection: Option<String>,
+}
+
+impl LocalKnowledgeBase {
+    /// 打开知识库，未配置目录时使用内存存储
+    pub fn new(config: KnowledgeConfig) -> AppResult<Self> {
+        let documents = if config.index_dir.as_os_str().is_empty() {
+            DocumentIndex::in_memory()?
+        } else {
+            DocumentIndex::open(&config.index_dir.join("documents"))?
+        };
+
+        let metadata = if config.metadata_dir.as_os_str().is_empty() {
+            sled::Config::new().temporary(true).open()?
+        } else {
+            fs::create_dir_all(&config.metadata_dir)?;
+            sled::open(config.metadata_dir.join("documents.sled"))?
+        };
+
+        let xrefs = XrefIndex::open(&metadata)?;
+        let git_state = metadata.open_tree("git_history")?;
+        let search_history = SearchHistoryStore::open(&metadata)?;
+
+        let embedder = create_provider(&config.embedding)?;
+        let mut vectors = if config.index_dir.as_os_str().is_empty() {
+            VectorIndex::in_memory()
+        } else {
+            VectorIndex::open(&config.index_dir.join("vectors"))?
+        };
+        if let Some(embedder) = &embedder {
+            vectors.set_provider(&embedder.id());
+        }
+
+        let indexer = CodeIndexer::new(config.clone())?;
+
+        Ok(Self {
+            config,
+            documents,
+            vectors,
+            embedder,
+            metadata,
+            xrefs,
+            git_state,
+            search_history,
+            indexer,
+            collection: None,
+        })
+    }
+
+    /// 设置后续添加的文档所属的集合
+    pub fn set_collection(&mut self, name: &str) -> AppResult<()> {
+        validate_collection_name(name)?;
+        self.collection = Some(name.to_string());
+        Ok(())
+    }
+
+    /// 获取配置中集合的默认路径
+    pub fn collection_paths(&self, name: &str) -> Vec<String> {
+        self.config
+            .collections
+            .get(name)
+            .map(|collection| collection.paths.clone())
+            .unwrap_or_default()
+    }
+
+    /// 遍历目录时使用的配置，包含当前集合的覆盖项
+    fn walk_config(&self) -> KnowledgeConfig {
+        let name = self.collection.as_deref().unwrap_or(DEFAULT_COLLECTION);
+        collection_config(&self.config, name)
+    }
+
+    /// 添加文件或目录，返回添加的文件数量（包括未修改的文件）
+    pub fn add_files(&mut self, paths: &[String], recursive: bool) -> AppResult<usize> {
+        let stats = self.add_files_with_progress(paths, recursive, &mut |_| {})?;
+        Ok(stats.processed())
+    }
+
+    /// 使用并行流水线添加文件或目录，通过 `on_event` 报告进度
+    ///
+    /// 每写入一批文件提交一次检查点，中断后再次添加时跳过索引缓存中未修改的文件
+    pub fn add_files_with_progress(
+        &mut self,
+        paths: &[String],
+        recursive: bool,
+        on_event: &mut dyn FnMut(IndexEvent),
+    ) -> AppResult<IndexStats> {
+        self.indexer.init_parsers()?;
+        let max_depth = if recursive { None } else { Some(1) };
+        let walker = ProjectWalker::from_config(&self.walk_config()).max_depth(max_depth);
+        let walk = || {
+            let mut files = Vec::new();
+            for path_str in paths {
+                let path = Path::new(path_str);
+                if path.is_dir() {
+                    files.extend(walker.walk(path)?.iter().map(|file| normalize_path(file)));
+                } else if path.is_file() {
+                    files.push(normalize_path(path));
+                } else {
+                    log::warn!("路径不存在，已跳过: {}", path_str);
+                }
+            }
+            // 重叠的路径只索引一次
+            files.sort();
+            files.dedup();
+            Ok(files)
+        };
+
+        let indexed = self.indexed_files()?;
+        let is_unchanged = |path: &Path, metadata: &fs::Metadata| {
+            indexed
+                .get(path.to_string_lossy().as_ref())
+                .is_some_and(|file| file.matches(path, metadata))
+        };
+        let pipeline =
+            IndexPipeline::new(self.config.index_workers).max_file_size(walker.file_size_limit());
+        pipeline.run(walk, &is_unchanged, self, on_event)
+    }
+
+    /// 收集已索引的本地文件，用于跳过未修改的文件；指定了集合时，其他集合中的文件需要重新添加
+    fn indexed_files(&self) -> AppResult<HashMap<String, IndexedFile>> {
+        let cached = self.indexer.cache_snapshot();
+        let embedded: HashSet<&str> = self
+            .vectors
+            .entries()
+            .iter()
+            .map(|entry| entry.path.as_str())
+            .collect();
+
+        let mut files = HashMap::new();
+        for meta in self.list()? {
+            let in_collection = self
+                .collection
+                .as_ref()
+                .is_none_or(|collection| *collection == meta.collection);
+            // 嵌入失败的文件需要重新计算向量
+            let embedded =
+                self.embedder.is_none() || meta.size == 0 || embedded.contains(meta.path.as_str());
+            if meta.source != DocumentSource::File || !in_collection || !embedded {
+                continue;
+            }
+            let file = IndexedFile {
+                size: meta.size,
+                indexed_at: meta.indexed_at,
+                cached: cached.get(&meta.path).copied(),
+            };
+            files.insert(meta.path, file);
+        }
+        Ok(files)
+    }
+
+    /// 设置并行读取和解析文件的线程数量，为0时使用CPU核心数
+    pub fn set_index_workers(&mut self, workers: usize) {
+        self.config.index_workers = workers;
+    }
+
+    /// 设置静默模式，开启后索引时不输出进度
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.indexer.set_quiet(quiet);
+    }
+
+    /// 读取并添加单个文件，二进制或过大的文件会被跳过；需调用 `commit` 提交
+    pub fn add_path(&mut self, path: &Path) -> AppResult<bool> {
+        self.add_path_with_cache(path, None)
+    }
+
+    /// 读取并添加单个文件，提供解析缓存时增量解析代码元素；需调用 `commit` 提交
+    pub fn add_path_with_cache(
+        &mut self,
+        path: &Path,
+        parse_cache: Option<&mut ParseCache>,
+    ) -> AppResult<bool> {
+        let max_size = ProjectWalker::from_config(&self.walk_config()).file_size_limit();
+        let content = match read_text_file(path, max_size) {
+            Ok(Some(content)) => content,
+            Ok(None) => return Ok(false),
+            Err(e) => {
+                log::warn!("跳过无法读取的文件 {:?}: {}", path, e);
+                return Ok(false);
+            }
+        };
+
+        let file = CodeFile {
+            path: path.to_path_buf(),
+            language: language_for_path(path).to_string(),
+            size: content.len() as u64,
+            content,
+            modified_at: chrono::Utc::now().timestamp() as u64,
+        };
+        match parse_cache {
+            Some(cache) => {
+                self.indexer.init_parsers()?;
+                let parsed = ParsedFile::parse_cached(&normalize_path(path), file.content, cache)?;
+                self.store_parsed(parsed, &file.language)?;
+            }
+            None => self.add_file_uncommitted(file)?,
+        }
+        Ok(true)
+    }
+
+    /// 添加文件但不提交索引，用于批量添加
+    fn add_file_uncommitted(&mut self, file: CodeFile) -> AppResult<()> {
+        self.indexer.init_parsers()?;
+
+        let path = normalize_path(&file.path);
+        let parsed = ParsedFile::parse(&path, file.content)?;
+        self.store_parsed(parsed, &file.language)?;
+        Ok(())
+    }
+
+    /// 写入已解析的文件：文档、交叉引用和代码符号，返回代码元素数量；需调用 `commit` 提交
+    fn store_parsed(&mut self, parsed: ParsedFile, language: &str) -> AppResult<usize> {
+        let key = parsed.path.to_string_lossy().to_string();
+        let title = parsed
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let meta = self.document_meta(
+            &key,
+            &title,
+            language,
+            DocumentSource::File,
+            &parsed.content,
+        );
+        self.store_document(&meta, &parsed.content)?;
+
+        // 有对应解析器的文件同时写入交叉引用和符号索引
+        let Some(elements) = parsed.elements else {
+            return Ok(0);
+        };
+        match parsed.xrefs {
+            Some(xrefs) => self.xrefs.update(&xrefs)?,
+            None => self.xrefs.remove(&key)?,
+        }
+        let file = CodeFile {
+            size: parsed.content.len() as u64,
+            path: parsed.path,
+            content: parsed.content,
+            language: language.to_string(),
+            modified_at: chrono::Utc::now().timestamp() as u64,
+        };
+        self.indexer
+            .store_elements(&file, &elements, parsed.changes.as_ref())?;
+        Ok(elements.len())
+    }
+
+    /// 添加抓取的网页
+    pub fn add_web_page(&mut self, url: &str, title: &str, content: &str) -> AppResult<()> {
+        let meta = self.document_meta(url, title, "html", DocumentSource::Web, content);
+        self.store_document(&meta, content)
+    }
+
+    /// 添加不依赖磁盘文件的文本文档，用于导入已有数据
+    pub fn add_text(&mut self, path: &str, content: &str, source: DocumentSource) -> AppResult<()> {
+        let title = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string());
+        let language = match source {
+            DocumentSource::File => language_for_path(Path::new(path)),
+            DocumentSource::Web => "html",
+            DocumentSource::Git => "git",
+        };
+        let meta = self.document_meta(path, &title, language, source, content);
+        self.store_document(&meta, content)
+    }
+
+    /// 构建文档元数据
+    fn document_meta(
+        &self,
+        path: &str,
+        title: &str,
+        language: &str,
+        source: DocumentSource,
+        content: &str,
+    ) -> DocumentMeta {
+        // 重新索引时保留文档原有的集合
+        let collection = self.collection.clone().unwrap_or_else(|| {
+            self.get(path)
+                .ok()
+                .flatten()
+                .map(|meta| meta.collection)
+                .unwrap_or_else(|| DEFAULT_COLLECTION.to_string())
+        });
+        DocumentMeta {
+            path: path.to_string(),
+            title: title.to_string(),
+            language: language.to_string(),
+            source,
+            size: content.len() as u64,
+            lines: content.lines().count(),
+            indexed_at: chrono::Utc::now().timestamp(),
+            collection,
+        }
+    }
+
+    /// 按代码元素切分文档，写入全文索引、片段向量和元数据
+    fn store_document(&mut self, meta: &DocumentMeta, content: &str) -> AppResult<()> {
+        let chunks = chunk_document(&meta.path, content);
+        self.documents.upsert(meta, content, &chunks)?;
+        self.embed_document(meta, chunks);
+        self.metadata
+            .insert(meta.path.as_bytes(), serde_json::to_vec(meta)?)?;
+        Ok(())
+    }
+
+    /// 计算文档片段的嵌入向量，嵌入服务不可用时只记录警告，不影响全文索引
+    fn embed_document(&mut self, meta: &DocumentMeta, chunks: Vec<TextChunk>) {
+        let Some(embedder) = &self.embedder else {
+            return;
+        };
+
+        let texts: Vec<String> = chunks.iter().map(|chunk| chunk.text.clone()).collect();
+        match embedder.embed(&texts) {
+            Ok(vectors) => {
+                let entries = chunks
+                    .into_iter()
+                    .zip(vectors)
+                    .map(|(chunk, vector)| VectorEntry {
+                        path: meta.path.clone(),
+                        start_line: chunk.start_line,
+                        end_line: chunk.end_line,
+                        collection: meta.collection.clone(),
+                        vector,
+                    })
+                    .collect();
+                self.vectors.replace_file(&meta.path, entries);
+            }
+            Err(e) => log::warn!("计算嵌入向量失败，{} 将无法被语义检索: {}", meta.path, e),
+        }
+    }
+
+    /// 索引 Git 仓库的提交历史，默认写入 `git` 集合
+    ///
+    /// 从上次索引到的提交继续，`full` 为真或上次的提交已不在当前历史中时重新索引全部提交。
+    /// 提交文档的索引时间取提交时间，可用 `after:`、`before:` 按提交日期过滤
+    pub fn add_git_history(
+        &mut self,
+        repo: &Path,
+        max_count: Option<usize>,
+        full: bool,
+    ) -> AppResult<GitIndexSummary> {
+        let repository = GitRepository::open(repo)?;
+        let key = repository.root().to_string_lossy().to_string();
+        let Some(head) = repository.head()? else {
+            return Ok(GitIndexSummary::default());
+        };
+
+        let last = match self.git_state.get(key.as_bytes())? {
+            Some(value) if !full => Some(String::from_utf8_lossy(&value).to_string()),
+            _ => None,
+        };
+        let since = last.filter(|commit| {
+            let reachable = repository.is_ancestor(commit);
+            if !reachable {
+                log::warn!("上次索引的提交 {} 已不在当前历史中，将重新索引", commit);
+            }
+            reachable
+        });
+
+        let mut summary = GitIndexSummary {
+            head: Some(head.clone()),
+            ..Default::default()
+        };
+        if since.as_deref() == Some(head.as_str()) {
+            return Ok(summary);
+        }
+
+        let collection = self
+            .collection
+            .clone()
+            .unwrap_or_else(|| GIT_COLLECTION.to_string());
+        for commit in repository.commits(since.as_deref(), &head, max_count)? {
+            let content = commit.to_document();
+            let mut meta = self.document_meta(
+                &commit.document_path(),
+                &commit.subject,
+                "git",
+                DocumentSource::Git,
+                &content,
+            );
+            meta.collection = collection.clone();
+            meta.indexed_at = commit.timestamp;
+            self.store_document(&meta, &content)?;
+            summary.commits += 1;
+            summary.files += commit.files.len();
+        }
+        self.commit()?;
+        self.git_state.insert(key.as_bytes(), head.as_bytes())?;
+        self.git_state.flush()?;
+        Ok(summary)
+    }
+
+    /// 提交所有挂起的修改
+    pub fn commit(&mut self) -> AppResult<()> {
+        self.documents.commit()?;
+        self.vectors.save()?;
+        self.indexer.commit()?;
+        if self.indexer.is_initialized() {
+            self.indexer.save_cache()?;
+        }
+        self.metadata.flush()?;
+        Ok(())
+    }
+
+    /// 获取文档元数据
+    pub fn get(&self, path: &str) -> AppResult<Option<DocumentMeta>> {
+        match self.metadata.get(path.as_bytes())? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 列出所有文档，按路径排序
+    pub fn list(&self) -> AppResult<Vec<DocumentMeta>> {
+        let mut documents = Vec::new();
+        for entry in self.metadata.iter() {
+            let (_, value) = entry?;
+            documents.push(serde_json::from_slice::<DocumentMeta>(&value)?);
+        }
+        Ok(documents)
+    }
+
+    /// 搜索代码符号，返回带评分的结果
+    pub fn search_symbols(&self, query: &str, limit: usize) -> AppResult<Vec<SymbolMatch>> {
+        self.search_symbols_in(query, limit, None)
+    }
+
+    /// 在指定集合中搜索代码符号，按符号所在文件的集合过滤
+    pub fn search_symbols_in(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&CollectionFilter>,
+    ) -> AppResult<Vec<SymbolMatch>> {
+        let candidates = limit * HYBRID_CANDIDATES;
+        let Some(filter) = filter else {
+            let symbols = self.indexer.search_symbols(query, None, candidates)?;
+            return self.rank_symbols(query, symbols, limit);
+        };
+
+        // 符号索引不记录集合，命中的符号大多在范围之外时需要继续向后取，
+        // 直到收集到足够的候选或索引中已没有更多结果
+        let mut scopes: HashMap<String, bool> = HashMap::new();
+        let mut fetch = candidates;
+        loop {
+            let found = self.indexer.search_symbols(query, None, fetch)?;
+            let exhausted = found.len() < fetch;
+            let mut symbols = Vec::new();
+            for symbol in found {
+                let path = &symbol.element.definition.file_path;
+                let in_scope = match scopes.get(path) {
+                    Some(in_scope) => *in_scope,
+                    None => {
+                        let in_scope = self
+                            .get(path)?
+                            .is_some_and(|meta| filter.matches(&meta.collection));
+                        scopes.insert(path.clone(), in_scope);
+                        in_scope
+                    }
+                };
+                if in_scope {
+                    symbols.push(symbol);
+                }
+            }
+            if exhausted || symbols.len() >= candidates {
+                symbols.truncate(candidates);
+                return self.rank_symbols(query, symbols, limit);
+            }
+            fetch *= 2;
+        }
+    }
+
+    /// 记录一次搜索，用于后续的个性化排序
+    pub fn record_search(&self, query: &str) -> AppResult<()> {
+        let config = &self.config.search;
+        if !config.enable_personalization || query.trim().is_empty() {
+            return Ok(());
+        }
+        let item = self.optimizer(0)?.history_item(query, Vec::new());
+        self.search_history.record(&item, config.max_history_items)
+    }
+
+    /// 记录用户在搜索结果中选择的结果，`result` 为符号位置（`路径:行号`）或文档路径
+    pub fn record_selection(&self, query: &str, result: &str) -> AppResult<()> {
+        let config = &self.config.search;
+        if !config.enable_personalization || query.trim().is_empty() {
+            return Ok(());
+        }
+        let item = self.optimizer(0)?.history_item(query, Vec::new());
+        self.search_history
+            .record_click(item, result, config.max_history_items)
+    }
+
+    /// 创建搜索结果优化器，加载持久化的搜索历史
+    fn optimizer(&self, limit: usize) -> AppResult<RuleBasedSearchResultOptimizer> {
+        let mut config = self.config.search.clone();
+        config.max_results = limit;
+        let mut optimizer = RuleBasedSearchResultOptimizer::new(config.clone());
+        if config.enable_personalization {
+            for item in self.search_history.recent(config.max_history_items)? {
+                optimizer.add_search_history(item);
+            }
+        }
+        Ok(optimizer)
+    }
+
+    /// 使用优化器对候选结果排序，返回排序后的条目
+    fn optimize(
+        &self,
+        query: &str,
+        candidates: Vec<SearchCandidate>,
+        limit: usize,
+    ) -> AppResult<Vec<SearchResultItem>> {
+        if candidates.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+        // 过滤条件不参与相关性计算
+        let text = SearchQuery::parse(query)?.semantic_text();
+        self.optimizer(limit)?.optimize_results(&text, candidates)
+    }
+
+    /// 按优化器的综合评分重新排序符号搜索结果
+    fn rank_symbols(
+        &self,
+        query: &str,
+        symbols: Vec<SymbolMatch>,
+        limit: usize,
+    ) -> AppResult<Vec<SymbolMatch>> {
+        let candidates = symbols
+            .iter()
+            .map(|symbol| {
+                let element = &symbol.element;
+                let path = &element.definition.file_path;
+                SearchCandidate {
+                    id: format!("{}:{}", path, element.definition.line),
+                    name: element.name.clone(),
+                    element_type: Some(element.element_type.clone()),
+                    path: path.clone(),
+                    documentation: element.documentation.clone().unwrap_or_default(),
+                    content: symbol.signature.clone(),
+                    engine_score: symbol.score,
+                    modified_at: modified_time(Path::new(path)),
+                }
+            })
+            .collect();
+        let ranked = self.optimize(query, candidates, limit)?;
+
+        let mut symbols: Vec<Option<SymbolMatch>> = symbols.into_iter().map(Some).collect();
+        Ok(ranked
+            .into_iter()
+            .filter_map(|item| {
+                let mut symbol = symbols[item.index].take()?;
+                symbol.explanation = item.relevance_explanation;
+                Some(symbol)
+            })
+            .collect())
+    }
+
+    /// 按优化器的综合评分重新排序文档搜索结果
+    fn rank_hits(
+        &self,
+        query: &str,
+        hits: Vec<SearchHit>,
+        limit: usize,
+    ) -> AppResult<Vec<SearchHit>> {
+        let mut candidates = Vec::with_capacity(hits.len());
+        for hit in &hits {
+            // 本地文件使用文件修改时间，网页和提交使用索引时间
+            let modified_at = match hit.source {
+                DocumentSource::File => modified_time(Path::new(&hit.path)),
+                _ => self.get(&hit.path)?.map(|meta| meta.indexed_at),
+            };
+            candidates.push(SearchCandidate {
+                id: hit.path.clone(),
+                name: hit.title.clone(),
+                element_type: None,
+                path: hit.path.clone(),
+                documentation: String::new(),
+                content: hit.snippet.clone(),
+                engine_score: hit.score,
+                modified_at,
+            });
+        }
+        let ranked = self.optimize(query, candidates, limit)?;
+
+        let mut hits: Vec<Option<SearchHit>> = hits.into_iter().map(Some).collect();
+        Ok(ranked
+            .into_iter()
+            .filter_map(|item| {
+                let mut hit = hits[item.index].take()?;
+                hit.explanation = item.relevance_explanation;
+                Some(hit)
+            })
+            .collect())
+    }
+
+    /// 统计各集合的文档，包含配置中尚未添加文档的集合，按名称排序
+    pub fn collection_stats(&self) -> AppResult<Vec<CollectionStats>> {
+        let mut stats: std::collections::BTreeMap<String, CollectionStats> = self
+            .config
+            .collections
+            .iter()
+            .map(|(name, config)| {
+                let stats = CollectionStats {
+                    name: name.clone(),
+                    description: config.description.clone(),
+                    ..Default::default()
+                };
+                (name.clone(), stats)
+            })
+            .collect();
+
+        for meta in self.list()? {
+            let entry = stats
+                .entry(meta.collection.clone())
+                .or_insert_with(|| CollectionStats {
+                    name: meta.collection.clone(),
+                    ..Default::default()
+                });
+            match meta.source {
+                DocumentSource::File => entry.files += 1,
+                DocumentSource::Web => entry.pages += 1,
+                DocumentSource::Git => entry.commits += 1,
+            }
+            entry.size += meta.size;
+            entry.lines += meta.lines;
+            entry.last_indexed = entry.last_indexed.max(Some(meta.indexed_at));
+        }
+        for (name, count) in self.vectors.counts_by_collection() {
+            if let Some(entry) = stats.get_mut(&name) {
+                entry.vectors = count;
+            }
+        }
+        Ok(stats.into_values().collect())
+    }
+
+    /// 删除集合中的所有文档，返回删除的文档数量
+    pub fn delete_collection(&mut self, name: &str) -> AppResult<usize> {
+        let mut removed = 0;
+        for meta in self.list()? {
+            if meta.collection != name {
+                continue;
+            }
+            self.remove_document(&meta)?;
+            removed += 1;
+        }
+        self.commit()?;
+        Ok(removed)
+    }
+
+    /// 从所有索引中删除文档；需调用 `commit` 提交
+    fn remove_document(&mut self, meta: &DocumentMeta) -> AppResult<()> {
+        self.documents.remove(&meta.path)?;
+        self.vectors.remove_file(&meta.path);
+        self.xrefs.remove(&meta.path)?;
+        self.metadata.remove(meta.path.as_bytes())?;
+        match meta.source {
+            DocumentSource::File => self.indexer.remove_file(Path::new(&meta.path))?,
+            DocumentSource::Git => self.forget_git_commit(&meta.path)?,
+            DocumentSource::Web => {}
+        }
+        Ok(())
+    }
+
+    /// 清空文档、符号、向量和交叉引用索引及提交索引进度
+    fn clear_indexes(&mut self) -> AppResult<()> {
+        self.documents.clear()?;
+        self.documents.commit()?;
+        self.vectors.clear();
+        self.vectors.save()?;
+        self.metadata.clear()?;
+        self.xrefs.clear()?;
+        self.git_state.clear()?;
+        self.metadata.flush()?;
+        self.indexer.clear()
+    }
+
+    /// 提交文档被删除后，清除包含该提交的仓库的索引进度，下次索引时补全历史
+    fn forget_git_commit(&mut self, path: &str) -> AppResult<()> {
+        let Some(hash) = path.strip_prefix(GIT_DOCUMENT_PREFIX) else {
+            return Ok(());
+        };
+        let roots = self
+            .git_state
+            .iter()
+            .keys()
+            .collect::<Result<Vec<_>, _>>()?;
+        for root in roots {
+            let owns_commit = GitRepository::open(Path::new(&*String::from_utf8_lossy(&root)))
+                .is_ok_and(|repository| repository.is_ancestor(hash));
+            if owns_commit {
+                self.git_state.remove(root)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// 统计知识库的文档、符号、向量和存储大小
+    pub fn stats(&mut self) -> AppResult<KnowledgeStats> {
+        let mut stats = KnowledgeStats::default();
+        for meta in self.list()? {
+            stats.documents += 1;
+            *stats
+                .by_source
+                .entry(meta.source.as_str().to_string())
+                .or_default() += 1;
+            *stats.by_language.entry(meta.language).or_default() += 1;
+            *stats.by_collection.entry(meta.collection).or_default() += 1;
+            if meta.source != DocumentSource::Git {
+                stats.last_updated = stats.last_updated.max(Some(meta.indexed_at));
+            }
+        }
+
+        let symbols = self.indexer.symbol_counts()?;
+        stats.symbols = symbols.by_kind.values().sum();
+        stats.symbols_by_kind = symbols.by_kind;
+        stats.vectors = self.vectors.len();
+        stats.xref_files = self.xrefs.file_count();
+        stats.cached_files = self.indexer.file_count();
+        stats.segments = self.documents.segment_count() + self.indexer.segment_count();
+        stats.index_size = dir_size(&self.config.index_dir);
+        stats.metadata_size = dir_size(&self.config.metadata_dir);
+        Ok(stats)
+    }
+
+    /// 检查元数据、全文索引、符号索引、向量索引和索引缓存之间的一致性
+    pub fn verify(&mut self) -> AppResult<VerifyReport> {
+        let documents = self.list()?;
+        let known: HashSet<&str> = documents.iter().map(|meta| meta.path.as_str()).collect();
+        let indexed = self.documents.paths()?;
+        let symbols = self.indexer.symbol_counts()?;
+
+        let mut issues = Vec::new();
+        let mut missing = HashSet::new();
+        for meta in &documents {
+            if meta.source == DocumentSource::File && !Path::new(&meta.path).exists() {
+                issues.push(HealthIssue::MissingFile(meta.path.clone()));
+                missing.insert(meta.path.as_str());
+            } else if !indexed.contains(&meta.path) {
+                issues.push(HealthIssue::MissingContent(meta.path.clone()));
+            }
+        }
+        for path in indexed.iter().filter(|path| !known.contains(path.as_str())) {
+            issues.push(HealthIssue::OrphanedDocument(path.clone()));
+        }
+        for (path, count) in &symbols.by_file {
+            if !known.contains(path.as_str()) {
+                issues.push(HealthIssue::OrphanedSymbols {
+                    path: path.clone(),
+                    count: *count,
+                });
+            }
+        }
+        let mut vectors: Vec<_> = self.vectors.counts_by_path().into_iter().collect();
+        vectors.sort();
+        for (path, count) in vectors {
+            if !known.contains(path.as_str()) {
+                issues.push(HealthIssue::OrphanedVectors { path, count });
+            }
+        }
+        for path in self.xrefs.paths()? {
+            if !known.contains(path.as_str()) {
+                issues.push(HealthIssue::OrphanedXrefs(path));
+            }
+        }
+
+        // 索引缓存与符号索引按文件比较代码元素数量，已不存在的文件会被整体删除，不再比较
+        let cache = &self.indexer.cache().files;
+        let mut cached: Vec<_> = cache.keys().chain(symbols.by_file.keys()).collect();
+        cached.sort();
+        cached.dedup();
+        for path in cached {
+            if !known.contains(path.as_str()) {
+                if cache.contains_key(path) {
+                    issues.push(HealthIssue::StaleCacheEntry(path.clone()));
+                }
+                continue;
+            }
+            if missing.contains(path.as_str()) {
+                continue;
+            }
+            let cached = cache.get(path).map_or(0, |item| item.element_count);
+            let indexed = symbols.by_file.get(path).copied().unwrap_or(0);
+            if cached != indexed {
+                issues.push(HealthIssue::CacheMismatch {
+                    path: path.clone(),
+                    cached,
+                    indexed,
+                });
+            }
+        }
+
+        Ok(VerifyReport {
+            checked: documents.len(),
+            issues,
+        })
+    }
+
+    /// 修复检查发现的问题：删除已不存在的文件和孤立数据，重新索引不一致的文件
+    pub fn repair(&mut self, report: &VerifyReport) -> AppResult<RepairSummary> {
+        let mut summary = RepairSummary::default();
+        let mut reindex = Vec::new();
+        for issue in &report.issues {
+            match issue {
+                HealthIssue::MissingFile(path) => {
+                    if let Some(meta) = self.get(path)? {
+                        self.remove_document(&meta)?;
+                        summary.removed += 1;
+                    }
+                }
+                HealthIssue::MissingContent(path) | HealthIssue::CacheMismatch { path, .. } => {
+                    reindex.push(path.clone());
+                }
+                HealthIssue::OrphanedDocument(path) => {
+                    self.documents.remove(path)?;
+                    summary.cleaned += 1;
+                }
+                HealthIssue::OrphanedSymbols { path, .. } | HealthIssue::StaleCacheEntry(path) => {
+                    self.indexer.remove_file(Path::new(path))?;
+                    summary.cleaned += 1;
+                }
+                HealthIssue::OrphanedVectors { path, .. } => {
+                    self.vectors.remove_file(path);
+                    summary.cleaned += 1;
+                }
+                HealthIssue::OrphanedXrefs(path) => {
+                    self.xrefs.remove(path)?;
+                    summary.cleaned += 1;
+                }
+            }
+        }
+
+        // 本地文件重新读取和解析；网页和提交的内容无法恢复，只能删除
+        reindex.sort();
+        reindex.dedup();
+        for path in reindex {
+            let Some(meta) = self.get(&path)? else {
+                continue;
+            };
+            if meta.source == DocumentSource::File && self.add_path(Path::new(&path))? {
+                summary.reindexed += 1;
+            } else {
+                self.remove_document(&meta)?;
+                summary.removed += 1;
+            }
+        }
+        self.commit()?;
+        Ok(summary)
+    }
+
+    /// 合并全文索引和符号索引的所有段并清理不再使用的文件
+    pub fn compact(&mut self) -> AppResult<CompactSummary> {
+        self.commit()?;
+        let size_before = dir_size(&self.config.index_dir);
+        let segments_before = self.documents.compact()? + self.indexer.compact()?;
+        self.commit()?;
+        Ok(CompactSummary {
+            segments_before,
+            segments_after: self.documents.segment_count() + self.indexer.segment_count(),
+            size_before,
+            size_after: dir_size(&self.config.index_dir),
+        })
+    }
+
+    /// 导出知识库归档，项目根目录下的文件路径改写为相对路径
+    pub fn export_bundle(&self, root: &Path) -> AppResult<Bundle> {
+        let mut documents = Vec::new();
+        let mut xrefs = Vec::new();
+        for meta in self.list()? {
+            let Some(content) = self.documents.content(&meta.path)? else {
+                log::warn!("文档内容缺失，未导出: {}", meta.path);
+                continue;
+            };
+            if let Some(file_xrefs) = self.xrefs.get(&meta.path)? {
+                xrefs.push(file_xrefs);
+            }
+            documents.push(BundleDocument { meta, content });
+        }
+
+        let vectors = BundleVectors {
+            provider: self.vectors.provider().to_string(),
+            entries: self.vectors.entries().to_vec(),
+        };
+        let mut bundle = Bundle::new(
+            documents,
+            self.indexer.cache().clone(),
+            xrefs,
+            Some(vectors),
+        );
+        bundle.relativize(&normalize_path(root));
+        Ok(bundle)
+    }
+
+    /// 导入知识库归档，相对路径以项目根目录还原
+    ///
+    /// 归档的嵌入模型与当前配置相同时直接导入向量，否则使用当前模型重新计算
+    pub fn import_bundle(
+        &mut self,
+        mut bundle: Bundle,
+        root: &Path,
+        mode: ImportMode,
+    ) -> AppResult<ImportSummary> {
+        // 归档中不包含搜索历史，替换时保留本机的历史
+        if mode == ImportMode::Replace {
+            self.clear_indexes()?;
+        }
+        bundle.absolutize(&normalize_path(root));
+
+        let mut vectors: HashMap<String, Vec<VectorEntry>> = HashMap::new();
+        let reuse_vectors = match bundle.vectors {
+            Some(bundled)
+                if self.embedder.is_some() && bundled.provider == self.vectors.provider() =>
+            {
+                for entry in bundled.entries {
+                    vectors.entry(entry.path.clone()).or_default().push(entry);
+                }
+                true
+            }
+            _ => false,
+        };
+        let mut xrefs: HashMap<String, FileXrefs> = bundle
+            .xrefs
+            .into_iter()
+            .map(|file_xrefs| (file_xrefs.path.clone(), file_xrefs))
+            .collect();
+        let mut cache = bundle.index_cache.files;
+
+        let mut summary = ImportSummary::default();
+        for BundleDocument { meta, content } in bundle.documents {
+            let chunks = chunk_document(&meta.path, &content);
+            self.documents.upsert(&meta, &content, &chunks)?;
+            self.metadata
+                .insert(meta.path.as_bytes(), serde_json::to_vec(&meta)?)?;
+
+            match vectors.remove(&meta.path) {
+                Some(entries) if reuse_vectors => {
+                    summary.vectors += entries.len();
+                    self.vectors.replace_file(&meta.path, entries);
+                }
+                _ if self.embedder.is_some() => {
+                    self.embed_document(&meta, chunks);
+                    summary.reembedded += 1;
+                }
+                _ => self.vectors.remove_file(&meta.path),
+            }
+
+            match xrefs.remove(&meta.path) {
+                Some(file_xrefs) => self.xrefs.update(&file_xrefs)?,
+                None => self.xrefs.remove(&meta.path)?,
+            }
+            if let Some(item) = cache.remove(&meta.path) {
+                summary.symbols += item.elements.len();
+                self.indexer.restore_file(item, &content)?;
+            }
+            summary.documents += 1;
+        }
+
+        self.commit()?;
+        Ok(summary)
+    }
+
+    /// 获取文档数量
+    pub fn document_count(&self) -> usize {
+        self.metadata.len()
+    }
+
+    /// 获取已生成向量的片段数量
+    pub fn vector_count(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// 混合检索：分别进行关键词和语义检索，使用倒数排名融合合并结果
+    pub fn search_hybrid(&self, query: &str, limit: usize) -> AppResult<Vec<SearchHit>> {
+        self.search_hybrid_in(query, limit, None)
+    }
+
+    /// 在指定集合中进行混合检索
+    pub fn search_hybrid_in(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&CollectionFilter>,
+    ) -> AppResult<Vec<SearchHit>> {
+        let embedder = self.embedder.as_ref().ok_or_else(|| {
+            AppError::knowledge(
+                "未配置嵌入模型，请在配置文件的 knowledge.embedding.provider 中设置 ollama 或 local",
+            )
+        })?;
+        let query = SearchQuery::parse(query)?;
+        if query.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let candidates = limit * HYBRID_CANDIDATES;
+        let keyword_hits = self.documents.search(&query, candidates, filter)?;
+        let semantic_text = query.semantic_text();
+        let semantic_hits = if semantic_text.trim().is_empty() {
+            Vec::new()
+        } else {
+            let query_vector = embedder
+                .embed(&[semantic_text])?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+            // 向量索引不含语言、路径和时间信息，按元数据过滤
+            let mut semantic_hits = Vec::new();
+            for hit in self.vectors.search(&query_vector, candidates, filter) {
+                if self
+                    .get(&hit.path)?
+                    .is_some_and(|meta| query.matches_document(&meta))
+                {
+                    semantic_hits.push(hit);
+                }
+            }
+            semantic_hits
+        };
+        let highlighter = query.highlighter();
+
+        // 同一文件可能有多个片段命中，按文件融合时只保留排名最高的片段
+        let mut best_keyword_hits: HashMap<String, SearchHit> = HashMap::new();
+        let mut keyword_ranking = Vec::new();
+        for hit in keyword_hits {
+            if !best_keyword_hits.contains_key(&hit.path) {
+                keyword_ranking.push(hit.path.clone());
+                best_keyword_hits.insert(hit.path.clone(), hit);
+            }
+        }
+        let rankings = [
+            keyword_ranking,
+            semantic_hits.iter().map(|hit| hit.path.clone()).collect(),
+        ];
+        let mut keyword_hits = best_keyword_hits;
+        let semantic_hits: HashMap<String, _> = semantic_hits
+            .into_iter()
+            .map(|hit| (hit.path.clone(), hit))
+            .collect();
+
+        let mut hits = Vec::new();
+        for (path, score) in reciprocal_rank_fusion(&rankings, RRF_K)
+            .into_iter()
+            .take(candidates)
+        {
+            let semantic = semantic_hits.get(&path);
+            let mut hit = match keyword_hits.remove(&path) {
+                Some(hit) => hit,
+                None => {
+                    let Some(meta) = self.get(&path)? else {
+                        continue;
+                    };
+                    SearchHit {
+                        path: meta.path,
+                        title: meta.title,
+                        language: meta.language,
+                        source: meta.source,
+                        snippet: String::new(),
+                        lines: None,
+                        highlights: Vec::new(),
+                        collection: meta.collection,
+                        score,
+                        explanation: String::new(),
+                    }
+                }
+            };
+
+            // 只有语义命中的文件使用匹配片段的内容作为摘要
+            if let (Some(semantic), None) = (semantic, hit.lines) {
+                let text = self
+                    .documents
+                    .content(&path)?
+                    .map(|content| {
+                        content
+                            .lines()
+                            .skip(semantic.start_line - 1)
+                            .take(semantic.end_line + 1 - semantic.start_line)
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })
+                    .unwrap_or_default();
+                hit.lines = Some((semantic.start_line, semantic.end_line));
+                hit.snippet = text.chars().take(SNIPPET_CHARS).collect();
+                hit.highlights = highlighter.highlight(&text, semantic.start_line);
+            }
+            hit.score = score;
+            hits.push(hit);
+        }
+        self.rank_hits(&query.text(), hits, limit)
+    }
+}
+
+impl IndexSink for LocalKnowledgeBase {
+    fn write_parsed(&mut self, parsed: ParsedFile) -> AppResult<usize> {
+        let language = language_for_path(&parsed.path);
+        self.store_parsed(parsed, language)
+    }
+
+    fn checkpoint(&mut self) -> AppResult<()> {
+        self.commit()
+    }
+}
+
+/// 已索引的本地文件，用于判断文件是否需要重新索引
+struct IndexedFile {
+    /// 索引时的内容大小
+    size: u64,
+    /// 索引时间（Unix秒）
+    indexed_at: i64,
+    /// 索引缓存中记录的修改时间和大小，没有解析器的文件为空
+    cached: Option<(std::time::SystemTime, u64)>,
+}
+
+impl IndexedFile {
+    /// 判断文件自索引后是否未修改：有解析器的文件与索引缓存比较，其他文件与索引时间比较
+    fn matches(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        let Ok(modified) = metadata.modified() else {
+            return false;
+        };
+        if self.size != metadata.len() {
+            return false;
+        }
+        match self.cached {
+            Some(cached) => cached == (modified, metadata.len()),
+            None => {
+                let has_parser = PARSER_REGISTRY
+                    .read()
+                    .unwrap()
+                    .get_parser_by_filename(&path.to_string_lossy())
+                    .is_some();
+                let modified_at = modified
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map_or(i64::MAX, |duration| duration.as_secs() as i64);
+                // 同一秒内的修改无法区分，按已修改处理
+                !has_parser && modified_at < self.indexed_at
+            }
+        }
+    }
+}
+
+impl KnowledgeBase for LocalKnowledgeBase {
+    fn init(&mut self) -> AppResult<()> {
+        self.indexer.init()
+    }
+
+    fn add_file(&mut self, file: CodeFile) -> AppResult<()> {
+        self.add_file_uncommitted(file)?;
+        self.commit()
+    }
+
+    fn remove_file(&mut self, path: &PathBuf) -> AppResult<()> {
+        let path = normalize_path(path);
+        let key = path.to_string_lossy();
+        self.documents.remove(&key)?;
+        self.vectors.remove_file(&key);
+        self.xrefs.remove(&key)?;
+        self.metadata.remove(key.as_bytes())?;
+        self.indexer.remove_file(&path)?;
+        self.commit()
+    }
+
+    fn search(&self, query: &str) -> AppResult<Vec<Arc<CodeElement>>> {
+        Ok(self
+            .search_symbols(query, DEFAULT_SEARCH_LIMIT)?
+            .into_iter()
+            .map(|symbol| Arc::new(symbol.element))
+            .collect())
+    }
+
+    fn search_documents(&self, query: &str, limit: usize) -> AppResult<Vec<SearchHit>> {
+        let hits =
+            self.documents
+                .search(&SearchQuery::parse(query)?, limit * HYBRID_CANDIDATES, None)?;
+        self.rank_hits(query, hits, limit)
+    }
+
+    fn search_documents_in(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: &CollectionFilter,
+    ) -> AppResult<Vec<SearchHit>> {
+        let hits = self.documents.search(
+            &SearchQuery::parse(query)?,
+            limit * HYBRID_CANDIDATES,
+            Some(filter),
+        )?;
+        self.rank_hits(query, hits, limit)
+    }
+
+    fn list_files(&self) -> AppResult<Vec<PathBuf>> {
+        Ok(self
+            .list()?
+            .into_iter()
+            .filter(|meta| meta.source == DocumentSource::File)
+            .map(|meta| PathBuf::from(meta.path))
+            .collect())
+    }
+
+    fn clear(&mut self) -> AppResult<()> {
+        self.search_history.clear()?;
+        self.clear_indexes()
+    }
+
+    fn xref(&self) -> Option<&XrefIndex> {
+        Some(&self.xrefs)
+    }
+}
+
+/// 获取文件的修改时间（Unix秒），文件不存在时返回空
+fn modified_time(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let seconds = modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some(seconds as i64)
+}
+
+/// 将路径规范化为绝对路径，已删除的文件使用其父目录规范化
+pub fn normalize_path(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            fs::canonicalize(parent)
+                .map(|parent| parent.join(name))
+                .unwrap_or_else(|_| path.to_path_buf())
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// 根据文件扩展名判断内容语言
+pub fn language_for_path(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("rs") => "rust",
+        Some("py") => "python",
+        Some("js") => "javascript",
+        Some("ts") => "typescript",
+        Some("java") => "java",
+        Some("c") | Some("h") => "c",
+        Some("cpp") | Some("cc") | Some("cxx") | Some("hpp") | Some("hh") | Some("hxx") => "cpp",
+        Some("go") => "go",
+        Some("rb") => "ruby",
+        Some("php") => "php",
+        Some("html") => "html",
+        Some("css") => "css",
+        Some("json") => "json",
+        Some("yaml") | Some("yml") => "yaml",
+        Some("toml") => "toml",
+        Some("md") => "markdown",
+        _ => "text",
+    }
+}
@@ -0,0 +1,85 @@
+//! 旧版知识库迁移
+//!
+//! 将旧版命令行在当前目录创建的 `.codex_kb`（sled）数据导入统一知识库
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::DocumentSource;
+use crate::knowledge::local::LocalKnowledgeBase;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 旧版元数据数据库目录名
+pub const LEGACY_DB_DIR: &str = ".codex_kb";
+
+/// 旧版全文索引目录名
+pub const LEGACY_INDEX_DIR: &str = ".codex_index";
+
+/// 迁移完成后旧目录追加的后缀
+const MIGRATED_SUFFIX: &str = ".migrated";
+
+/// 迁移结果
+#[derive(Debug, Default, Clone)]
+pub struct MigrationReport {
+    /// 导入的文件数量
+    pub files: usize,
+    /// 导入的网页数量
+    pub pages: usize,
+    /// 跳过的无效条目数量
+    pub skipped: usize,
+}
+
+/// 查找指定目录下的旧版知识库
+pub fn find_legacy_store(dir: &Path) -> Option<PathBuf> {
+    let path = dir.join(LEGACY_DB_DIR);
+    path.is_dir().then_some(path)
+}
+
+/// 导入旧版知识库数据，完成后将旧目录重命名为 `*.migrated`
+pub fn migrate_legacy_store(kb: &mut LocalKnowledgeBase, dir: &Path) -> AppResult<MigrationReport> {
+    let db_path = find_legacy_store(dir).ok_or_else(|| {
+        AppError::knowledge(&format!("未找到旧版知识库: {:?}", dir.join(LEGACY_DB_DIR)))
+    })?;
+
+    let mut report = MigrationReport::default();
+    {
+        let db = sled::open(&db_path)?;
+        for entry in db.iter() {
+            let (key, value) = entry?;
+            let (Ok(path), Ok(content)) = (
+                String::from_utf8(key.to_vec()),
+                String::from_utf8(value.to_vec()),
+            ) else {
+                report.skipped += 1;
+                continue;
+            };
+
+            if path.starts_with("http://") || path.starts_with("https://") {
+                kb.add_web_page(&path, &path, &content)?;
+                report.pages += 1;
+                continue;
+            }
+
+            // 旧版知识库中的相对路径相对于其所在目录
+            let file_path = dir.join(&path);
+            if file_path.is_file() {
+                // 文件仍然存在时重新读取，同时建立符号索引
+                kb.add_files(&[file_path.to_string_lossy().to_string()], false)?;
+                report.files += 1;
+            } else {
+                kb.add_text(&path, &content, DocumentSource::File)?;
+                report.files += 1;
+            }
+        }
+    }
+    kb.commit()?;
+
+    // 旧的全文索引可由导入的数据重建，一并标记为已迁移
+    for name in [LEGACY_DB_DIR, LEGACY_INDEX_DIR] {
+        let path = dir.join(name);
+        if path.exists() {
+            fs::rename(&path, dir.join(format!("{}{}", name, MIGRATED_SUFFIX)))?;
+        }
+    }
+
+    Ok(report)
+}
@@ -3,8 +3,27 @@
 //! 提供本地代码索引、搜索和管理功能
 
 pub mod base;
+pub mod bundle;
+pub mod chunker;
+pub mod collection;
+pub mod deps;
+pub mod document_index;
+pub mod embedding;
+pub mod git;
+pub mod health;
+pub mod hybrid;
 pub mod indexer;
+pub mod local;
+pub mod migrate;
 pub mod multilingual;
+pub mod pipeline;
+pub mod query;
 pub mod remote;
 pub mod scraper;
+pub mod search_result_optimizer;
 pub mod searcher;
+pub mod symbol_index;
+pub mod vector_index;
+pub mod walker;
+pub mod watcher;
+pub mod xref;
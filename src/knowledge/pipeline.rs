@@ -0,0 +1,447 @@
+//! 并行索引流水线
+//!
+//! 将目录索引拆分为遍历、读取、解析和写入四个阶段：读取和解析由有界的工作线程池并行执行，
+//! 写入在调用线程中串行进行。索引进度通过事件报告，并定期提交检查点，
+//! 中断后再次索引时根据索引缓存跳过已完成的文件
+
+use crate::error::AppResult;
+use crate::knowledge::indexer::CodeIndexer;
+use crate::knowledge::walker::{read_text_file, DEFAULT_MAX_FILE_SIZE};
+use crate::knowledge::xref::{extract_xrefs, FileXrefs};
+use crate::parse_cache::{ElementChanges, ParseCache};
+use crate::parsers::CodeElement;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 默认每写入多少个文件提交一次检查点
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 200;
+
+/// 每个工作线程可以缓存的待写入文件数量，限制内存占用
+const QUEUE_DEPTH: usize = 4;
+
+/// 各阶段耗时，读取和解析为所有工作线程的累计耗时
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// 遍历目录
+    pub walk: Duration,
+    /// 读取文件
+    pub read: Duration,
+    /// 解析代码元素和交叉引用
+    pub parse: Duration,
+    /// 写入索引和提交检查点
+    pub write: Duration,
+    /// 总耗时
+    pub total: Duration,
+}
+
+impl fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "耗时 {:.2}s（遍历 {:.2}s，读取 {:.2}s，解析 {:.2}s，写入 {:.2}s）",
+            self.total.as_secs_f64(),
+            self.walk.as_secs_f64(),
+            self.read.as_secs_f64(),
+            self.parse.as_secs_f64(),
+            self.write.as_secs_f64()
+        )
+    }
+}
+
+/// 索引统计信息
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IndexStats {
+    /// 遍历发现的文件数量
+    pub discovered: usize,
+    /// 重新索引的文件数量
+    pub indexed: usize,
+    /// 未修改而跳过的文件数量
+    pub unchanged: usize,
+    /// 二进制或过大而跳过的文件数量
+    pub skipped: usize,
+    /// 读取或解析失败的文件数量
+    pub failed: usize,
+    /// 索引的代码元素数量
+    pub elements: usize,
+    /// 提交的检查点数量
+    pub checkpoints: usize,
+    /// 各阶段耗时
+    pub timings: PhaseTimings,
+}
+
+impl IndexStats {
+    /// 已在知识库中的文件数量，包括未修改的文件
+    pub fn processed(&self) -> usize {
+        self.indexed + self.unchanged
+    }
+
+    /// 已处理完成的文件数量，包括跳过和失败的文件
+    pub fn done(&self) -> usize {
+        self.indexed + self.unchanged + self.skipped + self.failed
+    }
+}
+
+/// 单个文件的处理结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    /// 已重新索引，附带代码元素数量
+    Indexed(usize),
+    /// 文件未修改，沿用已有索引
+    Unchanged,
+    /// 二进制或过大的文件
+    Skipped,
+    /// 读取或解析失败
+    Failed(String),
+}
+
+/// 索引进度事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexEvent {
+    /// 遍历完成，开始处理文件
+    Started {
+        /// 待处理的文件数量
+        total: usize,
+    },
+    /// 文件处理完成
+    File {
+        /// 文件路径
+        path: PathBuf,
+        /// 处理结果
+        status: FileStatus,
+        /// 已处理的文件数量
+        done: usize,
+        /// 文件总数
+        total: usize,
+    },
+    /// 已提交检查点，中断后可从此处继续
+    Checkpoint {
+        /// 已处理的文件数量
+        done: usize,
+        /// 文件总数
+        total: usize,
+    },
+    /// 索引完成
+    Finished(IndexStats),
+}
+
+impl fmt::Display for IndexEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexEvent::Started { total } => write!(f, "开始索引 {} 个文件", total),
+            IndexEvent::File {
+                path,
+                status,
+                done,
+                total,
+            } => match status {
+                FileStatus::Indexed(elements) => write!(
+                    f,
+                    "[{}/{}] 已索引: {}（{} 个代码元素）",
+                    done,
+                    total,
+                    path.display(),
+                    elements
+                ),
+                FileStatus::Unchanged => {
+                    write!(f, "[{}/{}] 未修改: {}", done, total, path.display())
+                }
+                FileStatus::Skipped => write!(f, "[{}/{}] 已跳过: {}", done, total, path.display()),
+                FileStatus::Failed(error) => write!(
+                    f,
+                    "[{}/{}] 处理失败: {} ({})",
+                    done,
+                    total,
+                    path.display(),
+                    error
+                ),
+            },
+            IndexEvent::Checkpoint { done, total } => {
+                write!(f, "已保存检查点 {}/{}", done, total)
+            }
+            IndexEvent::Finished(stats) => write!(
+                f,
+                "索引完成：{} 个文件已索引，{} 个未修改，{} 个跳过，{} 个失败，{}",
+                stats.indexed, stats.unchanged, stats.skipped, stats.failed, stats.timings
+            ),
+        }
+    }
+}
+
+/// 工作线程读取和解析后的文件
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 文件内容
+    pub content: String,
+    /// 代码元素，没有对应解析器时为空
+    pub elements: Option<Vec<CodeElement>>,
+    /// 交叉引用，语言不支持时为空
+    pub xrefs: Option<FileXrefs>,
+    /// 增量解析得到的与上次解析相比的元素差异，完整解析时为空
+    pub changes: Option<ElementChanges>,
+}
+
+impl ParsedFile {
+    /// 读取文件后解析代码元素和交叉引用，文件路径应为索引中使用的路径
+    pub fn parse(path: &Path, content: String) -> AppResult<Self> {
+        let key = path.to_string_lossy();
+        let elements = CodeIndexer::parse_elements(&key, &content)?;
+        Self::with_elements(path, content, elements)
+    }
+
+    /// 使用增量解析缓存解析代码元素，缓存中已有该文件时只重新解析变化的部分并记录元素差异
+    pub fn parse_cached(path: &Path, content: String, cache: &mut ParseCache) -> AppResult<Self> {
+        let key = path.to_string_lossy();
+        // 未缓存的文件相当于与空文件比较，得到的差异不能用于更新已有的索引
+        let cached = cache.elements(&key).is_some();
+        let (elements, changes) = match cache.update(&key, &content)? {
+            Some(update) => (
+                cache.elements(&key).map(<[CodeElement]>::to_vec),
+                cached.then_some(update.elements),
+            ),
+            None => (None, None),
+        };
+        let mut parsed = Self::with_elements(path, content, elements)?;
+        parsed.changes = changes;
+        Ok(parsed)
+    }
+
+    /// 使用已解析的代码元素创建解析结果，有代码元素时提取交叉引用
+    fn with_elements(
+        path: &Path,
+        content: String,
+        elements: Option<Vec<CodeElement>>,
+    ) -> AppResult<Self> {
+        let key = path.to_string_lossy();
+        let xrefs = match elements {
+            Some(_) => extract_xrefs(&key, &content)?,
+            None => None,
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            content,
+            elements,
+            xrefs,
+            changes: None,
+        })
+    }
+}
+
+/// 索引写入目标，在调用线程中串行写入解析结果
+pub trait IndexSink {
+    /// 写入解析后的文件，返回写入的代码元素数量
+    fn write_parsed(&mut self, parsed: ParsedFile) -> AppResult<usize>;
+
+    /// 提交已写入的内容，中断后再次索引时从此处继续
+    fn checkpoint(&mut self) -> AppResult<()>;
+}
+
+/// 工作线程的处理结果
+enum WorkItem {
+    /// 已读取和解析
+    Parsed(ParsedFile),
+    /// 文件未修改
+    Unchanged(PathBuf),
+    /// 二进制或过大的文件
+    Skipped(PathBuf),
+    /// 处理失败
+    Failed(PathBuf, String),
+}
+
+/// 并行索引流水线
+pub struct IndexPipeline {
+    /// 读取和解析的工作线程数量
+    workers: usize,
+    /// 每写入多少个文件提交一次检查点
+    checkpoint_interval: usize,
+    /// 索引文件的最大大小（字节）
+    max_file_size: u64,
+}
+
+impl IndexPipeline {
+    /// 创建流水线，工作线程数量为0时使用可用的CPU核心数
+    pub fn new(workers: usize) -> Self {
+        let workers = if workers == 0 {
+            thread::available_parallelism().map_or(1, |count| count.get())
+        } else {
+            workers
+        };
+        Self {
+            workers,
+            checkpoint_interval: DEFAULT_CHECKPOINT_INTERVAL,
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+        }
+    }
+
+    /// 设置检查点间隔，为0时只在结束时提交
+    pub fn checkpoint_interval(mut self, interval: usize) -> Self {
+        self.checkpoint_interval = interval;
+        self
+    }
+
+    /// 设置索引文件的最大大小
+    pub fn max_file_size(mut self, max_file_size: u64) -> Self {
+        self.max_file_size = max_file_size;
+        self
+    }
+
+    /// 运行流水线
+    ///
+    /// `walk` 返回待索引的文件；`is_unchanged` 在工作线程中判断文件是否可以沿用已有索引；
+    /// 解析结果按完成顺序写入 `sink`，并定期提交检查点。写入失败时停止处理剩余文件并返回错误
+    pub fn run(
+        &self,
+        walk: impl FnOnce() -> AppResult<Vec<PathBuf>>,
+        is_unchanged: &(dyn Fn(&Path, &fs::Metadata) -> bool + Sync),
+        sink: &mut dyn IndexSink,
+        on_event: &mut dyn FnMut(IndexEvent),
+    ) -> AppResult<IndexStats> {
+        let started = Instant::now();
+        let files = walk()?;
+        let mut stats = IndexStats {
+            discovered: files.len(),
+            ..Default::default()
+        };
+        stats.timings.walk = started.elapsed();
+        let total = files.len();
+        on_event(IndexEvent::Started { total });
+
+        let next = AtomicUsize::new(0);
+        let stop = AtomicBool::new(false);
+        let (sender, receiver) = mpsc::sync_channel(self.workers * QUEUE_DEPTH);
+        let result = thread::scope(|scope| {
+            for _ in 0..self.workers.min(total) {
+                let sender = sender.clone();
+                let (files, next, stop) = (&files, &next, &stop);
+                scope.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let Some(path) = files.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                            break;
+                        };
+                        let result = self.process(path, is_unchanged);
+                        if sender.send(result).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(sender);
+
+            let mut since_checkpoint = 0;
+            for (item, read, parse) in receiver {
+                stats.timings.read += read;
+                stats.timings.parse += parse;
+                let written = Instant::now();
+                let (path, status) = match item {
+                    WorkItem::Parsed(parsed) => {
+                        let path = parsed.path.clone();
+                        match sink.write_parsed(parsed) {
+                            Ok(elements) => {
+                                stats.indexed += 1;
+                                stats.elements += elements;
+                                since_checkpoint += 1;
+                                (path, FileStatus::Indexed(elements))
+                            }
+                            Err(e) => {
+                                stop.store(true, Ordering::Relaxed);
+                                return Err(e);
+                            }
+                        }
+                    }
+                    WorkItem::Unchanged(path) => {
+                        stats.unchanged += 1;
+                        (path, FileStatus::Unchanged)
+                    }
+                    WorkItem::Skipped(path) => {
+                        stats.skipped += 1;
+                        (path, FileStatus::Skipped)
+                    }
+                    WorkItem::Failed(path, error) => {
+                        log::warn!("索引文件失败 {:?}: {}", path, error);
+                        stats.failed += 1;
+                        (path, FileStatus::Failed(error))
+                    }
+                };
+                on_event(IndexEvent::File {
+                    path,
+                    status,
+                    done: stats.done(),
+                    total,
+                });
+
+                if self.checkpoint_interval > 0 && since_checkpoint >= self.checkpoint_interval {
+                    if let Err(e) = sink.checkpoint() {
+                        stop.store(true, Ordering::Relaxed);
+                        return Err(e);
+                    }
+                    since_checkpoint = 0;
+                    stats.checkpoints += 1;
+                    on_event(IndexEvent::Checkpoint {
+                        done: stats.done(),
+                        total,
+                    });
+                }
+                stats.timings.write += written.elapsed();
+            }
+            Ok(())
+        });
+        result?;
+
+        let written = Instant::now();
+        sink.checkpoint()?;
+        stats.checkpoints += 1;
+        stats.timings.write += written.elapsed();
+        stats.timings.total = started.elapsed();
+        on_event(IndexEvent::Finished(stats.clone()));
+        Ok(stats)
+    }
+
+    /// 在工作线程中读取和解析单个文件，返回处理结果和读取、解析耗时
+    fn process(
+        &self,
+        path: &Path,
+        is_unchanged: &(dyn Fn(&Path, &fs::Metadata) -> bool + Sync),
+    ) -> (WorkItem, Duration, Duration) {
+        let started = Instant::now();
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(e) => {
+                let item = WorkItem::Failed(path.to_path_buf(), e.to_string());
+                return (item, started.elapsed(), Duration::ZERO);
+            }
+        };
+        if is_unchanged(path, &metadata) {
+            return (
+                WorkItem::Unchanged(path.to_path_buf()),
+                started.elapsed(),
+                Duration::ZERO,
+            );
+        }
+
+        let content = match read_text_file(path, self.max_file_size) {
+            Ok(Some(content)) => content,
+            Ok(None) => {
+                let item = WorkItem::Skipped(path.to_path_buf());
+                return (item, started.elapsed(), Duration::ZERO);
+            }
+            Err(e) => {
+                let item = WorkItem::Failed(path.to_path_buf(), e.to_string());
+                return (item, started.elapsed(), Duration::ZERO);
+            }
+        };
+        let read = started.elapsed();
+
+        let parsing = Instant::now();
+        let item = match ParsedFile::parse(path, content) {
+            Ok(parsed) => WorkItem::Parsed(parsed),
+            Err(e) => WorkItem::Failed(path.to_path_buf(), e.to_string()),
+        };
+        (item, read, parsing.elapsed())
+    }
+}
@@ -0,0 +1,555 @@
+//! 结构化搜索查询
+//!
+//! 解析形如 `lang:rust path:src/ai/** kind:fn name:generate_* retry~1 /back.*/ after:2024-01-01`
+//! 的查询。`lang`、`path`、`kind`、`name`、`after`、`before` 为过滤条件，其余部分为关键词、
+//! 引号包围的短语、斜杠包围的正则和带 `~` 的模糊词。正则和模糊词匹配单个小写词。
+//! `kind:fn` 同时匹配函数和类型中的方法，`kind:method` 只匹配方法。
+//!
+//! 文档没有元素类型和名称，含 `kind`、`name` 条件的查询不返回文档；符号没有索引时间，
+//! 含 `after`、`before` 条件的查询不返回符号。
+//!
+//! 同时负责在结果中定位并高亮匹配的内容
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::DocumentMeta;
+use crate::parsers::CodeElementType;
+use regex::Regex;
+
+/// 模糊词允许的最大编辑距离
+pub const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// 每个结果最多高亮的行数
+pub const MAX_HIGHLIGHT_LINES: usize = 3;
+
+/// 高亮行的最大字节数，超出部分被截断
+const MAX_LINE_BYTES: usize = 200;
+
+/// 查询词
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryTerm {
+    /// 关键词
+    Word(String),
+    /// 短语，需按顺序出现
+    Phrase(String),
+    /// 正则表达式，匹配单个词
+    Regex(String),
+    /// 模糊词，允许指定的编辑距离
+    Fuzzy {
+        /// 词
+        term: String,
+        /// 编辑距离
+        distance: u8,
+    },
+}
+
+/// 结构化搜索查询
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    /// 查询词
+    pub terms: Vec<QueryTerm>,
+    /// 语言过滤，满足任一即可
+    pub languages: Vec<String>,
+    /// 路径过滤（glob 语法），满足任一即可
+    pub paths: Vec<String>,
+    /// 元素类型过滤，满足任一即可
+    pub kinds: Vec<CodeElementType>,
+    /// 元素名称过滤（glob 语法，不区分大小写），满足任一即可
+    pub names: Vec<String>,
+    /// 只包含此时间（Unix秒）及之后索引的文档
+    pub after: Option<i64>,
+    /// 只包含此时间（Unix秒）之前索引的文档
+    pub before: Option<i64>,
+}
+
+impl SearchQuery {
+    /// 解析查询字符串
+    pub fn parse(input: &str) -> AppResult<Self> {
+        let mut query = Self::default();
+        for token in tokenize(input) {
+            query.add_token(&token)?;
+        }
+        Ok(query)
+    }
+
+    /// 解析单个词元
+    fn add_token(&mut self, token: &str) -> AppResult<()> {
+        if token.len() >= 2 && token.starts_with('/') && token.ends_with('/') {
+            let pattern = &token[1..token.len() - 1];
+            Regex::new(pattern)
+                .map_err(|e| AppError::knowledge(&format!("无效的正则表达式 {}: {}", token, e)))?;
+            self.terms.push(QueryTerm::Regex(pattern.to_string()));
+            return Ok(());
+        }
+        if let Some(phrase) = token.strip_prefix('"') {
+            let phrase = phrase.strip_suffix('"').unwrap_or(phrase).trim();
+            if !phrase.is_empty() {
+                self.terms.push(QueryTerm::Phrase(phrase.to_string()));
+            }
+            return Ok(());
+        }
+
+        if let Some((key, value)) = token.split_once(':') {
+            let value = unquote(value);
+            let known = match key {
+                "lang" | "language" => {
+                    self.languages.push(normalize_language(value));
+                    true
+                }
+                "path" => {
+                    self.paths.push(value.to_string());
+                    true
+                }
+                "kind" => {
+                    let kind = value.parse().map_err(|e: String| AppError::knowledge(&e))?;
+                    self.kinds.push(kind);
+                    true
+                }
+                "name" => {
+                    self.names.push(value.to_lowercase());
+                    true
+                }
+                "after" => {
+                    self.after = Some(parse_date(value)?);
+                    true
+                }
+                "before" => {
+                    self.before = Some(parse_date(value)?);
+                    true
+                }
+                _ => false,
+            };
+            if known {
+                if value.is_empty() {
+                    return Err(AppError::knowledge(&format!("过滤条件 {} 缺少值", key)));
+                }
+                return Ok(());
+            }
+        }
+
+        if let Some((term, distance)) = token.rsplit_once('~') {
+            if !term.is_empty() {
+                let distance = if distance.is_empty() {
+                    1
+                } else {
+                    distance.parse::<u8>().map_err(|_| {
+                        AppError::knowledge(&format!("无效的模糊编辑距离: {}", token))
+                    })?
+                };
+                if distance == 0 || distance > MAX_FUZZY_DISTANCE {
+                    return Err(AppError::knowledge(&format!(
+                        "模糊编辑距离必须在1到{}之间: {}",
+                        MAX_FUZZY_DISTANCE, token
+                    )));
+                }
+                self.terms.push(QueryTerm::Fuzzy {
+                    term: term.to_lowercase(),
+                    distance,
+                });
+                return Ok(());
+            }
+        }
+
+        self.terms.push(QueryTerm::Word(token.to_string()));
+        Ok(())
+    }
+
+    /// 查询是否既没有查询词也没有过滤条件
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+            && self.languages.is_empty()
+            && self.paths.is_empty()
+            && !self.has_symbol_filters()
+            && !self.has_date_filters()
+    }
+
+    /// 是否包含只适用于符号的过滤条件（类型、名称）
+    pub fn has_symbol_filters(&self) -> bool {
+        !self.kinds.is_empty() || !self.names.is_empty()
+    }
+
+    /// 是否包含只适用于文档的时间过滤条件
+    pub fn has_date_filters(&self) -> bool {
+        self.after.is_some() || self.before.is_some()
+    }
+
+    /// 关键词和短语组成的全文查询文本，短语保留引号
+    pub fn text(&self) -> String {
+        self.terms
+            .iter()
+            .filter_map(|term| match term {
+                QueryTerm::Word(word) => Some(word.clone()),
+                QueryTerm::Phrase(phrase) => Some(format!("\"{}\"", phrase)),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 用于语义检索的自然语言文本：所有查询词，不含过滤条件和查询语法
+    pub fn semantic_text(&self) -> String {
+        self.terms
+            .iter()
+            .map(|term| match term {
+                QueryTerm::Word(text) | QueryTerm::Phrase(text) | QueryTerm::Regex(text) => {
+                    text.as_str()
+                }
+                QueryTerm::Fuzzy { term, .. } => term.as_str(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 路径过滤条件对应的正则表达式，需完整匹配路径
+    pub fn path_expressions(&self) -> Vec<String> {
+        self.paths
+            .iter()
+            .map(|path| path_expression(path))
+            .collect()
+    }
+
+    /// 名称过滤条件对应的正则表达式，需完整匹配小写名称
+    pub fn name_expressions(&self) -> Vec<String> {
+        self.names
+            .iter()
+            .map(|name| glob_expression(name, false))
+            .collect()
+    }
+
+    /// 判断路径是否满足路径过滤条件
+    pub fn matches_path(&self, path: &str) -> bool {
+        self.paths.is_empty()
+            || self.path_expressions().iter().any(|expression| {
+                Regex::new(&format!("^{}$", expression)).is_ok_and(|regex| regex.is_match(path))
+            })
+    }
+
+    /// 判断文档是否满足语言、路径和时间过滤条件
+    pub fn matches_document(&self, meta: &DocumentMeta) -> bool {
+        !self.has_symbol_filters()
+            && (self.languages.is_empty() || self.languages.contains(&meta.language))
+            && self.matches_path(&meta.path)
+            && self.after.is_none_or(|after| meta.indexed_at >= after)
+            && self.before.is_none_or(|before| meta.indexed_at < before)
+    }
+
+    /// 创建高亮匹配内容的高亮器
+    pub fn highlighter(&self) -> Highlighter {
+        let mut highlighter = Highlighter::default();
+        for term in &self.terms {
+            match term {
+                QueryTerm::Word(word) => highlighter.words.extend(words(word)),
+                QueryTerm::Phrase(phrase) => {
+                    // 短语中的词之间允许任意分隔符，与索引分词一致
+                    let pattern = words(phrase)
+                        .map(|word| regex::escape(&word))
+                        .collect::<Vec<_>>()
+                        .join(r"[^\p{Alphabetic}\p{N}]+");
+                    if let Ok(regex) = Regex::new(&format!("(?i){}", pattern)) {
+                        highlighter.phrases.push(regex);
+                    }
+                }
+                QueryTerm::Regex(pattern) => {
+                    if let Ok(regex) = Regex::new(&format!("(?i)^(?:{})$", pattern)) {
+                        highlighter.regexes.push(regex);
+                    }
+                }
+                QueryTerm::Fuzzy { term, distance } => {
+                    highlighter.fuzzy.push((term.clone(), *distance))
+                }
+            }
+        }
+        highlighter
+    }
+}
+
+/// 按空白切分查询，引号内的空白和正则中的空白不切分
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quote = false;
+    let mut in_regex = false;
+    let mut escaped = false;
+
+    for c in input.chars() {
+        if in_regex {
+            current.push(c);
+            if c == '/' && !escaped {
+                in_regex = false;
+            }
+            escaped = c == '\\' && !escaped;
+            continue;
+        }
+        match c {
+            '"' => {
+                in_quote = !in_quote;
+                current.push(c);
+            }
+            '/' if current.is_empty() => {
+                in_regex = true;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quote => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+/// 去掉值两侧的引号
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .map(|value| value.strip_suffix('"').unwrap_or(value))
+        .unwrap_or(value)
+}
+
+/// 将语言别名规范化为索引中使用的语言名称
+fn normalize_language(language: &str) -> String {
+    let language = language.to_lowercase();
+    match language.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" => "javascript",
+        "ts" => "typescript",
+        "md" => "markdown",
+        "yml" => "yaml",
+        "c++" => "cpp",
+        "golang" => "go",
+        other => other,
+    }
+    .to_string()
+}
+
+/// 解析日期：`YYYY-MM-DD`（UTC零点）或相对时间，如 `12h`、`7d`、`2w`
+fn parse_date(value: &str) -> AppResult<i64> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .timestamp());
+    }
+
+    let invalid = || {
+        AppError::knowledge(&format!(
+            "无效的日期 {}，请使用 YYYY-MM-DD 或 12h、7d、2w 这样的相对时间",
+            value
+        ))
+    };
+    let unit = value.chars().last().ok_or_else(invalid)?;
+    let seconds = match unit {
+        'h' => 3600,
+        'd' => 86400,
+        'w' => 7 * 86400,
+        _ => return Err(invalid()),
+    };
+    let amount: i64 = value[..value.len() - 1].parse().map_err(|_| invalid())?;
+    Ok(chrono::Utc::now().timestamp() - amount * seconds)
+}
+
+/// 路径 glob 对应的正则：以 `/` 开头时匹配完整路径，否则匹配路径中任意目录之后的部分；
+/// 不含通配符时同时匹配该路径下的所有文件
+fn path_expression(pattern: &str) -> String {
+    let mut expression = if pattern.starts_with('/') {
+        String::new()
+    } else {
+        String::from("(.*/)?")
+    };
+    expression.push_str(&glob_expression(pattern.trim_end_matches('/'), true));
+    if !pattern.contains(['*', '?']) {
+        expression.push_str("(/.*)?");
+    }
+    expression
+}
+
+/// glob 转换为正则：`**` 匹配任意字符，`*` 和 `?` 在路径模式下不跨越 `/`
+fn glob_expression(pattern: &str, path: bool) -> String {
+    let mut expression = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    expression.push_str("(.*/)?");
+                } else {
+                    expression.push_str(".*");
+                }
+            }
+            '*' if path => expression.push_str("[^/]*"),
+            '*' => expression.push_str(".*"),
+            '?' if path => expression.push_str("[^/]"),
+            '?' => expression.push('.'),
+            c => expression.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    expression
+}
+
+/// 将文本切分为小写词，与全文索引的分词方式一致
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+}
+
+/// 带行号的高亮行
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightedLine {
+    /// 行号（从1开始）
+    pub line: usize,
+    /// 行内容
+    pub text: String,
+    /// 匹配内容的字节范围，按位置排序且互不重叠
+    pub ranges: Vec<(usize, usize)>,
+}
+
+impl HighlightedLine {
+    /// 按匹配范围切分行内容，返回 `(文本, 是否匹配)` 片段
+    pub fn segments(&self) -> Vec<(&str, bool)> {
+        let mut segments = Vec::new();
+        let mut position = 0;
+        for &(start, end) in &self.ranges {
+            if start > position {
+                segments.push((&self.text[position..start], false));
+            }
+            segments.push((&self.text[start..end], true));
+            position = end;
+        }
+        if position < self.text.len() {
+            segments.push((&self.text[position..], false));
+        }
+        segments
+    }
+}
+
+/// 匹配内容高亮器
+#[derive(Debug, Clone, Default)]
+pub struct Highlighter {
+    /// 小写关键词
+    words: Vec<String>,
+    /// 匹配短语的正则
+    phrases: Vec<Regex>,
+    /// 匹配单个词的正则
+    regexes: Vec<Regex>,
+    /// 模糊词及编辑距离
+    fuzzy: Vec<(String, u8)>,
+}
+
+impl Highlighter {
+    /// 查找文本中匹配的字节范围，按位置排序并合并重叠部分
+    pub fn ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+
+        let mut start = None;
+        for (index, c) in text.char_indices().chain([(text.len(), ' ')]) {
+            match (c.is_alphanumeric(), start) {
+                (true, None) => start = Some(index),
+                (false, Some(word_start)) => {
+                    if self.matches_word(&text[word_start..index]) {
+                        ranges.push((word_start, index));
+                    }
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        for phrase in &self.phrases {
+            ranges.extend(
+                phrase
+                    .find_iter(text)
+                    .map(|matched| (matched.start(), matched.end())),
+            );
+        }
+
+        ranges.sort_unstable();
+        let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+        for (start, end) in ranges {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    /// 判断单个词是否匹配
+    fn matches_word(&self, word: &str) -> bool {
+        let lower = word.to_lowercase();
+        self.words.contains(&lower)
+            || self.regexes.iter().any(|regex| regex.is_match(word))
+            || self
+                .fuzzy
+                .iter()
+                .any(|(term, distance)| edit_distance(term, &lower) <= *distance as usize)
+    }
+
+    /// 高亮从 `first_line` 开始的文本，返回最多 `MAX_HIGHLIGHT_LINES` 个匹配行；
+    /// 没有匹配时返回第一个非空行作为上下文
+    pub fn highlight(&self, text: &str, first_line: usize) -> Vec<HighlightedLine> {
+        let mut lines = Vec::new();
+        let mut fallback = None;
+        for (offset, line) in text.lines().enumerate() {
+            let line = truncate(line.trim_end(), MAX_LINE_BYTES);
+            let ranges = self.ranges(line);
+            if ranges.is_empty() {
+                if fallback.is_none() && !line.trim().is_empty() {
+                    fallback = Some(HighlightedLine {
+                        line: first_line + offset,
+                        text: line.to_string(),
+                        ranges,
+                    });
+                }
+                continue;
+            }
+            lines.push(HighlightedLine {
+                line: first_line + offset,
+                text: line.to_string(),
+                ranges,
+            });
+            if lines.len() == MAX_HIGHLIGHT_LINES {
+                break;
+            }
+        }
+        if lines.is_empty() {
+            lines.extend(fallback);
+        }
+        lines
+    }
+}
+
+/// 在字符边界处截断文本
+fn truncate(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// 计算两个词的编辑距离
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -239,7 +239,7 @@ impl KnowledgeBase for RemoteKnowledgeBase {
         Ok(())
     }
     
-    fn search(&mut self, query: &str) -> AppResult<Vec<Arc<CodeElement>>> {
+    fn search(&self, query: &str) -> AppResult<Vec<Arc<CodeElement>>> {
         // 搜索远程内容
         self.search_remote(query)
     }
@@ -1,15 +1,18 @@
 //! 搜索结果优化模块
-//! 
-//! 提供搜索结果的相关性排序、个性化排序、结果多样化和搜索意图理解功能
+//!
+//! 提供搜索结果的相关性排序、个性化排序、结果多样化和搜索意图理解功能。
+//! 知识库的符号和文档搜索结果都经过优化器重新排序，用户搜索历史保存在元数据数据库中，
+//! 重启后仍可用于个性化排序
 
 use crate::error::AppResult;
-use crate::parsers::CodeElement;
+use crate::parsers::CodeElementType;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::time::SystemTime;
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// 搜索结果优化配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchResultOptimizerConfig {
     /// 相关性权重
     pub relevance_weight: f32,
@@ -25,8 +28,12 @@ pub struct SearchResultOptimizerConfig {
     pub enable_personalization: bool,
     /// 是否启用结果多样化
     pub enable_diversity: bool,
-    /// 搜索意图理解阈值
+    /// 搜索意图理解阈值，置信度低于此值时不按意图提升元素类型
     pub intent_understanding_threshold: f32,
+    /// 时间分数减半所需的天数
+    pub recency_half_life_days: f32,
+    /// 保留的搜索历史数量
+    pub max_history_items: usize,
 }
 
 impl Default for SearchResultOptimizerConfig {
@@ -37,9 +44,11 @@ impl Default for SearchResultOptimizerConfig {
             diversity_weight: 0.1,
             time_weight: 0.1,
             max_results: 20,
-            enable_personalization: false,
+            enable_personalization: true,
             enable_diversity: true,
             intent_understanding_threshold: 0.8,
+            recency_half_life_days: 30.0,
+            max_history_items: 100,
         }
     }
 }
@@ -65,6 +74,22 @@ pub enum SearchIntent {
     Other,
 }
 
+impl SearchIntent {
+    /// 获取意图的显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchIntent::FunctionDefinition => "函数定义",
+            SearchIntent::ClassDefinition => "类型定义",
+            SearchIntent::VariableDefinition => "变量定义",
+            SearchIntent::FeatureSearch => "功能查找",
+            SearchIntent::ExampleSearch => "代码示例",
+            SearchIntent::DocumentationSearch => "文档查找",
+            SearchIntent::ErrorFixSearch => "错误修复",
+            SearchIntent::Other => "其他",
+        }
+    }
+}
+
 /// 搜索结果评分
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultScore {
@@ -99,18 +124,41 @@ pub enum MatchType {
     Semantic,
 }
 
+/// 待优化的搜索结果，由符号或文档搜索结果转换而来
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchCandidate {
+    /// 结果标识（符号位置或文档路径），用于记录用户选择
+    pub id: String,
+    /// 名称（符号名或文档标题）
+    pub name: String,
+    /// 代码元素类型，文档结果为空
+    pub element_type: Option<CodeElementType>,
+    /// 文件路径或网页URL
+    pub path: String,
+    /// 文档注释
+    pub documentation: String,
+    /// 代码内容（符号签名或匹配片段）
+    pub content: String,
+    /// 检索引擎给出的原始评分
+    pub engine_score: f32,
+    /// 最近修改时间（Unix秒）
+    pub modified_at: Option<i64>,
+}
+
 /// 搜索结果条目
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResultItem {
-    /// 代码元素
-    pub element: CodeElement,
+    /// 结果在优化前列表中的位置
+    pub index: usize,
+    /// 结果标识
+    pub id: String,
     /// 搜索结果评分
     pub score: SearchResultScore,
     /// 匹配的关键词
     pub matched_keywords: Vec<String>,
     /// 搜索意图
     pub intent: SearchIntent,
-    /// 结果类型
+    /// 结果类型：symbol 或 document
     pub result_type: String,
     /// 相关性解释
     pub relevance_explanation: String,
@@ -126,28 +174,49 @@ pub struct SearchIntentUnderstandingResult {
     /// 提取的关键词
     pub keywords: Vec<String>,
     /// 意图相关的代码元素类型
-    pub relevant_element_types: Vec<String>,
+    pub relevant_element_types: Vec<CodeElementType>,
+}
+
+impl SearchIntentUnderstandingResult {
+    /// 元素类型是否与意图相关，置信度不足时不作判断
+    fn boosts(&self, element_type: Option<&CodeElementType>, threshold: f32) -> bool {
+        self.confidence >= threshold
+            && element_type.is_some_and(|kind| self.relevant_element_types.contains(kind))
+    }
 }
 
 /// 搜索结果优化器
-trait SearchResultOptimizer {
+pub trait SearchResultOptimizer {
     /// 优化搜索结果
-    fn optimize_results(&self, query: &str, results: Vec<CodeElement>) -> AppResult<Vec<SearchResultItem>>;
-    
+    fn optimize_results(
+        &self,
+        query: &str,
+        results: Vec<SearchCandidate>,
+    ) -> AppResult<Vec<SearchResultItem>>;
+
     /// 计算结果相关性
-    fn calculate_relevance(&self, query: &str, element: &CodeElement) -> u8;
-    
+    fn calculate_relevance(&self, query: &str, candidate: &SearchCandidate) -> u8;
+
     /// 理解搜索意图
     fn understand_intent(&self, query: &str) -> SearchIntentUnderstandingResult;
-    
+
     /// 个性化排序
-    fn personalize_results(&self, query: &str, results: &mut Vec<SearchResultItem>);
-    
+    fn personalize_results(
+        &self,
+        query: &str,
+        candidates: &[SearchCandidate],
+        results: &mut [SearchResultItem],
+    );
+
     /// 结果多样化
-    fn diversify_results(&self, results: &mut Vec<SearchResultItem>);
-    
+    fn diversify_results(
+        &self,
+        candidates: &[SearchCandidate],
+        results: &mut Vec<SearchResultItem>,
+    );
+
     /// 综合排序
-    fn sort_results(&self, results: &mut Vec<SearchResultItem>);
+    fn sort_results(&self, results: &mut [SearchResultItem]);
 }
 
 /// 基于规则的搜索结果优化器实现
@@ -176,65 +245,96 @@ pub struct UserSearchHistoryItem {
 impl RuleBasedSearchResultOptimizer {
     /// 创建新的搜索结果优化器
     pub fn new(config: SearchResultOptimizerConfig) -> Self {
+        let max_history_items = config.max_history_items;
         Self {
             config,
             user_search_history: Vec::new(),
-            max_history_items: 100,
+            max_history_items,
         }
     }
-    
+
     /// 添加用户搜索历史
     pub fn add_search_history(&mut self, history_item: UserSearchHistoryItem) {
         self.user_search_history.push(history_item);
-        
+
         // 限制历史记录数量
         if self.user_search_history.len() > self.max_history_items {
             self.user_search_history.remove(0);
         }
     }
-    
+
     /// 获取用户搜索历史
     pub fn get_search_history(&self) -> &Vec<UserSearchHistoryItem> {
         &self.user_search_history
     }
+
+    /// 为查询创建搜索历史条目
+    pub fn history_item(&self, query: &str, clicked_results: Vec<String>) -> UserSearchHistoryItem {
+        UserSearchHistoryItem {
+            query: query.to_string(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            clicked_results,
+            intent: self.understand_intent(query).intent,
+        }
+    }
 }
 
 impl SearchResultOptimizer for RuleBasedSearchResultOptimizer {
-    fn optimize_results(&self, query: &str, results: Vec<CodeElement>) -> AppResult<Vec<SearchResultItem>> {
+    fn optimize_results(
+        &self,
+        query: &str,
+        results: Vec<SearchCandidate>,
+    ) -> AppResult<Vec<SearchResultItem>> {
         // 理解搜索意图
         let intent_understanding = self.understand_intent(query);
-        
+        let max_engine_score = results
+            .iter()
+            .map(|candidate| candidate.engine_score)
+            .fold(0.0_f32, f32::max);
+
         // 处理搜索结果，计算评分
         let mut result_items: Vec<SearchResultItem> = results
-            .into_iter()
-            .map(|element| {
-                // 计算相关性分数
-                let relevance = self.calculate_relevance(query, &element);
-                
-                // 计算个性化分数
-                let personalization = if self.config.enable_personalization {
-                    self.calculate_personalization(&element)
-                } else {
-                    50
-                };
-                
+            .iter()
+            .enumerate()
+            .map(|(index, candidate)| {
+                // 规则相关性、检索得分和意图对应的元素类型共同决定相关性分数
+                let mut relevance = self.calculate_relevance(query, candidate) as f32;
+                if max_engine_score > 0.0 {
+                    relevance += candidate.engine_score / max_engine_score * ENGINE_SCORE_POINTS;
+                }
+                if intent_understanding.boosts(
+                    candidate.element_type.as_ref(),
+                    self.config.intent_understanding_threshold,
+                ) {
+                    relevance += INTENT_BOOST_POINTS;
+                }
+                let relevance = relevance.clamp(0.0, 100.0) as u8;
+
+                // 个性化分数在个性化排序中更新
+                let personalization = 50;
+
                 // 计算多样性分数
                 let diversity = 50; // 初始值，后续在多样化处理中更新
-                
+
                 // 计算时间分数
-                let time = self.calculate_time_score(&element);
-                
+                let time = self.calculate_time_score(candidate);
+
                 // 计算综合分数
-                let overall = self.calculate_overall_score(relevance, personalization, diversity, time);
-                
+                let overall =
+                    self.calculate_overall_score(relevance, personalization, diversity, time);
+
                 // 确定匹配类型
-                let match_type = self.determine_match_type(query, &element);
-                
+                let match_type = self.determine_match_type(query, candidate);
+
                 // 提取匹配的关键词
-                let matched_keywords = self.extract_matched_keywords(query, &element);
-                
+                let matched_keywords = self.extract_matched_keywords(query, candidate);
+
                 SearchResultItem {
-                    element,
+                    index,
+                    id: candidate.id.clone(),
                     score: SearchResultScore {
                         relevance,
                         personalization,
@@ -245,129 +345,128 @@ impl SearchResultOptimizer for RuleBasedSearchResultOptimizer {
                     },
                     matched_keywords,
                     intent: intent_understanding.intent,
-                    result_type: "code_element".to_string(),
-                    relevance_explanation: self.generate_relevance_explanation(query, &element, relevance),
+                    result_type: if candidate.element_type.is_some() {
+                        "symbol".to_string()
+                    } else {
+                        "document".to_string()
+                    },
+                    relevance_explanation: String::new(),
                 }
             })
             .collect();
-        
+
         // 个性化排序
         if self.config.enable_personalization {
-            self.personalize_results(query, &mut result_items);
+            self.personalize_results(query, &results, &mut result_items);
         }
-        
+
         // 综合排序
         self.sort_results(&mut result_items);
-        
+
         // 结果多样化
         if self.config.enable_diversity {
-            self.diversify_results(&mut result_items);
+            self.diversify_results(&results, &mut result_items);
         }
-        
+
         // 限制结果数量
         if result_items.len() > self.config.max_results {
             result_items.truncate(self.config.max_results);
         }
-        
+
+        for item in &mut result_items {
+            item.relevance_explanation = self.generate_relevance_explanation(
+                &results[item.index],
+                item,
+                &intent_understanding,
+            );
+        }
         Ok(result_items)
     }
-    
-    fn calculate_relevance(&self, query: &str, element: &CodeElement) -> u8 {
+
+    fn calculate_relevance(&self, query: &str, candidate: &SearchCandidate) -> u8 {
         // 实现基于规则的相关性计算
-        let mut score = 0;
-        
+        let query_lower = query.to_lowercase();
+        let keywords = self.extract_keywords(&query_lower);
+        if keywords.is_empty() {
+            return 0;
+        }
+        let name_lower = candidate.name.to_lowercase();
+        let doc_lower = candidate.documentation.to_lowercase();
+        let content_lower = candidate.content.to_lowercase();
+        let share = |text: &str| {
+            keywords
+                .iter()
+                .filter(|keyword| text.contains(keyword.as_str()))
+                .count() as f32
+                / keywords.len() as f32
+        };
+
+        let mut score = 0.0;
+
         // 1. 完全匹配名称
-        if element.name == query {
-            score += 40;
+        if name_lower == query_lower.trim() || name_lower == keywords.join("_") {
+            score += 30.0;
         }
-        
+
         // 2. 名称包含关键词
-        if element.name.contains(query) {
-            score += 20;
-        }
-        
+        score += share(&name_lower) * 20.0;
+
         // 3. 文档包含关键词
-        if let Some(doc) = &element.doc {
-            if doc.contains(query) {
-                score += 15;
-            }
-        }
-        
+        score += share(&doc_lower) * 10.0;
+
         // 4. 代码包含关键词
-        if element.code.contains(query) {
-            score += 10;
-        }
-        
-        // 5. 基于元素类型的权重
-        let type_weight = match element.element_type {
-            crate::parsers::CodeElementType::Function => 15,
-            crate::parsers::CodeElementType::Class => 15,
-            crate::parsers::CodeElementType::Method => 10,
-            crate::parsers::CodeElementType::Variable => 5,
-            crate::parsers::CodeElementType::Constant => 5,
-            crate::parsers::CodeElementType::Interface => 12,
-            crate::parsers::CodeElementType::Struct => 12,
-            crate::parsers::CodeElementType::Enum => 10,
-            crate::parsers::CodeElementType::Module => 8,
-            _ => 5,
-        };
-        score += type_weight;
-        
+        score += share(&content_lower) * 10.0;
+
         // 确保分数在0-100之间
-        score.clamp(0, 100) as u8
+        score.clamp(0.0, 100.0) as u8
     }
-    
+
     fn understand_intent(&self, query: &str) -> SearchIntentUnderstandingResult {
         // 实现基于规则的搜索意图理解
         let query_lower = query.to_lowercase();
-        let mut keywords = Vec::new();
-        let mut relevant_element_types = Vec::new();
-        
+
         // 提取关键词
-        keywords = self.extract_keywords(&query_lower);
-        
+        let keywords = self.extract_keywords(&query_lower);
+
         // 检测搜索意图
         let (intent, confidence) = self.detect_intent(&query_lower, &keywords);
-        
+
         // 根据意图确定相关的代码元素类型
-        match intent {
+        let relevant_element_types = match intent {
             SearchIntent::FunctionDefinition => {
-                relevant_element_types.push("function".to_string());
-                relevant_element_types.push("method".to_string());
-            },
-            SearchIntent::ClassDefinition => {
-                relevant_element_types.push("class".to_string());
-                relevant_element_types.push("struct".to_string());
-                relevant_element_types.push("interface".to_string());
-            },
+                vec![CodeElementType::Function, CodeElementType::Method]
+            }
+            SearchIntent::ClassDefinition => vec![
+                CodeElementType::Class,
+                CodeElementType::Struct,
+                CodeElementType::Interface,
+                CodeElementType::Trait,
+                CodeElementType::Enum,
+            ],
             SearchIntent::VariableDefinition => {
-                relevant_element_types.push("variable".to_string());
-                relevant_element_types.push("constant".to_string());
-            },
-            SearchIntent::FeatureSearch => {
-                relevant_element_types.push("function".to_string());
-                relevant_element_types.push("class".to_string());
-                relevant_element_types.push("method".to_string());
-            },
-            SearchIntent::ExampleSearch => {
-                relevant_element_types.push("function".to_string());
-                relevant_element_types.push("class".to_string());
-            },
-            SearchIntent::DocumentationSearch => {
-                relevant_element_types.push("class".to_string());
-                relevant_element_types.push("function".to_string());
-                relevant_element_types.push("interface".to_string());
-            },
+                vec![CodeElementType::Variable, CodeElementType::Constant]
+            }
+            SearchIntent::FeatureSearch | SearchIntent::ExampleSearch => {
+                vec![
+                    CodeElementType::Function,
+                    CodeElementType::Method,
+                    CodeElementType::Class,
+                ]
+            }
+            SearchIntent::DocumentationSearch => vec![
+                CodeElementType::Class,
+                CodeElementType::Function,
+                CodeElementType::Method,
+                CodeElementType::Interface,
+                CodeElementType::Section,
+            ],
             SearchIntent::ErrorFixSearch => {
-                relevant_element_types.push("function".to_string());
-                relevant_element_types.push("method".to_string());
-            },
-            SearchIntent::Other => {
-                // 所有类型都相关
-                relevant_element_types.push("all".to_string());
-            },
-        }
-        
+                vec![CodeElementType::Function, CodeElementType::Method]
+            }
+            // 所有类型都相关
+            SearchIntent::Other => Vec::new(),
+        };
+
         SearchIntentUnderstandingResult {
             intent,
             confidence,
@@ -375,167 +474,219 @@ impl SearchResultOptimizer for RuleBasedSearchResultOptimizer {
             relevant_element_types,
         }
     }
-    
-    fn personalize_results(&self, query: &str, results: &mut Vec<SearchResultItem>) {
-        // 实现基于用户历史的个性化排序
-        // TODO: 主人~ 这里需要实现基于用户搜索历史的个性化排序逻辑
-        // 提示：根据用户历史搜索和点击记录，调整结果的个性化分数
-        
-        // 简单实现：如果用户历史中搜索过类似的查询，提高相关结果的个性化分数
+
+    fn personalize_results(
+        &self,
+        query: &str,
+        candidates: &[SearchCandidate],
+        results: &mut [SearchResultItem],
+    ) {
+        // 相似查询中点击过的结果大幅提升，其他查询中点击过的文件小幅提升
+        let similar: Vec<&UserSearchHistoryItem> = self
+            .user_search_history
+            .iter()
+            .filter(|item| self.is_similar_query(query, &item.query))
+            .collect();
         for result in results {
-            // 检查用户历史中是否有类似的查询
-            for history_item in &self.user_search_history {
-                if self.is_similar_query(query, &history_item.query) {
-                    // 提高个性化分数
-                    result.score.personalization = std::cmp::min(100, result.score.personalization + 10);
-                    break;
-                }
-            }
+            let personalization =
+                self.calculate_personalization(&candidates[result.index], &similar);
+            result.score.personalization = personalization;
+            result.score.overall = self.calculate_overall_score(
+                result.score.relevance,
+                personalization,
+                result.score.diversity,
+                result.score.time,
+            );
         }
     }
-    
-    fn diversify_results(&self, results: &mut Vec<SearchResultItem>) {
+
+    fn diversify_results(
+        &self,
+        candidates: &[SearchCandidate],
+        results: &mut Vec<SearchResultItem>,
+    ) {
         // 实现结果多样化，避免结果过于集中
-        let mut diversified_results = Vec::new();
+        let mut selected = vec![false; results.len()];
+        let mut order = Vec::with_capacity(results.len());
         let mut seen_types = HashSet::new();
         let mut seen_files = HashSet::new();
-        
+
         // 第一轮：选择不同类型的结果
-        for result in results.iter() {
-            let element_type = format!("{:?}", result.element.element_type);
-            if !seen_types.contains(&element_type) {
-                diversified_results.push(result.clone());
-                seen_types.insert(element_type);
-                seen_files.insert(result.element.file_path.clone());
+        for (position, result) in results.iter().enumerate() {
+            let candidate = &candidates[result.index];
+            if seen_types.insert(candidate.element_type.as_ref().map(|kind| kind.as_str())) {
+                selected[position] = true;
+                order.push(position);
+                seen_files.insert(candidate.path.as_str());
             }
         }
-        
+
         // 第二轮：选择不同文件的结果
-        for result in results.iter() {
-            if diversified_results.len() >= self.config.max_results {
-                break;
+        for (position, result) in results.iter().enumerate() {
+            if selected[position] {
+                continue;
             }
-            let file_path = result.element.file_path.clone();
-            if !seen_files.contains(&file_path) {
-                diversified_results.push(result.clone());
-                seen_files.insert(file_path);
+            if seen_files.insert(candidates[result.index].path.as_str()) {
+                selected[position] = true;
+                order.push(position);
             }
         }
-        
-        // 第三轮：填充剩余结果
-        for result in results.iter() {
-            if diversified_results.len() >= self.config.max_results {
-                break;
-            }
-            if !diversified_results.contains(result) {
-                diversified_results.push(result.clone());
+
+        // 第三轮：填充剩余结果，同一文件中的其他结果多样性较低
+        for (position, _) in results.iter().enumerate() {
+            if !selected[position] {
+                order.push(position);
             }
         }
-        
+
         // 更新结果列表
-        *results = diversified_results;
+        let mut items: Vec<Option<SearchResultItem>> = results.drain(..).map(Some).collect();
+        let distinct = selected.iter().filter(|selected| **selected).count();
+        for (rank, position) in order.into_iter().enumerate() {
+            if let Some(mut item) = items[position].take() {
+                item.score.diversity = if rank < distinct { 100 } else { 0 };
+                results.push(item);
+            }
+        }
     }
-    
-    fn sort_results(&self, results: &mut Vec<SearchResultItem>) {
+
+    fn sort_results(&self, results: &mut [SearchResultItem]) {
         // 实现基于综合分数的排序
         results.sort_by(|a, b| {
             // 首先按综合分数降序排序
-            let score_cmp = b.score.overall.cmp(&a.score.overall);
-            if score_cmp != std::cmp::Ordering::Equal {
-                return score_cmp;
-            }
-            
-            // 其次按相关性分数降序排序
-            let relevance_cmp = b.score.relevance.cmp(&a.score.relevance);
-            if relevance_cmp != std::cmp::Ordering::Equal {
-                return relevance_cmp;
-            }
-            
-            // 最后按时间分数降序排序
-            b.score.time.cmp(&a.score.time)
+            b.score
+                .overall
+                .cmp(&a.score.overall)
+                // 其次按相关性分数降序排序
+                .then_with(|| b.score.relevance.cmp(&a.score.relevance))
+                // 再按时间分数降序排序
+                .then_with(|| b.score.time.cmp(&a.score.time))
+                // 最后保持检索引擎的原始顺序
+                .then_with(|| a.index.cmp(&b.index))
         });
     }
 }
 
+/// 检索得分在相关性分数中占的分值
+const ENGINE_SCORE_POINTS: f32 = 40.0;
+
+/// 元素类型符合搜索意图时提升的相关性分值
+const INTENT_BOOST_POINTS: f32 = 15.0;
+
+/// 相似查询中点击过的结果提升的个性化分值
+const CLICKED_RESULT_POINTS: u8 = 40;
+
+/// 其他查询中点击过同一文件时提升的个性化分值
+const CLICKED_FILE_POINTS: u8 = 15;
+
 impl RuleBasedSearchResultOptimizer {
     /// 计算综合分数
-    fn calculate_overall_score(&self, relevance: u8, personalization: u8, diversity: u8, time: u8) -> u8 {
-        let overall = (
-            relevance as f32 * self.config.relevance_weight +
-            personalization as f32 * self.config.personalization_weight +
-            diversity as f32 * self.config.diversity_weight +
-            time as f32 * self.config.time_weight
-        ) as u8;
-        
-        overall.clamp(0, 100)
-    }
-    
+    fn calculate_overall_score(
+        &self,
+        relevance: u8,
+        personalization: u8,
+        diversity: u8,
+        time: u8,
+    ) -> u8 {
+        let overall = relevance as f32 * self.config.relevance_weight
+            + personalization as f32 * self.config.personalization_weight
+            + diversity as f32 * self.config.diversity_weight
+            + time as f32 * self.config.time_weight;
+
+        overall.clamp(0.0, 100.0) as u8
+    }
+
     /// 计算个性化分数
-    fn calculate_personalization(&self, element: &CodeElement) -> u8 {
-        // TODO: 主人~ 这里需要实现基于用户历史的个性化分数计算
-        // 提示：根据用户历史搜索和点击记录，计算元素的个性化分数
-        50 // 默认值
-    }
-    
-    /// 计算时间分数
-    fn calculate_time_score(&self, element: &CodeElement) -> u8 {
-        // TODO: 主人~ 这里需要实现基于时间的分数计算
-        // 提示：根据文件修改时间，计算元素的时间分数
-        50 // 默认值
-    }
-    
+    fn calculate_personalization(
+        &self,
+        candidate: &SearchCandidate,
+        similar: &[&UserSearchHistoryItem],
+    ) -> u8 {
+        let mut score = 50_u8;
+        if similar
+            .iter()
+            .any(|item| item.clicked_results.contains(&candidate.id))
+        {
+            score = score.saturating_add(CLICKED_RESULT_POINTS);
+        }
+        let clicked_file = self.user_search_history.iter().any(|item| {
+            item.clicked_results.iter().any(|clicked| {
+                clicked == &candidate.path || clicked.starts_with(&format!("{}:", candidate.path))
+            })
+        });
+        if clicked_file {
+            score = score.saturating_add(CLICKED_FILE_POINTS);
+        }
+        score.min(100)
+    }
+
+    /// 计算时间分数：按修改时间指数衰减，每经过半衰期分数减半
+    fn calculate_time_score(&self, candidate: &SearchCandidate) -> u8 {
+        let Some(modified_at) = candidate.modified_at else {
+            return 50;
+        };
+        let age_days = (chrono::Utc::now().timestamp() - modified_at).max(0) as f32 / 86_400.0;
+        let half_life = self.config.recency_half_life_days.max(f32::EPSILON);
+        (100.0 * 0.5_f32.powf(age_days / half_life)).clamp(0.0, 100.0) as u8
+    }
+
     /// 确定匹配类型
-    fn determine_match_type(&self, query: &str, element: &CodeElement) -> MatchType {
-        let query_lower = query.to_lowercase();
-        let name_lower = element.name.to_lowercase();
-        let doc_lower = element.doc.as_ref().map(|d| d.to_lowercase()).unwrap_or_default();
-        let code_lower = element.code.to_lowercase();
-        
+    fn determine_match_type(&self, query: &str, candidate: &SearchCandidate) -> MatchType {
+        let query_lower = query.trim().to_lowercase();
+        let name_lower = candidate.name.to_lowercase();
+        let doc_lower = candidate.documentation.to_lowercase();
+
+        if query_lower.is_empty() {
+            return MatchType::Semantic;
+        }
+
         // 完全匹配
         if name_lower == query_lower {
             return MatchType::Exact;
         }
-        
+
         // 前缀匹配
         if name_lower.starts_with(&query_lower) {
             return MatchType::Prefix;
         }
-        
+
         // 后缀匹配
         if name_lower.ends_with(&query_lower) {
             return MatchType::Suffix;
         }
-        
+
         // 包含匹配
         if name_lower.contains(&query_lower) || doc_lower.contains(&query_lower) {
             return MatchType::Contains;
         }
-        
+
         // 模糊匹配（简单实现：检查关键词是否有重叠）
         if self.has_keyword_overlap(&query_lower, &name_lower) {
             return MatchType::Fuzzy;
         }
-        
+
         // 默认语义匹配
         MatchType::Semantic
     }
-    
+
     /// 提取匹配的关键词
-    fn extract_matched_keywords(&self, query: &str, element: &CodeElement) -> Vec<String> {
+    fn extract_matched_keywords(&self, query: &str, candidate: &SearchCandidate) -> Vec<String> {
         let query_lower = query.to_lowercase();
         let keywords = self.extract_keywords(&query_lower);
-        let name_lower = element.name.to_lowercase();
-        let doc_lower = element.doc.as_ref().map(|d| d.to_lowercase()).unwrap_or_default();
-        let code_lower = element.code.to_lowercase();
-        
+        let name_lower = candidate.name.to_lowercase();
+        let doc_lower = candidate.documentation.to_lowercase();
+        let content_lower = candidate.content.to_lowercase();
+
         keywords
             .into_iter()
             .filter(|keyword| {
-                name_lower.contains(keyword) || doc_lower.contains(keyword) || code_lower.contains(keyword)
+                name_lower.contains(keyword)
+                    || doc_lower.contains(keyword)
+                    || content_lower.contains(keyword)
             })
             .collect()
     }
-    
+
     /// 提取关键词
     fn extract_keywords(&self, query: &str) -> Vec<String> {
         // 简单的关键词提取：分割为单词，去停用词
@@ -546,129 +697,259 @@ impl RuleBasedSearchResultOptimizer {
             .map(|word| word.to_string())
             .collect()
     }
-    
+
     /// 获取停用词列表
     fn get_stop_words(&self) -> HashSet<&str> {
         let stop_words = vec![
             "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "with", "by",
             "of", "from", "about", "into", "through", "after", "before", "during", "above",
             "below", "up", "down", "out", "over", "under", "again", "further", "then", "once",
-            "here", "there", "when", "where", "why", "how", "all", "any", "both", "each",
-            "few", "more", "most", "other", "some", "such", "no", "nor", "not", "only",
-            "own", "same", "so", "than", "too", "very", "s", "t", "can", "will", "just",
-            "don", "should", "now", "的", "了", "和", "是", "就", "都", "而", "及", "与",
-            "着", "或", "要", "在", "有", "来", "去", "你", "我", "他", "她", "它", "们"
+            "here", "there", "when", "where", "why", "how", "all", "any", "both", "each", "few",
+            "more", "most", "other", "some", "such", "no", "nor", "not", "only", "own", "same",
+            "so", "than", "too", "very", "s", "t", "can", "will", "just", "don", "should", "now",
+            "的", "了", "和", "是", "就", "都", "而", "及", "与", "着", "或", "要", "在", "有",
+            "来", "去", "你", "我", "他", "她", "它", "们",
         ];
         HashSet::from_iter(stop_words)
     }
-    
-    /// 检测搜索意图
+
+    /// 检测搜索意图，按完整的词匹配意图关键词，避免 `default` 被识别为 `def`
     fn detect_intent(&self, query: &str, keywords: &[String]) -> (SearchIntent, f32) {
-        // 简单的意图检测规则
-        let query_lower = query.to_lowercase();
-        
+        let has = |words: &[&str]| {
+            keywords
+                .iter()
+                .any(|keyword| words.contains(&keyword.as_str()))
+        };
+
+        if query.trim().is_empty() {
+            return (SearchIntent::Other, 0.0);
+        }
+
         // 检测函数定义意图
-        if query_lower.contains("function") || query_lower.contains("def") || query_lower.contains("func") {
+        if has(&["function", "fn", "def", "func", "method", "函数", "方法"]) {
             return (SearchIntent::FunctionDefinition, 0.9);
         }
-        
+
         // 检测类定义意图
-        if query_lower.contains("class") || query_lower.contains("struct") || query_lower.contains("interface") {
+        if has(&[
+            "class",
+            "struct",
+            "interface",
+            "trait",
+            "enum",
+            "type",
+            "类",
+            "结构体",
+        ]) {
             return (SearchIntent::ClassDefinition, 0.9);
         }
-        
+
         // 检测变量定义意图
-        if query_lower.contains("variable") || query_lower.contains("var") || query_lower.contains("const") {
+        if has(&["variable", "var", "const", "constant", "变量", "常量"]) {
             return (SearchIntent::VariableDefinition, 0.8);
         }
-        
+
         // 检测文档搜索意图
-        if query_lower.contains("doc") || query_lower.contains("documentation") || query_lower.contains("help") {
+        if has(&["doc", "docs", "documentation", "help", "readme", "文档"]) {
             return (SearchIntent::DocumentationSearch, 0.8);
         }
-        
+
         // 检测示例搜索意图
-        if query_lower.contains("example") || query_lower.contains("demo") || query_lower.contains("sample") {
+        if has(&[
+            "example", "examples", "demo", "sample", "usage", "示例", "例子",
+        ]) {
             return (SearchIntent::ExampleSearch, 0.8);
         }
-        
+
         // 检测错误修复意图
-        if query_lower.contains("fix") || query_lower.contains("bug") || query_lower.contains("error") {
+        if has(&["fix", "bug", "error", "panic", "crash", "错误", "修复"]) {
             return (SearchIntent::ErrorFixSearch, 0.8);
         }
-        
+
         // 默认意图
         (SearchIntent::FeatureSearch, 0.7)
     }
-    
+
     /// 生成相关性解释
-    fn generate_relevance_explanation(&self, query: &str, element: &CodeElement, relevance: u8) -> String {
+    fn generate_relevance_explanation(
+        &self,
+        candidate: &SearchCandidate,
+        item: &SearchResultItem,
+        intent: &SearchIntentUnderstandingResult,
+    ) -> String {
         let mut explanation = Vec::new();
-        
-        if element.name.to_lowercase().contains(&query.to_lowercase()) {
-            explanation.push("名称包含关键词".to_string());
-        }
-        
-        if element.doc.as_ref().map(|d| d.to_lowercase().contains(&query.to_lowercase())).unwrap_or(false) {
-            explanation.push("文档包含关键词".to_string());
+
+        match item.score.match_type {
+            MatchType::Exact => explanation.push("名称完全匹配".to_string()),
+            MatchType::Prefix => explanation.push("名称以查询开头".to_string()),
+            MatchType::Suffix => explanation.push("名称以查询结尾".to_string()),
+            _ => {}
         }
-        
-        if element.code.to_lowercase().contains(&query.to_lowercase()) {
-            explanation.push("代码包含关键词".to_string());
+        if !item.matched_keywords.is_empty() {
+            explanation.push(format!("包含关键词 {}", item.matched_keywords.join("、")));
         }
-        
         if explanation.is_empty() {
-            explanation.push("基于语义相关性匹配".to_string());
+            explanation.push("基于检索得分匹配".to_string());
+        }
+        explanation.push(format!("检索得分 {:.2}", candidate.engine_score));
+
+        if intent.boosts(
+            candidate.element_type.as_ref(),
+            self.config.intent_understanding_threshold,
+        ) {
+            explanation.push(format!("符合搜索意图（{}）", intent.intent.label()));
         }
-        
-        explanation.join("，")
+        if item.score.personalization >= 50 + CLICKED_RESULT_POINTS {
+            explanation.push("曾在相似搜索中选择".to_string());
+        } else if item.score.personalization > 50 {
+            explanation.push("曾选择过该文件".to_string());
+        }
+        if let Some(modified_at) = candidate.modified_at {
+            let days = (chrono::Utc::now().timestamp() - modified_at).max(0) / 86_400;
+            explanation.push(format!("{} 天前修改", days));
+        }
+        if item.score.diversity == 100 && item.score.overall > 0 {
+            explanation.push("补充不同类型或文件".to_string());
+        }
+
+        format!(
+            "{}；综合 {}（相关性 {}，个性化 {}，多样性 {}，时间 {}）",
+            explanation.join("，"),
+            item.score.overall,
+            item.score.relevance,
+            item.score.personalization,
+            item.score.diversity,
+            item.score.time
+        )
     }
-    
+
     /// 检查关键词重叠
     fn has_keyword_overlap(&self, query: &str, text: &str) -> bool {
         let query_keywords = self.extract_keywords(query);
         let text_keywords = self.extract_keywords(text);
-        
+
         // 检查是否有共同关键词
-        for query_keyword in &query_keywords {
-            for text_keyword in &text_keywords {
-                if query_keyword == text_keyword {
-                    return true;
-                }
-            }
-        }
-        
-        false
+        query_keywords
+            .iter()
+            .any(|keyword| text_keywords.contains(keyword))
     }
-    
+
     /// 检查查询是否相似
     fn is_similar_query(&self, query1: &str, query2: &str) -> bool {
         // 简单实现：检查关键词重叠
-        let keywords1 = self.extract_keywords(query1);
-        let keywords2 = self.extract_keywords(query2);
-        
+        let keywords1 = self.extract_keywords(&query1.to_lowercase());
+        let keywords2 = self.extract_keywords(&query2.to_lowercase());
+
         // 计算关键词重叠比例
         if keywords1.is_empty() || keywords2.is_empty() {
             return false;
         }
-        
-        let common_keywords = keywords1
-            .iter()
-            .filter(|k| keywords2.contains(k))
-            .count();
-        
-        let overlap_ratio = common_keywords as f32 / std::cmp::min(keywords1.len(), keywords2.len()) as f32;
-        
+
+        let common_keywords = keywords1.iter().filter(|k| keywords2.contains(k)).count();
+
+        let overlap_ratio =
+            common_keywords as f32 / std::cmp::min(keywords1.len(), keywords2.len()) as f32;
+
         overlap_ratio >= 0.5
     }
 }
 
+/// 持久化的用户搜索历史，保存在元数据数据库的独立树中，按记录时间排序
+pub struct SearchHistoryStore {
+    /// 历史记录树，键为记录时间（纳秒，大端序）
+    tree: sled::Tree,
+}
+
+impl SearchHistoryStore {
+    /// 打开数据库中的搜索历史
+    pub fn open(db: &sled::Db) -> AppResult<Self> {
+        Ok(Self {
+            tree: db.open_tree("search_history")?,
+        })
+    }
+
+    /// 记录一条搜索历史，超出数量上限时删除最早的记录
+    pub fn record(&self, item: &UserSearchHistoryItem, max_items: usize) -> AppResult<()> {
+        let mut key = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+        // 同一时刻的记录顺延，避免覆盖
+        while self.tree.contains_key(key.to_be_bytes())? {
+            key += 1;
+        }
+        self.tree
+            .insert(key.to_be_bytes(), serde_json::to_vec(item)?)?;
+
+        while self.tree.len() > max_items {
+            if self.tree.pop_min()?.is_none() {
+                break;
+            }
+        }
+        self.tree.flush()?;
+        Ok(())
+    }
+
+    /// 记录用户选择的结果，追加到同一查询最近的历史记录中
+    pub fn record_click(
+        &self,
+        item: UserSearchHistoryItem,
+        result: &str,
+        max_items: usize,
+    ) -> AppResult<()> {
+        for entry in self.tree.iter().rev() {
+            let (key, value) = entry?;
+            let mut existing: UserSearchHistoryItem = serde_json::from_slice(&value)?;
+            if existing.query != item.query {
+                continue;
+            }
+            if !existing
+                .clicked_results
+                .iter()
+                .any(|clicked| clicked == result)
+            {
+                existing.clicked_results.push(result.to_string());
+                self.tree.insert(key, serde_json::to_vec(&existing)?)?;
+                self.tree.flush()?;
+            }
+            return Ok(());
+        }
+
+        let mut item = item;
+        item.clicked_results = vec![result.to_string()];
+        self.record(&item, max_items)
+    }
+
+    /// 获取最近的搜索历史，按时间从早到晚排序
+    pub fn recent(&self, limit: usize) -> AppResult<Vec<UserSearchHistoryItem>> {
+        let mut items = Vec::new();
+        for entry in self.tree.iter().rev().take(limit) {
+            let (_, value) = entry?;
+            items.push(serde_json::from_slice(&value)?);
+        }
+        items.reverse();
+        Ok(items)
+    }
+
+    /// 清空搜索历史
+    pub fn clear(&self) -> AppResult<()> {
+        self.tree.clear()?;
+        Ok(())
+    }
+}
+
 /// 搜索结果优化器工厂
 pub struct SearchResultOptimizerFactory {
     /// 默认配置
     default_config: SearchResultOptimizerConfig,
 }
 
+impl Default for SearchResultOptimizerFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SearchResultOptimizerFactory {
     /// 创建新的搜索结果优化器工厂
     pub fn new() -> Self {
@@ -676,13 +957,16 @@ impl SearchResultOptimizerFactory {
             default_config: SearchResultOptimizerConfig::default(),
         }
     }
-    
+
     /// 创建搜索结果优化器实例
-    pub fn create_optimizer(&self, config: Option<SearchResultOptimizerConfig>) -> Box<dyn SearchResultOptimizer> {
+    pub fn create_optimizer(
+        &self,
+        config: Option<SearchResultOptimizerConfig>,
+    ) -> Box<dyn SearchResultOptimizer> {
         let config = config.unwrap_or(self.default_config.clone());
         Box::new(RuleBasedSearchResultOptimizer::new(config))
     }
-    
+
     /// 创建默认配置的搜索结果优化器
     pub fn create_default_optimizer(&self) -> Box<dyn SearchResultOptimizer> {
         self.create_optimizer(None)
@@ -3,8 +3,12 @@
 //! 搜索本地代码知识库，返回相关代码元素
 
 use crate::error::AppResult;
+use crate::knowledge::symbol_index::SymbolMatch;
 use crate::parsers::{CodeElement, CodeElementType};
 
+/// 默认返回的搜索结果数量
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
 /// 代码搜索引擎
 pub struct CodeSearcher {
     /// 索引器引用
@@ -21,9 +25,7 @@ impl CodeSearcher {
 
     /// 搜索代码
     pub fn search(&self, query: &str) -> AppResult<Vec<CodeElement>> {
-        // TODO: 主人~ 这里需要实现代码搜索逻辑
-        // 提示：调用索引器的search方法，处理搜索结果
-        Ok(Vec::new())
+        self.indexer.read().unwrap().search(query)
     }
 
     /// 搜索特定类型的代码元素
@@ -32,8 +34,23 @@ impl CodeSearcher {
         query: &str,
         element_type: &CodeElementType,
     ) -> AppResult<Vec<CodeElement>> {
-        // TODO: 主人~ 这里需要实现按类型搜索代码元素的逻辑
-        // 提示：先搜索所有结果，然后过滤出指定类型的元素
-        Ok(Vec::new())
+        Ok(self
+            .search_scored(query, Some(element_type), DEFAULT_SEARCH_LIMIT)?
+            .into_iter()
+            .map(|symbol| symbol.element)
+            .collect())
+    }
+
+    /// 搜索代码元素，返回带相关性评分的结果
+    pub fn search_scored(
+        &self,
+        query: &str,
+        element_type: Option<&CodeElementType>,
+        limit: usize,
+    ) -> AppResult<Vec<SymbolMatch>> {
+        self.indexer
+            .read()
+            .unwrap()
+            .search_symbols(query, element_type, limit)
     }
 }
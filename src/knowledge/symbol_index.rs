@@ -0,0 +1,560 @@
+//! 代码符号索引
+//!
+//! 将解析得到的代码元素写入tantivy索引，支持按名称、签名和文档搜索，
+//! 以及按类型、名称、语言和路径过滤
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::query::{QueryTerm, SearchQuery};
+use crate::parse_cache::ElementChanges;
+use crate::parsers::{CodeElement, CodeElementType, SourceLocation};
+use std::collections::BTreeMap;
+use std::path::Path;
+use tantivy::collector::{Count, TopDocs};
+use tantivy::directory::MmapDirectory;
+use tantivy::query::{
+    AllQuery, BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, RegexQuery, TermQuery,
+};
+use tantivy::schema::{Field, IndexRecordOption, Schema, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{doc, Document, Index, IndexReader, IndexWriter, ReloadPolicy, Term};
+
+/// 索引写入器的内存缓冲大小
+const WRITER_HEAP_SIZE: usize = 50_000_000;
+
+/// 签名的最大字符数
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// 名称字段的搜索权重，使名称匹配排在文档匹配之前
+const NAME_BOOST: f32 = 3.0;
+
+/// 符号索引字段
+#[derive(Clone, Copy)]
+struct SymbolFields {
+    name: Field,
+    kind: Field,
+    path: Field,
+    line: Field,
+    column: Field,
+    length: Field,
+    documentation: Field,
+    language: Field,
+    signature: Field,
+    symbol: Field,
+    element: Field,
+    key: Field,
+}
+
+/// 带评分的符号搜索结果
+#[derive(Debug, Clone)]
+pub struct SymbolMatch {
+    /// 匹配的代码元素
+    pub element: CodeElement,
+    /// 元素签名（定义所在的首行代码）
+    pub signature: String,
+    /// 相关性评分
+    pub score: f32,
+    /// 排序依据说明，由搜索结果优化器生成
+    pub explanation: String,
+}
+
+/// 按文件和元素类型统计的符号数量
+#[derive(Debug, Clone, Default)]
+pub struct SymbolCounts {
+    /// 每个文件的符号数量
+    pub by_file: BTreeMap<String, usize>,
+    /// 每种元素类型的符号数量
+    pub by_kind: BTreeMap<String, usize>,
+}
+
+/// 代码符号索引
+pub struct SymbolIndex {
+    /// tantivy索引
+    index: Index,
+    /// 索引读取器
+    reader: IndexReader,
+    /// 索引写入器，首次写入时创建，避免只读时占用索引锁
+    writer: Option<IndexWriter>,
+    /// 索引字段
+    fields: SymbolFields,
+}
+
+impl SymbolIndex {
+    /// 构建符号索引的模式
+    fn schema() -> (Schema, SymbolFields) {
+        let mut builder = Schema::builder();
+        let fields = SymbolFields {
+            name: builder.add_text_field("name", TEXT | STORED),
+            kind: builder.add_text_field("kind", STRING | STORED),
+            path: builder.add_text_field("path", STRING | STORED),
+            line: builder.add_u64_field("line", INDEXED | STORED | FAST),
+            column: builder.add_u64_field("column", STORED),
+            length: builder.add_u64_field("length", STORED),
+            documentation: builder.add_text_field("documentation", TEXT | STORED),
+            language: builder.add_text_field("language", STRING | STORED),
+            signature: builder.add_text_field("signature", TEXT | STORED),
+            symbol: builder.add_text_field("symbol", STRING),
+            element: builder.add_text_field("element", STORED),
+            key: builder.add_text_field("key", STRING),
+        };
+        (builder.build(), fields)
+    }
+
+    /// 打开或创建指定目录下的符号索引，旧格式的索引会按保存的字段重建
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        let (schema, fields) = Self::schema();
+        std::fs::create_dir_all(dir)?;
+
+        let mut legacy = None;
+        if Index::exists(&open_directory(dir)?).map_err(tantivy::TantivyError::from)? {
+            let index = Index::open(open_directory(dir)?)?;
+            if index.schema() != schema {
+                legacy = Some(read_legacy_symbols(&index)?);
+                drop(index);
+                std::fs::remove_dir_all(dir)?;
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+
+        let index = Index::open_or_create(open_directory(dir)?, schema)?;
+        let mut symbols = Self::with_index(index, fields)?;
+        if let Some(legacy) = legacy {
+            log::info!("正在重建 {} 个符号的索引", legacy.len());
+            let writer = symbols.writer()?;
+            for symbol in legacy {
+                writer.add_document(fields.document(&symbol.element, &symbol.signature))?;
+            }
+            symbols.commit()?;
+        }
+        Ok(symbols)
+    }
+
+    /// 创建内存中的符号索引
+    pub fn in_memory() -> AppResult<Self> {
+        let (schema, fields) = Self::schema();
+        Self::with_index(Index::create_in_ram(schema), fields)
+    }
+
+    /// 使用已打开的索引创建实例
+    fn with_index(index: Index, fields: SymbolFields) -> AppResult<Self> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            index,
+            reader,
+            writer: None,
+            fields,
+        })
+    }
+
+    /// 获取索引写入器
+    fn writer(&mut self) -> AppResult<&mut IndexWriter> {
+        if self.writer.is_none() {
+            self.writer = Some(self.index.writer(WRITER_HEAP_SIZE)?);
+        }
+        Ok(self.writer.as_mut().unwrap())
+    }
+
+    /// 替换文件的所有符号：先删除该文件的旧文档，再写入新的代码元素
+    pub fn replace_file(
+        &mut self,
+        path: &str,
+        content: &str,
+        elements: &[CodeElement],
+    ) -> AppResult<()> {
+        let fields = self.fields;
+        let lines: Vec<&str> = content.lines().collect();
+        let writer = self.writer()?;
+        writer.delete_term(Term::from_field_text(fields.path, path));
+
+        for element in elements {
+            writer.add_document(fields.file_document(path, &lines, element))?;
+        }
+        Ok(())
+    }
+
+    /// 按元素差异更新文件的符号：删除被删除或变化的旧符号，再写入变化后的元素
+    pub fn update_file(
+        &mut self,
+        path: &str,
+        content: &str,
+        changes: &ElementChanges,
+    ) -> AppResult<()> {
+        let fields = self.fields;
+        let lines: Vec<&str> = content.lines().collect();
+        let writer = self.writer()?;
+        for element in &changes.removed {
+            writer.delete_term(Term::from_field_text(
+                fields.key,
+                &symbol_key(path, element),
+            ));
+        }
+        for element in &changes.changed {
+            writer.add_document(fields.file_document(path, &lines, element))?;
+        }
+        Ok(())
+    }
+
+    /// 获取已提交的索引中文件的符号数量
+    pub fn file_symbol_count(&self, path: &str) -> AppResult<usize> {
+        let query = TermQuery::new(
+            Term::from_field_text(self.fields.path, path),
+            IndexRecordOption::Basic,
+        );
+        Ok(self.reader.searcher().search(&query, &Count)?)
+    }
+
+    /// 删除文件的所有符号
+    pub fn remove_file(&mut self, path: &str) -> AppResult<()> {
+        let field = self.fields.path;
+        self.writer()?
+            .delete_term(Term::from_field_text(field, path));
+        Ok(())
+    }
+
+    /// 清空索引
+    pub fn clear(&mut self) -> AppResult<()> {
+        self.writer()?.delete_all_documents()?;
+        Ok(())
+    }
+
+    /// 提交挂起的修改并刷新读取器
+    pub fn commit(&mut self) -> AppResult<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.commit()?;
+            self.reader.reload()?;
+        }
+        Ok(())
+    }
+
+    /// 获取索引中的符号数量
+    pub fn num_symbols(&self) -> u64 {
+        self.reader.searcher().num_docs()
+    }
+
+    /// 按文件和元素类型统计索引中的符号
+    pub fn counts(&self) -> AppResult<SymbolCounts> {
+        let searcher = self.reader.searcher();
+        let limit = (searcher.num_docs() as usize).max(1);
+        let mut counts = SymbolCounts::default();
+        for (_, address) in searcher.search(&AllQuery, &TopDocs::with_limit(limit))? {
+            let document = searcher.doc(address)?;
+            let text = |field: Field| {
+                document
+                    .get_first(field)
+                    .and_then(|value| value.as_text())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            *counts.by_file.entry(text(self.fields.path)).or_default() += 1;
+            *counts.by_kind.entry(text(self.fields.kind)).or_default() += 1;
+        }
+        Ok(counts)
+    }
+
+    /// 获取索引的段数量
+    pub fn segment_count(&self) -> usize {
+        self.reader.searcher().segment_readers().len()
+    }
+
+    /// 合并所有索引段并清理不再使用的文件，返回合并前的段数量
+    pub fn compact(&mut self) -> AppResult<usize> {
+        self.commit()?;
+        let segments = self.index.searchable_segment_ids()?;
+        let writer = self.writer()?;
+        if !segments.is_empty() {
+            writer.merge(&segments).wait()?;
+        }
+        writer.garbage_collect_files().wait()?;
+        self.reader.reload()?;
+        Ok(segments.len())
+    }
+
+    /// 搜索符号，可按元素类型过滤，结果按相关性排序
+    ///
+    /// 查询中的类型、名称、语言和路径条件在索引中过滤；含时间条件时不返回符号
+    pub fn search(
+        &self,
+        query: &SearchQuery,
+        element_type: Option<&CodeElementType>,
+        limit: usize,
+    ) -> AppResult<Vec<SymbolMatch>> {
+        if query.is_empty() || query.has_date_filters() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let term_query = |field: Field, text: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, text),
+                IndexRecordOption::Basic,
+            ))
+        };
+        let any_of = |clauses: Vec<Box<dyn Query>>| -> Box<dyn Query> {
+            Box::new(BooleanQuery::new(
+                clauses
+                    .into_iter()
+                    .map(|clause| (Occur::Should, clause))
+                    .collect(),
+            ))
+        };
+
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        if let Some(text_query) = self.terms_query(query)? {
+            clauses.push((Occur::Must, text_query));
+        }
+        let kinds: Vec<Box<dyn Query>> = query
+            .kinds
+            .iter()
+            .chain(element_type)
+            .flat_map(CodeElementType::filter_kinds)
+            .map(|kind| term_query(self.fields.kind, kind.as_str()))
+            .collect();
+        if !kinds.is_empty() {
+            clauses.push((Occur::Must, any_of(kinds)));
+        }
+        if !query.names.is_empty() {
+            let mut names = Vec::new();
+            for expression in query.name_expressions() {
+                names.push(
+                    Box::new(RegexQuery::from_pattern(&expression, self.fields.symbol)?)
+                        as Box<dyn Query>,
+                );
+            }
+            clauses.push((Occur::Must, any_of(names)));
+        }
+        if !query.languages.is_empty() {
+            let languages = query
+                .languages
+                .iter()
+                .map(|language| term_query(self.fields.language, language))
+                .collect();
+            clauses.push((Occur::Must, any_of(languages)));
+        }
+        if !query.paths.is_empty() {
+            let mut paths = Vec::new();
+            for expression in query.path_expressions() {
+                paths.push(
+                    Box::new(RegexQuery::from_pattern(&expression, self.fields.path)?)
+                        as Box<dyn Query>,
+                );
+            }
+            clauses.push((Occur::Must, any_of(paths)));
+        }
+        if clauses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let searcher = self.reader.searcher();
+        let top_docs = searcher.search(&BooleanQuery::new(clauses), &TopDocs::with_limit(limit))?;
+
+        let mut matches = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let document = searcher.doc(address)?;
+            matches.push(self.to_match(&document, score));
+        }
+        Ok(matches)
+    }
+
+    /// 构建查询词部分：关键词和短语按名称、签名和文档搜索，正则和模糊词匹配名称中的词；
+    /// 任一匹配即可，没有查询词时返回 `None`
+    fn terms_query(&self, query: &SearchQuery) -> AppResult<Option<Box<dyn Query>>> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+        // 只保留标识符字符，避免查询语法错误
+        let words: Vec<String> = query
+            .text()
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .filter(|term| !term.is_empty())
+            .map(|term| term.to_lowercase())
+            .collect();
+        if !words.is_empty() {
+            let mut parser = QueryParser::for_index(
+                &self.index,
+                vec![
+                    self.fields.name,
+                    self.fields.signature,
+                    self.fields.documentation,
+                ],
+            );
+            parser.set_field_boost(self.fields.name, NAME_BOOST);
+            let text_query = parser
+                .parse_query(&words.join(" "))
+                .map_err(|e| AppError::knowledge(&format!("无效的搜索查询: {}", e)))?;
+            clauses.push((Occur::Should, text_query));
+        }
+
+        for term in &query.terms {
+            let clause: Box<dyn Query> = match term {
+                QueryTerm::Regex(pattern) => Box::new(
+                    RegexQuery::from_pattern(pattern, self.fields.name)
+                        .map_err(|e| AppError::knowledge(&format!("无效的正则表达式: {}", e)))?,
+                ),
+                QueryTerm::Fuzzy { term, distance } => Box::new(FuzzyTermQuery::new(
+                    Term::from_field_text(self.fields.name, term),
+                    *distance,
+                    true,
+                )),
+                _ => continue,
+            };
+            clauses.push((Occur::Should, clause));
+        }
+
+        Ok(match clauses.len() {
+            0 => None,
+            1 => clauses.pop().map(|(_, clause)| clause),
+            _ => Some(Box::new(BooleanQuery::new(clauses))),
+        })
+    }
+
+    /// 将索引文档转换为搜索结果
+    fn to_match(&self, document: &Document, score: f32) -> SymbolMatch {
+        let text = |field: Field| {
+            document
+                .get_first(field)
+                .and_then(|value| value.as_text())
+                .unwrap_or("")
+                .to_string()
+        };
+        let number = |field: Field| {
+            document
+                .get_first(field)
+                .and_then(|value| value.as_u64())
+                .unwrap_or(0) as u32
+        };
+
+        // 完整的元素以JSON保存，旧索引中没有时按各字段还原
+        let element = serde_json::from_str(&text(self.fields.element));
+        let documentation = text(self.fields.documentation);
+        let element = element.unwrap_or_else(|_| CodeElement {
+            element_type: text(self.fields.kind)
+                .parse()
+                .unwrap_or(CodeElementType::Other),
+            name: text(self.fields.name),
+            definition: SourceLocation {
+                file_path: text(self.fields.path),
+                line: number(self.fields.line),
+                column: number(self.fields.column),
+                length: number(self.fields.length),
+                ..Default::default()
+            },
+            documentation: (!documentation.is_empty()).then_some(documentation),
+            language: text(self.fields.language),
+            ..Default::default()
+        });
+
+        SymbolMatch {
+            element,
+            signature: text(self.fields.signature),
+            score,
+            explanation: String::new(),
+        }
+    }
+}
+
+impl SymbolFields {
+    /// 创建文件中代码元素的索引文档，元素没有签名时取定义所在行
+    fn file_document(&self, path: &str, lines: &[&str], element: &CodeElement) -> Document {
+        let signature = element
+            .signature
+            .clone()
+            .unwrap_or_else(|| extract_signature(lines, element.definition.line));
+        let mut element = element.clone();
+        element.definition.file_path = path.to_string();
+        self.document(&element, &signature)
+    }
+
+    /// 创建代码元素的索引文档
+    fn document(&self, element: &CodeElement, signature: &str) -> Document {
+        doc!(
+            self.name => element.name.clone(),
+            self.kind => element.element_type.as_str(),
+            self.path => element.definition.file_path.clone(),
+            self.line => element.definition.line as u64,
+            self.column => element.definition.column as u64,
+            self.length => element.definition.length as u64,
+            self.documentation => element.documentation.clone().unwrap_or_default(),
+            self.language => element.language.clone(),
+            self.signature => signature,
+            self.symbol => element.name.to_lowercase(),
+            self.element => serde_json::to_string(element).unwrap_or_default(),
+            self.key => symbol_key(&element.definition.file_path, element),
+        )
+    }
+}
+
+/// 打开索引目录
+fn open_directory(dir: &Path) -> AppResult<MmapDirectory> {
+    MmapDirectory::open(dir).map_err(|e| AppError::Knowledge {
+        operation: "open".to_string(),
+        description: e.to_string(),
+        index_path: Some(dir.to_path_buf()),
+        source: Some(Box::new(e)),
+    })
+}
+
+/// 读取旧格式索引中的所有符号
+fn read_legacy_symbols(index: &Index) -> AppResult<Vec<SymbolMatch>> {
+    let schema = index.schema();
+    let field = |name: &str| {
+        schema
+            .get_field(name)
+            .ok_or_else(|| AppError::knowledge(&format!("旧版符号索引缺少字段 {}", name)))
+    };
+    let legacy = SymbolIndex {
+        index: index.clone(),
+        reader: index.reader()?,
+        writer: None,
+        fields: SymbolFields {
+            name: field("name")?,
+            kind: field("kind")?,
+            path: field("path")?,
+            line: field("line")?,
+            column: field("column")?,
+            length: field("length")?,
+            documentation: field("documentation")?,
+            language: field("language")?,
+            signature: field("signature")?,
+            // 旧索引没有以下字段，symbol 和 key 读取时不会用到，element 解析失败时按各字段还原
+            symbol: field("name")?,
+            element: field("name")?,
+            key: field("name")?,
+        },
+    };
+
+    let searcher = legacy.reader.searcher();
+    let limit = (searcher.num_docs() as usize).max(1);
+    let mut symbols = Vec::new();
+    for (_, address) in searcher.search(&AllQuery, &TopDocs::with_limit(limit))? {
+        symbols.push(legacy.to_match(&searcher.doc(address)?, 0.0));
+    }
+    Ok(symbols)
+}
+
+/// 符号在文件内的标识，由类型、完整名称和字节范围组成
+fn symbol_key(path: &str, element: &CodeElement) -> String {
+    format!(
+        "{}#{}:{}@{}-{}",
+        path,
+        element.element_type.as_str(),
+        element.qualified_name(),
+        element.definition.start_byte,
+        element.definition.end_byte
+    )
+}
+
+/// 提取元素定义所在行作为签名，去掉函数体
+fn extract_signature(lines: &[&str], line: u32) -> String {
+    let Some(text) = (line as usize)
+        .checked_sub(1)
+        .and_then(|index| lines.get(index))
+    else {
+        return String::new();
+    };
+
+    let text = text.trim();
+    let text = text
+        .split_once('{')
+        .map(|(head, _)| head.trim_end())
+        .unwrap_or(text);
+    text.chars().take(MAX_SIGNATURE_CHARS).collect()
+}
@@ -0,0 +1,200 @@
+//! 向量索引
+//!
+//! 保存代码片段的嵌入向量，与全文索引一同存放在 `index_dir` 下，
+//! 通过余弦相似度检索语义相近的片段
+
+use crate::error::AppResult;
+use crate::knowledge::collection::{default_collection, CollectionFilter};
+use crate::knowledge::embedding::cosine_similarity;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 向量索引文件名
+const VECTORS_FILE: &str = "vectors.json";
+
+/// 片段向量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorEntry {
+    /// 文件路径或网页URL
+    pub path: String,
+    /// 起始行
+    pub start_line: usize,
+    /// 结束行
+    pub end_line: usize,
+    /// 所属集合
+    #[serde(default = "default_collection")]
+    pub collection: String,
+    /// 嵌入向量
+    pub vector: Vec<f32>,
+}
+
+/// 向量检索结果
+#[derive(Debug, Clone)]
+pub struct VectorMatch {
+    /// 文件路径或网页URL
+    pub path: String,
+    /// 起始行
+    pub start_line: usize,
+    /// 结束行
+    pub end_line: usize,
+    /// 余弦相似度
+    pub score: f32,
+}
+
+/// 持久化的向量数据
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct VectorStore {
+    /// 生成向量的嵌入模型标识
+    provider: String,
+    /// 所有片段向量
+    entries: Vec<VectorEntry>,
+}
+
+/// 向量索引
+pub struct VectorIndex {
+    /// 索引文件路径，为空时仅保存在内存中
+    file: Option<PathBuf>,
+    /// 向量数据
+    store: VectorStore,
+    /// 是否有未保存的修改
+    dirty: bool,
+}
+
+impl VectorIndex {
+    /// 打开或创建指定目录下的向量索引
+    pub fn open(dir: &Path) -> AppResult<Self> {
+        fs::create_dir_all(dir)?;
+        let file = dir.join(VECTORS_FILE);
+        let store = if file.exists() {
+            serde_json::from_str(&fs::read_to_string(&file)?)?
+        } else {
+            VectorStore::default()
+        };
+        Ok(Self {
+            file: Some(file),
+            store,
+            dirty: false,
+        })
+    }
+
+    /// 创建内存中的向量索引
+    pub fn in_memory() -> Self {
+        Self {
+            file: None,
+            store: VectorStore::default(),
+            dirty: false,
+        }
+    }
+
+    /// 设置嵌入模型标识，与已有向量的模型不同时清空索引
+    pub fn set_provider(&mut self, provider: &str) {
+        if self.store.provider != provider {
+            if !self.store.entries.is_empty() {
+                log::warn!(
+                    "嵌入模型已从 {} 变更为 {}，已有向量将被重建",
+                    self.store.provider,
+                    provider
+                );
+            }
+            self.store.provider = provider.to_string();
+            self.store.entries.clear();
+            self.dirty = true;
+        }
+    }
+
+    /// 替换文件的所有片段向量
+    pub fn replace_file(&mut self, path: &str, entries: Vec<VectorEntry>) {
+        self.store.entries.retain(|entry| entry.path != path);
+        self.store.entries.extend(entries);
+        self.dirty = true;
+    }
+
+    /// 删除文件的所有片段向量
+    pub fn remove_file(&mut self, path: &str) {
+        let before = self.store.entries.len();
+        self.store.entries.retain(|entry| entry.path != path);
+        self.dirty |= self.store.entries.len() != before;
+    }
+
+    /// 清空索引
+    pub fn clear(&mut self) {
+        self.store.entries.clear();
+        self.dirty = true;
+    }
+
+    /// 获取嵌入模型标识
+    pub fn provider(&self) -> &str {
+        &self.store.provider
+    }
+
+    /// 获取所有片段向量
+    pub fn entries(&self) -> &[VectorEntry] {
+        &self.store.entries
+    }
+
+    /// 获取片段数量
+    pub fn len(&self) -> usize {
+        self.store.entries.len()
+    }
+
+    /// 索引是否为空
+    pub fn is_empty(&self) -> bool {
+        self.store.entries.is_empty()
+    }
+
+    /// 保存修改到磁盘
+    pub fn save(&mut self) -> AppResult<()> {
+        if let (true, Some(file)) = (self.dirty, &self.file) {
+            fs::write(file, serde_json::to_vec(&self.store)?)?;
+        }
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// 按集合统计片段数量
+    pub fn counts_by_collection(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for entry in &self.store.entries {
+            *counts.entry(entry.collection.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// 统计每个文件的片段数量
+    pub fn counts_by_path(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for entry in &self.store.entries {
+            *counts.entry(entry.path.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// 检索最相似的片段，每个文件只保留得分最高的片段，可限定在指定集合中
+    pub fn search(
+        &self,
+        query: &[f32],
+        limit: usize,
+        filter: Option<&CollectionFilter>,
+    ) -> Vec<VectorMatch> {
+        let mut matches: Vec<VectorMatch> = self
+            .store
+            .entries
+            .iter()
+            .filter(|entry| filter.is_none_or(|filter| filter.matches(&entry.collection)))
+            .map(|entry| VectorMatch {
+                path: entry.path.clone(),
+                start_line: entry.start_line,
+                end_line: entry.end_line,
+                score: cosine_similarity(query, &entry.vector),
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut seen = HashSet::new();
+        matches.retain(|m| seen.insert(m.path.clone()));
+        matches.truncate(limit);
+        matches
+    }
+}
@@ -0,0 +1,256 @@
+//! 项目目录遍历
+//!
+//! 所有索引器共用的目录遍历器：遵循 `.gitignore`、`.ignore` 和 `.codexignore`，
+//! 支持 gitignore 语法的排除模式、文件大小上限、符号链接策略，并在读取时跳过二进制文件
+
+use crate::config::app::KnowledgeConfig;
+use crate::error::{AppError, AppResult};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// 项目专用的忽略文件名，语法与 `.gitignore` 相同
+pub const CODEX_IGNORE_FILE: &str = ".codexignore";
+
+/// 默认的最大文件大小（1 MiB）
+pub const DEFAULT_MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+/// 判断二进制文件时检查的字节数
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// 项目目录遍历器
+#[derive(Debug, Clone)]
+pub struct ProjectWalker {
+    /// 允许的文件扩展名（小写），为空时不限制
+    extensions: Vec<String>,
+    /// gitignore 语法的排除模式
+    exclude_patterns: Vec<String>,
+    /// 最大文件大小（字节）
+    max_file_size: u64,
+    /// 是否跟随符号链接
+    follow_symlinks: bool,
+    /// 最大遍历深度
+    max_depth: Option<usize>,
+}
+
+impl Default for ProjectWalker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProjectWalker {
+    /// 创建使用默认策略的遍历器
+    pub fn new() -> Self {
+        Self {
+            extensions: Vec::new(),
+            exclude_patterns: Vec::new(),
+            max_file_size: DEFAULT_MAX_FILE_SIZE,
+            follow_symlinks: false,
+            max_depth: None,
+        }
+    }
+
+    /// 根据知识库配置创建遍历器
+    pub fn from_config(config: &KnowledgeConfig) -> Self {
+        Self::new()
+            .extensions(&config.supported_extensions)
+            .exclude_patterns(&config.exclude_patterns)
+            .max_file_size(config.max_file_size)
+            .follow_symlinks(config.follow_symlinks)
+    }
+
+    /// 设置允许的文件扩展名，为空时不限制
+    pub fn extensions(mut self, extensions: &[String]) -> Self {
+        self.extensions = extensions
+            .iter()
+            .map(|ext| ext.trim_start_matches('.').to_lowercase())
+            .collect();
+        self
+    }
+
+    /// 设置排除模式，语法与 `.gitignore` 相同
+    pub fn exclude_patterns(mut self, patterns: &[String]) -> Self {
+        self.exclude_patterns = patterns.to_vec();
+        self
+    }
+
+    /// 设置最大文件大小，为0时使用默认值
+    pub fn max_file_size(mut self, size: u64) -> Self {
+        self.max_file_size = if size == 0 {
+            DEFAULT_MAX_FILE_SIZE
+        } else {
+            size
+        };
+        self
+    }
+
+    /// 设置是否跟随符号链接
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// 设置最大遍历深度，1 表示只遍历根目录下的文件
+    pub fn max_depth(mut self, depth: Option<usize>) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// 获取最大文件大小
+    pub fn file_size_limit(&self) -> u64 {
+        self.max_file_size
+    }
+
+    /// 遍历目录，按路径排序返回需要处理的文件
+    pub fn walk(&self, root: &Path) -> AppResult<Vec<PathBuf>> {
+        let excludes = build_excludes(root, &self.exclude_patterns)?;
+
+        let mut builder = WalkBuilder::new(root);
+        builder
+            .hidden(false)
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(false)
+            .ignore(true)
+            .parents(true)
+            .require_git(false)
+            .follow_links(self.follow_symlinks)
+            .max_depth(self.max_depth)
+            .sort_by_file_path(|a, b| a.cmp(b))
+            .filter_entry(move |entry| {
+                if entry.file_name() == ".git" {
+                    return false;
+                }
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                !excludes.matched(entry.path(), is_dir).is_ignore()
+            });
+        builder.add_custom_ignore_filename(CODEX_IGNORE_FILE);
+
+        let mut files = Vec::new();
+        for entry in builder.build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::warn!("遍历目录时跳过无法访问的路径: {}", e);
+                    continue;
+                }
+            };
+            // 未跟随符号链接时，链接本身的类型不是普通文件
+            if !entry.file_type().is_some_and(|t| t.is_file()) {
+                continue;
+            }
+            // 在此处检查大小：WalkBuilder::max_filesize 会绕过 filter_entry
+            let too_large = entry
+                .metadata()
+                .map_or(true, |meta| meta.len() > self.max_file_size);
+            if !too_large && self.matches_extension(entry.path()) {
+                files.push(entry.into_path());
+            }
+        }
+        Ok(files)
+    }
+
+    /// 检查文件扩展名是否在允许列表中
+    fn matches_extension(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()))
+    }
+}
+
+/// 从排除模式构建匹配器
+fn build_excludes(root: &Path, patterns: &[String]) -> AppResult<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| AppError::knowledge(&format!("无效的排除模式 {}: {}", pattern, e)))?;
+    }
+    builder
+        .build()
+        .map_err(|e| AppError::knowledge(&format!("无法构建排除规则: {}", e)))
+}
+
+/// 单个路径的忽略规则匹配器，用于监听模式等无需遍历目录的场景
+///
+/// 只读取根目录下的忽略文件，子目录中的忽略文件不生效
+pub struct IgnoreMatcher {
+    /// 根目录
+    root: PathBuf,
+    /// 根目录下忽略文件和排除模式合并后的规则
+    rules: Gitignore,
+}
+
+impl IgnoreMatcher {
+    /// 读取根目录下的 `.gitignore`、`.ignore`、`.codexignore` 并合并排除模式
+    pub fn new(root: &Path, exclude_patterns: &[String]) -> AppResult<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        for name in [".gitignore", ".ignore", CODEX_IGNORE_FILE] {
+            let file = root.join(name);
+            if file.is_file() {
+                if let Some(e) = builder.add(&file) {
+                    log::warn!("忽略文件 {:?} 解析失败: {}", file, e);
+                }
+            }
+        }
+        for pattern in exclude_patterns {
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| AppError::knowledge(&format!("无效的排除模式 {}: {}", pattern, e)))?;
+        }
+        let rules = builder
+            .build()
+            .map_err(|e| AppError::knowledge(&format!("无法构建忽略规则: {}", e)))?;
+        Ok(Self {
+            root: root.to_path_buf(),
+            rules,
+        })
+    }
+
+    /// 判断路径是否被忽略，根目录之外的路径不做判断
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.root) else {
+            return false;
+        };
+        if relative.components().any(|c| c.as_os_str() == ".git") {
+            return true;
+        }
+        self.rules
+            .matched_path_or_any_parents(relative, is_dir)
+            .is_ignore()
+    }
+}
+
+/// 判断内容是否为二进制：开头部分包含 NUL 字节
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0)
+}
+
+/// 读取文本文件，超过大小上限或为二进制文件时返回 `None`
+///
+/// 非 UTF-8 内容按有损方式转换
+pub fn read_text_file(path: &Path, max_size: u64) -> AppResult<Option<String>> {
+    let file = fs::File::open(path)?;
+    if file.metadata()?.len() > max_size {
+        log::debug!("跳过超过大小上限的文件: {:?}", path);
+        return Ok(None);
+    }
+
+    let mut bytes = Vec::new();
+    file.take(max_size + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > max_size || is_binary(&bytes) {
+        log::debug!("跳过二进制或过大的文件: {:?}", path);
+        return Ok(None);
+    }
+
+    Ok(Some(match String::from_utf8(bytes) {
+        Ok(content) => content,
+        Err(e) => String::from_utf8_lossy(e.as_bytes()).into_owned(),
+    }))
+}
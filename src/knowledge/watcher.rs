@@ -0,0 +1,302 @@
+//! 知识库文件监听
+//!
+//! 监听项目目录的文件变化，去抖后增量更新知识库：修改的文件重新索引，
+//! 删除的文件从索引和索引缓存中移除
+
+use crate::config::app::KnowledgeConfig;
+use crate::error::{AppError, AppResult};
+use crate::knowledge::base::KnowledgeBase;
+use crate::knowledge::local::{normalize_path, LocalKnowledgeBase};
+use crate::knowledge::walker::IgnoreMatcher;
+use crate::parse_cache::ParseCache;
+use notify::{EventKind, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// 默认去抖时间（毫秒）
+pub const DEFAULT_DEBOUNCE_MS: u64 = 500;
+
+/// 检查停止标志的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 监听进度事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// 开始监听目录
+    Started(PathBuf),
+    /// 文件已重新索引
+    Indexed(PathBuf),
+    /// 文件已从索引中移除
+    Removed(PathBuf),
+    /// 文件处理失败
+    Failed(PathBuf, String),
+    /// 一批变化处理完成
+    BatchDone {
+        /// 重新索引的文件数量
+        indexed: usize,
+        /// 移除的文件数量
+        removed: usize,
+        /// 耗时（毫秒）
+        elapsed_ms: u128,
+    },
+    /// 停止监听
+    Stopped,
+}
+
+impl std::fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WatchEvent::Started(root) => write!(f, "开始监听: {}", root.display()),
+            WatchEvent::Indexed(path) => write!(f, "已索引: {}", path.display()),
+            WatchEvent::Removed(path) => write!(f, "已移除: {}", path.display()),
+            WatchEvent::Failed(path, error) => {
+                write!(f, "处理失败: {} ({})", path.display(), error)
+            }
+            WatchEvent::BatchDone {
+                indexed,
+                removed,
+                elapsed_ms,
+            } => write!(
+                f,
+                "索引已更新: {} 个文件重新索引，{} 个文件移除，耗时 {} ms",
+                indexed, removed, elapsed_ms
+            ),
+            WatchEvent::Stopped => write!(f, "已停止监听"),
+        }
+    }
+}
+
+/// 文件变化去抖器：同一文件在静默期内的多次变化只处理一次
+pub struct Debouncer {
+    /// 静默期
+    delay: Duration,
+    /// 待处理的文件及其最后一次变化时间
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    /// 创建新的去抖器
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// 记录文件变化
+    pub fn push(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now);
+    }
+
+    /// 取出静默期已过的文件
+    pub fn drain_ready(&mut self, now: Instant) -> Vec<PathBuf> {
+        let ready: Vec<PathBuf> = self
+            .pending
+            .iter()
+            .filter(|(_, changed_at)| now.duration_since(**changed_at) >= self.delay)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &ready {
+            self.pending.remove(path);
+        }
+        ready
+    }
+
+    /// 是否没有待处理的文件
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+/// 知识库文件监听器
+pub struct KnowledgeWatcher {
+    /// 知识库配置
+    config: KnowledgeConfig,
+    /// 监听的根目录
+    root: PathBuf,
+    /// 去抖时间
+    debounce: Duration,
+    /// 根目录下的忽略规则
+    ignore: IgnoreMatcher,
+    /// 已索引文件的语法树，文件再次变化时增量解析
+    parse_cache: Mutex<ParseCache>,
+}
+
+impl KnowledgeWatcher {
+    /// 创建新的监听器
+    pub fn new(config: KnowledgeConfig, root: &Path, debounce: Duration) -> AppResult<Self> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| AppError::knowledge(&format!("无法监听目录 {:?}: {}", root, e)))?;
+        let ignore = IgnoreMatcher::new(&root, &config.exclude_patterns)?;
+        Ok(Self {
+            config,
+            root,
+            debounce,
+            ignore,
+            parse_cache: Mutex::new(ParseCache::new()),
+        })
+    }
+
+    /// 获取监听的根目录
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 判断文件变化是否需要处理
+    pub fn should_index(&self, path: &Path) -> bool {
+        // 忽略知识库自身的存储目录，避免索引写入触发新的变化
+        for dir in [&self.config.index_dir, &self.config.metadata_dir] {
+            if !dir.as_os_str().is_empty() && path.starts_with(dir) {
+                return false;
+            }
+        }
+
+        if self.ignore.is_ignored(path, false) {
+            return false;
+        }
+
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                self.config
+                    .supported_extensions
+                    .contains(&ext.to_lowercase())
+            })
+    }
+
+    /// 将一批文件变化应用到知识库：存在的文件重新索引，不存在的文件移除
+    pub fn apply(&self, paths: &[PathBuf], on_event: &mut dyn FnMut(WatchEvent)) -> AppResult<()> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+
+        let started = Instant::now();
+        // 每批变化单独打开知识库，处理完成后释放存储锁，不影响其他命令
+        let mut kb = LocalKnowledgeBase::new(self.config.clone())?;
+        kb.set_quiet(true);
+        kb.init()?;
+
+        let mut parse_cache = self.parse_cache.lock().unwrap();
+        let (mut indexed, mut removed) = (0, 0);
+        for path in paths {
+            let result = if path.is_file() {
+                kb.add_path_with_cache(path, Some(&mut parse_cache))
+                    .map(|added| {
+                        if added {
+                            indexed += 1;
+                            on_event(WatchEvent::Indexed(path.clone()));
+                        }
+                    })
+            } else {
+                parse_cache.remove(&normalize_path(path).to_string_lossy());
+                kb.remove_file(path).map(|()| {
+                    removed += 1;
+                    on_event(WatchEvent::Removed(path.clone()));
+                })
+            };
+            if let Err(e) = result {
+                on_event(WatchEvent::Failed(path.clone(), e.to_string()));
+            }
+        }
+        kb.commit()?;
+
+        on_event(WatchEvent::BatchDone {
+            indexed,
+            removed,
+            elapsed_ms: started.elapsed().as_millis(),
+        });
+        Ok(())
+    }
+
+    /// 阻塞监听目录，直到停止标志被设置
+    pub fn run(&self, stop: &AtomicBool, on_event: &mut dyn FnMut(WatchEvent)) -> AppResult<()> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)
+            .map_err(|e| AppError::knowledge(&format!("创建文件监听器失败: {}", e)))?;
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .map_err(|e| AppError::knowledge(&format!("监听目录失败: {}", e)))?;
+        on_event(WatchEvent::Started(self.root.clone()));
+
+        let mut debouncer = Debouncer::new(self.debounce);
+        while !stop.load(Ordering::Relaxed) {
+            match receiver.recv_timeout(POLL_INTERVAL) {
+                Ok(Ok(event)) => {
+                    if matches!(
+                        event.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    ) {
+                        let now = Instant::now();
+                        for path in event.paths {
+                            if self.should_index(&path) {
+                                debouncer.push(path, now);
+                            }
+                        }
+                    }
+                }
+                Ok(Err(e)) => on_event(WatchEvent::Failed(self.root.clone(), e.to_string())),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+
+            if !debouncer.is_empty() {
+                let ready = debouncer.drain_ready(Instant::now());
+                if let Err(e) = self.apply(&ready, on_event) {
+                    on_event(WatchEvent::Failed(self.root.clone(), e.to_string()));
+                }
+            }
+        }
+
+        on_event(WatchEvent::Stopped);
+        Ok(())
+    }
+
+    /// 在后台线程中监听，进度事件通过通道发送
+    pub fn spawn(self, events: mpsc::Sender<WatchEvent>) -> WatchHandle {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let thread = std::thread::spawn(move || {
+            let error_events = events.clone();
+            let result = self.run(&thread_stop, &mut |event| {
+                let _ = events.send(event);
+            });
+            if let Err(e) = result {
+                let _ = error_events.send(WatchEvent::Failed(self.root.clone(), e.to_string()));
+            }
+        });
+
+        WatchHandle {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+/// 后台监听线程句柄，丢弃时停止监听
+pub struct WatchHandle {
+    /// 停止标志
+    stop: Arc<AtomicBool>,
+    /// 监听线程
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WatchHandle {
+    /// 停止监听并等待线程退出
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,586 @@
+//! 交叉引用索引
+//!
+//! 从 tree-sitter 语法树中提取定义、调用、标识符引用和导入，
+//! 支持跳转到定义、查找引用以及调用者/被调用者查询
+
+use crate::error::{AppError, AppResult};
+use crate::parsers::{CodeElementType, PARSER_REGISTRY};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tree_sitter::{Node as TsNode, Parser as TsParser};
+
+/// 交叉引用操作枚举
+#[derive(Debug, Clone, Subcommand)]
+pub enum XrefActions {
+    /// 跳转到符号定义
+    Def {
+        /// 符号名称，如 `generate_response` 或 `AIClient::generate_response`
+        symbol: String,
+    },
+
+    /// 查找符号的所有引用
+    Refs {
+        /// 符号名称
+        symbol: String,
+    },
+
+    /// 查找调用该函数的位置
+    Callers {
+        /// 函数名称
+        symbol: String,
+    },
+
+    /// 查找该函数调用的其他函数
+    Callees {
+        /// 函数名称
+        symbol: String,
+    },
+}
+
+/// 引用类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    /// 函数或方法调用
+    Call,
+    /// 标识符引用
+    Reference,
+    /// 导入语句
+    Import,
+}
+
+impl ReferenceKind {
+    /// 获取引用类型名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReferenceKind::Call => "call",
+            ReferenceKind::Reference => "reference",
+            ReferenceKind::Import => "import",
+        }
+    }
+}
+
+/// 符号定义
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrefDefinition {
+    /// 名称
+    pub name: String,
+    /// 限定名称，如 `AIClient::generate_response`
+    pub qualified_name: String,
+    /// 元素类型
+    pub kind: CodeElementType,
+    /// 起始行（从1开始）
+    pub line: usize,
+    /// 结束行（包含）
+    pub end_line: usize,
+}
+
+/// 符号引用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct XrefReference {
+    /// 被引用的名称
+    pub name: String,
+    /// 限定前缀，如 `AIClient::new` 中的 `AIClient`
+    pub qualifier: Option<String>,
+    /// 引用类型
+    pub kind: ReferenceKind,
+    /// 行号（从1开始）
+    pub line: usize,
+    /// 列号（从1开始）
+    pub column: usize,
+    /// 所在函数的限定名称
+    pub caller: Option<String>,
+}
+
+/// 单个文件的交叉引用
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FileXrefs {
+    /// 文件路径
+    pub path: String,
+    /// 语言
+    pub language: String,
+    /// 文件中的定义
+    pub definitions: Vec<XrefDefinition>,
+    /// 文件中的引用
+    pub references: Vec<XrefReference>,
+}
+
+/// 带文件路径的查询结果
+#[derive(Debug, Clone)]
+pub struct XrefLocation<T> {
+    /// 文件路径
+    pub path: String,
+    /// 定义或引用
+    pub item: T,
+}
+
+/// 语言的语法节点规则
+struct LanguageRules {
+    /// 限定名称分隔符
+    separator: &'static str,
+    /// 函数类定义节点
+    functions: &'static [&'static str],
+    /// 类型类定义节点，其中的方法以类型名限定
+    types: &'static [&'static str],
+    /// 为子节点提供限定前缀的节点及其类型字段（如 Rust 的 impl）
+    containers: &'static [(&'static str, &'static str)],
+    /// 调用节点及其被调用者字段
+    calls: &'static [(&'static str, &'static str)],
+    /// 导入节点
+    imports: &'static [&'static str],
+    /// 标识符节点
+    identifiers: &'static [&'static str],
+    /// 表示当前实例的接收者
+    receivers: &'static [&'static str],
+}
+
+/// 获取语言的语法节点规则
+fn rules_for(language: &str) -> Option<LanguageRules> {
+    match language {
+        "rust" => Some(LanguageRules {
+            separator: "::",
+            functions: &["function_item", "function_signature_item"],
+            types: &["struct_item", "enum_item", "trait_item", "union_item"],
+            containers: &[("impl_item", "type")],
+            calls: &[
+                ("call_expression", "function"),
+                ("macro_invocation", "macro"),
+            ],
+            imports: &["use_declaration"],
+            identifiers: &["identifier", "type_identifier", "field_identifier"],
+            receivers: &["self", "Self"],
+        }),
+        "python" => Some(LanguageRules {
+            separator: ".",
+            functions: &["function_definition"],
+            types: &["class_definition"],
+            containers: &[],
+            calls: &[("call", "function")],
+            imports: &["import_statement", "import_from_statement"],
+            identifiers: &["identifier"],
+            receivers: &["self", "cls"],
+        }),
+        "javascript" | "typescript" => Some(LanguageRules {
+            separator: ".",
+            functions: &[
+                "function_declaration",
+                "generator_function_declaration",
+                "method_definition",
+                "method_signature",
+            ],
+            types: &[
+                "class_declaration",
+                "abstract_class_declaration",
+                "interface_declaration",
+            ],
+            containers: &[],
+            calls: &[
+                ("call_expression", "function"),
+                ("new_expression", "constructor"),
+            ],
+            imports: &["import_statement"],
+            identifiers: &[
+                "identifier",
+                "property_identifier",
+                "type_identifier",
+                "shorthand_property_identifier",
+            ],
+            receivers: &["this"],
+        }),
+        _ => None,
+    }
+}
+
+/// 语法树遍历状态
+struct Extractor<'a> {
+    /// 源代码
+    source: &'a [u8],
+    /// 语言规则
+    rules: LanguageRules,
+    /// 已作为定义名或调用名处理的节点
+    consumed: HashSet<usize>,
+    /// 提取结果
+    xrefs: FileXrefs,
+}
+
+impl<'a> Extractor<'a> {
+    /// 获取节点文本
+    fn text(&self, node: TsNode) -> &'a str {
+        node.utf8_text(self.source).unwrap_or("")
+    }
+
+    /// 拼接限定名称
+    fn qualify(&self, container: Option<&str>, name: &str) -> String {
+        match container {
+            Some(container) => format!("{}{}{}", container, self.rules.separator, name),
+            None => name.to_string(),
+        }
+    }
+
+    /// 获取 JS/TS 中以函数表达式赋值的变量名，如 `const f = () => {}`
+    fn function_variable_name(&self, node: TsNode<'a>) -> Option<TsNode<'a>> {
+        if node.kind() != "variable_declarator" {
+            return None;
+        }
+        let value = node.child_by_field_name("value")?;
+        matches!(
+            value.kind(),
+            "arrow_function" | "function" | "function_expression"
+        )
+        .then(|| node.child_by_field_name("name"))
+        .flatten()
+    }
+
+    /// 解析被调用者节点，返回名称节点和限定前缀
+    fn resolve_callee(
+        &self,
+        node: TsNode<'a>,
+        container: Option<&str>,
+    ) -> Option<(TsNode<'a>, Option<String>)> {
+        let (name, qualifier, is_path) = match node.kind() {
+            // Rust 路径调用 `Type::func()`
+            "scoped_identifier" => (
+                node.child_by_field_name("name")?,
+                node.child_by_field_name("path"),
+                true,
+            ),
+            // Rust 方法调用 `value.func()`
+            "field_expression" => (
+                node.child_by_field_name("field")?,
+                node.child_by_field_name("value"),
+                false,
+            ),
+            // Python 属性调用 `obj.func()`
+            "attribute" => (
+                node.child_by_field_name("attribute")?,
+                node.child_by_field_name("object"),
+                false,
+            ),
+            // JS/TS 成员调用 `obj.func()`
+            "member_expression" => (
+                node.child_by_field_name("property")?,
+                node.child_by_field_name("object"),
+                false,
+            ),
+            // Rust 泛型调用 `func::<T>()`
+            "generic_function" => {
+                return self.resolve_callee(node.child_by_field_name("function")?, container)
+            }
+            _ if self.rules.identifiers.contains(&node.kind()) => (node, None, false),
+            _ => return None,
+        };
+
+        let qualifier = qualifier.and_then(|qualifier| {
+            let text = self.text(qualifier);
+            if self.rules.receivers.contains(&text) {
+                // self.method() 限定为当前类型
+                return container.map(|container| container.to_string());
+            }
+            // 只保留路径的最后一段，如 `crate::ai::AIClient` 中的 `AIClient`
+            let last = text.rsplit(self.rules.separator).next().unwrap_or(text);
+            let last = last.split('<').next().unwrap_or(last);
+            // 变量接收者的类型无法静态确定，只有路径和类型名作为限定前缀
+            (is_path || last.starts_with(char::is_uppercase)).then(|| last.to_string())
+        });
+        Some((name, qualifier))
+    }
+
+    /// 记录引用
+    fn push_reference(
+        &mut self,
+        node: TsNode,
+        kind: ReferenceKind,
+        qualifier: Option<String>,
+        caller: Option<&str>,
+    ) {
+        let position = node.start_position();
+        self.consumed.insert(node.id());
+        self.xrefs.references.push(XrefReference {
+            name: self.text(node).to_string(),
+            qualifier,
+            kind,
+            line: position.row + 1,
+            column: position.column + 1,
+            caller: caller.map(|caller| caller.to_string()),
+        });
+    }
+
+    /// 递归遍历语法树
+    fn walk(&mut self, node: TsNode<'a>, container: Option<&str>, caller: Option<&str>) {
+        let kind = node.kind();
+
+        // 导入语句中的名称全部记为导入
+        if self.rules.imports.contains(&kind) {
+            self.collect_imports(node, caller);
+            return;
+        }
+
+        // 为子节点提供限定前缀的容器
+        if let Some((_, field)) = self.rules.containers.iter().find(|(k, _)| *k == kind) {
+            let name = node.child_by_field_name(field).map(|type_node| {
+                let text = self.text(type_node);
+                text.split('<').next().unwrap_or(text).trim().to_string()
+            });
+            self.walk_children(node, name.as_deref().or(container), caller);
+            return;
+        }
+
+        // 函数和类型定义
+        let is_function = self.rules.functions.contains(&kind);
+        let is_type = self.rules.types.contains(&kind);
+        let name_node = if is_function || is_type {
+            node.child_by_field_name("name")
+        } else {
+            self.function_variable_name(node)
+        };
+        if let Some(name_node) = name_node {
+            let name = self.text(name_node).to_string();
+            let qualified_name = self.qualify(container, &name);
+            self.consumed.insert(name_node.id());
+            self.xrefs.definitions.push(XrefDefinition {
+                name: name.clone(),
+                qualified_name: qualified_name.clone(),
+                kind: if is_type {
+                    match kind {
+                        "enum_item" => CodeElementType::Enum,
+                        "trait_item" => CodeElementType::Trait,
+                        "interface_declaration" => CodeElementType::Interface,
+                        "struct_item" | "union_item" => CodeElementType::Struct,
+                        _ => CodeElementType::Class,
+                    }
+                } else {
+                    CodeElementType::Function
+                },
+                line: node.start_position().row + 1,
+                end_line: node.end_position().row + 1,
+            });
+
+            if is_type {
+                self.walk_children(node, Some(&name), caller);
+            } else {
+                self.walk_children(node, container, Some(&qualified_name));
+            }
+            return;
+        }
+
+        // 调用表达式
+        if let Some((_, field)) = self.rules.calls.iter().find(|(k, _)| *k == kind) {
+            if let Some((name, qualifier)) = node
+                .child_by_field_name(field)
+                .and_then(|callee| self.resolve_callee(callee, container))
+            {
+                self.push_reference(name, ReferenceKind::Call, qualifier, caller);
+            }
+        } else if self.rules.identifiers.contains(&kind) && !self.consumed.contains(&node.id()) {
+            self.push_reference(node, ReferenceKind::Reference, None, caller);
+        }
+
+        self.walk_children(node, container, caller);
+    }
+
+    /// 遍历子节点
+    fn walk_children(&mut self, node: TsNode<'a>, container: Option<&str>, caller: Option<&str>) {
+        let mut cursor = node.walk();
+        let children: Vec<TsNode<'a>> = node.children(&mut cursor).collect();
+        for child in children {
+            self.walk(child, container, caller);
+        }
+    }
+
+    /// 收集导入语句中的标识符
+    fn collect_imports(&mut self, node: TsNode<'a>, caller: Option<&str>) {
+        if self.rules.identifiers.contains(&node.kind()) {
+            self.push_reference(node, ReferenceKind::Import, None, caller);
+            return;
+        }
+        let mut cursor = node.walk();
+        let children: Vec<TsNode<'a>> = node.children(&mut cursor).collect();
+        for child in children {
+            self.collect_imports(child, caller);
+        }
+    }
+}
+
+/// 提取文件的交叉引用，不支持的语言返回 `None`
+pub fn extract_xrefs(path: &str, content: &str) -> AppResult<Option<FileXrefs>> {
+    let Some(parser) = PARSER_REGISTRY.read().unwrap().get_parser_by_filename(path) else {
+        return Ok(None);
+    };
+    let language = parser.language_name();
+    let Some(rules) = rules_for(&language) else {
+        return Ok(None);
+    };
+
+    let mut ts_parser = TsParser::new();
+    ts_parser
+        .set_language(parser.tree_sitter_language())
+        .map_err(|e| AppError::knowledge(&format!("无法加载 {} 语法: {}", language, e)))?;
+    let tree = ts_parser
+        .parse(content, None)
+        .ok_or_else(|| AppError::knowledge(&format!("解析文件失败: {}", path)))?;
+
+    let mut extractor = Extractor {
+        source: content.as_bytes(),
+        rules,
+        consumed: HashSet::new(),
+        xrefs: FileXrefs {
+            path: path.to_string(),
+            language,
+            ..Default::default()
+        },
+    };
+    extractor.walk(tree.root_node(), None, None);
+    Ok(Some(extractor.xrefs))
+}
+
+/// 拆分符号为限定前缀和名称，支持 `::` 和 `.` 分隔
+fn split_symbol(symbol: &str) -> (Option<&str>, &str) {
+    for separator in ["::", "."] {
+        if let Some((qualifier, name)) = symbol.rsplit_once(separator) {
+            let qualifier = qualifier.rsplit(separator).next().unwrap_or(qualifier);
+            return (Some(qualifier), name);
+        }
+    }
+    (None, symbol)
+}
+
+/// 判断限定名称是否与查询符号匹配
+fn qualified_matches(qualified_name: &str, symbol: &str) -> bool {
+    let (qualifier, name) = split_symbol(symbol);
+    let (own_qualifier, own_name) = split_symbol(qualified_name);
+    own_name == name && (qualifier.is_none() || qualifier == own_qualifier)
+}
+
+/// 交叉引用索引，保存在知识库元数据数据库中
+pub struct XrefIndex {
+    /// 按文件路径保存的交叉引用
+    tree: sled::Tree,
+}
+
+impl XrefIndex {
+    /// 使用元数据数据库创建索引
+    pub fn open(db: &sled::Db) -> AppResult<Self> {
+        Ok(Self {
+            tree: db.open_tree("xref")?,
+        })
+    }
+
+    /// 替换文件的交叉引用
+    pub fn update(&self, xrefs: &FileXrefs) -> AppResult<()> {
+        self.tree
+            .insert(xrefs.path.as_bytes(), serde_json::to_vec(xrefs)?)?;
+        Ok(())
+    }
+
+    /// 获取文件的交叉引用
+    pub fn get(&self, path: &str) -> AppResult<Option<FileXrefs>> {
+        match self.tree.get(path.as_bytes())? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 删除文件的交叉引用
+    pub fn remove(&self, path: &str) -> AppResult<()> {
+        self.tree.remove(path.as_bytes())?;
+        Ok(())
+    }
+
+    /// 清空索引
+    pub fn clear(&self) -> AppResult<()> {
+        self.tree.clear()?;
+        Ok(())
+    }
+
+    /// 获取已建立交叉引用的文件数量
+    pub fn file_count(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// 获取已建立交叉引用的文件路径
+    pub fn paths(&self) -> AppResult<Vec<String>> {
+        let mut paths = Vec::new();
+        for key in self.tree.iter().keys() {
+            paths.push(String::from_utf8_lossy(&key?).to_string());
+        }
+        Ok(paths)
+    }
+
+    /// 遍历所有文件的交叉引用
+    fn files(&self) -> impl Iterator<Item = AppResult<FileXrefs>> + '_ {
+        self.tree.iter().values().map(|value| {
+            let value = value?;
+            Ok(serde_json::from_slice(&value)?)
+        })
+    }
+
+    /// 查找符号的定义
+    pub fn definitions(&self, symbol: &str) -> AppResult<Vec<XrefLocation<XrefDefinition>>> {
+        let mut results = Vec::new();
+        for file in self.files() {
+            let file = file?;
+            for definition in file.definitions {
+                if qualified_matches(&definition.qualified_name, symbol) {
+                    results.push(XrefLocation {
+                        path: file.path.clone(),
+                        item: definition,
+                    });
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// 按条件查找引用
+    fn find_references(
+        &self,
+        filter: impl Fn(&XrefReference) -> bool,
+    ) -> AppResult<Vec<XrefLocation<XrefReference>>> {
+        let mut results = Vec::new();
+        for file in self.files() {
+            let file = file?;
+            for reference in file.references {
+                if filter(&reference) {
+                    results.push(XrefLocation {
+                        path: file.path.clone(),
+                        item: reference,
+                    });
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// 查找符号的所有引用（包括调用和导入）
+    pub fn references(&self, symbol: &str) -> AppResult<Vec<XrefLocation<XrefReference>>> {
+        let (qualifier, name) = split_symbol(symbol);
+        self.find_references(|reference| {
+            reference.name == name
+                && match (qualifier, &reference.qualifier) {
+                    (Some(expected), Some(actual)) => expected == actual,
+                    _ => true,
+                }
+        })
+    }
+
+    /// 查找调用该函数的位置
+    pub fn callers(&self, symbol: &str) -> AppResult<Vec<XrefLocation<XrefReference>>> {
+        Ok(self
+            .references(symbol)?
+            .into_iter()
+            .filter(|location| location.item.kind == ReferenceKind::Call)
+            .collect())
+    }
+
+    /// 查找该函数中的调用
+    pub fn callees(&self, symbol: &str) -> AppResult<Vec<XrefLocation<XrefReference>>> {
+        self.find_references(|reference| {
+            reference.kind == ReferenceKind::Call
+                && reference
+                    .caller
+                    .as_deref()
+                    .is_some_and(|caller| qualified_matches(caller, symbol))
+        })
+    }
+}
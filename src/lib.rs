@@ -19,9 +19,13 @@ pub mod frameworks;
 pub mod hook;
 pub mod i18n;
 pub mod knowledge;
+pub mod mentions;
+pub mod parse_cache;
 pub mod parsers;
 pub mod plugins;
+pub mod refactor;
 pub mod scraper;
+pub mod session;
 pub mod solo;
 pub mod subagent;
 pub mod task;
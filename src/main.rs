@@ -8,6 +8,7 @@ mod config;
 mod core;
 mod error;
 mod knowledge;
+mod parse_cache;
 mod parsers;
 mod tools;
 
@@ -19,9 +20,11 @@ mod command;
 mod context;
 mod docs;
 mod hook;
-mod knowledge_base;
+mod mentions;
 mod plugins;
+mod refactor;
 mod scraper;
+mod session;
 mod solo;
 mod subagent;
 mod task;
@@ -32,7 +35,10 @@ use crate::config::loader::ConfigLoader;
 use crate::error::{init_error_reporting, AppResult};
 
 // Import knowledge and task actions from their respective modules
-use knowledge_base::KnowledgeActions;
+use knowledge::base::KnowledgeActions;
+use knowledge::deps::DepsActions;
+use knowledge::xref::XrefActions;
+use session::SessionActions;
 use task::TaskActions;
 
 // Import provider actions from ai adapter
@@ -59,6 +65,10 @@ struct Cli {
     /// Configuration file path
     #[arg(long, value_name = "FILE")]
     config: Option<String>,
+
+    /// Continue the most recent chat session in the current directory
+    #[arg(long = "continue")]
+    continue_session: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -84,6 +94,12 @@ enum Commands {
         output: Option<String>,
     },
 
+    /// Ask a question; @path, @path:10-40, @dir/*.rs and @Symbol mentions are attached as context
+    Ask {
+        /// Question, may contain @ mentions
+        prompt: String,
+    },
+
     /// Knowledge base management
     Knowledge {
         #[command(subcommand)]
@@ -102,6 +118,10 @@ enum Commands {
         /// Add to knowledge base
         #[arg(short, long)]
         add_to_kb: bool,
+
+        /// Knowledge base collection for the scraped pages
+        #[arg(long)]
+        collection: Option<String>,
     },
 
     /// Task management
@@ -145,6 +165,61 @@ enum Commands {
         #[command(subcommand)]
         action: PluginCommands,
     },
+
+    /// Chat session management
+    Session {
+        #[command(subcommand)]
+        action: SessionActions,
+    },
+
+    /// Cross-reference queries: definitions, references and call graph
+    Xref {
+        #[command(subcommand)]
+        action: XrefActions,
+    },
+
+    /// Import dependency graph: export as DOT/JSON, detect cycles, find dependents
+    Deps {
+        #[command(subcommand)]
+        action: DepsActions,
+    },
+
+    /// Check files for syntax errors and missing tokens using the tree-sitter parsers
+    CheckSyntax {
+        /// Files to check
+        #[arg(required = true)]
+        files: Vec<std::path::PathBuf>,
+    },
+
+    /// Refactor code and show the result as a diff; rename, extract_variable and inline_variable
+    /// are applied with tree-sitter, other kinds are delegated to the AI
+    Refactor {
+        /// Refactoring kind, e.g. rename, extract_variable, inline_variable, extract_function
+        kind: String,
+
+        /// File to refactor, or the project directory for renames
+        path: std::path::PathBuf,
+
+        /// Symbol to rename, variable to inline or name of the extracted variable
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// New name for renames
+        #[arg(long)]
+        new_name: Option<String>,
+
+        /// Expression to extract
+        #[arg(short, long)]
+        expression: Option<String>,
+
+        /// Line of the expression to extract or of the variable declaration to inline
+        #[arg(short, long)]
+        line: Option<u32>,
+
+        /// Write the changes instead of only showing the diff
+        #[arg(long)]
+        apply: bool,
+    },
 }
 
 /// Plugin subcommands
@@ -219,11 +294,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = config_loader.load(cli.config.as_deref())?;
     let language = &config.app.language;
 
+    // Resolve the session to continue when --continue is given
+    let continue_session = if cli.continue_session {
+        let session_id = cli::latest_session_id(&config)?;
+        if session_id.is_none() {
+            println!("当前目录下没有可继续的会话，将开始新会话。");
+        }
+        session_id
+    } else {
+        None
+    };
+
     // Handle commands
     match cli.command {
         Some(Commands::Interactive { tab }) => {
             // Start interactive UI mode
-            cli::handle_interactive(tab.clone())?;
+            cli::handle_interactive(tab.clone(), continue_session, &config)?;
         }
         Some(Commands::Code {
             prompt,
@@ -233,17 +319,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // Handle code generation
             cli::handle_code(&prompt, language.clone(), output.clone())?;
         }
+        Some(Commands::Ask { prompt }) => {
+            // Answer a question with mentioned files pinned into context
+            cli::handle_ask(&prompt, &config).await?;
+        }
         Some(Commands::Knowledge { action }) => {
             // Handle knowledge base commands
-            cli::handle_knowledge(format!("{:?}", action))?;
+            cli::handle_knowledge(action, &config, cli.config.as_deref())?;
         }
         Some(Commands::Scrape {
             urls,
             depth,
             add_to_kb,
+            collection,
         }) => {
             // Handle web scraping
-            cli::handle_scrape(&urls, depth, add_to_kb).await?;
+            cli::handle_scrape(&urls, depth, add_to_kb, collection.as_deref(), &config).await?;
         }
         Some(Commands::Task { action }) => {
             // Handle task management
@@ -288,6 +379,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
         }
+        Some(Commands::Session { action }) => {
+            // Handle chat session management
+            cli::handle_session(action, &config)?;
+        }
+        Some(Commands::Xref { action }) => {
+            // Handle cross-reference queries
+            cli::handle_xref(action, &config)?;
+        }
+        Some(Commands::Deps { action }) => {
+            // Handle dependency graph queries
+            cli::handle_deps(action, &config)?;
+        }
+        Some(Commands::CheckSyntax { files }) => {
+            // Report syntax diagnostics for the given files
+            cli::handle_check_syntax(&files)?;
+        }
+        Some(Commands::Refactor {
+            kind,
+            path,
+            name,
+            new_name,
+            expression,
+            line,
+            apply,
+        }) => {
+            // Show the refactoring as a diff and optionally write it
+            let target = cli::refactor_target(&path, name, new_name, expression.as_deref(), line)?;
+            cli::handle_refactor(&kind, &path, &target, apply).await?;
+        }
+        None if cli.continue_session => {
+            // Continue the last chat session in interactive mode
+            cli::handle_interactive(None, continue_session, &config)?;
+        }
         None => {
             // Default: enter solo mode for AI programming
             if language == "zh" {
@@ -0,0 +1,416 @@
+//! @ 引用解析模块
+//!
+//! 识别输入中的 `@path/to/file`、`@path:10-40`、目录/通配符和 `@Symbol` 引用，
+//! 并将引用的内容作为固定（pinned）代码片段加入上下文
+
+use crate::code::CodeProgrammer;
+use crate::context::ContextManager;
+use crate::error::{AppError, AppResult};
+use crate::knowledge::indexer::CodeIndexer;
+use crate::parsers::{CodeElement, PARSER_REGISTRY};
+use globset::GlobBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// 目录或通配符引用最多展开的文件数
+const MAX_EXPANDED_FILES: usize = 20;
+
+/// 单个被引用文件的最大字节数
+const MAX_FILE_SIZE: u64 = 256 * 1024;
+
+/// 未能确定结束位置时，符号片段最多包含的行数
+const MAX_SYMBOL_LINES: usize = 200;
+
+/// 展开目录时跳过的目录名
+const SKIPPED_DIRS: &[&str] = &[".git", "target", "node_modules", "venv", ".venv"];
+
+/// 引用类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MentionKind {
+    /// 整个文件
+    File(PathBuf),
+    /// 文件中的行范围（从1开始，包含两端）
+    LineRange(PathBuf, usize, usize),
+    /// 目录或通配符
+    Glob(String),
+    /// 代码符号
+    Symbol(String),
+}
+
+/// 从输入中识别出的引用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mention {
+    /// 原始文本（不含 `@`）
+    pub raw: String,
+    /// 引用类型
+    pub kind: MentionKind,
+}
+
+/// 解析后的引用内容
+#[derive(Debug, Clone)]
+pub struct ResolvedMention {
+    /// 显示标签，如 `src/main.rs:10-40`
+    pub label: String,
+    /// 代码语言
+    pub language: String,
+    /// 引用的内容
+    pub content: String,
+}
+
+impl ResolvedMention {
+    /// 生成放入上下文的文本
+    pub fn to_context_text(&self) -> String {
+        format!(
+            "{}\n```{}\n{}\n```",
+            self.label, self.language, self.content
+        )
+    }
+}
+
+/// 引用解析器
+pub struct MentionResolver {
+    /// 相对路径的根目录
+    root: PathBuf,
+    /// 用于查找符号的索引器
+    indexer: Option<CodeIndexer>,
+}
+
+impl MentionResolver {
+    /// 创建新的引用解析器
+    pub fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            indexer: None,
+        }
+    }
+
+    /// 设置用于查找符号的索引器
+    pub fn with_indexer(mut self, indexer: CodeIndexer) -> Self {
+        self.indexer = Some(indexer);
+        self
+    }
+
+    /// 从输入文本中提取引用
+    pub fn parse(&self, input: &str) -> Vec<Mention> {
+        let mut mentions = Vec::new();
+
+        for token in input.split_whitespace() {
+            let Some(raw) = token.strip_prefix('@') else {
+                continue;
+            };
+            // 中文标点之后的内容不属于引用，例如 "@src/*.rs，还有"
+            let raw = raw
+                .split(['，', '。', '？', '！', '；', '、', '）'])
+                .next()
+                .unwrap_or("");
+            // 去掉句末标点，例如 "看看 @src/main.rs."
+            let raw = raw.trim_end_matches([',', '.', ';', '!', '?', ')']);
+            if raw.is_empty() {
+                continue;
+            }
+
+            if let Some(kind) = self.classify(raw) {
+                mentions.push(Mention {
+                    raw: raw.to_string(),
+                    kind,
+                });
+            }
+        }
+
+        mentions
+    }
+
+    /// 判断引用类型
+    fn classify(&self, raw: &str) -> Option<MentionKind> {
+        // 行范围：path:10-40 或 path:10
+        if let Some((path, range)) = raw.rsplit_once(':') {
+            if let Some((start, end)) = parse_line_range(range) {
+                if self.root.join(path).is_file() {
+                    return Some(MentionKind::LineRange(PathBuf::from(path), start, end));
+                }
+            }
+        }
+
+        if raw.contains(['*', '?', '[']) {
+            return Some(MentionKind::Glob(raw.to_string()));
+        }
+
+        let path = self.root.join(raw);
+        if path.is_file() {
+            return Some(MentionKind::File(PathBuf::from(raw)));
+        }
+        if path.is_dir() {
+            return Some(MentionKind::Glob(format!(
+                "{}/**/*",
+                raw.trim_end_matches('/')
+            )));
+        }
+
+        let is_identifier = raw
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '.')
+            && raw
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_');
+        if is_identifier {
+            return Some(MentionKind::Symbol(raw.to_string()));
+        }
+
+        None
+    }
+
+    /// 解析引用，读取其内容
+    pub fn resolve(&self, mention: &Mention) -> AppResult<Vec<ResolvedMention>> {
+        match &mention.kind {
+            MentionKind::File(path) => Ok(vec![self.resolve_file(path)?]),
+            MentionKind::LineRange(path, start, end) => {
+                Ok(vec![self.resolve_range(path, *start, *end)?])
+            }
+            MentionKind::Glob(pattern) => self.resolve_glob(pattern),
+            MentionKind::Symbol(name) => self.resolve_symbol(name),
+        }
+    }
+
+    /// 提取并解析输入中的所有引用，返回成功解析的内容和失败信息
+    pub fn resolve_all(&self, input: &str) -> (Vec<ResolvedMention>, Vec<String>) {
+        let mut resolved = Vec::new();
+        let mut errors = Vec::new();
+
+        for mention in self.parse(input) {
+            match self.resolve(&mention) {
+                Ok(items) if items.is_empty() => {
+                    errors.push(format!("@{}: 没有匹配的内容", mention.raw))
+                }
+                Ok(items) => resolved.extend(items),
+                Err(e) => errors.push(format!("@{}: {}", mention.raw, e)),
+            }
+        }
+
+        (resolved, errors)
+    }
+
+    /// 将解析后的引用固定到上下文，返回每项的来源和令牌数
+    pub fn pin_into(
+        mentions: &[ResolvedMention],
+        context: &mut ContextManager,
+    ) -> Vec<(String, usize)> {
+        let mut attached = Vec::new();
+        for mention in mentions {
+            let id = context.add_pinned_snippet(
+                &mention.to_context_text(),
+                &mention.language,
+                &mention.label,
+            );
+            let tokens = context
+                .pinned_items()
+                .iter()
+                .find(|item| item.id == id)
+                .map(|item| item.token_count)
+                .unwrap_or(0);
+            attached.push((mention.label.clone(), tokens));
+        }
+        attached
+    }
+
+    /// 读取整个文件
+    fn resolve_file(&self, path: &Path) -> AppResult<ResolvedMention> {
+        let content = self.read_file(path)?;
+        Ok(ResolvedMention {
+            label: path.display().to_string(),
+            language: language_of(path),
+            content,
+        })
+    }
+
+    /// 读取文件中的行范围
+    fn resolve_range(&self, path: &Path, start: usize, end: usize) -> AppResult<ResolvedMention> {
+        let content = self.read_file(path)?;
+        let lines: Vec<&str> = content.lines().collect();
+        if start > lines.len() {
+            return Err(AppError::Other(format!(
+                "行号 {} 超出文件范围（共 {} 行）",
+                start,
+                lines.len()
+            )));
+        }
+        let end = end.min(lines.len());
+
+        Ok(ResolvedMention {
+            label: format!("{}:{}-{}", path.display(), start, end),
+            language: language_of(path),
+            content: lines[start - 1..end].join("\n"),
+        })
+    }
+
+    /// 展开目录或通配符
+    fn resolve_glob(&self, pattern: &str) -> AppResult<Vec<ResolvedMention>> {
+        let matcher = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| AppError::Other(format!("无效的通配符: {}", e)))?
+            .compile_matcher();
+
+        // 从第一个通配符之前的目录开始遍历，避免扫描整个项目
+        let base = pattern
+            .split('/')
+            .take_while(|part| !part.contains(['*', '?', '[']))
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let mut resolved = Vec::new();
+        let walker = WalkDir::new(self.root.join(&base))
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| {
+                !SKIPPED_DIRS
+                    .iter()
+                    .any(|dir| e.file_name().to_str() == Some(dir))
+            });
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Ok(relative) = entry.path().strip_prefix(&self.root) else {
+                continue;
+            };
+            if !matcher.is_match(relative) {
+                continue;
+            }
+            // 跳过过大或非UTF-8的文件
+            if let Ok(item) = self.resolve_file(relative) {
+                resolved.push(item);
+            }
+            if resolved.len() >= MAX_EXPANDED_FILES {
+                log::warn!(
+                    "@{} 匹配的文件过多，只附加前 {} 个",
+                    pattern,
+                    MAX_EXPANDED_FILES
+                );
+                break;
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// 通过索引器或解析器查找符号定义
+    fn resolve_symbol(&self, name: &str) -> AppResult<Vec<ResolvedMention>> {
+        // 支持 Type::method 或 module.func 形式，按最后一段名称查找
+        let short_name = name.rsplit([':', '.']).next().unwrap_or(name);
+
+        let mut elements = self
+            .indexer
+            .as_ref()
+            .map(|indexer| indexer.find_symbol(short_name))
+            .unwrap_or_default();
+        if elements.is_empty() {
+            elements = self.scan_symbol(short_name);
+        }
+
+        let mut resolved = Vec::new();
+        for element in elements.iter().take(MAX_EXPANDED_FILES) {
+            let path = PathBuf::from(&element.definition.file_path);
+            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+            let content = match self.read_file(relative) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let lines: Vec<&str> = content.lines().collect();
+            let start = (element.definition.line as usize).max(1);
+            if start > lines.len() {
+                continue;
+            }
+            let end = symbol_end_line(element, &lines, start);
+
+            resolved.push(ResolvedMention {
+                label: format!("{} ({}:{}-{})", name, relative.display(), start, end),
+                language: element.language.clone(),
+                content: lines[start - 1..end].join("\n"),
+            });
+        }
+
+        Ok(resolved)
+    }
+
+    /// 索引中没有找到符号时，直接解析项目中的源文件查找定义
+    fn scan_symbol(&self, name: &str) -> Vec<CodeElement> {
+        let registry = PARSER_REGISTRY.read().unwrap();
+        let mut found = Vec::new();
+
+        let walker = WalkDir::new(&self.root).into_iter().filter_entry(|e| {
+            !SKIPPED_DIRS
+                .iter()
+                .any(|dir| e.file_name().to_str() == Some(dir))
+        });
+        for entry in walker.filter_map(|e| e.ok()) {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let path_str = entry.path().to_string_lossy().to_string();
+            let Some(parser) = registry.get_parser_by_filename(&path_str) else {
+                continue;
+            };
+            let Ok(content) = fs::read_to_string(entry.path()) else {
+                continue;
+            };
+            if !content.contains(name) {
+                continue;
+            }
+            if let Ok(elements) = parser.parse_file(&path_str, &content) {
+                for mut element in elements.into_iter().filter(|e| e.name == name) {
+                    element.definition.file_path = path_str.clone();
+                    found.push(element);
+                }
+            }
+        }
+
+        found
+    }
+
+    /// 读取相对于根目录的文件
+    fn read_file(&self, path: &Path) -> AppResult<String> {
+        let full_path = self.root.join(path);
+        let size = fs::metadata(&full_path)?.len();
+        if size > MAX_FILE_SIZE {
+            return Err(AppError::Other(format!(
+                "文件过大（{} 字节，上限 {} 字节）",
+                size, MAX_FILE_SIZE
+            )));
+        }
+        Ok(fs::read_to_string(full_path)?)
+    }
+}
+
+/// 解析 `10-40` 或 `10` 形式的行范围
+fn parse_line_range(range: &str) -> Option<(usize, usize)> {
+    let (start, end) = match range.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let line = range.parse().ok()?;
+            (line, line)
+        }
+    };
+    if start == 0 || end < start {
+        return None;
+    }
+    Some((start, end))
+}
+
+/// 根据文件扩展名获取语言名称
+fn language_of(path: &Path) -> String {
+    let language: &str = CodeProgrammer::get_language_from_path(path).into();
+    language.to_string()
+}
+
+/// 获取符号定义的结束行
+///
+/// 旧缓存中没有结束行，按 `SourceLocation::length` 的 "行数差 * 100 + 列数差" 编码还原行数差
+fn symbol_end_line(element: &CodeElement, lines: &[&str], start: usize) -> usize {
+    let end = match element.definition.end_line as usize {
+        0 => start + (element.definition.length / 100) as usize,
+        end => end.max(start),
+    };
+    end.min(start + MAX_SYMBOL_LINES - 1).min(lines.len())
+}
@@ -0,0 +1,401 @@
+//! 增量解析缓存
+//!
+//! 为代码浏览器和文件监听保存每个文件的语法树，应用文本编辑后由 tree-sitter 增量重新解析，
+//! 并报告语法树中发生变化的范围和受影响的代码元素，使索引器只需更新这些符号
+
+use crate::error::{AppError, AppResult};
+use crate::parsers::{CodeElement, LanguageParser, PARSER_REGISTRY};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tree_sitter::{InputEdit, Parser as TsParser, Point, Tree};
+
+/// 默认最多缓存的文件数量
+const DEFAULT_CAPACITY: usize = 256;
+
+/// 文本编辑：将 `start_byte..old_end_byte` 替换为 `new_text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// 起始字节偏移
+    pub start_byte: usize,
+    /// 被替换文本的结束字节偏移（不包含）
+    pub old_end_byte: usize,
+    /// 替换后的文本
+    pub new_text: String,
+}
+
+impl TextEdit {
+    /// 将指定范围替换为新文本
+    pub fn replace(start_byte: usize, end_byte: usize, text: &str) -> Self {
+        Self {
+            start_byte,
+            old_end_byte: end_byte,
+            new_text: text.to_string(),
+        }
+    }
+}
+
+/// 编辑后语法树发生变化的范围，以新内容为准
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChangedRange {
+    /// 起始字节偏移
+    pub start_byte: usize,
+    /// 结束字节偏移（不包含）
+    pub end_byte: usize,
+    /// 起始行号
+    pub start_line: u32,
+    /// 结束行号（包含）
+    pub end_line: u32,
+}
+
+/// 两次解析之间代码元素的差异
+#[derive(Debug, Clone, Default)]
+pub struct ElementChanges {
+    /// 新增或发生变化的元素（新版本）
+    pub changed: Vec<CodeElement>,
+    /// 被删除或发生变化的元素（旧版本）
+    pub removed: Vec<CodeElement>,
+}
+
+/// 一次增量解析的结果
+#[derive(Debug, Clone, Default)]
+pub struct ParseUpdate {
+    /// 发生变化的范围，按位置排序且互不重叠
+    pub changed_ranges: Vec<ChangedRange>,
+    /// 代码元素的差异
+    pub elements: ElementChanges,
+}
+
+/// 缓存的文件解析结果
+struct CachedFile {
+    /// 文件对应的解析器
+    parser: Arc<dyn LanguageParser + Send + Sync>,
+    /// 当前内容的语法树
+    tree: Tree,
+    /// 当前内容
+    content: String,
+    /// 当前内容的代码元素
+    elements: Vec<CodeElement>,
+    /// 最近一次解析的序号，用于淘汰最久未使用的文件
+    last_used: u64,
+}
+
+/// 按文件保存语法树的增量解析缓存，超出容量时淘汰最久未解析的文件
+pub struct ParseCache {
+    /// 从文件路径到解析结果的映射
+    files: HashMap<String, CachedFile>,
+    /// 最多缓存的文件数量
+    capacity: usize,
+    /// 解析次数，每次解析后递增
+    clock: u64,
+}
+
+impl Default for ParseCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParseCache {
+    /// 创建使用默认容量的空解析缓存
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// 创建最多缓存 `capacity` 个文件的空解析缓存
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            files: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    /// 获取下一个解析序号
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    /// 缓存超出容量时移除最久未解析的文件
+    fn evict(&mut self) {
+        while self.files.len() > self.capacity {
+            let oldest = self
+                .files
+                .iter()
+                .min_by_key(|(_, file)| file.last_used)
+                .map(|(path, _)| path.clone());
+            match oldest {
+                Some(path) => self.files.remove(&path),
+                None => break,
+            };
+        }
+    }
+
+    /// 完整解析文件并加入缓存，返回代码元素；没有对应解析器的文件返回 `None`
+    pub fn open(&mut self, path: &str, content: &str) -> AppResult<Option<&[CodeElement]>> {
+        let Some(parser) = PARSER_REGISTRY.read().unwrap().get_parser_by_filename(path) else {
+            self.files.remove(path);
+            return Ok(None);
+        };
+        let tree = parse(parser.as_ref(), content, None)?;
+        let elements = parser.build_code_elements(tree.root_node(), content, path);
+        let file = CachedFile {
+            parser,
+            tree,
+            content: content.to_string(),
+            elements,
+            last_used: self.tick(),
+        };
+        self.files.insert(path.to_string(), file);
+        self.evict();
+        Ok(self.elements(path))
+    }
+
+    /// 依次应用文本编辑并增量重新解析，每个编辑的位置以应用前一个编辑后的内容为准
+    ///
+    /// 任一编辑的范围无效时返回错误，缓存保持不变
+    pub fn edit(&mut self, path: &str, edits: &[TextEdit]) -> AppResult<ParseUpdate> {
+        let last_used = self.tick();
+        let file = self
+            .files
+            .get_mut(path)
+            .ok_or_else(|| AppError::knowledge(&format!("文件未打开: {}", path)))?;
+
+        let mut content = file.content.clone();
+        let mut tree = file.tree.clone();
+        let mut edited: Vec<(usize, usize)> = Vec::new();
+        for edit in edits {
+            let (start, old_end) = (edit.start_byte, edit.old_end_byte);
+            if start > old_end
+                || old_end > content.len()
+                || !content.is_char_boundary(start)
+                || !content.is_char_boundary(old_end)
+            {
+                return Err(AppError::knowledge(&format!(
+                    "无效的编辑范围 {}..{}，文件 {} 共 {} 字节",
+                    start,
+                    old_end,
+                    path,
+                    content.len()
+                )));
+            }
+
+            let start_position = point_at(&content, start);
+            let old_end_position = point_at(&content, old_end);
+            content.replace_range(start..old_end, &edit.new_text);
+            let new_end = start + edit.new_text.len();
+            tree.edit(&InputEdit {
+                start_byte: start,
+                old_end_byte: old_end,
+                new_end_byte: new_end,
+                start_position,
+                old_end_position,
+                new_end_position: point_at(&content, new_end),
+            });
+            shift_ranges(&mut edited, start, old_end, new_end);
+        }
+
+        let new_tree = parse(file.parser.as_ref(), &content, Some(&tree))?;
+        // 语法树报告结构变化的范围，编辑过的文本即使结构不变也计入
+        edited.extend(
+            tree.changed_ranges(&new_tree)
+                .map(|range| (range.start_byte, range.end_byte)),
+        );
+        let changed_ranges = merge_ranges(edited)
+            .into_iter()
+            .map(|(start, end)| changed_range(&content, start, end))
+            .collect();
+
+        let elements = file
+            .parser
+            .build_code_elements(new_tree.root_node(), &content, path);
+        let changes = diff_elements(&file.elements, &elements);
+        file.tree = new_tree;
+        file.content = content;
+        file.elements = elements;
+        file.last_used = last_used;
+        Ok(ParseUpdate {
+            changed_ranges,
+            elements: changes,
+        })
+    }
+
+    /// 用新内容更新文件，按与旧内容的差异增量解析；未缓存的文件完整解析，所有元素都视为变化
+    ///
+    /// 没有对应解析器的文件返回 `None`
+    pub fn update(&mut self, path: &str, content: &str) -> AppResult<Option<ParseUpdate>> {
+        let Some(file) = self.files.get(path) else {
+            let Some(elements) = self.open(path, content)? else {
+                return Ok(None);
+            };
+            let elements = ElementChanges {
+                changed: elements.to_vec(),
+                removed: Vec::new(),
+            };
+            let changed_ranges = if content.is_empty() {
+                Vec::new()
+            } else {
+                vec![changed_range(content, 0, content.len())]
+            };
+            return Ok(Some(ParseUpdate {
+                changed_ranges,
+                elements,
+            }));
+        };
+
+        if file.content == content {
+            let last_used = self.tick();
+            if let Some(file) = self.files.get_mut(path) {
+                file.last_used = last_used;
+            }
+            return Ok(Some(ParseUpdate::default()));
+        }
+        let edit = single_edit(&file.content, content);
+        self.edit(path, &[edit]).map(Some)
+    }
+
+    /// 获取文件的当前代码元素
+    pub fn elements(&self, path: &str) -> Option<&[CodeElement]> {
+        self.files.get(path).map(|file| file.elements.as_slice())
+    }
+
+    /// 从缓存中移除文件，返回文件是否在缓存中
+    pub fn remove(&mut self, path: &str) -> bool {
+        self.files.remove(path).is_some()
+    }
+}
+
+/// 比较两次解析的代码元素：位置、签名或文档等任一属性不同的元素都视为变化
+pub fn diff_elements(old: &[CodeElement], new: &[CodeElement]) -> ElementChanges {
+    ElementChanges {
+        changed: unmatched(new, old),
+        removed: unmatched(old, new),
+    }
+}
+
+/// 获取 `elements` 中在 `others` 里没有相同元素的元素，重复的元素按次数匹配
+fn unmatched(elements: &[CodeElement], others: &[CodeElement]) -> Vec<CodeElement> {
+    let mut remaining: HashMap<String, usize> = HashMap::new();
+    for element in others {
+        *remaining.entry(element_json(element)).or_default() += 1;
+    }
+    elements
+        .iter()
+        .filter(|element| match remaining.get_mut(&element_json(element)) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+/// 元素的序列化形式，用于比较（不含父子元素）
+fn element_json(element: &CodeElement) -> String {
+    serde_json::to_string(element).unwrap_or_default()
+}
+
+/// 使用文件的解析器解析内容，提供旧语法树时增量解析
+fn parse(
+    parser: &(dyn LanguageParser + Send + Sync),
+    content: &str,
+    old_tree: Option<&Tree>,
+) -> AppResult<Tree> {
+    let mut ts_parser = TsParser::new();
+    ts_parser
+        .set_language(parser.tree_sitter_language())
+        .map_err(|e| AppError::knowledge(&format!("无法加载语法: {}", e)))?;
+    ts_parser
+        .parse(content, old_tree)
+        .ok_or_else(|| AppError::knowledge("解析源代码失败"))
+}
+
+/// 计算字节偏移对应的行列位置（从0开始，列按字节计）
+fn point_at(content: &str, byte: usize) -> Point {
+    let before = &content.as_bytes()[..byte];
+    match before.iter().rposition(|&b| b == b'\n') {
+        Some(newline) => Point::new(
+            before.iter().filter(|&&b| b == b'\n').count(),
+            byte - newline - 1,
+        ),
+        None => Point::new(0, byte),
+    }
+}
+
+/// 将字节范围转换为变化范围，结束于行首的范围不包含该行
+fn changed_range(content: &str, start: usize, end: usize) -> ChangedRange {
+    let start_point = point_at(content, start);
+    let end_point = point_at(content, end);
+    let end_row = if end_point.column == 0 && end_point.row > start_point.row {
+        end_point.row - 1
+    } else {
+        end_point.row
+    };
+    ChangedRange {
+        start_byte: start,
+        end_byte: end,
+        start_line: start_point.row as u32 + 1,
+        end_line: end_row as u32 + 1,
+    }
+}
+
+/// 将已记录的编辑范围移动到新编辑之后的位置，与新编辑重叠的范围合并到新编辑中
+fn shift_ranges(ranges: &mut Vec<(usize, usize)>, start: usize, old_end: usize, new_end: usize) {
+    let (mut merged_start, mut merged_end) = (start, new_end);
+    ranges.retain_mut(|range| {
+        if range.1 < start {
+            true
+        } else if range.0 > old_end {
+            range.0 = range.0 - old_end + new_end;
+            range.1 = range.1 - old_end + new_end;
+            true
+        } else {
+            merged_start = merged_start.min(range.0);
+            merged_end = merged_end.max((range.1 + new_end).saturating_sub(old_end));
+            false
+        }
+    });
+    ranges.push((merged_start, merged_end));
+}
+
+/// 排序并合并重叠或相邻的范围
+fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// 根据新旧内容的公共前缀和后缀得到一个等价的编辑，边界对齐到字符
+fn single_edit(old: &str, new: &str) -> TextEdit {
+    let (old_bytes, new_bytes) = (old.as_bytes(), new.as_bytes());
+    let mut prefix = old_bytes
+        .iter()
+        .zip(new_bytes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+        prefix -= 1;
+    }
+
+    let max_suffix = old.len().min(new.len()) - prefix;
+    let mut suffix = old_bytes
+        .iter()
+        .rev()
+        .zip(new_bytes.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+
+    TextEdit::replace(prefix, old.len() - suffix, &new[prefix..new.len() - suffix])
+}
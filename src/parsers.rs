@@ -3,20 +3,23 @@
 //! 定义LanguageParser trait和解析器注册表，支持多种编程语言的解析
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::RwLock;
 
 use serde::{Deserialize, Serialize};
 use tree_sitter::Parser as TsParser;
-use tree_sitter::{Language as TsLanguage, Node as TsNode};
+use tree_sitter::{Language as TsLanguage, Node as TsNode, Query, QueryCursor};
 
-use crate::error::AppResult;
+use crate::error::{AppError, AppResult};
 
 /// 代码元素类型
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
 pub enum CodeElementType {
     /// 函数
     Function,
+    /// 方法（定义在类、结构体、接口或实现块中的函数）
+    Method,
     /// 类
     Class,
     /// 结构体
@@ -39,12 +42,104 @@ pub enum CodeElementType {
     Implementation,
     /// 宏
     Macro,
+    /// 文档章节或配置文件中的表，如 Markdown 标题和 TOML 的 `[profile.release]`
+    Section,
+    /// 文档中的代码块
+    CodeBlock,
+    /// 配置文件中的键
+    Key,
     /// 其他
+    #[default]
     Other,
 }
 
+impl CodeElementType {
+    /// 获取元素类型的名称，用于索引存储和命令行过滤
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CodeElementType::Function => "function",
+            CodeElementType::Method => "method",
+            CodeElementType::Class => "class",
+            CodeElementType::Struct => "struct",
+            CodeElementType::Enum => "enum",
+            CodeElementType::Interface => "interface",
+            CodeElementType::Module => "module",
+            CodeElementType::Variable => "variable",
+            CodeElementType::Constant => "constant",
+            CodeElementType::TypeAlias => "type_alias",
+            CodeElementType::Trait => "trait",
+            CodeElementType::Implementation => "impl",
+            CodeElementType::Macro => "macro",
+            CodeElementType::Section => "section",
+            CodeElementType::CodeBlock => "code_block",
+            CodeElementType::Key => "key",
+            CodeElementType::Other => "other",
+        }
+    }
+
+    /// 按类型过滤时匹配的元素类型：函数同时匹配定义在类型中的方法
+    pub fn filter_kinds(&self) -> &[CodeElementType] {
+        match self {
+            CodeElementType::Function => &[CodeElementType::Function, CodeElementType::Method],
+            kind => std::slice::from_ref(kind),
+        }
+    }
+}
+
+impl std::str::FromStr for CodeElementType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "function" | "fn" => Ok(CodeElementType::Function),
+            "method" => Ok(CodeElementType::Method),
+            "class" => Ok(CodeElementType::Class),
+            "struct" => Ok(CodeElementType::Struct),
+            "enum" => Ok(CodeElementType::Enum),
+            "interface" => Ok(CodeElementType::Interface),
+            "module" | "mod" => Ok(CodeElementType::Module),
+            "variable" | "var" => Ok(CodeElementType::Variable),
+            "constant" | "const" => Ok(CodeElementType::Constant),
+            "type_alias" | "type" => Ok(CodeElementType::TypeAlias),
+            "trait" => Ok(CodeElementType::Trait),
+            "impl" | "implementation" => Ok(CodeElementType::Implementation),
+            "macro" => Ok(CodeElementType::Macro),
+            "section" | "heading" | "table" => Ok(CodeElementType::Section),
+            "code_block" | "codeblock" => Ok(CodeElementType::CodeBlock),
+            "key" => Ok(CodeElementType::Key),
+            "other" => Ok(CodeElementType::Other),
+            _ => Err(format!("未知的代码元素类型: {}", s)),
+        }
+    }
+}
+
+/// 代码元素的可见性
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    /// 公开或导出
+    Public,
+    /// 仅子类可见
+    Protected,
+    /// 仅在包、crate 或模块内可见
+    Internal,
+    /// 私有
+    Private,
+}
+
+impl Visibility {
+    /// 获取可见性的名称
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Visibility::Public => "public",
+            Visibility::Protected => "protected",
+            Visibility::Internal => "internal",
+            Visibility::Private => "private",
+        }
+    }
+}
+
 /// 代码元素
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CodeElement {
     /// 元素类型
     pub element_type: CodeElementType,
@@ -54,7 +149,22 @@ pub struct CodeElement {
     pub definition: SourceLocation,
     /// 文档注释
     pub documentation: Option<String>,
-    /// 父元素（序列化时忽略，避免循环引用）
+    /// 声明签名，不含定义体，如 `pub fn add(a: i32, b: i32) -> i32`
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// 可见性，语言没有对应概念时为空
+    #[serde(default)]
+    pub visibility: Option<Visibility>,
+    /// 泛型或模板参数，如 `<T: Clone>`
+    #[serde(default)]
+    pub generics: Option<String>,
+    /// 属性、装饰器或注解，如 `#[derive(Debug)]`、`@property`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// 所在的类型或模块路径，如方法所属的 `geometry::Shape`
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// 父元素，不包含其子元素（序列化时忽略，避免循环引用）
     #[serde(skip)]
     pub parent: Option<Arc<CodeElement>>,
     /// 子元素（序列化时忽略，避免循环引用）
@@ -64,8 +174,18 @@ pub struct CodeElement {
     pub language: String,
 }
 
+impl CodeElement {
+    /// 包含所在作用域的完整名称，如 `Shape::area`
+    pub fn qualified_name(&self) -> String {
+        match &self.scope {
+            Some(scope) => format!("{}{}{}", scope, scope_separator(&self.language), self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
 /// 源位置
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SourceLocation {
     /// 文件路径
     pub file_path: String,
@@ -73,8 +193,70 @@ pub struct SourceLocation {
     pub line: u32,
     /// 列号
     pub column: u32,
-    /// 长度
+    /// 长度，按 "行数差 * 100 + 列数差" 估算，新代码应使用结束位置
     pub length: u32,
+    /// 结束行号（包含），旧缓存中为0
+    #[serde(default)]
+    pub end_line: u32,
+    /// 结束列号
+    #[serde(default)]
+    pub end_column: u32,
+    /// 起始字节偏移
+    #[serde(default)]
+    pub start_byte: usize,
+    /// 结束字节偏移（不包含）
+    #[serde(default)]
+    pub end_byte: usize,
+}
+
+/// 导入语句
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Import {
+    /// 导入的模块路径，保持源代码中的写法，如 `crate::knowledge::xref`、`..pkg.mod`、`./utils`；
+    /// C/C++ 的系统头文件保留尖括号，如 `<stdio.h>`
+    pub module: String,
+    /// 从模块中导入的名称，如 `from os import path` 中的 `path`，整体导入时为空
+    pub names: Vec<String>,
+    /// 行号
+    pub line: u32,
+}
+
+/// 语法诊断的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyntaxDiagnosticKind {
+    /// 无法解析的代码（tree-sitter 的 ERROR 节点）
+    Error,
+    /// 缺少的记号，如语句末尾的 `;`（tree-sitter 的 MISSING 节点）
+    Missing,
+}
+
+/// 语法诊断
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntaxDiagnostic {
+    /// 诊断类型
+    pub kind: SyntaxDiagnosticKind,
+    /// 诊断信息，如 "缺少 `;`"
+    pub message: String,
+    /// 行号
+    pub line: u32,
+    /// 列号
+    pub column: u32,
+    /// 结束行号（包含）
+    pub end_line: u32,
+    /// 结束列号
+    pub end_column: u32,
+    /// 出错位置所在的源代码行
+    pub context: String,
+}
+
+impl std::fmt::Display for SyntaxDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)?;
+        if !self.context.is_empty() {
+            write!(f, "\n    {}", self.context)?;
+        }
+        Ok(())
+    }
 }
 
 /// 语言解析器 trait，定义解析器的基本接口
@@ -101,6 +283,23 @@ pub trait LanguageParser {
         content: &str,
         file_path: &str,
     ) -> Vec<CodeElement>;
+
+    /// 从tree-sitter节点提取导入语句，Rust 的 `mod name;` 按 `self::name` 导入处理
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import>;
+
+    /// 解析代码中的导入语句
+    fn parse_imports(&self, content: &str) -> AppResult<Vec<Import>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_imports(tree.root_node(), content))
+    }
+
+    /// 检查代码的语法，返回所有错误和缺失记号的诊断，没有语法错误时为空
+    fn check_syntax(&self, content: &str) -> AppResult<Vec<SyntaxDiagnostic>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        let mut diagnostics = Vec::new();
+        collect_diagnostics(tree.root_node(), content, &mut diagnostics);
+        Ok(diagnostics)
+    }
 }
 
 /// 解析器注册表，管理所有可用的解析器
@@ -197,30 +396,769 @@ pub fn initialize_parsers() -> AppResult<()> {
     let ts_parser = TypeScriptParser::new();
     registry.register(Arc::new(ts_parser));
 
+    // 注册Go、Java、C和C++解析器
+    registry.register(Arc::new(GoParser::new()));
+    registry.register(Arc::new(JavaParser::new()));
+    registry.register(Arc::new(CParser::new()));
+    registry.register(Arc::new(CppParser::new()));
+
+    // 注册文档和配置文件解析器
+    registry.register(Arc::new(MarkdownParser::new()));
+    registry.register(Arc::new(TomlParser::new()));
+    registry.register(Arc::new(YamlParser::new()));
+    registry.register(Arc::new(JsonParser::new()));
+
     Ok(())
 }
 
-/// Rust解析器实现
-pub struct RustParser {
-    /// tree-sitter解析器实例
-    parser: TsParser,
+/// 检查文件内容的语法，没有对应解析器的文件返回 `None`
+pub fn check_file_syntax(path: &str, content: &str) -> AppResult<Option<Vec<SyntaxDiagnostic>>> {
+    let parser = PARSER_REGISTRY.read().unwrap().get_parser_by_filename(path);
+    match parser {
+        Some(parser) => parser.check_syntax(content).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// 签名的最大字符数，超出部分截断
+const MAX_SIGNATURE_CHARS: usize = 200;
+
+/// 诊断信息中引用的代码片段的最大字符数
+const MAX_DIAGNOSTIC_SNIPPET_CHARS: usize = 40;
+
+/// 获取语言中作用域与名称之间的分隔符
+pub fn scope_separator(language: &str) -> &'static str {
+    match language {
+        "rust" | "cpp" => "::",
+        "markdown" => " > ",
+        _ => ".",
+    }
+}
+
+/// 是否为文档或配置文件语言，这些语言只提取章节和键，没有导入语句
+pub fn is_document_language(language: &str) -> bool {
+    matches!(language, "markdown" | "toml" | "yaml" | "json")
+}
+
+/// 元素查询的文件名，位于查询目录下的语言子目录中，如 `~/.codex/queries/rust/elements.scm`
+pub const ELEMENT_QUERY_FILE: &str = "elements.scm";
+
+/// 用户主目录和项目目录下存放查询文件的目录
+pub const QUERY_DIR: &str = ".codex/queries";
+
+/// 查询文件以此行开头时追加到优先级更低的查询之后，否则替换之前的查询
+const EXTENDS_MODELINE: &str = "; extends";
+
+/// 查询文件的搜索目录，未设置时使用默认目录
+static QUERY_DIRS: once_cell::sync::Lazy<RwLock<Option<Vec<PathBuf>>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(None));
+
+/// 已编译的元素查询，按语言缓存
+static ELEMENT_QUERIES: once_cell::sync::Lazy<RwLock<HashMap<String, Arc<Query>>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 内置的元素查询
+///
+/// 查询中的 `@definition.<类型>` 捕获定义节点，类型为 `CodeElementType` 的名称，
+/// 如 `function`、`struct`、`section`；`@name` 捕获名称节点，没有名称节点的模式
+/// 可以用 `(#set! name "...")` 指定名称。同一节点被多个模式匹配时使用靠前的模式
+pub fn bundled_element_query(language: &str) -> Option<&'static str> {
+    let query = match language {
+        "rust" => include_str!("../queries/rust/elements.scm"),
+        "python" => include_str!("../queries/python/elements.scm"),
+        "javascript" => include_str!("../queries/javascript/elements.scm"),
+        "typescript" => concat!(
+            include_str!("../queries/javascript/elements.scm"),
+            include_str!("../queries/typescript/elements.scm")
+        ),
+        "go" => include_str!("../queries/go/elements.scm"),
+        "java" => include_str!("../queries/java/elements.scm"),
+        "c" => include_str!("../queries/c/elements.scm"),
+        "cpp" => concat!(
+            include_str!("../queries/c/elements.scm"),
+            include_str!("../queries/cpp/elements.scm")
+        ),
+        "markdown" => include_str!("../queries/markdown/elements.scm"),
+        "toml" => include_str!("../queries/toml/elements.scm"),
+        "yaml" => include_str!("../queries/yaml/elements.scm"),
+        "json" => include_str!("../queries/json/elements.scm"),
+        _ => return None,
+    };
+    Some(query)
+}
+
+/// 默认的查询目录，按优先级从低到高排列：用户目录下的 `~/.codex/queries` 和
+/// 当前项目的 `.codex/queries`
+pub fn default_query_dirs() -> Vec<PathBuf> {
+    let mut query_dirs = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        query_dirs.push(home.join(QUERY_DIR));
+    }
+    query_dirs.push(PathBuf::from(QUERY_DIR));
+    query_dirs
+}
+
+/// 获取查询文件的搜索目录
+pub fn query_dirs() -> Vec<PathBuf> {
+    QUERY_DIRS
+        .read()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(default_query_dirs)
+}
+
+/// 设置查询文件的搜索目录，按优先级从低到高排列，并清除已编译的查询
+pub fn set_query_dirs(query_dirs: Vec<PathBuf>) {
+    *QUERY_DIRS.write().unwrap() = Some(query_dirs);
+    reload_queries();
+}
+
+/// 清除已编译的查询，下次解析时重新读取查询文件
+pub fn reload_queries() {
+    ELEMENT_QUERIES.write().unwrap().clear();
+}
+
+/// 组合语言的元素查询源码：内置查询依次被各查询目录中的查询文件扩展或替换
+pub fn element_query_source(language: &str) -> Option<String> {
+    let mut source = bundled_element_query(language)?.to_string();
+    for dir in query_dirs() {
+        let path = dir.join(language).join(ELEMENT_QUERY_FILE);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                log::warn!("读取查询文件 {} 失败: {}", path.display(), e);
+                continue;
+            }
+        };
+        if text.trim_start().starts_with(EXTENDS_MODELINE) {
+            source.push('\n');
+            source.push_str(&text);
+        } else {
+            source = text;
+        }
+    }
+    Some(source)
+}
+
+/// 编译元素查询，并检查定义捕获的类型是否有效
+fn compile_element_query(language: TsLanguage, source: &str) -> AppResult<Query> {
+    let query = Query::new(language, source)
+        .map_err(|e| AppError::knowledge(&format!("第 {} 行: {}", e.row + 1, e.message)))?;
+    for name in query.capture_names() {
+        if let Some(kind) = name.strip_prefix("definition.") {
+            kind.parse::<CodeElementType>()
+                .map_err(|e| AppError::knowledge(&format!("@{}: {}", name, e)))?;
+        }
+    }
+    Ok(query)
+}
+
+/// 获取语言的元素查询，查询文件无效时记录警告并使用内置查询
+fn element_query(language: &str, grammar: TsLanguage) -> AppResult<Arc<Query>> {
+    if let Some(query) = ELEMENT_QUERIES.read().unwrap().get(language) {
+        return Ok(query.clone());
+    }
+    let bundled = bundled_element_query(language)
+        .ok_or_else(|| AppError::knowledge(&format!("没有 {} 的元素查询", language)))?;
+    let source = element_query_source(language).unwrap_or_else(|| bundled.to_string());
+    let query = match compile_element_query(grammar, &source) {
+        Ok(query) => query,
+        Err(e) if source != bundled => {
+            log::warn!("{} 的元素查询无效，使用内置查询: {}", language, e);
+            compile_element_query(grammar, bundled)?
+        }
+        Err(e) => {
+            return Err(AppError::knowledge(&format!(
+                "{} 的内置元素查询无效: {}",
+                language, e
+            )))
+        }
+    };
+    let query = Arc::new(query);
+    ELEMENT_QUERIES
+        .write()
+        .unwrap()
+        .insert(language.to_string(), query.clone());
+    Ok(query)
+}
+
+/// 语言特定的代码元素提取规则
+struct ElementRules {
+    /// 语言名称
+    language: &'static str,
+    /// 从元素查询中 `@name` 捕获的节点获取元素名称
+    name: fn(TsNode, &str) -> Option<String>,
+    /// 判断定义的可见性，参数为节点、源代码和名称
+    visibility: fn(TsNode, &str, &str) -> Option<Visibility>,
+    /// 提取定义的属性、装饰器或注解
+    attributes: fn(TsNode, &str) -> Vec<String>,
+    /// 定义在类型外部的方法所属的类型，如 Go 的接收者和 C++ 的 `Shape::area`
+    owner: fn(TsNode, &str) -> Option<String>,
+    /// 提取节点中的导入语句，不是导入节点时返回空
+    imports: fn(TsNode, &str) -> Vec<Import>,
+}
+
+/// 语言没有属性或装饰器时使用
+fn no_attributes(_node: TsNode, _content: &str) -> Vec<String> {
+    Vec::new()
+}
+
+/// 方法总是定义在类型内部时使用
+fn no_owner(_node: TsNode, _content: &str) -> Option<String> {
+    None
+}
+
+/// 文档和配置文件没有可见性概念时使用
+fn no_visibility(_node: TsNode, _content: &str, _name: &str) -> Option<Visibility> {
+    None
+}
+
+/// 文档和配置文件没有导入语句时使用
+fn no_imports(_node: TsNode, _content: &str) -> Vec<Import> {
+    Vec::new()
+}
+
+/// 以节点文本作为元素名称
+fn node_name(node: TsNode, content: &str) -> Option<String> {
+    Some(node_text(node, content).trim().to_string())
+}
+
+/// 以去掉引号的节点文本作为元素名称，用于配置文件中带引号的键
+fn unquoted_name(node: TsNode, content: &str) -> Option<String> {
+    Some(unquote(node_text(node, content).trim()))
+}
+
+/// 使用指定语法解析源代码
+fn parse_source(language: TsLanguage, content: &str) -> AppResult<tree_sitter::Tree> {
+    let mut parser = TsParser::new();
+    parser
+        .set_language(language)
+        .map_err(|e| AppError::knowledge(&format!("无法加载语法: {}", e)))?;
+    parser
+        .parse(content, None)
+        .ok_or_else(|| AppError::knowledge("解析源代码失败"))
+}
+
+/// 获取节点的源代码文本
+fn node_text(node: TsNode, content: &str) -> String {
+    node.utf8_text(content.as_bytes())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// 获取节点指定字段的源代码文本
+fn field_text(node: TsNode, field: &str, content: &str) -> Option<String> {
+    node.child_by_field_name(field)
+        .map(|child| node_text(child, content))
+}
+
+/// 获取定义体节点，赋值给变量的函数使用函数表达式的定义体
+fn definition_body(node: TsNode) -> Option<TsNode> {
+    if let Some(body) = node.child_by_field_name("body") {
+        return Some(body);
+    }
+    let mut cursor = node.walk();
+    let declarator = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "variable_declarator")?;
+    declarator
+        .child_by_field_name("value")?
+        .child_by_field_name("body")
+}
+
+/// 提取声明签名：定义体之前的文本，合并空白并去掉末尾的 `{`、`:` 和 `;`
+fn signature_text(node: TsNode, content: &str) -> Option<String> {
+    let text = match definition_body(node) {
+        Some(body) => content.get(node.start_byte()..body.start_byte())?,
+        None => {
+            let text = content.get(node.start_byte()..node.end_byte())?;
+            // 没有定义体的声明只取第一行，避免带上常量的值或字段列表
+            if node.kind().contains("signature") {
+                text
+            } else {
+                text.lines().next().unwrap_or_default()
+            }
+        }
+    };
+    let signature = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let signature = signature.trim_end_matches(['{', ':', ';']).trim_end();
+    if signature.is_empty() {
+        return None;
+    }
+    if signature.chars().count() > MAX_SIGNATURE_CHARS {
+        let truncated: String = signature.chars().take(MAX_SIGNATURE_CHARS).collect();
+        return Some(format!("{}...", truncated));
+    }
+    Some(signature.to_string())
+}
+
+/// 提取泛型参数，C++模板的参数在外层的模板声明中
+fn generics_text(node: TsNode, content: &str) -> Option<String> {
+    if let Some(text) = field_text(node, "type_parameters", content) {
+        return Some(text);
+    }
+    let template = node
+        .parent()
+        .filter(|parent| parent.kind() == "template_declaration")?;
+    field_text(template, "parameters", content)
+}
+
+/// 根据tree-sitter节点的位置创建代码元素
+fn node_element(
+    node: TsNode,
+    element_type: CodeElementType,
+    name: String,
+    file_path: &str,
+    language: String,
+) -> CodeElement {
+    let start_pos = node.start_position();
+    let end_pos = node.end_position();
+    // 使用安全的方式计算长度，避免溢出
+    let line_diff = (end_pos.row.saturating_sub(start_pos.row)) as u32;
+    let column_diff = (end_pos.column.saturating_sub(start_pos.column)) as u32;
+    let length = line_diff.saturating_mul(100).saturating_add(column_diff);
+    // 包含行尾换行的节点（如 Markdown 章节、YAML 映射）结束于下一行行首，不计入该行
+    let end_row = if end_pos.column == 0 && end_pos.row > start_pos.row {
+        end_pos.row - 1
+    } else {
+        end_pos.row
+    };
+    CodeElement {
+        element_type,
+        name,
+        definition: SourceLocation {
+            file_path: file_path.to_string(),
+            line: start_pos.row as u32 + 1,
+            column: start_pos.column as u32 + 1,
+            length,
+            end_line: end_row as u32 + 1,
+            end_column: end_pos.column as u32 + 1,
+            start_byte: node.start_byte(),
+            end_byte: node.end_byte(),
+        },
+        language,
+        ..Default::default()
+    }
+}
+
+/// 按元素查询提取代码元素，结果按定义的先后顺序排列，外层元素在内层元素之前
+fn collect_elements(
+    root_node: TsNode,
+    content: &str,
+    file_path: &str,
+    rules: &ElementRules,
+    query: &Query,
+) -> Vec<CodeElement> {
+    let name_index = query.capture_index_for_name("name");
+    // 同一节点被多个模式匹配时保留靠前的模式，如 Go 的结构体同时匹配通用的类型定义模式
+    let mut definitions: HashMap<usize, (usize, TsNode, CodeElementType, String)> = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    for query_match in cursor.matches(query, root_node, content.as_bytes()) {
+        let Some((node, element_type)) = query_match.captures.iter().find_map(|capture| {
+            let kind = query.capture_names()[capture.index as usize].strip_prefix("definition.")?;
+            Some((capture.node, kind.parse::<CodeElementType>().ok()?))
+        }) else {
+            continue;
+        };
+        let name = match query_match
+            .captures
+            .iter()
+            .find(|capture| Some(capture.index) == name_index)
+        {
+            Some(capture) => (rules.name)(capture.node, content),
+            // 没有名称捕获时使用 `#set! name` 设置的名称
+            None => query
+                .property_settings(query_match.pattern_index)
+                .iter()
+                .find(|property| &*property.key == "name")
+                .and_then(|property| property.value.as_deref())
+                .map(str::to_string),
+        };
+        let Some(name) = name.filter(|name| !name.is_empty()) else {
+            continue;
+        };
+        let pattern = query_match.pattern_index;
+        if definitions
+            .get(&node.id())
+            .is_some_and(|(existing, ..)| *existing <= pattern)
+        {
+            continue;
+        }
+        definitions.insert(node.id(), (pattern, node, element_type, name));
+    }
+
+    let mut definitions: Vec<_> = definitions.into_values().collect();
+    definitions.sort_by_key(|(pattern, node, ..)| {
+        (
+            node.start_byte(),
+            std::cmp::Reverse(node.end_byte()),
+            *pattern,
+        )
+    });
+    definitions
+        .into_iter()
+        .map(|(_, node, element_type, name)| {
+            let mut element = node_element(
+                node,
+                element_type,
+                name,
+                file_path,
+                rules.language.to_string(),
+            );
+            element.signature = signature_text(node, content);
+            element.visibility = (rules.visibility)(node, content, &element.name);
+            element.generics = generics_text(node, content);
+            element.attributes = (rules.attributes)(node, content);
+            // 先记录外部定义的所属类型，建立层级时再与外层作用域合并
+            element.scope = (rules.owner)(node, content);
+            element
+        })
+        .collect()
+}
+
+/// 可以包含其他元素的容器类型
+fn is_container(element_type: &CodeElementType) -> bool {
+    is_type_container(element_type)
+        || matches!(
+            element_type,
+            CodeElementType::Module | CodeElementType::Section
+        )
+}
+
+/// 其中的函数视为方法的类型容器
+fn is_type_container(element_type: &CodeElementType) -> bool {
+    matches!(
+        element_type,
+        CodeElementType::Class
+            | CodeElementType::Struct
+            | CodeElementType::Enum
+            | CodeElementType::Interface
+            | CodeElementType::Trait
+            | CodeElementType::Implementation
+    )
 }
 
+/// 按定义范围建立元素层级：确定每个元素的作用域，将类型中的函数标记为方法，
+/// 并填充父元素和子元素
+fn build_hierarchy(elements: &mut [CodeElement]) {
+    // 元素按先序排列，用栈记录当前所在的容器
+    let mut parents: Vec<Option<usize>> = Vec::with_capacity(elements.len());
+    let mut stack: Vec<usize> = Vec::new();
+    for index in 0..elements.len() {
+        let location = &elements[index].definition;
+        while let Some(&top) = stack.last() {
+            let container = &elements[top].definition;
+            if container.start_byte <= location.start_byte
+                && location.end_byte <= container.end_byte
+            {
+                break;
+            }
+            stack.pop();
+        }
+        parents.push(stack.last().copied());
+        if is_container(&elements[index].element_type) {
+            stack.push(index);
+        }
+    }
+
+    // 父元素总在子元素之前，其作用域已经确定
+    for index in 0..elements.len() {
+        let owner = elements[index].scope.take();
+        let parent_scope = parents[index].map(|parent| elements[parent].qualified_name());
+        let in_type =
+            parents[index].is_some_and(|parent| is_type_container(&elements[parent].element_type));
+        let element = &mut elements[index];
+        if element.element_type == CodeElementType::Function && (in_type || owner.is_some()) {
+            element.element_type = CodeElementType::Method;
+        }
+        element.scope = match (parent_scope, owner) {
+            (Some(scope), Some(owner)) => Some(format!(
+                "{}{}{}",
+                scope,
+                scope_separator(&element.language),
+                owner
+            )),
+            (scope, owner) => scope.or(owner),
+        };
+    }
+
+    for index in 0..elements.len() {
+        if let Some(parent) = parents[index] {
+            let mut parent = elements[parent].clone();
+            parent.children.clear();
+            elements[index].parent = Some(Arc::new(parent));
+        }
+    }
+    // 从后向前填充子元素，保证子元素自身的子元素已经完整
+    for index in (0..elements.len()).rev() {
+        if let Some(parent) = parents[index] {
+            let child = Arc::new(elements[index].clone());
+            elements[parent].children.push(child);
+        }
+    }
+    for element in elements.iter_mut() {
+        element.children.reverse();
+    }
+}
+
+/// 遍历语法树收集语法诊断，只进入包含错误的子树，ERROR 节点内部不再重复报告
+fn collect_diagnostics(node: TsNode, content: &str, diagnostics: &mut Vec<SyntaxDiagnostic>) {
+    let kind = if node.is_error() {
+        SyntaxDiagnosticKind::Error
+    } else if node.is_missing() {
+        SyntaxDiagnosticKind::Missing
+    } else {
+        if node.has_error() {
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                collect_diagnostics(child, content, diagnostics);
+            }
+        }
+        return;
+    };
+
+    let message = match kind {
+        SyntaxDiagnosticKind::Missing => format!("缺少 `{}`", node.kind()),
+        SyntaxDiagnosticKind::Error => {
+            let text = node_text(node, content);
+            let snippet = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if snippet.is_empty() {
+                "语法错误".to_string()
+            } else if snippet.chars().count() > MAX_DIAGNOSTIC_SNIPPET_CHARS {
+                let truncated: String =
+                    snippet.chars().take(MAX_DIAGNOSTIC_SNIPPET_CHARS).collect();
+                format!("无法解析 `{}...`", truncated)
+            } else {
+                format!("无法解析 `{}`", snippet)
+            }
+        }
+    };
+    let start = node.start_position();
+    let end = node.end_position();
+    let context = content
+        .lines()
+        .nth(start.row)
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    diagnostics.push(SyntaxDiagnostic {
+        kind,
+        message,
+        line: start.row as u32 + 1,
+        column: start.column as u32 + 1,
+        end_line: end.row as u32 + 1,
+        end_column: end.column as u32 + 1,
+        context,
+    });
+}
+
+/// 按语言规则提取代码元素并建立层级
+fn extract_elements(
+    root_node: TsNode,
+    content: &str,
+    file_path: &str,
+    rules: &ElementRules,
+) -> Vec<CodeElement> {
+    let query = match element_query(rules.language, root_node.language()) {
+        Ok(query) => query,
+        Err(e) => {
+            log::error!("{}", e);
+            return Vec::new();
+        }
+    };
+    let mut elements = collect_elements(root_node, content, file_path, rules, &query);
+    build_hierarchy(&mut elements);
+    elements
+}
+
+/// 遍历语法树，按语言规则提取导入语句
+fn collect_imports(
+    root_node: TsNode,
+    content: &str,
+    rules: &ElementRules,
+    imports: &mut Vec<Import>,
+) {
+    let mut cursor = root_node.walk();
+    for node in root_node.children(&mut cursor) {
+        let found = (rules.imports)(node, content);
+        if found.is_empty() {
+            collect_imports(node, content, rules, imports);
+        } else {
+            imports.extend(found);
+        }
+    }
+}
+
+/// 按语言规则提取导入语句
+fn extract_imports(root_node: TsNode, content: &str, rules: &ElementRules) -> Vec<Import> {
+    let mut imports = Vec::new();
+    collect_imports(root_node, content, rules, &mut imports);
+    imports
+}
+
+/// 创建位于节点所在行的导入
+fn node_import(node: TsNode, module: String, names: Vec<String>) -> Import {
+    Import {
+        module,
+        names,
+        line: node.start_position().row as u32 + 1,
+    }
+}
+
+/// 去掉字符串字面量两端的引号
+fn unquote(text: &str) -> String {
+    text.trim_matches(|c| matches!(c, '"' | '\'' | '`'))
+        .to_string()
+}
+
+/// 获取节点之前紧邻的属性类兄弟节点的文本，跳过注释
+fn preceding_attributes(node: TsNode, content: &str, kinds: &[&str]) -> Vec<String> {
+    let mut attributes = Vec::new();
+    let mut sibling = node.prev_sibling();
+    while let Some(current) = sibling {
+        if kinds.contains(&current.kind()) {
+            attributes.push(node_text(current, content));
+        } else if !current.kind().contains("comment") {
+            break;
+        }
+        sibling = current.prev_sibling();
+    }
+    attributes.reverse();
+    attributes
+}
+
+/// 获取指定类型子节点的文本
+fn child_texts(node: TsNode, content: &str, kinds: &[&str]) -> Vec<String> {
+    let mut cursor = node.walk();
+    let texts = node
+        .named_children(&mut cursor)
+        .filter(|child| kinds.contains(&child.kind()))
+        .map(|child| node_text(child, content))
+        .collect();
+    texts
+}
+
+/// Rust的可见性：`pub` 为公开，`pub(crate)` 等受限可见性为内部，
+/// trait 中的定义和 trait 实现中的方法与 trait 一致，按公开处理
+fn rust_visibility(node: TsNode, content: &str, _name: &str) -> Option<Visibility> {
+    if node.kind() == "impl_item" {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let modifier = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "visibility_modifier");
+    if let Some(modifier) = modifier {
+        return Some(match node_text(modifier, content).as_str() {
+            "pub" => Visibility::Public,
+            "pub(self)" => Visibility::Private,
+            _ => Visibility::Internal,
+        });
+    }
+    let container = node.parent().and_then(|parent| parent.parent());
+    let in_trait = container.is_some_and(|container| {
+        container.kind() == "trait_item"
+            || (container.kind() == "impl_item" && container.child_by_field_name("trait").is_some())
+    });
+    Some(if in_trait {
+        Visibility::Public
+    } else {
+        Visibility::Private
+    })
+}
+
+/// Rust的属性位于定义之前，如 `#[derive(Debug)]`
+fn rust_attributes(node: TsNode, content: &str) -> Vec<String> {
+    preceding_attributes(node, content, &["attribute_item"])
+}
+
+/// 展开 `use` 树中的所有路径，如 `a::{b::C, d}` 展开为 `a::b::C` 和 `a::d`
+fn rust_use_paths(node: TsNode, prefix: &str, content: &str, paths: &mut Vec<String>) {
+    let join = |path: String| match prefix {
+        "" => path,
+        _ => format!("{}::{}", prefix, path),
+    };
+    match node.kind() {
+        "scoped_use_list" => {
+            let prefix = match field_text(node, "path", content) {
+                Some(path) => join(path),
+                None => prefix.to_string(),
+            };
+            if let Some(list) = node.child_by_field_name("list") {
+                rust_use_paths(list, &prefix, content, paths);
+            }
+        }
+        "use_list" => {
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                rust_use_paths(child, prefix, content, paths);
+            }
+        }
+        "use_as_clause" => {
+            if let Some(path) = field_text(node, "path", content) {
+                paths.push(join(path));
+            }
+        }
+        // 通配符导入依赖其前缀所指的模块
+        "use_wildcard" => match node.named_child(0) {
+            Some(path) => paths.push(join(node_text(path, content))),
+            None => paths.push(prefix.to_string()),
+        },
+        // 列表中的 `self` 指前缀本身
+        "self" if !prefix.is_empty() => paths.push(prefix.to_string()),
+        kind if kind.contains("comment") || kind == "attribute_item" => {}
+        _ => paths.push(join(node_text(node, content))),
+    }
+}
+
+/// 提取Rust的 `use` 声明和 `mod name;` 声明
+fn rust_imports(node: TsNode, content: &str) -> Vec<Import> {
+    match node.kind() {
+        "use_declaration" => {
+            let mut paths = Vec::new();
+            if let Some(argument) = node.child_by_field_name("argument") {
+                rust_use_paths(argument, "", content, &mut paths);
+            }
+            paths
+                .into_iter()
+                .map(|path| node_import(node, path, Vec::new()))
+                .collect()
+        }
+        // 没有定义体的模块声明从同名文件加载
+        "mod_item" if node.child_by_field_name("body").is_none() => {
+            field_text(node, "name", content)
+                .map(|name| vec![node_import(node, format!("self::{}", name), Vec::new())])
+                .unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Rust代码元素提取规则
+const RUST_RULES: ElementRules = ElementRules {
+    language: "rust",
+    name: node_name,
+    visibility: rust_visibility,
+    attributes: rust_attributes,
+    owner: no_owner,
+    imports: rust_imports,
+};
+
+/// Rust解析器实现
+#[derive(Default)]
+pub struct RustParser;
+
 impl RustParser {
     /// 创建新的Rust解析器
     pub fn new() -> Self {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_rust::language())
-            .expect("Could not load Rust grammar");
-
-        Self { parser }
+        Self
     }
 }
 
 impl LanguageParser for RustParser {
     fn language_name(&self) -> String {
-        "rust".to_string()
+        RUST_RULES.language.to_string()
     }
 
     fn supported_extensions(&self) -> Vec<&'static str> {
@@ -232,20 +1170,12 @@ impl LanguageParser for RustParser {
     }
 
     fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
-        self.parse_snippet(content)
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
     }
 
     fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_rust::language())
-            .expect("Could not load Rust grammar");
-
-        let tree = parser.parse(content, None).unwrap();
-        let root_node = tree.root_node();
-
-        let elements = self.build_code_elements(root_node, content, "");
-        Ok(elements)
+        self.parse_file("", content)
     }
 
     fn build_code_elements(
@@ -254,74 +1184,98 @@ impl LanguageParser for RustParser {
         content: &str,
         file_path: &str,
     ) -> Vec<CodeElement> {
-        // TODO: 实现从Rust AST构建代码元素的逻辑
-        // 遍历tree-sitter节点，识别函数、结构体、枚举等元素
-        let mut elements = Vec::new();
-
-        // 简单实现：只识别函数
-        let mut cursor = root_node.walk();
-        for node in root_node.children(&mut cursor) {
-            if node.kind() == "function_item" {
-                // 解析函数名
-                let name_node = node.child_by_field_name("name").unwrap();
-                let name = name_node.utf8_text(content.as_bytes()).unwrap().to_string();
-
-                // 解析位置信息
-                let start_pos = node.start_position();
-                let end_pos = node.end_position();
-                // 计算长度，使用安全的方式避免溢出
-                let line_diff = (end_pos.row - start_pos.row) as u32;
-                let column_diff = (end_pos.column - start_pos.column) as u32;
-                // 长度估算：行数差 * 100（每行平均字符数） + 列数差
-                let length = line_diff * 100 + column_diff;
-                let location = SourceLocation {
-                    file_path: file_path.to_string(),
-                    line: start_pos.row as u32 + 1,
-                    column: start_pos.column as u32 + 1,
-                    length,
-                };
-
-                let element = CodeElement {
-                    element_type: CodeElementType::Function,
-                    name,
-                    definition: location,
-                    documentation: None,
-                    parent: None,
-                    children: Vec::new(),
-                    language: self.language_name(),
-                };
-
-                elements.push(element);
+        extract_elements(root_node, content, file_path, &RUST_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &RUST_RULES)
+    }
+}
+
+/// Python按命名约定判断可见性：`__name` 为私有，`_name` 为内部，其余为公开
+fn python_visibility(_node: TsNode, _content: &str, name: &str) -> Option<Visibility> {
+    Some(if name.starts_with("__") && !name.ends_with("__") {
+        Visibility::Private
+    } else if name.starts_with('_') {
+        Visibility::Internal
+    } else {
+        Visibility::Public
+    })
+}
+
+/// Python的装饰器位于外层的 `decorated_definition` 中
+fn python_attributes(node: TsNode, content: &str) -> Vec<String> {
+    node.parent()
+        .filter(|parent| parent.kind() == "decorated_definition")
+        .map(|parent| child_texts(parent, content, &["decorator"]))
+        .unwrap_or_default()
+}
+
+/// 获取Python导入名称的模块部分，忽略 `as` 别名
+fn python_import_name(node: TsNode, content: &str) -> Option<String> {
+    match node.kind() {
+        "dotted_name" | "relative_import" => Some(node_text(node, content)),
+        "aliased_import" => field_text(node, "name", content),
+        "wildcard_import" => Some("*".to_string()),
+        _ => None,
+    }
+}
+
+/// 提取Python的 `import` 和 `from ... import` 语句
+fn python_imports(node: TsNode, content: &str) -> Vec<Import> {
+    let mut cursor = node.walk();
+    let names: Vec<String> = node
+        .children_by_field_name("name", &mut cursor)
+        .filter_map(|name| python_import_name(name, content))
+        .collect();
+    match node.kind() {
+        "import_statement" => names
+            .into_iter()
+            .map(|name| node_import(node, name, Vec::new()))
+            .collect(),
+        "import_from_statement" => {
+            let Some(module) = field_text(node, "module_name", content) else {
+                return Vec::new();
+            };
+            // 通配符导入不是 name 字段
+            let mut names = names;
+            let mut cursor = node.walk();
+            if node
+                .named_children(&mut cursor)
+                .any(|child| child.kind() == "wildcard_import")
+            {
+                names.push("*".to_string());
             }
-            // 递归处理子节点
-            elements.extend(self.build_code_elements(node, content, file_path));
+            vec![node_import(node, module, names)]
         }
-
-        elements
+        _ => Vec::new(),
     }
 }
 
+/// Python代码元素提取规则
+const PYTHON_RULES: ElementRules = ElementRules {
+    language: "python",
+    name: node_name,
+    visibility: python_visibility,
+    attributes: python_attributes,
+    owner: no_owner,
+    imports: python_imports,
+};
+
 /// Python解析器实现
-pub struct PythonParser {
-    /// tree-sitter解析器实例
-    parser: TsParser,
-}
+#[derive(Default)]
+pub struct PythonParser;
 
 impl PythonParser {
     /// 创建新的Python解析器
     pub fn new() -> Self {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_python::language())
-            .expect("Could not load Python grammar");
-
-        Self { parser }
+        Self
     }
 }
 
 impl LanguageParser for PythonParser {
     fn language_name(&self) -> String {
-        "python".to_string()
+        PYTHON_RULES.language.to_string()
     }
 
     fn supported_extensions(&self) -> Vec<&'static str> {
@@ -333,20 +1287,12 @@ impl LanguageParser for PythonParser {
     }
 
     fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
-        self.parse_snippet(content)
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
     }
 
     fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_python::language())
-            .expect("Could not load Python grammar");
-
-        let tree = parser.parse(content, None).unwrap();
-        let root_node = tree.root_node();
-
-        let elements = self.build_code_elements(root_node, content, "");
-        Ok(elements)
+        self.parse_file("", content)
     }
 
     fn build_code_elements(
@@ -355,72 +1301,144 @@ impl LanguageParser for PythonParser {
         content: &str,
         file_path: &str,
     ) -> Vec<CodeElement> {
-        // TODO: 实现从Python AST构建代码元素的逻辑
-        let mut elements = Vec::new();
-
-        // 简单实现：只识别函数定义
-        let mut cursor = root_node.walk();
-        for node in root_node.children(&mut cursor) {
-            if node.kind() == "function_definition" {
-                // 解析函数名
-                let name_node = node.child_by_field_name("name").unwrap();
-                let name = name_node.utf8_text(content.as_bytes()).unwrap().to_string();
-
-                // 解析位置信息
-                let start_pos = node.start_position();
-                let end_pos = node.end_position();
-                // 使用安全的方式计算长度，避免溢出
-                let line_diff = (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                let column_diff = (end_pos.column.saturating_sub(start_pos.column)) as u32;
-                let length = line_diff.saturating_mul(100).saturating_add(column_diff);
-                let location = SourceLocation {
-                    file_path: file_path.to_string(),
-                    line: start_pos.row as u32 + 1,
-                    column: start_pos.column as u32 + 1,
-                    length,
-                };
-
-                let element = CodeElement {
-                    element_type: CodeElementType::Function,
-                    name,
-                    definition: location,
-                    documentation: None,
-                    parent: None,
-                    children: Vec::new(),
-                    language: self.language_name(),
-                };
-
-                elements.push(element);
+        extract_elements(root_node, content, file_path, &PYTHON_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &PYTHON_RULES)
+    }
+}
+
+/// JavaScript和TypeScript的可见性：访问修饰符和 `#name` 决定成员的可见性，
+/// 其他成员公开；导出的定义公开，未导出的定义仅在模块内可见
+fn js_visibility(node: TsNode, content: &str, name: &str) -> Option<Visibility> {
+    let mut cursor = node.walk();
+    let modifier = node
+        .children(&mut cursor)
+        .find(|child| child.kind() == "accessibility_modifier");
+    if let Some(modifier) = modifier {
+        return Some(match node_text(modifier, content).as_str() {
+            "private" => Visibility::Private,
+            "protected" => Visibility::Protected,
+            _ => Visibility::Public,
+        });
+    }
+    if name.starts_with('#') {
+        return Some(Visibility::Private);
+    }
+    if matches!(
+        node.kind(),
+        "method_definition" | "method_signature" | "abstract_method_signature"
+    ) {
+        return Some(Visibility::Public);
+    }
+    let exported = node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "export_statement");
+    Some(if exported {
+        Visibility::Public
+    } else {
+        Visibility::Internal
+    })
+}
+
+/// JavaScript和TypeScript的装饰器，导出的类的装饰器位于导出语句中
+fn js_attributes(node: TsNode, content: &str) -> Vec<String> {
+    let mut decorators = node
+        .parent()
+        .filter(|parent| parent.kind() == "export_statement")
+        .map(|parent| child_texts(parent, content, &["decorator"]))
+        .unwrap_or_default();
+    decorators.extend(child_texts(node, content, &["decorator"]));
+    decorators
+}
+
+/// 获取导入子句中绑定的名称，包括默认导入和具名导入
+fn js_import_names(node: TsNode, content: &str, names: &mut Vec<String>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match child.kind() {
+            "identifier" if node.kind() == "import_clause" => names.push(node_text(child, content)),
+            "import_specifier" | "export_specifier" => {
+                names.extend(field_text(child, "name", content))
             }
-            // 递归处理子节点
-            elements.extend(self.build_code_elements(node, content, file_path));
+            "named_imports" | "export_clause" => js_import_names(child, content, names),
+            _ => {}
         }
+    }
+}
 
-        elements
+/// 提取JavaScript和TypeScript的 `import`、`export ... from`、`require()` 和动态 `import()`
+fn js_imports(node: TsNode, content: &str) -> Vec<Import> {
+    match node.kind() {
+        "import_statement" | "export_statement" => {
+            let Some(source) = field_text(node, "source", content) else {
+                return Vec::new();
+            };
+            let mut names = Vec::new();
+            let mut cursor = node.walk();
+            for child in node.named_children(&mut cursor) {
+                if matches!(child.kind(), "import_clause" | "export_clause") {
+                    js_import_names(child, content, &mut names);
+                }
+            }
+            vec![node_import(node, unquote(&source), names)]
+        }
+        "call_expression" => {
+            let is_import = node
+                .child_by_field_name("function")
+                .is_some_and(|function| {
+                    matches!(node_text(function, content).as_str(), "require" | "import")
+                });
+            let source = node
+                .child_by_field_name("arguments")
+                .and_then(|arguments| arguments.named_child(0))
+                .filter(|argument| argument.kind() == "string");
+            match (is_import, source) {
+                (true, Some(source)) => {
+                    vec![node_import(
+                        node,
+                        unquote(&node_text(source, content)),
+                        Vec::new(),
+                    )]
+                }
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
     }
 }
 
+/// JavaScript代码元素提取规则
+const JAVASCRIPT_RULES: ElementRules = ElementRules {
+    language: "javascript",
+    name: node_name,
+    visibility: js_visibility,
+    attributes: js_attributes,
+    owner: no_owner,
+    imports: js_imports,
+};
+
+/// TypeScript代码元素提取规则
+const TYPESCRIPT_RULES: ElementRules = ElementRules {
+    language: "typescript",
+    ..JAVASCRIPT_RULES
+};
+
 /// JavaScript解析器实现
-pub struct JavaScriptParser {
-    /// tree-sitter解析器实例
-    parser: TsParser,
-}
+#[derive(Default)]
+pub struct JavaScriptParser;
 
 impl JavaScriptParser {
     /// 创建新的JavaScript解析器
     pub fn new() -> Self {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_javascript::language())
-            .expect("Could not load JavaScript grammar");
-
-        Self { parser }
+        Self
     }
 }
 
 impl LanguageParser for JavaScriptParser {
     fn language_name(&self) -> String {
-        "javascript".to_string()
+        JAVASCRIPT_RULES.language.to_string()
     }
 
     fn supported_extensions(&self) -> Vec<&'static str> {
@@ -432,20 +1450,12 @@ impl LanguageParser for JavaScriptParser {
     }
 
     fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
-        self.parse_snippet(content)
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
     }
 
     fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_javascript::language())
-            .expect("Could not load JavaScript grammar");
-
-        let tree = parser.parse(content, None).unwrap();
-        let root_node = tree.root_node();
-
-        let elements = self.build_code_elements(root_node, content, "");
-        Ok(elements)
+        self.parse_file("", content)
     }
 
     fn build_code_elements(
@@ -454,150 +1464,28 @@ impl LanguageParser for JavaScriptParser {
         content: &str,
         file_path: &str,
     ) -> Vec<CodeElement> {
-        // TODO: 实现从JavaScript AST构建代码元素的逻辑
-        let mut elements = Vec::new();
-
-        // 打印根节点信息，用于调试
-        println!(
-            "根节点: {}, 子节点数量: {}",
-            root_node.kind(),
-            root_node.child_count()
-        );
-
-        // 简单实现：识别函数定义和赋值给变量的箭头函数
-        let mut cursor = root_node.walk();
-        for node in root_node.children(&mut cursor) {
-            println!(
-                "处理节点: {}, 子节点数量: {}",
-                node.kind(),
-                node.child_count()
-            );
-            match node.kind() {
-                "program" => {
-                    // 处理program节点，递归处理其子节点
-                    elements.extend(self.build_code_elements(node, content, file_path));
-                }
-                "function_declaration" => {
-                    // 解析命名函数
-                    println!("发现函数声明节点");
-                    let name = if let Some(name_node) = node.child_by_field_name("name") {
-                        name_node.utf8_text(content.as_bytes()).unwrap().to_string()
-                    } else {
-                        "anonymous".to_string()
-                    };
-
-                    // 解析位置信息
-                    let start_pos = node.start_position();
-                    let end_pos = node.end_position();
-                    // 使用安全的方式计算长度，避免溢出
-                    let line_diff = (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                    let column_diff = (end_pos.column.saturating_sub(start_pos.column)) as u32;
-                    let length = line_diff.saturating_mul(100).saturating_add(column_diff);
-                    let location = SourceLocation {
-                        file_path: file_path.to_string(),
-                        line: start_pos.row as u32 + 1,
-                        column: start_pos.column as u32 + 1,
-                        length,
-                    };
-
-                    let element = CodeElement {
-                        element_type: CodeElementType::Function,
-                        name,
-                        definition: location,
-                        documentation: None,
-                        parent: None,
-                        children: Vec::new(),
-                        language: self.language_name(),
-                    };
-
-                    elements.push(element);
-                }
-                "lexical_declaration" => {
-                    // 处理变量声明，可能包含箭头函数赋值
-                    println!("发现词法声明");
-                    let mut var_cursor = node.walk();
-                    for var_node in node.children(&mut var_cursor) {
-                        println!("词法声明子节点: {}", var_node.kind());
-                        if var_node.kind() == "variable_declarator" {
-                            // 解析变量名
-                            if let Some(name_node) = var_node.child_by_field_name("name") {
-                                let name =
-                                    name_node.utf8_text(content.as_bytes()).unwrap().to_string();
-                                println!("变量名: {}", name);
-
-                                // 解析赋值表达式的右侧箭头函数
-                                if let Some(value_node) = var_node.child_by_field_name("value") {
-                                    println!("变量值节点类型: {}", value_node.kind());
-                                    if value_node.kind() == "arrow_function" {
-                                        println!("发现箭头函数赋值: {}", name);
-                                        // 解析位置信息
-                                        let start_pos = node.start_position();
-                                        let end_pos = node.end_position();
-                                        // 使用安全的方式计算长度，避免溢出
-                                        let line_diff =
-                                            (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                                        let column_diff =
-                                            (end_pos.column.saturating_sub(start_pos.column))
-                                                as u32;
-                                        let length = line_diff
-                                            .saturating_mul(100)
-                                            .saturating_add(column_diff);
-                                        let location = SourceLocation {
-                                            file_path: file_path.to_string(),
-                                            line: start_pos.row as u32 + 1,
-                                            column: start_pos.column as u32 + 1,
-                                            length,
-                                        };
-
-                                        let element = CodeElement {
-                                            element_type: CodeElementType::Function,
-                                            name,
-                                            definition: location,
-                                            documentation: None,
-                                            parent: None,
-                                            children: Vec::new(),
-                                            language: self.language_name(),
-                                        };
-
-                                        elements.push(element);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    // 递归处理其他节点
-                    elements.extend(self.build_code_elements(node, content, file_path));
-                }
-            }
-        }
+        extract_elements(root_node, content, file_path, &JAVASCRIPT_RULES)
+    }
 
-        elements
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &JAVASCRIPT_RULES)
     }
 }
 
 /// TypeScript解析器实现
-pub struct TypeScriptParser {
-    /// tree-sitter解析器实例
-    parser: TsParser,
-}
+#[derive(Default)]
+pub struct TypeScriptParser;
 
 impl TypeScriptParser {
     /// 创建新的TypeScript解析器
     pub fn new() -> Self {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_typescript::language_typescript())
-            .expect("Could not load TypeScript grammar");
-
-        Self { parser }
+        Self
     }
 }
 
 impl LanguageParser for TypeScriptParser {
     fn language_name(&self) -> String {
-        "typescript".to_string()
+        TYPESCRIPT_RULES.language.to_string()
     }
 
     fn supported_extensions(&self) -> Vec<&'static str> {
@@ -609,20 +1497,12 @@ impl LanguageParser for TypeScriptParser {
     }
 
     fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
-        self.parse_snippet(content)
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
     }
 
     fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
-        let mut parser = TsParser::new();
-        parser
-            .set_language(tree_sitter_typescript::language_typescript())
-            .expect("Could not load TypeScript grammar");
-
-        let tree = parser.parse(content, None).unwrap();
-        let root_node = tree.root_node();
-
-        let elements = self.build_code_elements(root_node, content, "");
-        Ok(elements)
+        self.parse_file("", content)
     }
 
     fn build_code_elements(
@@ -631,136 +1511,688 @@ impl LanguageParser for TypeScriptParser {
         content: &str,
         file_path: &str,
     ) -> Vec<CodeElement> {
-        // TODO: 实现从TypeScript AST构建代码元素的逻辑
-        let mut elements = Vec::new();
-
-        // 简单实现：识别函数和接口定义
-        let mut cursor = root_node.walk();
-        for node in root_node.children(&mut cursor) {
-            match node.kind() {
-                "function_declaration" => {
-                    // 解析命名函数
-                    let name = if let Some(name_node) = node.child_by_field_name("name") {
-                        name_node.utf8_text(content.as_bytes()).unwrap().to_string()
-                    } else {
-                        "anonymous".to_string()
-                    };
-
-                    // 解析位置信息
-                    let start_pos = node.start_position();
-                    let end_pos = node.end_position();
-                    // 使用安全的方式计算长度，避免溢出
-                    let line_diff = (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                    let column_diff = (end_pos.column.saturating_sub(start_pos.column)) as u32;
-                    let length = line_diff.saturating_mul(100).saturating_add(column_diff);
-                    let location = SourceLocation {
-                        file_path: file_path.to_string(),
-                        line: start_pos.row as u32 + 1,
-                        column: start_pos.column as u32 + 1,
-                        length,
-                    };
-
-                    let element = CodeElement {
-                        element_type: CodeElementType::Function,
-                        name,
-                        definition: location,
-                        documentation: None,
-                        parent: None,
-                        children: Vec::new(),
-                        language: self.language_name(),
-                    };
-
-                    elements.push(element);
-                }
-                "lexical_declaration" => {
-                    // 处理变量声明，可能包含箭头函数赋值
-                    let mut var_cursor = node.walk();
-                    for var_node in node.children(&mut var_cursor) {
-                        if var_node.kind() == "variable_declarator" {
-                            // 解析变量名
-                            if let Some(name_node) = var_node.child_by_field_name("name") {
-                                let name =
-                                    name_node.utf8_text(content.as_bytes()).unwrap().to_string();
-
-                                // 解析赋值表达式的右侧箭头函数
-                                if let Some(value_node) = var_node.child_by_field_name("value") {
-                                    if value_node.kind() == "arrow_function" {
-                                        // 解析位置信息
-                                        let start_pos = node.start_position();
-                                        let end_pos = node.end_position();
-                                        // 使用安全的方式计算长度，避免溢出
-                                        let line_diff =
-                                            (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                                        let column_diff =
-                                            (end_pos.column.saturating_sub(start_pos.column))
-                                                as u32;
-                                        let length = line_diff
-                                            .saturating_mul(100)
-                                            .saturating_add(column_diff);
-                                        let location = SourceLocation {
-                                            file_path: file_path.to_string(),
-                                            line: start_pos.row as u32 + 1,
-                                            column: start_pos.column as u32 + 1,
-                                            length,
-                                        };
-
-                                        let element = CodeElement {
-                                            element_type: CodeElementType::Function,
-                                            name,
-                                            definition: location,
-                                            documentation: None,
-                                            parent: None,
-                                            children: Vec::new(),
-                                            language: self.language_name(),
-                                        };
-
-                                        elements.push(element);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                "interface_declaration" => {
-                    // 解析接口名
-                    let name = if let Some(name_node) = node.child_by_field_name("name") {
-                        name_node.utf8_text(content.as_bytes()).unwrap().to_string()
-                    } else {
-                        "anonymous_interface".to_string()
-                    };
-
-                    // 解析位置信息
-                    let start_pos = node.start_position();
-                    let end_pos = node.end_position();
-                    // 使用安全的方式计算长度，避免溢出
-                    let line_diff = (end_pos.row.saturating_sub(start_pos.row)) as u32;
-                    let column_diff = (end_pos.column.saturating_sub(start_pos.column)) as u32;
-                    let length = line_diff.saturating_mul(100).saturating_add(column_diff);
-                    let location = SourceLocation {
-                        file_path: file_path.to_string(),
-                        line: start_pos.row as u32 + 1,
-                        column: start_pos.column as u32 + 1,
-                        length,
-                    };
-
-                    let element = CodeElement {
-                        element_type: CodeElementType::Interface,
-                        name,
-                        definition: location,
-                        documentation: None,
-                        parent: None,
-                        children: Vec::new(),
-                        language: self.language_name(),
-                    };
-
-                    elements.push(element);
-                }
+        extract_elements(root_node, content, file_path, &TYPESCRIPT_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &TYPESCRIPT_RULES)
+    }
+}
+
+/// Go按首字母判断可见性：大写开头的名称导出，其余仅在包内可见
+fn go_visibility(node: TsNode, _content: &str, name: &str) -> Option<Visibility> {
+    if node.kind() == "package_clause" {
+        return None;
+    }
+    Some(if name.starts_with(char::is_uppercase) {
+        Visibility::Public
+    } else {
+        Visibility::Internal
+    })
+}
+
+/// Go方法的接收者类型，去掉指针和类型参数
+fn go_receiver(node: TsNode, content: &str) -> Option<String> {
+    let receiver = node.child_by_field_name("receiver")?;
+    let mut cursor = receiver.walk();
+    let parameter = receiver
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "parameter_declaration")?;
+    let receiver_type = field_text(parameter, "type", content)?;
+    let receiver_type = receiver_type.trim_start_matches('*');
+    Some(
+        receiver_type
+            .split('[')
+            .next()
+            .unwrap_or(receiver_type)
+            .to_string(),
+    )
+}
+
+/// 提取Go的导入声明，每个导入路径对应一个导入
+fn go_imports(node: TsNode, content: &str) -> Vec<Import> {
+    match node.kind() {
+        "import_spec" => field_text(node, "path", content)
+            .map(|path| vec![node_import(node, unquote(&path), Vec::new())])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Go代码元素提取规则
+const GO_RULES: ElementRules = ElementRules {
+    language: "go",
+    name: node_name,
+    visibility: go_visibility,
+    attributes: no_attributes,
+    owner: go_receiver,
+    imports: go_imports,
+};
+
+/// Go解析器实现
+#[derive(Default)]
+pub struct GoParser;
+
+impl GoParser {
+    /// 创建新的Go解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for GoParser {
+    fn language_name(&self) -> String {
+        GO_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["go"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_go::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &GO_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &GO_RULES)
+    }
+}
+
+/// 获取Java定义的修饰符节点
+fn java_modifiers(node: TsNode) -> Option<TsNode> {
+    let mut cursor = node.walk();
+    let modifiers = node
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "modifiers");
+    modifiers
+}
+
+/// Java的访问修饰符，没有修饰符时接口成员公开，其余仅在包内可见
+fn java_visibility(node: TsNode, _content: &str, _name: &str) -> Option<Visibility> {
+    if node.kind() == "package_declaration" {
+        return None;
+    }
+    if let Some(modifiers) = java_modifiers(node) {
+        let mut cursor = modifiers.walk();
+        for modifier in modifiers.children(&mut cursor) {
+            match modifier.kind() {
+                "public" => return Some(Visibility::Public),
+                "protected" => return Some(Visibility::Protected),
+                "private" => return Some(Visibility::Private),
                 _ => {}
             }
-            // 递归处理子节点
-            elements.extend(self.build_code_elements(node, content, file_path));
         }
+    }
+    let in_interface = node
+        .parent()
+        .is_some_and(|parent| parent.kind() == "interface_body");
+    Some(if in_interface {
+        Visibility::Public
+    } else {
+        Visibility::Internal
+    })
+}
+
+/// Java的注解位于修饰符中
+fn java_attributes(node: TsNode, content: &str) -> Vec<String> {
+    java_modifiers(node)
+        .map(|modifiers| child_texts(modifiers, content, &["annotation", "marker_annotation"]))
+        .unwrap_or_default()
+}
+
+/// 提取Java的导入声明，`import a.b.*;` 的名称为 `*`
+fn java_imports(node: TsNode, content: &str) -> Vec<Import> {
+    if node.kind() != "import_declaration" {
+        return Vec::new();
+    }
+    let mut cursor = node.walk();
+    let children: Vec<TsNode> = node.named_children(&mut cursor).collect();
+    let Some(path) = children
+        .iter()
+        .find(|child| matches!(child.kind(), "identifier" | "scoped_identifier"))
+    else {
+        return Vec::new();
+    };
+    let names = if children.iter().any(|child| child.kind() == "asterisk") {
+        vec!["*".to_string()]
+    } else {
+        Vec::new()
+    };
+    vec![node_import(node, node_text(*path, content), names)]
+}
+
+/// Java代码元素提取规则
+const JAVA_RULES: ElementRules = ElementRules {
+    language: "java",
+    name: node_name,
+    visibility: java_visibility,
+    attributes: java_attributes,
+    owner: no_owner,
+    imports: java_imports,
+};
+
+/// Java解析器实现
+#[derive(Default)]
+pub struct JavaParser;
+
+impl JavaParser {
+    /// 创建新的Java解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for JavaParser {
+    fn language_name(&self) -> String {
+        JAVA_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["java"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_java::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &JAVA_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &JAVA_RULES)
+    }
+}
+
+/// 获取C/C++声明符中声明的名称，跳过指针、引用、函数参数等修饰，限定名称只保留最后一部分
+fn declarator_name(declarator: TsNode, content: &str) -> Option<String> {
+    let mut node = declarator;
+    loop {
+        match node.kind() {
+            "identifier"
+            | "field_identifier"
+            | "type_identifier"
+            | "namespace_identifier"
+            | "destructor_name"
+            | "operator_name" => return Some(node_text(node, content)),
+            "qualified_identifier" => node = node.child_by_field_name("name")?,
+            _ => {
+                node = node
+                    .child_by_field_name("declarator")
+                    .or_else(|| node.named_child(0))?
+            }
+        }
+    }
+}
+
+/// C的函数按链接属性判断可见性：`static` 函数仅在文件内可见，其余公开
+fn c_visibility(node: TsNode, content: &str, _name: &str) -> Option<Visibility> {
+    if node.kind() != "function_definition" {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let is_static = node.children(&mut cursor).any(|child| {
+        child.kind() == "storage_class_specifier" && node_text(child, content) == "static"
+    });
+    Some(if is_static {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    })
+}
+
+/// 提取C/C++的 `#include`，系统头文件保留尖括号
+fn c_includes(node: TsNode, content: &str) -> Vec<Import> {
+    if node.kind() != "preproc_include" {
+        return Vec::new();
+    }
+    let Some(path) = node.child_by_field_name("path") else {
+        return Vec::new();
+    };
+    let module = match path.kind() {
+        "system_lib_string" => node_text(path, content),
+        _ => unquote(&node_text(path, content)),
+    };
+    vec![node_import(node, module, Vec::new())]
+}
+
+/// C代码元素提取规则
+const C_RULES: ElementRules = ElementRules {
+    language: "c",
+    name: declarator_name,
+    visibility: c_visibility,
+    attributes: no_attributes,
+    owner: no_owner,
+    imports: c_includes,
+};
+
+/// C解析器实现
+#[derive(Default)]
+pub struct CParser;
+
+impl CParser {
+    /// 创建新的C解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for CParser {
+    fn language_name(&self) -> String {
+        C_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["c", "h"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_c::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &C_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &C_RULES)
+    }
+}
+
+/// C++类成员的可见性由之前最近的访问说明符决定，没有说明符时 class 私有、struct 公开；
+/// 类外的定义与C相同
+fn cpp_visibility(node: TsNode, content: &str, name: &str) -> Option<Visibility> {
+    // 模板成员的访问说明符位于模板声明之前
+    let member = node
+        .parent()
+        .filter(|parent| parent.kind() == "template_declaration")
+        .unwrap_or(node);
+    let Some(list) = member
+        .parent()
+        .filter(|parent| parent.kind() == "field_declaration_list")
+    else {
+        return c_visibility(node, content, name);
+    };
+    let mut sibling = member.prev_sibling();
+    while let Some(current) = sibling {
+        if current.kind() == "access_specifier" {
+            return Some(
+                match node_text(current, content).trim_end_matches(':').trim() {
+                    "private" => Visibility::Private,
+                    "protected" => Visibility::Protected,
+                    _ => Visibility::Public,
+                },
+            );
+        }
+        sibling = current.prev_sibling();
+    }
+    let in_class = list
+        .parent()
+        .is_some_and(|parent| parent.kind() == "class_specifier");
+    Some(if in_class {
+        Visibility::Private
+    } else {
+        Visibility::Public
+    })
+}
+
+/// C++的属性说明符，如 `[[nodiscard]]`
+fn cpp_attributes(node: TsNode, content: &str) -> Vec<String> {
+    child_texts(node, content, &["attribute_declaration"])
+}
+
+/// 类外定义的C++成员函数所属的作用域，如 `Shape::area` 的 `Shape`
+fn cpp_owner(node: TsNode, content: &str) -> Option<String> {
+    if node.kind() != "function_definition" {
+        return None;
+    }
+    let mut declarator = node.child_by_field_name("declarator")?;
+    loop {
+        match declarator.kind() {
+            "qualified_identifier" => {
+                let text = node_text(declarator, content);
+                return text
+                    .rsplit_once("::")
+                    .map(|(scope, _)| scope.trim().to_string())
+                    .filter(|scope| !scope.is_empty());
+            }
+            "identifier" | "field_identifier" | "destructor_name" | "operator_name" => return None,
+            _ => {
+                declarator = declarator
+                    .child_by_field_name("declarator")
+                    .or_else(|| declarator.named_child(0))?
+            }
+        }
+    }
+}
+
+/// C++代码元素提取规则
+const CPP_RULES: ElementRules = ElementRules {
+    language: "cpp",
+    name: declarator_name,
+    visibility: cpp_visibility,
+    attributes: cpp_attributes,
+    owner: cpp_owner,
+    imports: c_includes,
+};
+
+/// C++解析器实现
+#[derive(Default)]
+pub struct CppParser;
+
+impl CppParser {
+    /// 创建新的C++解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for CppParser {
+    fn language_name(&self) -> String {
+        CPP_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["cpp", "cc", "cxx", "hpp", "hh", "hxx"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_cpp::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &CPP_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &CPP_RULES)
+    }
+}
+
+/// Markdown代码元素提取规则
+const MARKDOWN_RULES: ElementRules = ElementRules {
+    language: "markdown",
+    name: node_name,
+    visibility: no_visibility,
+    attributes: no_attributes,
+    owner: no_owner,
+    imports: no_imports,
+};
+
+/// Markdown解析器实现
+#[derive(Default)]
+pub struct MarkdownParser;
+
+impl MarkdownParser {
+    /// 创建新的Markdown解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for MarkdownParser {
+    fn language_name(&self) -> String {
+        MARKDOWN_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["md"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_md::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &MARKDOWN_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &MARKDOWN_RULES)
+    }
+}
+
+/// TOML代码元素提取规则
+const TOML_RULES: ElementRules = ElementRules {
+    language: "toml",
+    name: unquoted_name,
+    visibility: no_visibility,
+    attributes: no_attributes,
+    owner: no_owner,
+    imports: no_imports,
+};
+
+/// TOML解析器实现
+#[derive(Default)]
+pub struct TomlParser;
+
+impl TomlParser {
+    /// 创建新的TOML解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for TomlParser {
+    fn language_name(&self) -> String {
+        TOML_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["toml"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_toml::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &TOML_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &TOML_RULES)
+    }
+}
+
+/// YAML代码元素提取规则
+const YAML_RULES: ElementRules = ElementRules {
+    language: "yaml",
+    name: unquoted_name,
+    visibility: no_visibility,
+    attributes: no_attributes,
+    owner: no_owner,
+    imports: no_imports,
+};
+
+/// YAML解析器实现
+#[derive(Default)]
+pub struct YamlParser;
+
+impl YamlParser {
+    /// 创建新的YAML解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for YamlParser {
+    fn language_name(&self) -> String {
+        YAML_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["yaml", "yml"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_yaml::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &YAML_RULES)
+    }
+
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &YAML_RULES)
+    }
+}
+
+/// JSON代码元素提取规则
+const JSON_RULES: ElementRules = ElementRules {
+    language: "json",
+    name: unquoted_name,
+    visibility: no_visibility,
+    attributes: no_attributes,
+    owner: no_owner,
+    imports: no_imports,
+};
+
+/// JSON解析器实现
+#[derive(Default)]
+pub struct JsonParser;
+
+impl JsonParser {
+    /// 创建新的JSON解析器
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl LanguageParser for JsonParser {
+    fn language_name(&self) -> String {
+        JSON_RULES.language.to_string()
+    }
+
+    fn supported_extensions(&self) -> Vec<&'static str> {
+        vec!["json"]
+    }
+
+    fn tree_sitter_language(&self) -> TsLanguage {
+        tree_sitter_json::language()
+    }
+
+    fn parse_file(&self, file_path: &str, content: &str) -> AppResult<Vec<CodeElement>> {
+        let tree = parse_source(self.tree_sitter_language(), content)?;
+        Ok(self.build_code_elements(tree.root_node(), content, file_path))
+    }
+
+    fn parse_snippet(&self, content: &str) -> AppResult<Vec<CodeElement>> {
+        self.parse_file("", content)
+    }
+
+    fn build_code_elements(
+        &self,
+        root_node: TsNode,
+        content: &str,
+        file_path: &str,
+    ) -> Vec<CodeElement> {
+        extract_elements(root_node, content, file_path, &JSON_RULES)
+    }
 
-        elements
+    fn build_imports(&self, root_node: TsNode, content: &str) -> Vec<Import> {
+        extract_imports(root_node, content, &JSON_RULES)
     }
 }
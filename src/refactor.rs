@@ -0,0 +1,1100 @@
+//! 语法感知的重构
+//!
+//! 基于 tree-sitter 语法树实现确定性的重构：按标识符匹配跨文件重命名符号，
+//! 以及 Rust、Python、JavaScript 和 TypeScript 的提取变量和内联变量。
+//! 重构结果不直接写入文件，而是以统一差异格式展示，确认后再应用
+
+use crate::error::{AppError, AppResult};
+use crate::knowledge::walker::{read_text_file, ProjectWalker, DEFAULT_MAX_FILE_SIZE};
+use crate::parsers::{check_file_syntax, PARSER_REGISTRY};
+use similar::TextDiff;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node as TsNode, Parser as TsParser, Tree};
+
+/// 差异中每处修改前后保留的上下文行数
+const DIFF_CONTEXT_LINES: usize = 3;
+
+/// 重构的目标，不同的重构类型使用其中不同的字段
+#[derive(Debug, Clone, Default)]
+pub struct RefactorTarget {
+    /// 要重命名或内联的名称，提取变量时为新变量的名称
+    pub name: Option<String>,
+    /// 重命名后的名称
+    pub new_name: Option<String>,
+    /// 要提取的表达式的字节范围
+    pub selection: Option<Range<usize>>,
+    /// 要内联的变量的声明所在行，同名变量有多个声明时用于区分
+    pub line: Option<u32>,
+}
+
+/// 单个文件的修改
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    /// 文件路径
+    pub path: PathBuf,
+    /// 修改前的内容
+    pub original: String,
+    /// 修改后的内容
+    pub modified: String,
+}
+
+impl FileChange {
+    /// 生成统一格式的差异
+    ///
+    /// 当前目录下的文件使用带 `a/`、`b/` 前缀的相对路径，以便 `git apply`；
+    /// 其他文件使用不带前缀的绝对路径
+    pub fn diff(&self) -> String {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let path = self
+            .path
+            .strip_prefix(&cwd)
+            .or_else(|_| self.path.strip_prefix("."))
+            .unwrap_or(&self.path);
+        let (old, new) = if path.is_absolute() {
+            (path.display().to_string(), path.display().to_string())
+        } else {
+            (
+                format!("a/{}", path.display()),
+                format!("b/{}", path.display()),
+            )
+        };
+        TextDiff::from_lines(&self.original, &self.modified)
+            .unified_diff()
+            .context_radius(DIFF_CONTEXT_LINES)
+            .header(&old, &new)
+            .to_string()
+    }
+}
+
+/// 重构结果
+#[derive(Debug, Clone)]
+pub struct Refactoring {
+    /// 重构说明
+    pub description: String,
+    /// 发生修改的文件
+    pub changes: Vec<FileChange>,
+}
+
+impl Refactoring {
+    /// 创建重构结果，忽略没有修改的文件；修改后的代码新增语法错误时返回错误
+    pub fn new(description: String, changes: Vec<FileChange>) -> AppResult<Self> {
+        let changes: Vec<FileChange> = changes
+            .into_iter()
+            .filter(|change| change.original != change.modified)
+            .collect();
+        for change in &changes {
+            let path = change.path.to_string_lossy();
+            let errors = |code: &str| -> AppResult<usize> {
+                Ok(check_file_syntax(&path, code)?.map_or(0, |diagnostics| diagnostics.len()))
+            };
+            if errors(&change.modified)? > errors(&change.original)? {
+                return Err(AppError::Other(format!(
+                    "重构后 {} 出现语法错误，已放弃修改",
+                    change.path.display()
+                )));
+            }
+        }
+        Ok(Self {
+            description,
+            changes,
+        })
+    }
+
+    /// 是否没有任何修改
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// 生成所有文件的统一格式差异
+    pub fn diff(&self) -> String {
+        self.changes.iter().map(FileChange::diff).collect()
+    }
+
+    /// 将修改写入文件
+    pub fn apply(&self) -> AppResult<()> {
+        for change in &self.changes {
+            fs::write(&change.path, &change.modified)?;
+        }
+        Ok(())
+    }
+}
+
+/// 变量声明
+struct Declaration<'a> {
+    /// 声明语句，内联后删除
+    statement: TsNode<'a>,
+    /// 变量名节点
+    name: TsNode<'a>,
+    /// 初始值节点
+    value: TsNode<'a>,
+    /// 变量是否声明为可变
+    mutable: bool,
+    /// 变量的作用域，在其中查找引用
+    scope: TsNode<'a>,
+}
+
+/// 语言的重构规则
+struct LanguageRules {
+    /// 重命名时匹配的标识符节点
+    identifiers: &'static [&'static str],
+    /// 提取和内联变量的规则，不支持时为空
+    variables: Option<VariableRules>,
+}
+
+/// 提取和内联变量的语法规则
+struct VariableRules {
+    /// 变量引用节点
+    references: &'static [&'static str],
+    /// 包含语句的块节点，提取的变量声明插入到块中的语句之前
+    blocks: &'static [&'static str],
+    /// 表达式不能跨越的节点：闭包、循环条件和条件分支等，跨越后求值次数或时机会改变
+    barriers: &'static [&'static str],
+    /// 只在部分情况下求值的子节点：父节点类型、字段和运算符，运算符为空时表示任意运算符
+    conditional: &'static [(&'static str, &'static str, &'static [&'static str])],
+    /// 绑定或修改变量的位置：父节点类型和字段，字段为空时表示任意子节点
+    bindings: &'static [(&'static str, Option<&'static str>)],
+    /// 总是绑定变量的节点，如 JavaScript 解构中的简写属性
+    binding_nodes: &'static [&'static str],
+    /// 不是变量引用的位置：父节点类型和字段，如 Python 的属性名和关键字参数名
+    non_references: &'static [(&'static str, &'static str)],
+    /// 求值时有副作用或创建新对象的节点，含有这些节点的初始值不能内联到多处
+    effects: &'static [&'static str],
+    /// 替换变量时无需加括号的表达式节点
+    atomic: &'static [&'static str],
+    /// 其中的变量替换为任意表达式都无需加括号的父节点
+    open_parents: &'static [&'static str],
+    /// 可能以 `{name}` 形式引用变量的格式化字符串节点
+    format_strings: &'static [&'static str],
+    /// 生成变量声明语句
+    declare: fn(&str, &str) -> String,
+    /// 识别变量声明语句
+    declaration: fn(TsNode<'_>) -> Option<Declaration<'_>>,
+}
+
+/// 获取语言的重构规则
+fn rules_for(language: &str) -> Option<LanguageRules> {
+    match language {
+        "rust" => Some(LanguageRules {
+            identifiers: &[
+                "identifier",
+                "type_identifier",
+                "field_identifier",
+                "shorthand_field_identifier",
+            ],
+            variables: Some(VariableRules {
+                references: &["identifier"],
+                blocks: &["block"],
+                barriers: &[
+                    "closure_expression",
+                    "async_block",
+                    "while_expression",
+                    "loop_expression",
+                    "for_expression",
+                    "match_arm",
+                    "else_clause",
+                    "let_chain",
+                ],
+                conditional: &[
+                    ("if_expression", "consequence", &[]),
+                    ("binary_expression", "right", &["&&", "||"]),
+                ],
+                bindings: &[
+                    ("let_declaration", Some("pattern")),
+                    ("let_condition", Some("pattern")),
+                    ("for_expression", Some("pattern")),
+                    ("parameter", Some("pattern")),
+                    ("closure_parameters", None),
+                    ("assignment_expression", Some("left")),
+                    ("compound_assignment_expr", Some("left")),
+                ],
+                binding_nodes: &[],
+                non_references: &[
+                    ("scoped_identifier", "name"),
+                    ("scoped_identifier", "path"),
+                    ("scoped_type_identifier", "path"),
+                    ("macro_invocation", "macro"),
+                ],
+                effects: &["call_expression", "macro_invocation", "await_expression"],
+                atomic: &[
+                    "identifier",
+                    "self",
+                    "integer_literal",
+                    "float_literal",
+                    "string_literal",
+                    "raw_string_literal",
+                    "char_literal",
+                    "boolean_literal",
+                    "unit_expression",
+                    "call_expression",
+                    "macro_invocation",
+                    "field_expression",
+                    "index_expression",
+                    "scoped_identifier",
+                    "generic_function",
+                    "parenthesized_expression",
+                    "tuple_expression",
+                    "array_expression",
+                    "struct_expression",
+                    "try_expression",
+                    "await_expression",
+                ],
+                open_parents: &[
+                    "arguments",
+                    "let_declaration",
+                    "assignment_expression",
+                    "expression_statement",
+                    "return_expression",
+                    "block",
+                    "parenthesized_expression",
+                    "tuple_expression",
+                    "array_expression",
+                    "field_initializer",
+                    "token_tree",
+                ],
+                format_strings: &["string_literal", "raw_string_literal"],
+                declare: |name, value| format!("let {} = {};", name, value),
+                declaration: rust_declaration,
+            }),
+        }),
+        "python" => Some(LanguageRules {
+            identifiers: &["identifier"],
+            variables: Some(VariableRules {
+                references: &["identifier"],
+                blocks: &["block", "module"],
+                barriers: &[
+                    "lambda",
+                    "while_statement",
+                    "elif_clause",
+                    "conditional_expression",
+                    "boolean_operator",
+                    "list_comprehension",
+                    "dictionary_comprehension",
+                    "set_comprehension",
+                    "generator_expression",
+                ],
+                conditional: &[("if_statement", "consequence", &[])],
+                bindings: &[
+                    ("assignment", Some("left")),
+                    ("augmented_assignment", Some("left")),
+                    ("for_statement", Some("left")),
+                    ("for_in_clause", Some("left")),
+                    ("named_expression", Some("name")),
+                    ("parameters", None),
+                    ("lambda_parameters", None),
+                    ("default_parameter", Some("name")),
+                    ("typed_parameter", None),
+                    ("typed_default_parameter", Some("name")),
+                    ("pattern_list", None),
+                    ("as_pattern_target", None),
+                    ("except_clause", None),
+                    ("global_statement", None),
+                    ("nonlocal_statement", None),
+                    ("function_definition", Some("name")),
+                    ("class_definition", Some("name")),
+                    ("dotted_name", None),
+                    ("aliased_import", Some("alias")),
+                ],
+                binding_nodes: &[],
+                non_references: &[("attribute", "attribute"), ("keyword_argument", "name")],
+                effects: &[
+                    "call",
+                    "await",
+                    "yield",
+                    "list",
+                    "dictionary",
+                    "set",
+                    "list_comprehension",
+                    "dictionary_comprehension",
+                    "set_comprehension",
+                    "generator_expression",
+                ],
+                atomic: &[
+                    "identifier",
+                    "integer",
+                    "float",
+                    "string",
+                    "concatenated_string",
+                    "true",
+                    "false",
+                    "none",
+                    "call",
+                    "attribute",
+                    "subscript",
+                    "parenthesized_expression",
+                    "list",
+                    "dictionary",
+                    "set",
+                    "tuple",
+                    "list_comprehension",
+                    "dictionary_comprehension",
+                    "set_comprehension",
+                ],
+                open_parents: &[
+                    "argument_list",
+                    "keyword_argument",
+                    "assignment",
+                    "augmented_assignment",
+                    "expression_statement",
+                    "return_statement",
+                    "parenthesized_expression",
+                    "list",
+                    "tuple",
+                    "set",
+                    "pair",
+                    "expression_list",
+                    "if_statement",
+                    "interpolation",
+                ],
+                format_strings: &[],
+                declare: |name, value| format!("{} = {}", name, value),
+                declaration: python_declaration,
+            }),
+        }),
+        "javascript" | "typescript" => Some(LanguageRules {
+            identifiers: &[
+                "identifier",
+                "property_identifier",
+                "type_identifier",
+                "shorthand_property_identifier",
+                "shorthand_property_identifier_pattern",
+            ],
+            variables: Some(VariableRules {
+                references: &["identifier", "shorthand_property_identifier"],
+                blocks: &[
+                    "statement_block",
+                    "program",
+                    "switch_case",
+                    "switch_default",
+                ],
+                barriers: &[
+                    "arrow_function",
+                    "function",
+                    "function_expression",
+                    "class_body",
+                    "while_statement",
+                    "do_statement",
+                    "for_statement",
+                    "else_clause",
+                    "ternary_expression",
+                ],
+                conditional: &[
+                    ("if_statement", "consequence", &[]),
+                    ("for_in_statement", "body", &[]),
+                    ("binary_expression", "right", &["&&", "||", "??"]),
+                    (
+                        "augmented_assignment_expression",
+                        "right",
+                        &["&&=", "||=", "??="],
+                    ),
+                ],
+                bindings: &[
+                    ("variable_declarator", Some("name")),
+                    ("assignment_expression", Some("left")),
+                    ("augmented_assignment_expression", Some("left")),
+                    ("update_expression", None),
+                    ("formal_parameters", None),
+                    ("required_parameter", Some("pattern")),
+                    ("optional_parameter", Some("pattern")),
+                    ("arrow_function", Some("parameter")),
+                    ("catch_clause", Some("parameter")),
+                    ("for_in_statement", Some("left")),
+                    ("function_declaration", Some("name")),
+                    ("class_declaration", Some("name")),
+                    ("import_specifier", None),
+                    ("import_clause", None),
+                    ("namespace_import", None),
+                ],
+                binding_nodes: &["shorthand_property_identifier_pattern"],
+                non_references: &[],
+                effects: &[
+                    "call_expression",
+                    "new_expression",
+                    "await_expression",
+                    "yield_expression",
+                    "update_expression",
+                    "assignment_expression",
+                    "array",
+                    "object",
+                    "function",
+                    "function_expression",
+                    "arrow_function",
+                    "class",
+                ],
+                atomic: &[
+                    "identifier",
+                    "this",
+                    "number",
+                    "string",
+                    "template_string",
+                    "regex",
+                    "true",
+                    "false",
+                    "null",
+                    "undefined",
+                    "call_expression",
+                    "member_expression",
+                    "subscript_expression",
+                    "parenthesized_expression",
+                    "array",
+                    "object",
+                ],
+                open_parents: &[
+                    "arguments",
+                    "variable_declarator",
+                    "assignment_expression",
+                    "expression_statement",
+                    "return_statement",
+                    "parenthesized_expression",
+                    "array",
+                    "pair",
+                    "template_substitution",
+                ],
+                format_strings: &[],
+                declare: |name, value| format!("const {} = {};", name, value),
+                declaration: javascript_declaration,
+            }),
+        }),
+        "go" => Some(LanguageRules {
+            identifiers: &["identifier", "type_identifier", "field_identifier"],
+            variables: None,
+        }),
+        "java" => Some(LanguageRules {
+            identifiers: &["identifier", "type_identifier"],
+            variables: None,
+        }),
+        "c" | "cpp" => Some(LanguageRules {
+            identifiers: &[
+                "identifier",
+                "type_identifier",
+                "field_identifier",
+                "namespace_identifier",
+            ],
+            variables: None,
+        }),
+        _ => None,
+    }
+}
+
+/// 识别 Rust 的 `let name = value;`
+fn rust_declaration(node: TsNode<'_>) -> Option<Declaration<'_>> {
+    if node.kind() != "let_declaration" || node.child_by_field_name("alternative").is_some() {
+        return None;
+    }
+    let name = node
+        .child_by_field_name("pattern")
+        .filter(|pattern| pattern.kind() == "identifier")?;
+    let mut cursor = node.walk();
+    let mutable = node
+        .children(&mut cursor)
+        .any(|child| child.kind() == "mutable_specifier");
+    Some(Declaration {
+        statement: node,
+        name,
+        value: node.child_by_field_name("value")?,
+        mutable,
+        scope: node.parent()?,
+    })
+}
+
+/// 识别 Python 的 `name = value`，作用域为所在函数或模块
+fn python_declaration(node: TsNode<'_>) -> Option<Declaration<'_>> {
+    if node.kind() != "expression_statement" || node.named_child_count() != 1 {
+        return None;
+    }
+    let assignment = node
+        .named_child(0)
+        .filter(|child| child.kind() == "assignment")?;
+    let name = assignment
+        .child_by_field_name("left")
+        .filter(|left| left.kind() == "identifier")?;
+    // 链式赋值 `a = b = 1` 的右侧也是赋值
+    let value = assignment
+        .child_by_field_name("right")
+        .filter(|right| right.kind() != "assignment")?;
+    let scope = ancestors(node).find_map(|ancestor| match ancestor.kind() {
+        "function_definition" => ancestor.child_by_field_name("body"),
+        "module" => Some(ancestor),
+        _ => None,
+    })?;
+    Some(Declaration {
+        statement: node,
+        name,
+        value,
+        mutable: false,
+        scope,
+    })
+}
+
+/// 识别 JavaScript/TypeScript 的 `const name = value;`，`var` 的作用域为所在函数
+fn javascript_declaration(node: TsNode<'_>) -> Option<Declaration<'_>> {
+    if !matches!(node.kind(), "lexical_declaration" | "variable_declaration") {
+        return None;
+    }
+    let mut cursor = node.walk();
+    let declarators: Vec<TsNode> = node
+        .named_children(&mut cursor)
+        .filter(|child| child.kind() == "variable_declarator")
+        .collect();
+    let [declarator] = declarators[..] else {
+        return None;
+    };
+    let name = declarator
+        .child_by_field_name("name")
+        .filter(|name| name.kind() == "identifier")?;
+    let scope = if node.kind() == "variable_declaration" {
+        ancestors(node).find_map(|ancestor| match ancestor.kind() {
+            "function_declaration"
+            | "generator_function_declaration"
+            | "function"
+            | "function_expression"
+            | "arrow_function"
+            | "method_definition" => ancestor.child_by_field_name("body"),
+            "program" => Some(ancestor),
+            _ => None,
+        })?
+    } else {
+        node.parent()?
+    };
+    Some(Declaration {
+        statement: node,
+        name,
+        value: declarator.child_by_field_name("value")?,
+        mutable: false,
+        scope,
+    })
+}
+
+/// 遍历节点的所有祖先节点，从父节点开始
+fn ancestors(node: TsNode<'_>) -> impl Iterator<Item = TsNode<'_>> {
+    std::iter::successors(node.parent(), |node| node.parent())
+}
+
+/// 遍历节点及其所有后代节点
+fn descendants(node: TsNode<'_>) -> Vec<TsNode<'_>> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![node];
+    while let Some(node) = stack.pop() {
+        nodes.push(node);
+        let mut cursor = node.walk();
+        let children: Vec<TsNode> = node.children(&mut cursor).collect();
+        stack.extend(children.into_iter().rev());
+    }
+    nodes
+}
+
+/// 获取节点的源代码文本
+fn text<'a>(node: TsNode, content: &'a str) -> &'a str {
+    node.utf8_text(content.as_bytes()).unwrap_or("")
+}
+
+/// 判断名称是否为合法的标识符
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// 检查名称，不是合法标识符时返回错误
+fn check_identifier(name: &str) -> AppResult<()> {
+    if is_identifier(name) {
+        Ok(())
+    } else {
+        Err(AppError::Other(format!("`{}` 不是合法的标识符", name)))
+    }
+}
+
+/// 获取文件的语言名称并解析内容
+fn parse_file(path: &Path, content: &str) -> AppResult<(String, Tree)> {
+    let parser = PARSER_REGISTRY
+        .read()
+        .unwrap()
+        .get_parser_by_filename(&path.to_string_lossy())
+        .ok_or_else(|| AppError::Other(format!("不支持的文件类型: {}", path.display())))?;
+    let mut ts_parser = TsParser::new();
+    ts_parser
+        .set_language(parser.tree_sitter_language())
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    let tree = ts_parser
+        .parse(content, None)
+        .ok_or_else(|| AppError::Other(format!("解析文件失败: {}", path.display())))?;
+    Ok((parser.language_name(), tree))
+}
+
+/// 获取文件语言的变量重构规则，不支持时返回错误
+fn variable_rules(language: &str, operation: &str) -> AppResult<VariableRules> {
+    rules_for(language)
+        .and_then(|rules| rules.variables)
+        .ok_or_else(|| AppError::Other(format!("{} 不支持{}", language, operation)))
+}
+
+/// 判断文件是否支持提取和内联变量
+pub fn supports_variables(path: &Path) -> bool {
+    let parser = PARSER_REGISTRY
+        .read()
+        .unwrap()
+        .get_parser_by_filename(&path.to_string_lossy());
+    parser.is_some_and(|parser| {
+        rules_for(&parser.language_name()).is_some_and(|rules| rules.variables.is_some())
+    })
+}
+
+/// 按字节范围替换内容，范围不能重叠
+fn apply_edits(content: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut result = String::with_capacity(content.len());
+    let mut position = 0;
+    for (range, replacement) in edits {
+        result.push_str(&content[position..range.start]);
+        result.push_str(&replacement);
+        position = range.end;
+    }
+    result.push_str(&content[position..]);
+    result
+}
+
+/// 在项目中重命名符号：所有文件中与旧名称相同的标识符都会被替换
+///
+/// `root` 为文件时只修改该文件；新名称已在某个文件中使用时返回错误，避免与已有符号混淆
+pub fn rename_symbol(root: &Path, old_name: &str, new_name: &str) -> AppResult<Refactoring> {
+    check_identifier(old_name)?;
+    check_identifier(new_name)?;
+    if old_name == new_name {
+        return Err(AppError::Other("新名称与旧名称相同".to_string()));
+    }
+
+    let files = if root.is_file() {
+        vec![root.to_path_buf()]
+    } else {
+        ProjectWalker::new().walk(root)?
+    };
+
+    let mut changes = Vec::new();
+    let mut occurrences = 0;
+    for path in files {
+        let Some(rules) = PARSER_REGISTRY
+            .read()
+            .unwrap()
+            .get_parser_by_filename(&path.to_string_lossy())
+            .and_then(|parser| rules_for(&parser.language_name()))
+        else {
+            continue;
+        };
+        let Some(content) = read_text_file(&path, DEFAULT_MAX_FILE_SIZE)? else {
+            continue;
+        };
+        // 只包含新名称的文件也要检查，新名称在项目任意位置已被使用时拒绝
+        if !content.contains(old_name) && !content.contains(new_name) {
+            continue;
+        }
+
+        let (_, tree) = parse_file(&path, &content)?;
+        let mut edits = Vec::new();
+        for node in descendants(tree.root_node()) {
+            if !rules.identifiers.contains(&node.kind()) {
+                continue;
+            }
+            match text(node, &content) {
+                name if name == old_name => {
+                    edits.push((node.byte_range(), new_name.to_string()));
+                }
+                name if name == new_name => {
+                    return Err(AppError::Other(format!(
+                        "名称 `{}` 已在 {} 中使用",
+                        new_name,
+                        path.display()
+                    )));
+                }
+                _ => {}
+            }
+        }
+        if !edits.is_empty() {
+            occurrences += edits.len();
+            let modified = apply_edits(&content, edits);
+            changes.push(FileChange {
+                path,
+                original: content,
+                modified,
+            });
+        }
+    }
+
+    Refactoring::new(
+        format!(
+            "将 `{}` 重命名为 `{}`：{} 个文件中的 {} 处",
+            old_name,
+            new_name,
+            changes.len(),
+            occurrences
+        ),
+        changes,
+    )
+}
+
+/// 在文件中查找表达式的字节范围，指定行号时只匹配从该行开始的位置；表达式出现多次时返回错误
+pub fn find_selection(
+    content: &str,
+    expression: &str,
+    line: Option<u32>,
+) -> AppResult<Range<usize>> {
+    let expression = expression.trim();
+    if expression.is_empty() {
+        return Err(AppError::Other("表达式不能为空".to_string()));
+    }
+    let matches: Vec<usize> = content
+        .match_indices(expression)
+        .map(|(start, _)| start)
+        .filter(|start| {
+            line.is_none_or(|line| content[..*start].matches('\n').count() as u32 + 1 == line)
+        })
+        .collect();
+    match matches[..] {
+        [start] => Ok(start..start + expression.len()),
+        [] => Err(AppError::Other(format!("未找到表达式 `{}`", expression))),
+        _ => Err(AppError::Other(format!(
+            "表达式 `{}` 出现 {} 次，请指定所在行",
+            expression,
+            matches.len()
+        ))),
+    }
+}
+
+/// 判断节点是否处于绑定或修改变量的位置
+fn is_binding(node: TsNode, rules: &VariableRules) -> bool {
+    if rules.binding_nodes.contains(&node.kind()) {
+        return true;
+    }
+    let Some(parent) = node.parent() else {
+        return false;
+    };
+    if parent.kind().ends_with("_pattern") {
+        return true;
+    }
+    // Rust 的 `&mut name`
+    if parent.kind() == "reference_expression" {
+        let mut cursor = parent.walk();
+        if parent
+            .children(&mut cursor)
+            .any(|child| child.kind() == "mutable_specifier")
+        {
+            return true;
+        }
+    }
+    rules.bindings.iter().any(|(kind, field)| {
+        parent.kind() == *kind
+            && field.is_none_or(|field| parent.child_by_field_name(field) == Some(node))
+    })
+}
+
+/// 判断标识符节点是否为变量引用
+fn is_reference(node: TsNode, rules: &VariableRules) -> bool {
+    if !rules.references.contains(&node.kind()) {
+        return false;
+    }
+    let Some(parent) = node.parent() else {
+        return true;
+    };
+    !rules.non_references.iter().any(|(kind, field)| {
+        parent.kind() == *kind && parent.child_by_field_name(field) == Some(node)
+    })
+}
+
+/// 判断节点是否为简写形式的字段，如 Rust 的 `Point { x }` 和 JavaScript 的 `{ x }`
+fn is_shorthand(node: TsNode) -> bool {
+    node.kind() == "shorthand_property_identifier"
+        || node
+            .parent()
+            .is_some_and(|parent| parent.kind() == "shorthand_field_initializer")
+}
+
+/// 判断子节点在父节点中的求值次数或时机是否与父节点不同
+fn is_barrier(parent: TsNode, child: TsNode, rules: &VariableRules) -> bool {
+    rules.barriers.contains(&parent.kind())
+        || rules.conditional.iter().any(|(kind, field, operators)| {
+            parent.kind() == *kind
+                && parent.child_by_field_name(field) == Some(child)
+                && (operators.is_empty()
+                    || parent
+                        .child_by_field_name("operator")
+                        .is_some_and(|operator| operators.contains(&operator.kind())))
+        })
+}
+
+/// 判断节点到作用域之间是否跨越了闭包、循环或条件求值的位置
+fn crosses_barrier(node: TsNode, scope: TsNode, rules: &VariableRules) -> bool {
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if parent == scope {
+            return false;
+        }
+        if is_barrier(parent, current, rules) {
+            return true;
+        }
+        current = parent;
+    }
+    false
+}
+
+/// 查找表达式所在的语句，表达式跨越闭包、循环条件或条件分支时返回错误
+fn enclosing_statement<'a>(node: TsNode<'a>, rules: &VariableRules) -> AppResult<TsNode<'a>> {
+    let mut current = node;
+    while let Some(parent) = current.parent() {
+        if rules.blocks.contains(&parent.kind()) {
+            return Ok(current);
+        }
+        if is_barrier(parent, current, rules) {
+            return Err(AppError::Other(format!(
+                "表达式位于 {} 中，提取到语句之前会改变求值",
+                parent.kind()
+            )));
+        }
+        current = parent;
+    }
+    Err(AppError::Other("表达式不在语句块中".to_string()))
+}
+
+/// 获取节点所在行中位于节点之前的缩进，节点之前还有其他代码时返回 `None`
+fn indentation(content: &str, start: usize) -> Option<&str> {
+    let line_start = content[..start].rfind('\n').map_or(0, |index| index + 1);
+    let prefix = &content[line_start..start];
+    prefix.trim().is_empty().then_some(prefix)
+}
+
+/// 获取删除语句的范围：语句独占一行时包含整行
+fn statement_removal(content: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = content[..range.start]
+        .rfind('\n')
+        .map_or(0, |index| index + 1);
+    let line_end = content[range.end..]
+        .find('\n')
+        .map_or(content.len(), |index| range.end + index + 1);
+    if content[line_start..range.start].trim().is_empty()
+        && content[range.end..line_end].trim().is_empty()
+    {
+        line_start..line_end
+    } else {
+        range
+    }
+}
+
+/// 是否为方法调用的被调用部分，提取后会丢失接收者
+fn is_method_callee(node: TsNode) -> bool {
+    matches!(node.kind(), "field_expression" | "member_expression")
+        && node.parent().is_some_and(|parent| {
+            parent.kind() == "call_expression"
+                && parent.child_by_field_name("function") == Some(node)
+        })
+}
+
+/// 将选中的表达式提取为变量，在所在语句之前声明
+pub fn extract_variable(
+    path: &Path,
+    content: &str,
+    selection: Range<usize>,
+    name: &str,
+) -> AppResult<Refactoring> {
+    check_identifier(name)?;
+    let (language, tree) = parse_file(path, content)?;
+    let rules = variable_rules(&language, "提取变量")?;
+
+    let selected = content
+        .get(selection.clone())
+        .ok_or_else(|| AppError::Other("选择范围超出文件内容".to_string()))?;
+    let start = selection.start + (selected.len() - selected.trim_start().len());
+    let end = selection.end - (selected.len() - selected.trim_end().len());
+    let expression = tree
+        .root_node()
+        .named_descendant_for_byte_range(start, end)
+        .filter(|node| node.start_byte() == start && node.end_byte() == end)
+        .ok_or_else(|| AppError::Other("选中的文本不是完整的表达式".to_string()))?;
+    let kind = expression.kind();
+    if kind.ends_with("statement")
+        || kind.ends_with("declaration")
+        || kind.ends_with("definition")
+        || kind.ends_with("_item")
+        || (kind.contains("type") && !kind.ends_with("expression"))
+        || kind.contains("pattern")
+        || rules.blocks.contains(&kind)
+        || is_binding(expression, &rules)
+        || (rules.references.contains(&kind) && !is_reference(expression, &rules))
+        || is_method_callee(expression)
+    {
+        return Err(AppError::Other(format!(
+            "选中的 {} 不是可以提取的表达式",
+            kind
+        )));
+    }
+
+    let statement = enclosing_statement(expression, &rules)?;
+    let block = statement.parent().unwrap_or(statement);
+    if descendants(block)
+        .into_iter()
+        .any(|node| rules.references.contains(&node.kind()) && text(node, content) == name)
+    {
+        return Err(AppError::Other(format!("名称 `{}` 已在作用域中使用", name)));
+    }
+
+    let declaration = (rules.declare)(name, text(expression, content));
+    let insertion = match indentation(content, statement.start_byte()) {
+        Some(indent) => format!("{}\n{}", declaration, indent),
+        None => format!("{} ", declaration),
+    };
+    let modified = apply_edits(
+        content,
+        vec![
+            (statement.start_byte()..statement.start_byte(), insertion),
+            (expression.byte_range(), name.to_string()),
+        ],
+    );
+    Refactoring::new(
+        format!(
+            "将第 {} 行的表达式提取为变量 `{}`",
+            expression.start_position().row + 1,
+            name
+        ),
+        vec![FileChange {
+            path: path.to_path_buf(),
+            original: content.to_string(),
+            modified,
+        }],
+    )
+}
+
+/// 内联变量：用初始值替换所有引用并删除声明；`line` 为声明所在行，同名变量有多个声明时必须指定
+pub fn inline_variable(
+    path: &Path,
+    content: &str,
+    name: &str,
+    line: Option<u32>,
+) -> AppResult<Refactoring> {
+    let (language, tree) = parse_file(path, content)?;
+    let rules = variable_rules(&language, "内联变量")?;
+
+    let declarations: Vec<Declaration> = descendants(tree.root_node())
+        .into_iter()
+        .filter_map(rules.declaration)
+        .filter(|declaration| text(declaration.name, content) == name)
+        .filter(|declaration| {
+            line.is_none_or(|line| {
+                let first = declaration.statement.start_position().row as u32 + 1;
+                let last = declaration.statement.end_position().row as u32 + 1;
+                (first..=last).contains(&line)
+            })
+        })
+        .collect();
+    let declaration = match declarations.len() {
+        1 => &declarations[0],
+        0 => return Err(AppError::Other(format!("未找到变量 `{}` 的声明", name))),
+        count => {
+            return Err(AppError::Other(format!(
+                "变量 `{}` 有 {} 个声明，请指定所在行",
+                name, count
+            )))
+        }
+    };
+    if declaration.mutable {
+        return Err(AppError::Other(format!(
+            "变量 `{}` 是可变的，不能内联",
+            name
+        )));
+    }
+    if !declaration
+        .statement
+        .parent()
+        .is_some_and(|parent| rules.blocks.contains(&parent.kind()))
+    {
+        return Err(AppError::Other(format!(
+            "只能内联语句块中的局部变量 `{}`",
+            name
+        )));
+    }
+
+    // 作用域中声明之后的引用
+    let after = declaration.statement.end_byte();
+    let mut references = Vec::new();
+    for node in descendants(declaration.scope) {
+        if node.start_byte() < after {
+            continue;
+        }
+        if rules.format_strings.contains(&node.kind()) {
+            let literal = text(node, content);
+            if literal.contains(&format!("{{{}}}", name))
+                || literal.contains(&format!("{{{}:", name))
+            {
+                return Err(AppError::Other(format!(
+                    "变量 `{}` 在第 {} 行的格式化字符串中使用",
+                    name,
+                    node.start_position().row + 1
+                )));
+            }
+            continue;
+        }
+        let is_name =
+            rules.references.contains(&node.kind()) || rules.binding_nodes.contains(&node.kind());
+        if !is_name || text(node, content) != name {
+            continue;
+        }
+        if is_binding(node, &rules) {
+            return Err(AppError::Other(format!(
+                "变量 `{}` 在第 {} 行被重新绑定或修改",
+                name,
+                node.start_position().row + 1
+            )));
+        }
+        if is_reference(node, &rules) {
+            references.push(node);
+        }
+    }
+
+    let value = declaration.value;
+    let has_effects = descendants(value)
+        .into_iter()
+        .any(|node| rules.effects.contains(&node.kind()));
+    if has_effects {
+        let repeated =
+            references.len() != 1 || crosses_barrier(references[0], declaration.scope, &rules);
+        if repeated {
+            return Err(AppError::Other(format!(
+                "变量 `{}` 的初始值有副作用，内联后求值次数会改变",
+                name
+            )));
+        }
+    }
+
+    let value_text = text(value, content);
+    let atomic = rules.atomic.contains(&value.kind());
+    let mut edits: Vec<(Range<usize>, String)> = references
+        .iter()
+        .map(|reference| {
+            let replacement = if is_shorthand(*reference) {
+                format!("{}: {}", name, value_text)
+            } else if atomic
+                || reference
+                    .parent()
+                    .is_some_and(|parent| rules.open_parents.contains(&parent.kind()))
+            {
+                value_text.to_string()
+            } else {
+                format!("({})", value_text)
+            };
+            (reference.byte_range(), replacement)
+        })
+        .collect();
+    edits.push((
+        statement_removal(content, declaration.statement.byte_range()),
+        String::new(),
+    ));
+
+    let modified = apply_edits(content, edits);
+    Refactoring::new(
+        format!("内联变量 `{}`：替换 {} 处引用", name, references.len()),
+        vec![FileChange {
+            path: path.to_path_buf(),
+            original: content.to_string(),
+            modified,
+        }],
+    )
+}
@@ -0,0 +1,312 @@
+//! 会话持久化模块
+//!
+//! 将 `ContextManager` 中的对话保存到数据目录，支持列出、恢复、删除、重命名和分叉会话
+
+use crate::context::{ContextExportFormat, ContextItemType, ContextManager};
+use crate::error::{AppError, AppResult};
+use clap::Subcommand;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 新会话的默认标题
+const DEFAULT_TITLE: &str = "未命名会话";
+
+/// 自动生成标题时保留的最大字符数
+const TITLE_MAX_CHARS: usize = 50;
+
+/// 会话操作枚举
+#[derive(Debug, Clone, Subcommand)]
+pub enum SessionActions {
+    /// 列出已保存的会话
+    List {
+        /// 只显示当前目录下的会话
+        #[arg(long, short)]
+        here: bool,
+    },
+
+    /// 恢复会话并进入交互模式
+    Resume {
+        /// 会话ID（支持唯一前缀）
+        id: String,
+    },
+
+    /// 删除会话
+    Delete {
+        /// 会话ID（支持唯一前缀）
+        id: String,
+    },
+
+    /// 重命名会话
+    Rename {
+        /// 会话ID（支持唯一前缀）
+        id: String,
+
+        /// 新标题
+        title: String,
+    },
+
+    /// 从指定消息处分叉出新会话
+    Fork {
+        /// 会话ID（支持唯一前缀）
+        id: String,
+
+        /// 保留的消息数量（从1开始计数，分叉点之后的消息会被丢弃）
+        #[arg(long)]
+        at: usize,
+    },
+
+    /// 导出会话记录
+    Export {
+        /// 会话ID（支持唯一前缀）
+        id: String,
+
+        /// 导出格式（md, html, json, yaml, text）
+        #[arg(long, short, default_value = "md")]
+        format: String,
+
+        /// 输出文件或目录路径，不指定则输出到标准输出
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// 会话元数据
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMeta {
+    /// 会话唯一标识符
+    pub id: String,
+    /// 会话标题
+    pub title: String,
+    /// 会话创建时所在的工作目录
+    pub cwd: PathBuf,
+    /// 创建时间（Unix秒）
+    pub created_at: i64,
+    /// 更新时间（Unix秒）
+    pub updated_at: i64,
+    /// 消息数量
+    pub message_count: usize,
+    /// 分叉来源会话ID
+    #[serde(default)]
+    pub forked_from: Option<String>,
+    /// 分叉时保留的消息数量
+    #[serde(default)]
+    pub forked_at: Option<usize>,
+}
+
+impl SessionMeta {
+    /// 创建新的会话元数据
+    pub fn new(cwd: &Path) -> Self {
+        let now = chrono::Utc::now().timestamp();
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            title: DEFAULT_TITLE.to_string(),
+            cwd: cwd.to_path_buf(),
+            created_at: now,
+            updated_at: now,
+            message_count: 0,
+            forked_from: None,
+            forked_at: None,
+        }
+    }
+
+    /// 获取用于显示的短ID
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(8)]
+    }
+}
+
+/// 会话存储，每个会话保存在 `<data_dir>/sessions/<id>/` 目录下
+pub struct SessionStore {
+    /// 会话根目录
+    root: PathBuf,
+}
+
+impl SessionStore {
+    /// 创建新的会话存储实例
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            root: data_dir.join("sessions"),
+        }
+    }
+
+    /// 获取会话目录
+    fn session_dir(&self, id: &str) -> PathBuf {
+        self.root.join(id)
+    }
+
+    /// 获取会话元数据文件路径
+    fn meta_path(&self, id: &str) -> PathBuf {
+        self.session_dir(id).join("meta.json")
+    }
+
+    /// 获取会话上下文文件路径
+    fn context_path(&self, id: &str) -> PathBuf {
+        self.session_dir(id).join("context.json")
+    }
+
+    /// 保存会话上下文并更新元数据
+    pub fn save(&self, meta: &mut SessionMeta, context: &ContextManager) -> AppResult<()> {
+        let data = context
+            .export(ContextExportFormat::Json)
+            .map_err(|e| AppError::Other(format!("导出会话上下文失败: {}", e)))?;
+
+        meta.updated_at = chrono::Utc::now().timestamp();
+        meta.message_count = context.len();
+        if meta.title == DEFAULT_TITLE {
+            if let Some(title) = Self::title_from_context(context) {
+                meta.title = title;
+            }
+        }
+
+        fs::create_dir_all(self.session_dir(&meta.id))?;
+        fs::write(self.context_path(&meta.id), data)?;
+        self.write_meta(meta)
+    }
+
+    /// 加载会话元数据和上下文
+    pub fn load(&self, id: &str) -> AppResult<(SessionMeta, ContextManager)> {
+        let meta = self.get(id)?;
+
+        let mut context = ContextManager::default();
+        let context_path = self.context_path(&meta.id);
+        if context_path.exists() {
+            let data = fs::read_to_string(&context_path)?;
+            context
+                .import(&data, ContextExportFormat::Json)
+                .map_err(|e| AppError::Other(format!("导入会话上下文失败: {}", e)))?;
+        }
+
+        Ok((meta, context))
+    }
+
+    /// 获取会话元数据，支持使用唯一ID前缀
+    pub fn get(&self, id: &str) -> AppResult<SessionMeta> {
+        let id = self.resolve_id(id)?;
+        let data = fs::read_to_string(self.meta_path(&id))?;
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// 列出所有会话，按更新时间倒序排列
+    pub fn list(&self) -> AppResult<Vec<SessionMeta>> {
+        let mut sessions = Vec::new();
+        if !self.root.exists() {
+            return Ok(sessions);
+        }
+
+        for entry in fs::read_dir(&self.root)? {
+            let meta_path = entry?.path().join("meta.json");
+            if !meta_path.exists() {
+                continue;
+            }
+            let data = fs::read_to_string(&meta_path)?;
+            match serde_json::from_str::<SessionMeta>(&data) {
+                Ok(meta) => sessions.push(meta),
+                Err(e) => log::warn!("跳过无法解析的会话 {:?}: {}", meta_path, e),
+            }
+        }
+
+        sessions.sort_by_key(|meta| std::cmp::Reverse(meta.updated_at));
+        Ok(sessions)
+    }
+
+    /// 获取指定目录下最近更新的会话
+    pub fn latest_for_dir(&self, cwd: &Path) -> AppResult<Option<SessionMeta>> {
+        Ok(self.list()?.into_iter().find(|meta| meta.cwd == cwd))
+    }
+
+    /// 删除会话
+    pub fn delete(&self, id: &str) -> AppResult<SessionMeta> {
+        let meta = self.get(id)?;
+        fs::remove_dir_all(self.session_dir(&meta.id))?;
+        Ok(meta)
+    }
+
+    /// 重命名会话
+    pub fn rename(&self, id: &str, title: &str) -> AppResult<SessionMeta> {
+        let mut meta = self.get(id)?;
+        meta.title = title.to_string();
+        meta.updated_at = chrono::Utc::now().timestamp();
+        self.write_meta(&meta)?;
+        Ok(meta)
+    }
+
+    /// 从指定会话的前 `at` 条消息分叉出新会话
+    pub fn fork(&self, id: &str, at: usize) -> AppResult<SessionMeta> {
+        let (source, mut context) = self.load(id)?;
+        if at == 0 || at > context.len() {
+            return Err(AppError::Other(format!(
+                "分叉位置无效: {}，会话共有 {} 条消息",
+                at,
+                context.len()
+            )));
+        }
+        context.truncate(at);
+
+        let mut meta = SessionMeta::new(&source.cwd);
+        meta.title = format!("{} (分叉 #{})", source.title, at);
+        meta.forked_from = Some(source.id.clone());
+        meta.forked_at = Some(at);
+        self.save(&mut meta, &context)?;
+        Ok(meta)
+    }
+
+    /// 导出会话记录，Markdown和HTML格式使用会话标题作为文档标题
+    pub fn export(&self, id: &str, format: ContextExportFormat) -> AppResult<String> {
+        let (meta, context) = self.load(id)?;
+        match format {
+            ContextExportFormat::Markdown => Ok(context.render_markdown(&meta.title)),
+            ContextExportFormat::Html => Ok(context.render_html(&meta.title)),
+            _ => context
+                .export(format)
+                .map_err(|e| AppError::Other(format!("导出会话失败: {}", e))),
+        }
+    }
+
+    /// 将ID前缀解析为完整的会话ID
+    fn resolve_id(&self, prefix: &str) -> AppResult<String> {
+        // ID会拼接到会话目录下，拒绝可能指向目录之外的路径
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(AppError::Other(format!("无效的会话ID: {}", prefix)));
+        }
+        if self.meta_path(prefix).exists() {
+            return Ok(prefix.to_string());
+        }
+
+        let matches: Vec<String> = self
+            .list()?
+            .into_iter()
+            .map(|meta| meta.id)
+            .filter(|id| id.starts_with(prefix))
+            .collect();
+
+        match matches.len() {
+            0 => Err(AppError::Other(format!("未找到会话: {}", prefix))),
+            1 => Ok(matches.into_iter().next().unwrap()),
+            n => Err(AppError::Other(format!(
+                "会话ID前缀 '{}' 匹配到 {} 个会话，请提供更长的前缀",
+                prefix, n
+            ))),
+        }
+    }
+
+    /// 写入会话元数据
+    fn write_meta(&self, meta: &SessionMeta) -> AppResult<()> {
+        let data = serde_json::to_string_pretty(meta)?;
+        fs::write(self.meta_path(&meta.id), data)?;
+        Ok(())
+    }
+
+    /// 使用第一条用户消息生成会话标题
+    fn title_from_context(context: &ContextManager) -> Option<String> {
+        context
+            .get_context()
+            .into_iter()
+            .find(|item| item.item_type == ContextItemType::UserMessage)
+            .map(|item| {
+                let first_line = item.content.lines().next().unwrap_or("").trim();
+                first_line.chars().take(TITLE_MAX_CHARS).collect::<String>()
+            })
+            .filter(|title| !title.is_empty())
+    }
+}
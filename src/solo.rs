@@ -1,8 +1,20 @@
 use crate::ai::AIClient;
+use crate::code::CodeProgrammer;
+use crate::knowledge::walker::ProjectWalker;
 use crate::task::{TaskManager, TaskStatus};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Directories that are never shown in the codebase structure
+const SKIP_DIRS: [&str; 5] = ["target", ".git", "node_modules", "venv", ".venv"];
+
+/// File extensions shown in the codebase structure
+const CODE_EXTENSIONS: [&str; 17] = [
+    "rs", "py", "js", "ts", "jsx", "tsx", "go", "java", "cpp", "c", "h", "html", "css", "json",
+    "yaml", "yml", "toml",
+];
 
 /// Solo mode step structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,9 +237,27 @@ impl SoloAgent {
             return Err(format!("Path does not exist: {}", path.display()).into());
         }
 
+        // Collect the code files that are not ignored, plus their parent directories
+        let code_extensions: Vec<String> = CODE_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+        let mut visible = HashSet::new();
+        if path.is_dir() {
+            for file in ProjectWalker::new()
+                .extensions(&code_extensions)
+                .exclude_patterns(&SKIP_DIRS.map(String::from))
+                .walk(path)?
+            {
+                for ancestor in file.ancestors() {
+                    if !visible.insert(ancestor.to_path_buf()) || ancestor == path {
+                        break;
+                    }
+                }
+            }
+        }
+        visible.insert(path.to_path_buf());
+
         // Get directory structure
         let mut dir_structure = String::new();
-        self.get_directory_structure(path, "", &mut dir_structure)?;
+        self.get_directory_structure(path, "", &visible, &mut dir_structure)?;
 
         // Analyze codebase with AI
         let prompt = format!(
@@ -243,28 +273,26 @@ impl SoloAgent {
     }
 
     /// Helper function to get directory structure
+    ///
+    /// Only entries in `visible` (files kept by the project walker and their
+    /// parent directories) are listed.
     fn get_directory_structure(
         &self,
         path: &Path,
         prefix: &str,
+        visible: &HashSet<PathBuf>,
         output: &mut String,
     ) -> Result<(), Box<dyn Error>> {
-        // Skip certain directories
-        let skip_dirs = ["target", ".git", "node_modules", "venv", ".venv"];
         let file_name = path.file_name().unwrap_or_default().to_str().unwrap_or("");
 
         if path.is_dir() {
-            // Skip certain directories
-            if skip_dirs.contains(&file_name) {
-                return Ok(());
-            }
-
             // Add directory to output
             output.push_str(&format!("{}📁 {}/\n", prefix, file_name));
 
             // Recursively process files and subdirectories
             let mut entries = std::fs::read_dir(path)?
                 .filter_map(|e| e.ok())
+                .filter(|e| visible.contains(&e.path()))
                 .collect::<Vec<_>>();
 
             // Sort entries: directories first, then files
@@ -281,7 +309,7 @@ impl SoloAgent {
                 }
             });
 
-            let last_index = entries.len() - 1;
+            let last_index = entries.len().saturating_sub(1);
 
             for (i, entry) in entries.into_iter().enumerate() {
                 // Determine next prefix for recursion
@@ -292,17 +320,13 @@ impl SoloAgent {
                 };
 
                 // Recursively process entry
-                self.get_directory_structure(&entry.path(), &next_prefix, output)?;
+                self.get_directory_structure(&entry.path(), &next_prefix, visible, output)?;
             }
         } else {
             // Add file to output (only for certain file types)
             let file_ext = path.extension().unwrap_or_default().to_str().unwrap_or("");
-            let code_extensions = [
-                "rs", "py", "js", "ts", "jsx", "tsx", "go", "java", "cpp", "c", "h", "html", "css",
-                "json", "yaml", "yml", "toml",
-            ];
 
-            if code_extensions.contains(&file_ext) {
+            if CODE_EXTENSIONS.contains(&file_ext) {
                 output.push_str(&format!(
                     "{}📄 {} ({} bytes)\n",
                     prefix,
@@ -446,10 +470,12 @@ impl SoloAgent {
         );
 
         let response = self.ai_client.generate_response(&prompt, None).await?;
-        let updated_content = response.content().to_string();
 
-        // Write updated content back to file
-        std::fs::write(file_path, updated_content.clone())?;
+        // Make sure the updated code parses before writing it back to the file
+        let path = Path::new(file_path);
+        let updated_content =
+            CodeProgrammer::repair_syntax(&self.ai_client, path, response.content()).await?;
+        CodeProgrammer::write_code_to_file(path, &updated_content)?;
 
         Ok(updated_content)
     }
@@ -6,10 +6,39 @@ use ratatui::{prelude::*, widgets::*};
 use std::collections::HashMap;
 use std::error::Error;
 use std::io::stdout;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use crate::ai::adapter::AIClient;
+use crate::config::app::KnowledgeConfig;
+use crate::context::{ContextItem, ContextItemType};
+use crate::knowledge::base::KnowledgeBase;
+use crate::knowledge::local::LocalKnowledgeBase;
+use crate::knowledge::pipeline::{FileStatus, IndexEvent};
+use crate::knowledge::query::{HighlightedLine, SearchQuery};
+use crate::knowledge::walker::{read_text_file, ProjectWalker};
+use crate::knowledge::watcher::{KnowledgeWatcher, WatchEvent, WatchHandle, DEFAULT_DEBOUNCE_MS};
+use crate::mentions::MentionResolver;
+use crate::parse_cache::{ChangedRange, ParseCache};
+use crate::session::{SessionMeta, SessionStore};
+
+/// Maximum number of symbols and documents shown in search mode
+const SEARCH_RESULT_LIMIT: usize = 20;
+
+/// Number of files the code browser keeps parsed
+const CODE_BROWSER_CACHE_FILES: usize = 32;
+
+/// A knowledge base search result shown in search mode
+struct SearchResult {
+    /// Symbol location (`path:line`) or document path, recorded when selected
+    id: String,
+    /// Result kind, name and location
+    title: String,
+    /// Matched lines with their line numbers
+    lines: Vec<HighlightedLine>,
+}
 
 /// Widget cache for optimized rendering
 struct WidgetCache {
@@ -44,7 +73,11 @@ struct App {
     /// Search query
     search_query: String,
     /// Search results
-    search_results: Vec<String>,
+    search_results: Vec<SearchResult>,
+    /// Knowledge base opened while search mode is active
+    search_kb: Option<LocalKnowledgeBase>,
+    /// Error from opening the knowledge base or parsing the query
+    search_error: Option<String>,
     /// Selected search result index
     selected_result: usize,
     /// Search history
@@ -59,12 +92,34 @@ struct App {
     file_content: Vec<String>,
     /// File content offset
     file_offset: usize,
+    /// Parsed files, so reopening a file after an edit only reparses what changed
+    code_cache: ParseCache,
+    /// Ranges of the open file that changed since it was last opened
+    file_changes: Vec<ChangedRange>,
     /// Settings mode enabled
     settings_mode: bool,
     /// Current setting being edited
     selected_setting: usize,
     /// Setting options
     settings: Vec<(String, String)>,
+    /// Persistent chat session store
+    session_store: Option<SessionStore>,
+    /// Current chat session
+    session: Option<SessionMeta>,
+    /// Quit flag, set by Ctrl+C or the `exit` command
+    should_quit: bool,
+    /// Resolver for @file and @symbol mentions in chat input
+    mention_resolver: Option<MentionResolver>,
+    /// Items pinned into the AI context via @ mentions, refreshed when pins change
+    attached: Vec<ContextItem>,
+    /// Knowledge base settings from the loaded configuration
+    knowledge_config: KnowledgeConfig,
+    /// Background knowledge base watcher and its progress events
+    watcher: Option<(WatchHandle, Receiver<WatchEvent>)>,
+    /// Progress events from a background `index` run
+    indexing: Option<Receiver<Result<IndexEvent, String>>>,
+    /// Position of the output line that shows indexing progress
+    index_progress_line: Option<usize>,
 
     // Rendering optimization fields
     /// Last render time
@@ -111,6 +166,8 @@ impl App {
             search_mode: false,
             search_query: String::new(),
             search_results: Vec::new(),
+            search_kb: None,
+            search_error: None,
             selected_result: 0,
             search_history: Vec::new(),
             search_history_index: None,
@@ -118,6 +175,8 @@ impl App {
             selected_file: String::new(),
             file_content: Vec::new(),
             file_offset: 0,
+            code_cache: ParseCache::with_capacity(CODE_BROWSER_CACHE_FILES),
+            file_changes: Vec::new(),
             settings_mode: false,
             selected_setting: 0,
             settings: vec![
@@ -127,6 +186,15 @@ impl App {
                 ("Max Tokens".to_string(), "1024".to_string()),
                 ("Temperature".to_string(), "0.7".to_string()),
             ],
+            session_store: None,
+            session: None,
+            should_quit: false,
+            mention_resolver: None,
+            attached: Vec::new(),
+            knowledge_config: KnowledgeConfig::default(),
+            watcher: None,
+            indexing: None,
+            index_progress_line: None,
 
             // Rendering optimization defaults
             last_render: now,
@@ -202,6 +270,402 @@ impl App {
         self.last_render = Instant::now();
         self.render_count += 1;
     }
+
+    /// Attach a chat session and replay its transcript into the output area
+    fn attach_session(&mut self, store: SessionStore, session: SessionMeta) {
+        if session.message_count > 0 {
+            self.output.push(format!(
+                "Resumed session {} - {}",
+                session.short_id(),
+                session.title
+            ));
+            for item in self.ai_client.get_context() {
+                match item.item_type {
+                    ContextItemType::UserMessage => self.output.push(format!("$ {}", item.content)),
+                    ContextItemType::AIMessage => {
+                        self.output.push(format!("Codex: {}", item.content))
+                    }
+                    _ => {}
+                }
+            }
+            self.output_offset = self.output.len().saturating_sub(1);
+        }
+
+        self.session_store = Some(store);
+        self.session = Some(session);
+        self.refresh_attached();
+        self.mark_output_dirty();
+    }
+
+    /// Get the items pinned into the AI context via @ mentions
+    fn attached_items(&self) -> &[ContextItem] {
+        &self.attached
+    }
+
+    /// Reload the pinned items after they were attached or detached
+    fn refresh_attached(&mut self) {
+        self.attached = self
+            .ai_client
+            .context_manager()
+            .read()
+            .unwrap()
+            .pinned_items();
+    }
+
+    /// Enter search mode and open the knowledge base for live search
+    fn open_search(&mut self) {
+        self.search_mode = true;
+        self.search_query.clear();
+        self.search_results.clear();
+        self.selected_result = 0;
+        self.search_error = None;
+
+        let kb = LocalKnowledgeBase::new(self.knowledge_config.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|mut kb| {
+                kb.set_quiet(true);
+                kb.init().map_err(|e| e.to_string())?;
+                Ok(kb)
+            });
+        match kb {
+            Ok(kb) => self.search_kb = Some(kb),
+            // The watcher holds the index lock while it is running
+            Err(e) => self.search_error = Some(format!("Knowledge base unavailable: {}", e)),
+        }
+    }
+
+    /// Leave search mode and release the knowledge base
+    fn close_search(&mut self) {
+        self.search_mode = false;
+        self.search_kb = None;
+        self.search_results.clear();
+        self.search_error = None;
+    }
+
+    /// Run the current query against the knowledge base
+    fn run_search(&mut self) {
+        let Some(kb) = &self.search_kb else {
+            return;
+        };
+        self.selected_result = 0;
+        if self.search_query.trim().is_empty() {
+            self.search_results.clear();
+            self.search_error = None;
+            return;
+        }
+
+        let results = SearchQuery::parse(&self.search_query).and_then(|query| {
+            let highlighter = query.highlighter();
+            let mut results = Vec::new();
+            for symbol in kb.search_symbols(&self.search_query, SEARCH_RESULT_LIMIT)? {
+                let definition = &symbol.element.definition;
+                let mut lines = Vec::new();
+                if !symbol.signature.is_empty() {
+                    lines.push(HighlightedLine {
+                        line: definition.line as usize,
+                        ranges: highlighter.ranges(&symbol.signature),
+                        text: symbol.signature.clone(),
+                    });
+                }
+                results.push(SearchResult {
+                    id: format!("{}:{}", definition.file_path, definition.line),
+                    title: format!(
+                        "{} {} - {}:{}",
+                        symbol.element.element_type.as_str(),
+                        symbol.element.name,
+                        definition.file_path,
+                        definition.line
+                    ),
+                    lines,
+                });
+            }
+            for hit in kb.search_documents(&self.search_query, SEARCH_RESULT_LIMIT)? {
+                let location = match hit.lines {
+                    Some((start, end)) => format!("{}:{}-{}", hit.path, start, end),
+                    None => hit.path.clone(),
+                };
+                results.push(SearchResult {
+                    id: hit.path.clone(),
+                    title: format!("{} - {}", hit.title, location),
+                    lines: hit.highlights,
+                });
+            }
+            Ok(results)
+        });
+        match results {
+            Ok(results) => {
+                self.search_results = results;
+                self.search_error = None;
+            }
+            // Keep the previous results while the query is being typed
+            Err(e) => self.search_error = Some(e.to_string()),
+        }
+    }
+
+    /// Start watching the current directory and re-indexing changed files
+    fn start_watch(&mut self) {
+        if self.watcher.is_some() {
+            self.output
+                .push("[watch] Already watching, use 'watch stop' to stop".to_string());
+            self.mark_output_dirty();
+            return;
+        }
+
+        let watcher = std::env::current_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|cwd| {
+                KnowledgeWatcher::new(
+                    self.knowledge_config.clone(),
+                    &cwd,
+                    Duration::from_millis(DEFAULT_DEBOUNCE_MS),
+                )
+                .map_err(|e| e.to_string())
+            });
+        match watcher {
+            Ok(watcher) => {
+                // Index logging from the watcher thread would draw over the terminal UI
+                log::set_max_level(log::LevelFilter::Error);
+                let (sender, receiver) = mpsc::channel();
+                self.watcher = Some((watcher.spawn(sender), receiver));
+            }
+            Err(e) => self.output.push(format!("[watch] Failed to start: {}", e)),
+        }
+        self.mark_output_dirty();
+    }
+
+    /// Stop the background watcher
+    fn stop_watch(&mut self) {
+        match self.watcher.take() {
+            Some((mut handle, receiver)) => {
+                handle.stop();
+                self.output
+                    .extend(receiver.try_iter().map(|event| format!("[watch] {}", event)));
+            }
+            None => self.output.push("[watch] Not watching".to_string()),
+        }
+        self.mark_output_dirty();
+    }
+
+    /// List the files the knowledge base would index when the code browser is first shown
+    fn enter_code_browser(&mut self) {
+        if !self.code_files.is_empty() {
+            return;
+        }
+        let Ok(root) = std::env::current_dir() else {
+            return;
+        };
+        match ProjectWalker::from_config(&self.knowledge_config).walk(&root) {
+            Ok(paths) => {
+                self.code_files = paths
+                    .iter()
+                    .map(|path| {
+                        let path = path.strip_prefix(&root).unwrap_or(path);
+                        path.to_string_lossy().to_string()
+                    })
+                    .collect();
+            }
+            Err(e) => self.file_content = vec![format!("Failed to list files: {}", e)],
+        }
+        if let Some(first) = self.code_files.first() {
+            self.selected_file = first.clone();
+            self.open_code_file();
+        }
+    }
+
+    /// Move the code browser selection by `delta` files and open the selected file
+    fn select_code_file(&mut self, delta: isize) {
+        let Some(last) = self.code_files.len().checked_sub(1) else {
+            return;
+        };
+        let current = self
+            .code_files
+            .iter()
+            .position(|file| *file == self.selected_file)
+            .unwrap_or(0);
+        let index = current.saturating_add_signed(delta).min(last);
+        if index != current || self.file_content.is_empty() {
+            self.selected_file = self.code_files[index].clone();
+            self.open_code_file();
+        }
+    }
+
+    /// Read the selected file and parse it, incrementally when it was opened before
+    fn open_code_file(&mut self) {
+        let path = self.selected_file.clone();
+        let max_size = ProjectWalker::from_config(&self.knowledge_config).file_size_limit();
+        self.file_offset = 0;
+        self.file_changes.clear();
+        let content = match read_text_file(Path::new(&path), max_size) {
+            Ok(Some(content)) => content,
+            Ok(None) => {
+                self.file_content = vec!["Binary or too large to display".to_string()];
+                return;
+            }
+            Err(e) => {
+                self.code_cache.remove(&path);
+                self.file_content = vec![format!("Failed to read {}: {}", path, e)];
+                return;
+            }
+        };
+
+        // A file parsed for the first time has no earlier version to compare with
+        let reopened = self.code_cache.elements(&path).is_some();
+        match self.code_cache.update(&path, &content) {
+            Ok(Some(update)) if reopened => self.file_changes = update.changed_ranges,
+            Ok(_) => {}
+            Err(e) => log::warn!("Failed to parse {}: {}", path, e),
+        }
+        self.file_content = content.lines().map(str::to_string).collect();
+    }
+
+    /// Index the current directory in a background thread
+    fn start_index(&mut self) {
+        if self.indexing.is_some() {
+            self.output.push("[index] Indexing is already running".to_string());
+            self.mark_output_dirty();
+            return;
+        }
+
+        let config = self.knowledge_config.clone();
+        // Index logging from the worker threads would draw over the terminal UI
+        log::set_max_level(log::LevelFilter::Error);
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let result = std::env::current_dir()
+                .map_err(|e| e.to_string())
+                .and_then(|cwd| {
+                    let mut kb = LocalKnowledgeBase::new(config).map_err(|e| e.to_string())?;
+                    kb.set_quiet(true);
+                    kb.init().map_err(|e| e.to_string())?;
+                    let paths = [cwd.to_string_lossy().to_string()];
+                    kb.add_files_with_progress(&paths, true, &mut |event| {
+                        let _ = sender.send(Ok(event));
+                    })
+                    .map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                let _ = sender.send(Err(e));
+            }
+        });
+        self.indexing = Some(receiver);
+        self.output.push("[index] Scanning files...".to_string());
+        self.index_progress_line = Some(self.output.len() - 1);
+        self.mark_output_dirty();
+    }
+
+    /// Show progress of the background index run on a single output line
+    fn poll_index_events(&mut self) {
+        let Some(receiver) = &self.indexing else {
+            return;
+        };
+        let mut finished = false;
+        let mut lines = Vec::new();
+        let mut progress = None;
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(event)) => match &event {
+                    IndexEvent::Started { total } => {
+                        progress = Some(format!("[index] 0/{} files", total));
+                    }
+                    IndexEvent::File {
+                        status: FileStatus::Failed(_),
+                        ..
+                    } => lines.push(format!("[index] {}", event)),
+                    IndexEvent::File { done, total, .. } => {
+                        progress = Some(format!("[index] {}/{} files", done, total));
+                    }
+                    IndexEvent::Checkpoint { .. } => {}
+                    IndexEvent::Finished(_) => {
+                        progress = Some(format!("[index] {}", event));
+                        finished = true;
+                    }
+                },
+                Ok(Err(e)) => {
+                    progress = Some(format!("[index] Failed: {}", e));
+                    finished = true;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    finished = true;
+                    break;
+                }
+            }
+        }
+
+        if let Some(text) = progress {
+            // The progress line is re-created when the output has been cleared
+            match self
+                .index_progress_line
+                .and_then(|line| self.output.get_mut(line))
+            {
+                Some(output) => *output = text,
+                None => {
+                    self.output.push(text);
+                    self.index_progress_line = Some(self.output.len() - 1);
+                }
+            }
+            self.mark_output_dirty();
+        }
+        if !lines.is_empty() {
+            self.output.extend(lines);
+            self.mark_output_dirty();
+        }
+        if finished {
+            self.indexing = None;
+            self.index_progress_line = None;
+        }
+    }
+
+    /// Show progress events reported by the background watcher
+    fn poll_watch_events(&mut self) {
+        let Some((_, receiver)) = &self.watcher else {
+            return;
+        };
+        let lines: Vec<String> = receiver
+            .try_iter()
+            .filter(|event| !matches!(event, WatchEvent::Indexed(_) | WatchEvent::Removed(_)))
+            .map(|event| format!("[watch] {}", event))
+            .collect();
+        if !lines.is_empty() {
+            self.output.extend(lines);
+            self.mark_output_dirty();
+        }
+    }
+
+    /// Resolve @ mentions in the input and pin them into the AI context
+    fn attach_mentions(&mut self, input: &str) {
+        let Some(resolver) = &self.mention_resolver else {
+            return;
+        };
+
+        let (mentions, errors) = resolver.resolve_all(input);
+        for error in errors {
+            self.output.push(format!("Cannot attach {}", error));
+        }
+        if mentions.is_empty() {
+            return;
+        }
+
+        let context = self.ai_client.context_manager();
+        let attached = MentionResolver::pin_into(&mentions, &mut context.write().unwrap());
+        for (label, tokens) in attached {
+            self.output
+                .push(format!("Attached: {} ({} tokens)", label, tokens));
+        }
+        self.refresh_attached();
+        self.mark_output_dirty();
+    }
+
+    /// Persist the current conversation into the attached session
+    fn save_session(&mut self) {
+        if let (Some(store), Some(session)) = (&self.session_store, self.session.as_mut()) {
+            let context = self.ai_client.context_manager();
+            let result = store.save(session, &context.read().unwrap());
+            if let Err(e) = result {
+                self.output.push(format!("Failed to save session: {}", e));
+            }
+        }
+    }
 }
 
 /// Render search mode UI
@@ -244,10 +708,35 @@ fn render_search_mode(f: &mut Frame, app: &App) {
             Style::default().fg(Color::Gray)
         };
 
-        items.push(ListItem::new(Span::styled(result.clone(), style)));
+        let mut lines = vec![Line::from(Span::styled(result.title.clone(), style))];
+        for line in &result.lines {
+            let mut spans = vec![Span::styled(
+                format!("{:>5} │ ", line.line),
+                Style::default().fg(Color::DarkGray),
+            )];
+            spans.extend(line.segments().into_iter().map(|(text, matched)| {
+                if matched {
+                    Span::styled(
+                        text.to_string(),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    Span::raw(text.to_string())
+                }
+            }));
+            lines.push(Line::from(spans));
+        }
+        items.push(ListItem::new(lines));
     }
 
-    if items.is_empty() {
+    if let Some(error) = &app.search_error {
+        items.insert(
+            0,
+            ListItem::new(Span::styled(error.clone(), Style::default().fg(Color::Red))),
+        );
+    } else if items.is_empty() {
         items.push(ListItem::new(Span::styled(
             "No results found",
             Style::default().fg(Color::Red),
@@ -372,17 +861,38 @@ fn render_code_browser(f: &mut Frame, area: Rect, app: &App) {
         .style(Style::default())
         .highlight_style(Style::default().add_modifier(Modifier::BOLD));
 
-    f.render_stateful_widget(file_list, layout[0], &mut ListState::default());
+    // Keep the selected file in view when the list is longer than the panel
+    let selected = app.code_files.iter().position(|file| *file == app.selected_file);
+    f.render_stateful_widget(
+        file_list,
+        layout[0],
+        &mut ListState::default().with_selected(selected),
+    );
 
     // Render file content
-    let content_block = Block::default()
-        .borders(Borders::ALL)
-        .title(format!("File: {}", app.selected_file));
+    let title = match app.code_cache.elements(&app.selected_file) {
+        Some(elements) => format!("File: {} ({} symbols)", app.selected_file, elements.len()),
+        None => format!("File: {}", app.selected_file),
+    };
+    let content_block = Block::default().borders(Borders::ALL).title(title);
 
     let content_items = app
         .file_content
         .iter()
-        .map(|line| ListItem::new(line.as_str()))
+        .enumerate()
+        .map(|(i, line)| {
+            let number = i as u32 + 1;
+            let changed = app
+                .file_changes
+                .iter()
+                .any(|range| (range.start_line..=range.end_line).contains(&number));
+            let style = if changed {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default()
+            };
+            ListItem::new(Span::styled(line.as_str(), style))
+        })
         .collect::<Vec<_>>();
 
     let content_list = List::new(content_items)
@@ -397,13 +907,31 @@ fn render_code_browser(f: &mut Frame, area: Rect, app: &App) {
     );
 }
 
-/// Run the UI application
-pub fn run(tab: Option<String>) -> Result<(), Box<dyn Error>> {
+/// Run the UI application, optionally resuming a saved chat session
+pub fn run(
+    tab: Option<String>,
+    session_id: Option<String>,
+    config: &crate::config::app::AppConfig,
+) -> Result<(), Box<dyn Error>> {
     // Initialize AI client
     let ai_client = tokio::runtime::Runtime::new()
         .unwrap()
         .block_on(async { crate::ai::adapter::AIClient::new().await })?;
 
+    // Load the requested session, or start a new one for the current directory
+    let session_store = SessionStore::new(&config.app.data_dir);
+    let session = match session_id {
+        Some(id) => {
+            let (session, context) = session_store.load(&id)?;
+            ai_client.restore_context(context);
+            session
+        }
+        None => SessionMeta::new(&std::env::current_dir()?),
+    };
+
+    // Build the mention resolver before entering raw mode, as loading the index prints
+    let mention_resolver = crate::cli::build_mention_resolver(config).ok();
+
     // Initialize terminal
     terminal::enable_raw_mode()?;
     let mut stdout = stdout();
@@ -417,17 +945,26 @@ pub fn run(tab: Option<String>) -> Result<(), Box<dyn Error>> {
 
     // Create app with AI client
     let mut app = App::new(ai_client);
+    app.attach_session(session_store, session);
+    app.mention_resolver = mention_resolver;
+    app.knowledge_config = config.knowledge.clone();
 
     // Set initial tab if specified
     if let Some(tab_name) = tab {
         if let Some(index) = app.tabs.iter().position(|t| t == &tab_name) {
             app.active_tab = index;
             app.mark_tabs_dirty();
+            if index == 1 {
+                app.enter_code_browser();
+            }
         }
     }
 
     // Run main loop
     loop {
+        app.poll_watch_events();
+        app.poll_index_events();
+
         // Check if render is needed based on dirty flags and throttling
         if app.should_render() {
             // Draw UI with optimized rendering
@@ -467,12 +1004,19 @@ fn render_app(f: &mut Frame, app: &App) {
         render_settings_mode(f, app);
     } else {
         // Normal mode layout
+        let attached = app.attached_items();
+        let attached_height = if attached.is_empty() {
+            0
+        } else {
+            attached.len().min(5) as u16 + 2
+        };
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3), // Tabs
-                Constraint::Min(0),    // Output area
-                Constraint::Length(3), // Input area
+                Constraint::Length(3),               // Tabs
+                Constraint::Min(0),                  // Output area
+                Constraint::Length(attached_height), // Attached context items
+                Constraint::Length(3),               // Input area
             ])
             .split(f.size());
 
@@ -487,8 +1031,13 @@ fn render_app(f: &mut Frame, app: &App) {
             render_output(f, layout[1], app);
         }
 
+        // Render attached context items
+        if !attached.is_empty() {
+            render_attached(f, layout[2], attached);
+        }
+
         // Render input area
-        render_input(f, layout[2], app);
+        render_input(f, layout[3], app);
     }
 }
 
@@ -540,6 +1089,33 @@ fn render_output(f: &mut Frame, area: Rect, app: &App) {
     );
 }
 
+/// Render the list of pinned context items with their token counts
+fn render_attached(f: &mut Frame, area: Rect, attached: &[ContextItem]) {
+    let total_tokens: usize = attached.iter().map(|item| item.token_count).sum();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Attached ({} tokens)", total_tokens));
+
+    let items = attached
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            ListItem::new(format!(
+                "{}. {} ({} tokens)",
+                i + 1,
+                item.source().unwrap_or("snippet"),
+                item.token_count
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    let list = List::new(items)
+        .block(block)
+        .style(Style::default().fg(Color::Cyan));
+
+    f.render_widget(list, area);
+}
+
 /// Render input area
 fn render_input(f: &mut Frame, area: Rect, app: &App) {
     let block = Block::default().borders(Borders::ALL).title("Input");
@@ -589,7 +1165,7 @@ fn handle_events(app: &mut App) -> Result<bool, Box<dyn Error>> {
         }
     }
 
-    Ok(true)
+    Ok(!app.should_quit)
 }
 
 /// Handle key events
@@ -615,13 +1191,12 @@ fn handle_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box<dyn Er
         KeyCode::Esc | KeyCode::Char('c')
             if key_event.modifiers.contains(KeyModifiers::CONTROL) =>
         {
+            app.should_quit = true;
             return Ok(());
         }
         // Start search with '/' key
         KeyCode::Char('/') => {
-            app.search_mode = true;
-            app.search_query.clear();
-            app.selected_result = 0;
+            app.open_search();
         }
         // Start settings mode with ':' key
         KeyCode::Char(':') => {
@@ -631,9 +1206,15 @@ fn handle_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box<dyn Er
         // Tab navigation
         KeyCode::Tab => {
             app.active_tab = (app.active_tab + 1) % app.tabs.len();
+            if app.active_tab == 1 {
+                app.enter_code_browser();
+            }
         }
         KeyCode::BackTab => {
             app.active_tab = (app.active_tab + app.tabs.len() - 1) % app.tabs.len();
+            if app.active_tab == 1 {
+                app.enter_code_browser();
+            }
         }
         // Enter key - process input
         KeyCode::Enter => {
@@ -708,18 +1289,25 @@ fn handle_search_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box
     match key_event.code {
         // Exit search mode
         KeyCode::Esc => {
-            app.search_mode = false;
+            app.close_search();
             app.search_query.clear();
         }
         // Select search result
         KeyCode::Enter => {
             if !app.search_results.is_empty() {
                 // Process the selected result
-                let selected = app.search_results[app.selected_result].clone();
-                app.output.push(format!("Selected: {}", selected));
+                let selected = &app.search_results[app.selected_result];
+                app.output.push(format!("Selected: {}", selected.title));
+                // Remember the choice so similar searches rank it higher
+                if let Some(kb) = &app.search_kb {
+                    if let Err(e) = kb.record_selection(&app.search_query, &selected.id) {
+                        app.output
+                            .push(format!("Failed to record search history: {}", e));
+                    }
+                }
                 app.search_history.push(app.search_query.clone());
             }
-            app.search_mode = false;
+            app.close_search();
         }
         // Navigate search results
         KeyCode::Up => {
@@ -728,19 +1316,19 @@ fn handle_search_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box
             }
         }
         KeyCode::Down => {
-            if app.selected_result < app.search_results.len() - 1 {
+            if app.selected_result + 1 < app.search_results.len() {
                 app.selected_result += 1;
             }
         }
         // Backspace - delete last character
         KeyCode::Backspace => {
             app.search_query.pop();
-            // TODO: 主人~ 这里需要实现实时搜索功能，根据 search_query 更新 search_results
+            app.run_search();
         }
         // Character input
         KeyCode::Char(c) => {
             app.search_query.push(c);
-            // TODO: 主人~ 这里需要实现实时搜索功能，根据 search_query 更新 search_results
+            app.run_search();
         }
         // Search history navigation
         KeyCode::PageUp => {
@@ -753,6 +1341,7 @@ fn handle_search_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box
                     app.search_query = app.search_history[*index].clone();
                 }
             }
+            app.run_search();
         }
         KeyCode::PageDown => {
             if let Some(index) = app.search_history_index.as_mut() {
@@ -764,6 +1353,7 @@ fn handle_search_key_event(app: &mut App, key_event: KeyEvent) -> Result<(), Box
                     app.search_query.clear();
                 }
             }
+            app.run_search();
         }
         _ => {
             // Ignore other keys
@@ -815,16 +1405,10 @@ fn handle_code_browser_key_event(app: &mut App, key_event: KeyEvent) -> Result<(
             app.active_tab = 0;
         }
         // Navigate file list
-        KeyCode::Up => {
-            // TODO: 主人~ 这里需要实现文件列表向上导航
-        }
-        KeyCode::Down => {
-            // TODO: 主人~ 这里需要实现文件列表向下导航
-        }
-        // Select file
-        KeyCode::Enter => {
-            // TODO: 主人~ 这里需要实现选择文件并加载其内容
-        }
+        KeyCode::Up => app.select_code_file(-1),
+        KeyCode::Down => app.select_code_file(1),
+        // Reload the selected file, highlighting what changed since it was last opened
+        KeyCode::Enter => app.open_code_file(),
         // Scroll file content
         KeyCode::PageUp => {
             if app.file_offset > 0 {
@@ -857,15 +1441,74 @@ fn process_input(app: &mut App, input: &str) -> Result<(), Box<dyn Error>> {
             app.output.push("  clear - Clear output".to_string());
             app.output.push("  exit - Exit the application".to_string());
             app.output.push("  tabs - List available tabs".to_string());
+            app.output
+                .push("  attached - List context items attached with @".to_string());
+            app.output
+                .push("  detach <n|all> - Remove an attached context item".to_string());
+            app.output.push(
+                "  index - Index the current directory into the knowledge base".to_string(),
+            );
+            app.output.push(
+                "  watch [stop] - Re-index changed files in the knowledge base".to_string(),
+            );
+            app.output.push(
+                "  Use @path, @path:10-40, @dir/*.rs or @Symbol in a message to attach context"
+                    .to_string(),
+            );
             app.mark_output_dirty();
         }
         "clear" => {
             app.output.clear();
+            app.index_progress_line = None;
             app.mark_output_dirty();
         }
         "exit" => {
+            app.should_quit = true;
             return Ok(());
         }
+        "index" => app.start_index(),
+        "watch" => app.start_watch(),
+        "watch stop" => app.stop_watch(),
+        "attached" => {
+            if app.attached.is_empty() {
+                app.output.push("No attached context items.".to_string());
+            }
+            for (i, item) in app.attached.iter().enumerate() {
+                app.output.push(format!(
+                    "  {}. {} ({} tokens)",
+                    i + 1,
+                    item.source().unwrap_or("snippet"),
+                    item.token_count
+                ));
+            }
+            app.mark_output_dirty();
+        }
+        command if command.starts_with("detach ") => {
+            let attached = app.attached_items().to_vec();
+            let target = command.trim_start_matches("detach ").trim();
+            let selected: Vec<&ContextItem> = if target == "all" {
+                attached.iter().collect()
+            } else {
+                target
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|n| attached.get(n.wrapping_sub(1)))
+                    .into_iter()
+                    .collect()
+            };
+
+            if selected.is_empty() {
+                app.output.push(format!("No attached item: {}", target));
+            }
+            let context = app.ai_client.context_manager();
+            for item in selected {
+                context.write().unwrap().unpin(&item.id);
+                app.output
+                    .push(format!("Detached: {}", item.source().unwrap_or("snippet")));
+            }
+            app.refresh_attached();
+            app.mark_output_dirty();
+        }
         "tabs" => {
             app.output.push("Available tabs:".to_string());
             for (i, tab) in app.tabs.iter().enumerate() {
@@ -874,6 +1517,9 @@ fn process_input(app: &mut App, input: &str) -> Result<(), Box<dyn Error>> {
             app.mark_output_dirty();
         }
         _ => {
+            // Pin @-mentioned files and symbols into the context
+            app.attach_mentions(input);
+
             // Call AI client to generate response
             app.output.push("Thinking...".to_string());
             app.mark_output_dirty();
@@ -894,6 +1540,7 @@ fn process_input(app: &mut App, input: &str) -> Result<(), Box<dyn Error>> {
             // Replace "Thinking..." with actual response
             app.output.pop();
             app.output.push(format!("Codex: {}", response.content()));
+            app.save_session();
             app.mark_output_dirty();
         }
     }
@@ -3,6 +3,17 @@ use codex::knowledge::indexer::CodeIndexer;
 use codex::parsers::initialize_parsers;
 use std::path::Path;
 use std::time::Instant;
+use tempfile::TempDir;
+
+/// 创建元数据写入临时目录的默认配置，避免改写仓库中的index_cache.json
+fn benchmark_config() -> (TempDir, KnowledgeConfig) {
+    let dir = tempfile::tempdir().unwrap();
+    let config = KnowledgeConfig {
+        metadata_dir: dir.path().to_path_buf(),
+        ..Default::default()
+    };
+    (dir, config)
+}
 
 /// 测试索引器性能
 #[cfg(test)]
@@ -12,7 +23,7 @@ fn test_indexer_performance() {
     initialize_parsers().unwrap();
 
     // 创建默认配置
-    let config = KnowledgeConfig::default();
+    let (_dir, config) = benchmark_config();
 
     // 创建索引器
     let mut indexer = CodeIndexer::new(config).unwrap();
@@ -39,7 +50,7 @@ fn test_search_performance() {
     initialize_parsers().unwrap();
 
     // 创建默认配置
-    let config = KnowledgeConfig::default();
+    let (_dir, config) = benchmark_config();
 
     // 创建索引器
     let mut indexer = CodeIndexer::new(config).unwrap();
@@ -70,7 +81,7 @@ fn test_memory_usage() {
     initialize_parsers().unwrap();
 
     // 创建默认配置
-    let config = KnowledgeConfig::default();
+    let (_dir, config) = benchmark_config();
 
     // 创建索引器
     let mut indexer = CodeIndexer::new(config).unwrap();
@@ -103,7 +114,7 @@ fn test_concurrent_search() {
         let query_clone = query.to_string();
         let handle = std::thread::spawn(move || {
             // 每个线程创建自己的索引器实例
-            let config = KnowledgeConfig::default();
+            let (_dir, config) = benchmark_config();
             let mut indexer = CodeIndexer::new(config).unwrap();
             // 索引当前目录
             indexer.index_directory(Path::new(".")).unwrap();
@@ -0,0 +1,27 @@
+//! 集成测试共用的知识库夹具
+//!
+//! 每个测试文件只使用其中一部分函数
+#![allow(dead_code)]
+
+use codex::config::app::KnowledgeConfig;
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::local::LocalKnowledgeBase;
+use std::path::Path;
+
+/// 创建使用指定目录存储数据、只索引给定扩展名文件的配置
+pub fn temp_config(dir: &Path, extensions: &[&str]) -> KnowledgeConfig {
+    KnowledgeConfig {
+        index_dir: dir.join("index"),
+        metadata_dir: dir.join("metadata"),
+        supported_extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+/// 使用配置创建已初始化且不输出进度的知识库
+pub fn open_knowledge_base(config: KnowledgeConfig) -> LocalKnowledgeBase {
+    let mut kb = LocalKnowledgeBase::new(config).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+    kb
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use codex::knowledge::base::{DocumentSource, KnowledgeBase};
+use codex::knowledge::bundle::{
+    absolutize_path, relativize_path, Bundle, BundleVectors, ImportMode, BUNDLE_FORMAT_VERSION,
+};
+use codex::knowledge::indexer::IndexCache;
+use codex::knowledge::search_result_optimizer::SearchHistoryStore;
+use codex::knowledge::vector_index::VectorEntry;
+use common::{open_knowledge_base, temp_config};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 在指定目录创建示例项目，返回规范化后的项目根目录
+fn sample_project(dir: &Path) -> PathBuf {
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(
+        dir.join("src/client.rs"),
+        "pub struct Client;\n\nimpl Client {\n    pub fn connect() -> Client {\n        Client\n    }\n}\n\nfn main() {\n    Client::connect();\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("README.md"),
+        "# Client\n\nConnects to the server.\n",
+    )
+    .unwrap();
+    fs::canonicalize(dir).unwrap()
+}
+
+#[test]
+fn test_export_import_rewrites_paths() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source_root = sample_project(&temp_dir.path().join("a/project"));
+    let mut source = open_knowledge_base(temp_config(&temp_dir.path().join("a/kb"), EXTENSIONS));
+    source.set_collection("repo").unwrap();
+    source
+        .add_files(&[source_root.to_string_lossy().to_string()], true)
+        .unwrap();
+    source
+        .add_web_page("https://example.com/guide", "Guide", "Connecting clients")
+        .unwrap();
+    source.commit().unwrap();
+
+    let archive = temp_dir.path().join("kb.tar.gz");
+    let bundle = source.export_bundle(&source_root).unwrap();
+    assert_eq!(bundle.manifest.format_version, BUNDLE_FORMAT_VERSION);
+    assert_eq!(bundle.manifest.documents, 3);
+    assert!(bundle.manifest.symbols > 0);
+    let mut paths: Vec<&str> = bundle
+        .documents
+        .iter()
+        .map(|document| document.meta.path.as_str())
+        .collect();
+    paths.sort();
+    assert_eq!(
+        paths,
+        vec!["README.md", "https://example.com/guide", "src/client.rs"],
+        "项目根目录下的路径应改写为相对路径，网页URL保持不变"
+    );
+    bundle.write(&archive).unwrap();
+
+    // 在另一位置的同一项目中导入
+    let target_root = sample_project(&temp_dir.path().join("b/checkout"));
+    let mut target = open_knowledge_base(temp_config(&temp_dir.path().join("b/kb"), EXTENSIONS));
+    let summary = target
+        .import_bundle(
+            Bundle::read(&archive).unwrap(),
+            &target_root,
+            ImportMode::Merge,
+        )
+        .unwrap();
+    assert_eq!(summary.documents, 3);
+    assert_eq!(summary.symbols, bundle.manifest.symbols);
+
+    let client = target_root.join("src/client.rs");
+    let meta = target.get(&client.to_string_lossy()).unwrap().unwrap();
+    assert_eq!(meta.collection, "repo", "导入后应保留文档所属集合");
+    let page = target.get("https://example.com/guide").unwrap().unwrap();
+    assert_eq!(page.source, DocumentSource::Web);
+
+    let hits = target.search_documents("connects", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(
+        hits[0].path,
+        target_root.join("README.md").to_string_lossy()
+    );
+
+    let symbols = target.search_symbols("connect", 10).unwrap();
+    assert!(symbols
+        .iter()
+        .any(|symbol| symbol.element.definition.file_path == client.to_string_lossy()));
+    let callers = target.xref().unwrap().callers("Client::connect").unwrap();
+    assert_eq!(callers.len(), 1);
+    assert_eq!(callers[0].path, client.to_string_lossy());
+}
+
+#[test]
+fn test_import_merge_and_replace() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = sample_project(&temp_dir.path().join("project"));
+    let mut source = open_knowledge_base(temp_config(&temp_dir.path().join("source"), EXTENSIONS));
+    source
+        .add_files(
+            &[root.join("README.md").to_string_lossy().to_string()],
+            false,
+        )
+        .unwrap();
+    let bundle = source.export_bundle(&root).unwrap();
+
+    let mut target = open_knowledge_base(temp_config(&temp_dir.path().join("target"), EXTENSIONS));
+    target
+        .add_text("notes/todo.md", "remember the milk", DocumentSource::File)
+        .unwrap();
+    target.commit().unwrap();
+
+    target
+        .import_bundle(bundle.clone(), &root, ImportMode::Merge)
+        .unwrap();
+    assert_eq!(target.document_count(), 2, "合并导入应保留已有文档");
+
+    target.record_search("milk").unwrap();
+    target
+        .import_bundle(bundle, &root, ImportMode::Replace)
+        .unwrap();
+    assert_eq!(target.document_count(), 1, "替换导入应清空已有文档");
+    assert!(target.search_documents("milk", 10).unwrap().is_empty());
+
+    // 归档不包含搜索历史，替换导入时保留本机的历史
+    drop(target);
+    let db = sled::open(temp_dir.path().join("target/metadata/documents.sled")).unwrap();
+    let history = SearchHistoryStore::open(&db).unwrap().recent(10).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].query, "milk");
+}
+
+#[test]
+fn test_read_rejects_unsupported_versions() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let archive = temp_dir.path().join("future.tar.gz");
+    let mut bundle = Bundle::new(Vec::new(), IndexCache::default(), Vec::new(), None);
+    bundle.manifest.format_version = BUNDLE_FORMAT_VERSION + 1;
+    bundle.write(&archive).unwrap();
+    assert!(Bundle::read(&archive).is_err(), "更新版本的归档应被拒绝");
+
+    let mut cache = IndexCache::default();
+    cache.version += 1;
+    Bundle::new(Vec::new(), cache, Vec::new(), None)
+        .write(&archive)
+        .unwrap();
+    assert!(
+        Bundle::read(&archive).is_err(),
+        "索引缓存版本不兼容时应报错"
+    );
+
+    fs::write(&archive, b"not an archive").unwrap();
+    assert!(Bundle::read(&archive).is_err());
+}
+
+#[test]
+fn test_path_rewriting() {
+    let root = Path::new("/work/project");
+    assert_eq!(
+        relativize_path("/work/project/src/lib.rs", root),
+        "src/lib.rs"
+    );
+    assert_eq!(
+        relativize_path("/elsewhere/lib.rs", root),
+        "/elsewhere/lib.rs"
+    );
+    assert_eq!(
+        relativize_path("https://docs.rs/axum", root),
+        "https://docs.rs/axum"
+    );
+
+    let other = Path::new("/home/dev/checkout");
+    assert_eq!(
+        absolutize_path("src/lib.rs", other),
+        "/home/dev/checkout/src/lib.rs"
+    );
+    assert_eq!(
+        absolutize_path("/elsewhere/lib.rs", other),
+        "/elsewhere/lib.rs"
+    );
+    assert_eq!(absolutize_path("../escape.rs", other), "../escape.rs");
+
+    // 向量中的路径与文档一同改写
+    let vectors = BundleVectors {
+        provider: "test".to_string(),
+        entries: vec![VectorEntry {
+            path: "/work/project/src/lib.rs".to_string(),
+            start_line: 1,
+            end_line: 3,
+            collection: "default".to_string(),
+            vector: vec![1.0, 0.0],
+        }],
+    };
+    let mut bundle = Bundle::new(Vec::new(), IndexCache::default(), Vec::new(), Some(vectors));
+    assert_eq!(bundle.manifest.embedding_provider.as_deref(), Some("test"));
+    bundle.relativize(root);
+    assert_eq!(
+        bundle.vectors.as_ref().unwrap().entries[0].path,
+        "src/lib.rs"
+    );
+    bundle.absolutize(other);
+    assert_eq!(
+        bundle.vectors.as_ref().unwrap().entries[0].path,
+        "/home/dev/checkout/src/lib.rs"
+    );
+}
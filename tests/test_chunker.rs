@@ -0,0 +1,143 @@
+mod common;
+
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::chunker::{chunk_document, CHUNK_LINES};
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::parsers::initialize_parsers;
+use common::temp_config;
+use std::fs;
+use tantivy::schema::{Schema, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{doc, Index};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "txt"];
+
+/// 生成包含指定行数函数体的 Rust 函数
+fn rust_function(name: &str, body_lines: usize) -> String {
+    let mut source = format!("fn {}() {{\n", name);
+    for i in 0..body_lines {
+        source.push_str(&format!("    let v{} = {};\n", i, i));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// 生成包含小函数、带文档注释的函数、超长 impl 和超长函数的 Rust 源码
+fn rust_source() -> String {
+    let mut source = String::from("use std::fmt;\n\n");
+    source.push_str(&rust_function("small", 8));
+    source.push_str("\n/// Documented helper\n");
+    source.push_str(&rust_function("helper", 20));
+    source.push_str("\nstruct Big;\n\nimpl Big {\n");
+    for method in ["first", "second", "third"] {
+        for line in rust_function(method, 18).lines() {
+            source.push_str(&format!("    {}\n", line));
+        }
+    }
+    source.push_str("}\n\n");
+    source.push_str(&rust_function("huge", 90));
+    source
+}
+
+#[test]
+fn test_chunk_rust_source_by_elements() {
+    initialize_parsers().unwrap();
+    let chunks = chunk_document("big.rs", &rust_source());
+    let ranges: Vec<(usize, usize)> = chunks
+        .iter()
+        .map(|chunk| (chunk.start_line, chunk.end_line))
+        .collect();
+
+    // 小元素合并为一个片段，超长的 impl 和函数在内部切分并相互重叠
+    assert_eq!(
+        ranges,
+        vec![
+            (1, 39),
+            (40, 60),
+            (51, 80),
+            (71, 102),
+            (103, 142),
+            (133, 172),
+            (163, 194)
+        ]
+    );
+    assert!(chunks[0]
+        .text
+        .contains("/// Documented helper\nfn helper()"));
+    assert!(chunks[1].text.starts_with("impl Big {\n    fn first()"));
+    assert!(chunks
+        .iter()
+        .all(|chunk| chunk.end_line + 1 - chunk.start_line <= CHUNK_LINES));
+}
+
+#[test]
+fn test_chunk_unparsed_files_by_lines() {
+    let text: String = (1..=100).map(|i| format!("line {}\n", i)).collect();
+    let ranges: Vec<(usize, usize)> = chunk_document("notes.txt", &text)
+        .iter()
+        .map(|chunk| (chunk.start_line, chunk.end_line))
+        .collect();
+    assert_eq!(ranges, vec![(1, 40), (31, 70), (61, 100)]);
+
+    let chunks = chunk_document("short.txt", "one\ntwo\n");
+    assert_eq!(chunks.len(), 1);
+    assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 2));
+    assert!(chunk_document("empty.txt", "\n\n").is_empty());
+}
+
+#[test]
+fn test_search_returns_chunk_line_ranges() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("big.rs"), rust_source()).unwrap();
+
+    let mut kb = LocalKnowledgeBase::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    kb.init().unwrap();
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+
+    let hits = kb.search_documents("third", 10).unwrap();
+    assert_eq!(hits.len(), 1, "只有包含 third 方法的片段应命中");
+    assert_eq!(hits[0].lines, Some((71, 102)));
+    assert!(hits[0].snippet.contains("third"));
+    assert_eq!(kb.document_count(), 1);
+}
+
+#[test]
+fn test_legacy_whole_file_index_is_rebuilt() {
+    let dir = tempfile::tempdir().unwrap();
+    let documents_dir = dir.path().join("index").join("documents");
+    fs::create_dir_all(&documents_dir).unwrap();
+
+    // 旧格式：每个文件一条整文件记录
+    let mut builder = Schema::builder();
+    let path = builder.add_text_field("path", STRING | STORED);
+    let title = builder.add_text_field("title", TEXT | STORED);
+    let content = builder.add_text_field("content", TEXT | STORED);
+    let language = builder.add_text_field("language", STRING | STORED);
+    let source = builder.add_text_field("source", STRING | STORED);
+    let timestamp = builder.add_i64_field("timestamp", INDEXED | STORED | FAST);
+    let index = Index::create_in_dir(&documents_dir, builder.build()).unwrap();
+    let mut writer = index.writer(15_000_000).unwrap();
+    let text: String = (1..=60).map(|i| format!("line {}\n", i)).collect();
+    writer
+        .add_document(doc!(
+            path => "notes.txt",
+            title => "notes.txt",
+            content => format!("{}needle\n", text),
+            language => "text",
+            source => "file",
+            timestamp => 0i64,
+        ))
+        .unwrap();
+    writer.commit().unwrap();
+    drop(writer);
+    drop(index);
+
+    let kb = LocalKnowledgeBase::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    let hits = kb.search_documents("needle", 5).unwrap();
+    assert_eq!(hits.len(), 1, "旧索引中的文档应被重建为分段");
+    assert_eq!(hits[0].path, "notes.txt");
+    assert_eq!(hits[0].lines, Some((31, 61)));
+}
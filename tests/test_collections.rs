@@ -0,0 +1,240 @@
+mod common;
+
+use codex::config::app::{CollectionConfig, KnowledgeConfig};
+use codex::context::ContextCollector;
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::collection::{CollectionFilter, DEFAULT_COLLECTION};
+use codex::knowledge::local::LocalKnowledgeBase;
+use common::temp_config;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建定义了指定集合的配置
+fn collections_config(
+    dir: &Path,
+    collections: BTreeMap<String, CollectionConfig>,
+) -> KnowledgeConfig {
+    KnowledgeConfig {
+        collections,
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建包含源码和文档的临时项目，两处都提到 router
+fn sample_project(dir: &Path) -> (PathBuf, PathBuf) {
+    let repo = dir.join("repo");
+    let docs = dir.join("docs");
+    fs::create_dir_all(&repo).unwrap();
+    fs::create_dir_all(&docs).unwrap();
+    fs::write(
+        repo.join("server.rs"),
+        "fn build_router() -> Router {\n    Router::new()\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        docs.join("routing.md"),
+        "# Routing\n\nThe router dispatches requests to handlers.\n",
+    )
+    .unwrap();
+    fs::write(docs.join("notes.rs"), "fn router_example() {}\n").unwrap();
+    (repo, docs)
+}
+
+/// 打开知识库并分别添加到 repo 和 docs:axum 集合
+fn populated_knowledge_base(
+    dir: &Path,
+    collections: BTreeMap<String, CollectionConfig>,
+) -> LocalKnowledgeBase {
+    let (repo, docs) = sample_project(dir);
+    let mut kb = LocalKnowledgeBase::new(collections_config(dir, collections)).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+
+    kb.set_collection("repo").unwrap();
+    kb.add_files(&[repo.to_string_lossy().to_string()], true)
+        .unwrap();
+    kb.set_collection("docs:axum").unwrap();
+    kb.add_files(&[docs.to_string_lossy().to_string()], true)
+        .unwrap();
+    kb
+}
+
+#[test]
+fn test_collection_filter() {
+    let filter = CollectionFilter::parse("repo, docs:*").unwrap();
+    assert_eq!(filter.patterns(), ["repo", "docs:*"]);
+    assert!(filter.matches("repo"));
+    assert!(filter.matches("docs:axum"));
+    assert!(filter.matches("docs:"));
+    assert!(!filter.matches("repository"), "模式应完整匹配集合名称");
+    assert!(!filter.matches("notes"));
+
+    assert!(CollectionFilter::parse(" , ").is_err(), "空过滤条件应报错");
+    assert!(
+        CollectionFilter::parse("bad name").is_err(),
+        "非法集合名称应报错"
+    );
+}
+
+#[test]
+fn test_scoped_search_stats_and_delete() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut kb = populated_knowledge_base(temp_dir.path(), BTreeMap::new());
+
+    let all = kb.search_documents("router", 10).unwrap();
+    assert_eq!(all.len(), 3, "未限定集合时应搜索整个知识库");
+
+    let docs = kb
+        .search_documents_in("router", 10, &CollectionFilter::parse("docs:*").unwrap())
+        .unwrap();
+    assert_eq!(docs.len(), 2);
+    assert!(docs.iter().all(|hit| hit.collection == "docs:axum"));
+
+    let symbols = kb
+        .search_symbols_in(
+            "router",
+            10,
+            Some(&CollectionFilter::parse("repo").unwrap()),
+        )
+        .unwrap();
+    assert!(!symbols.is_empty());
+    assert!(symbols.iter().all(|symbol| symbol
+        .element
+        .definition
+        .file_path
+        .ends_with("server.rs")));
+
+    let stats = kb.collection_stats().unwrap();
+    let names: Vec<&str> = stats.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["docs:axum", "repo"]);
+    assert_eq!(stats[0].files, 2);
+    assert_eq!(stats[1].files, 1);
+    assert!(stats[0].last_indexed.is_some());
+
+    assert_eq!(kb.delete_collection("docs:axum").unwrap(), 2);
+    assert_eq!(kb.document_count(), 1);
+    assert!(
+        kb.search_documents("dispatches", 10).unwrap().is_empty(),
+        "删除集合后其文档不应再被检索到"
+    );
+    let remaining = kb.list().unwrap();
+    assert_eq!(remaining[0].collection, "repo");
+}
+
+#[test]
+fn test_scoped_symbol_search_skips_other_collections() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let (repo, docs) = sample_project(temp_dir.path());
+    // 其他集合中有大量更匹配的符号，排在 repo 中唯一符号的前面
+    fs::write(
+        repo.join("server.rs"),
+        "fn start_router_service_with_defaults() {}\n",
+    )
+    .unwrap();
+    let examples: String = (0..40)
+        .map(|i| format!("fn router_{}(router: Router) -> Router {{ router }}\n", i))
+        .collect();
+    fs::write(docs.join("notes.rs"), examples).unwrap();
+    let mut kb =
+        LocalKnowledgeBase::new(collections_config(temp_dir.path(), BTreeMap::new())).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+    kb.set_collection("docs:axum").unwrap();
+    kb.add_files(&[docs.to_string_lossy().to_string()], true)
+        .unwrap();
+    kb.set_collection("repo").unwrap();
+    kb.add_files(&[repo.to_string_lossy().to_string()], true)
+        .unwrap();
+
+    let symbols = kb
+        .search_symbols_in("router", 1, Some(&CollectionFilter::parse("repo").unwrap()))
+        .unwrap();
+    assert_eq!(symbols.len(), 1, "应继续查找直到找到集合内的符号");
+    assert_eq!(
+        symbols[0].element.name,
+        "start_router_service_with_defaults"
+    );
+}
+
+#[test]
+fn test_default_collection_and_readd_keeps_collection() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let (repo, _) = sample_project(temp_dir.path());
+    let mut kb =
+        LocalKnowledgeBase::new(collections_config(temp_dir.path(), BTreeMap::new())).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+
+    let file = repo.join("server.rs").to_string_lossy().to_string();
+    kb.add_files(std::slice::from_ref(&file), false).unwrap();
+    assert_eq!(kb.list().unwrap()[0].collection, DEFAULT_COLLECTION);
+
+    kb.set_collection("repo").unwrap();
+    kb.add_files(std::slice::from_ref(&file), false).unwrap();
+    assert_eq!(kb.list().unwrap()[0].collection, "repo");
+
+    // 重新打开知识库后未指定集合，更新文件时保留原集合
+    drop(kb);
+    let mut kb =
+        LocalKnowledgeBase::new(collections_config(temp_dir.path(), BTreeMap::new())).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+    kb.add_files(&[file], false).unwrap();
+    assert_eq!(kb.list().unwrap()[0].collection, "repo");
+
+    assert!(kb.set_collection("bad name").is_err());
+}
+
+#[test]
+fn test_collection_config_overrides_extensions() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut collections = BTreeMap::new();
+    collections.insert(
+        "docs:axum".to_string(),
+        CollectionConfig {
+            description: "Axum documentation".to_string(),
+            supported_extensions: vec!["md".to_string()],
+            ..Default::default()
+        },
+    );
+    let kb = populated_knowledge_base(temp_dir.path(), collections);
+
+    let docs: Vec<String> = kb
+        .list()
+        .unwrap()
+        .into_iter()
+        .filter(|meta| meta.collection == "docs:axum")
+        .map(|meta| meta.path)
+        .collect();
+    assert_eq!(docs.len(), 1, "集合配置的文件类型应覆盖全局配置");
+    assert!(docs[0].ends_with("routing.md"));
+
+    let stats = kb.collection_stats().unwrap();
+    assert_eq!(stats[0].description, "Axum documentation");
+}
+
+#[test]
+fn test_context_collector_scoped_to_collections() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let kb = populated_knowledge_base(temp_dir.path(), BTreeMap::new());
+    let kb: Arc<dyn KnowledgeBase> = Arc::new(kb);
+
+    let items = ContextCollector::new()
+        .with_knowledge_base(kb.clone())
+        .with_collections(CollectionFilter::parse("repo").unwrap())
+        .collect_related_code("router");
+    assert_eq!(items.len(), 1);
+    assert!(items[0].content.starts_with("fn build_router()"));
+    assert!(items[0].tags.contains(&"collection:repo".to_string()));
+    assert!(items[0].source().unwrap().ends_with("server.rs:1-3"));
+
+    let items = ContextCollector::new()
+        .with_knowledge_base(kb)
+        .collect_related_code("router");
+    assert_eq!(items.len(), 3, "未限定集合时应从整个知识库收集");
+}
@@ -0,0 +1,137 @@
+use codex::knowledge::deps::DependencyGraph;
+use codex::knowledge::walker::ProjectWalker;
+use std::fs;
+use std::path::Path;
+
+/// 在项目目录中写入文件，自动创建上级目录
+fn write(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// 获取文件依赖的项目内文件
+fn targets(graph: &DependencyGraph, path: &str) -> Vec<String> {
+    graph
+        .file(path)
+        .unwrap()
+        .dependencies
+        .iter()
+        .filter_map(|dependency| dependency.target.clone())
+        .collect()
+}
+
+#[test]
+fn test_rust_module_dependencies() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    write(
+        root,
+        "src/main.rs",
+        "mod config;\nmod net;\n\nuse std::io;\n",
+    );
+    write(root, "src/config.rs", "use crate::net::client::Client;\n");
+    write(root, "src/net/mod.rs", "pub mod client;\n");
+    write(
+        root,
+        "src/net/client.rs",
+        "use super::super::config;\nuse serde::Deserialize;\n",
+    );
+
+    let graph = DependencyGraph::build(root, &ProjectWalker::new()).unwrap();
+    assert_eq!(
+        targets(&graph, "src/main.rs"),
+        vec!["src/config.rs", "src/net/mod.rs"]
+    );
+    assert_eq!(targets(&graph, "src/config.rs"), vec!["src/net/client.rs"]);
+    assert_eq!(targets(&graph, "src/net/mod.rs"), vec!["src/net/client.rs"]);
+    assert_eq!(targets(&graph, "src/net/client.rs"), vec!["src/config.rs"]);
+
+    // 外部 crate 以名称表示
+    let external: Vec<String> = graph
+        .external()
+        .into_iter()
+        .map(|(_, package)| package)
+        .collect();
+    assert_eq!(external, vec!["std", "serde"]);
+
+    // 循环依赖和反向依赖
+    assert_eq!(
+        graph.cycles(),
+        vec![vec![
+            "src/config.rs".to_string(),
+            "src/net/client.rs".to_string()
+        ]]
+    );
+    assert_eq!(
+        graph.dependents("src/net/client.rs", false),
+        vec!["src/config.rs", "src/net/mod.rs"]
+    );
+    assert_eq!(
+        graph.dependents("src/net/client.rs", true),
+        vec!["src/config.rs", "src/main.rs", "src/net/mod.rs"]
+    );
+}
+
+#[test]
+fn test_python_and_typescript_dependencies() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let root = temp_dir.path();
+    write(root, "app/__init__.py", "");
+    write(root, "app/models.py", "import os\n");
+    write(
+        root,
+        "app/views.py",
+        "from . import models\nfrom .utils import helper\n",
+    );
+    write(root, "app/utils.py", "def helper():\n    pass\n");
+    write(
+        root,
+        "main.py",
+        "import app.views\nfrom app import models\n",
+    );
+
+    write(
+        root,
+        "tsconfig.json",
+        "{\n  // 路径别名\n  \"compilerOptions\": {\n    \"baseUrl\": \".\",\n    \"paths\": { \"@/*\": [\"web/*\"] }\n  }\n}\n",
+    );
+    write(
+        root,
+        "web/index.ts",
+        "import { api } from './api';\nimport { Button } from '@/components';\nimport React from 'react';\n",
+    );
+    write(root, "web/api.ts", "export const api = {};\n");
+    write(
+        root,
+        "web/components/index.tsx",
+        "export const Button = 1;\n",
+    );
+
+    let graph = DependencyGraph::build(root, &ProjectWalker::new()).unwrap();
+    assert_eq!(
+        targets(&graph, "app/views.py"),
+        vec!["app/models.py", "app/utils.py"]
+    );
+    assert_eq!(
+        targets(&graph, "main.py"),
+        vec!["app/views.py", "app/models.py"]
+    );
+    assert_eq!(
+        targets(&graph, "web/index.ts"),
+        vec!["web/api.ts", "web/components/index.tsx"]
+    );
+    assert!(graph.cycles().is_empty());
+
+    // DOT 和 JSON 输出
+    let dot = graph.to_dot(true);
+    assert!(dot.starts_with("digraph dependencies {"));
+    assert!(dot.contains("\"main.py\" -> \"app/views.py\";"));
+    assert!(dot.contains("\"web/index.ts\" -> \"react\" [style=dashed];"));
+    let json = graph.to_json(false);
+    assert!(json["edges"]
+        .as_array()
+        .unwrap()
+        .contains(&serde_json::json!({ "from": "web/index.ts", "to": "web/api.ts" })));
+    assert!(json.get("external").is_none());
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use codex::context::ContextCollector;
+use codex::knowledge::base::{DocumentSource, KnowledgeBase};
+use codex::knowledge::git::{blame_lines, parse_log, GitRepository, GIT_COLLECTION};
+use common::{open_knowledge_base, temp_config};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs"];
+
+/// 在仓库中执行 git 命令，固定作者和提交时间
+fn git(repo: &Path, args: &[&str], date: &str) {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .env("GIT_AUTHOR_NAME", "Alice")
+        .env("GIT_AUTHOR_EMAIL", "alice@example.com")
+        .env("GIT_COMMITTER_NAME", "Alice")
+        .env("GIT_COMMITTER_EMAIL", "alice@example.com")
+        .env("GIT_AUTHOR_DATE", date)
+        .env("GIT_COMMITTER_DATE", date)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} 执行失败", args);
+}
+
+/// 写入文件并提交
+fn commit_file(repo: &Path, file: &str, content: &str, message: &str, date: &str) {
+    fs::write(repo.join(file), content).unwrap();
+    git(repo, &["add", file], date);
+    git(repo, &["commit", "-q", "-m", message], date);
+}
+
+/// 创建包含两个提交的示例仓库，返回规范化后的仓库目录
+fn sample_repo(dir: &Path) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    let repo = fs::canonicalize(dir).unwrap();
+    git(&repo, &["init", "-q"], "2023-01-01T00:00:00Z");
+    commit_file(
+        &repo,
+        "client.rs",
+        "pub fn connect() {\n    open();\n}\n",
+        "Add client",
+        "2023-01-01T00:00:00Z",
+    );
+    commit_file(
+        &repo,
+        "client.rs",
+        "pub fn connect() {\n    for _ in 0..3 {\n        open();\n    }\n}\n",
+        "Add retry logic to connect\n\nThe server drops the first connection after a restart.",
+        "2024-06-01T00:00:00Z",
+    );
+    repo
+}
+
+#[test]
+fn test_parse_log() {
+    let output = "\x1eabc123\x1fAlice\x1falice@example.com\x1f1700000000\x1fFix retry\n\nDetails here.\n\x1f\n\
+diff --git a/src/net.rs b/src/net.rs\n\
+index 1111111..2222222 100644\n\
+--- a/src/net.rs\n\
++++ b/src/net.rs\n\
+@@ -1,2 +1,2 @@\n\
+-retry(1);\n\
++retry(3);\n\
+\x20done();\n\
+@@ -10 +10 @@\n\
+-a\n\
++b\n\
+diff --git a/logo.png b/logo.png\n\
+Binary files a/logo.png and b/logo.png differ\n";
+    let commits = parse_log(output);
+    assert_eq!(commits.len(), 1);
+    let commit = &commits[0];
+    assert_eq!(commit.hash, "abc123");
+    assert_eq!(commit.author, "Alice");
+    assert_eq!(commit.timestamp, 1_700_000_000);
+    assert_eq!(commit.subject, "Fix retry");
+    assert_eq!(commit.message, "Fix retry\n\nDetails here.");
+    assert_eq!(commit.files.len(), 2);
+    assert_eq!(commit.files[0].path, "src/net.rs");
+    assert_eq!(
+        commit.files[0].hunks,
+        vec![
+            "@@ -1,2 +1,2 @@\n-retry(1);\n+retry(3);\n done();".to_string(),
+            "@@ -10 +10 @@\n-a\n+b".to_string(),
+        ],
+        "文件头不应计入差异片段"
+    );
+    assert_eq!(commit.files[1].path, "logo.png");
+    assert!(commit.files[1].hunks.is_empty());
+}
+
+#[test]
+fn test_add_git_history_is_incremental() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let repo = sample_repo(&temp_dir.path().join("repo"));
+    let mut kb = open_knowledge_base(temp_config(&temp_dir.path().join("kb"), EXTENSIONS));
+
+    let summary = kb.add_git_history(&repo, None, false).unwrap();
+    assert_eq!(summary.commits, 2);
+    assert_eq!(summary.files, 2);
+
+    let hits = kb.search_documents("retry", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].title, "Add retry logic to connect");
+    assert_eq!(hits[0].source, DocumentSource::Git);
+    assert_eq!(hits[0].collection, GIT_COLLECTION);
+    let meta = kb.get(&hits[0].path).unwrap().unwrap();
+    assert!(hits[0].path.starts_with("git://"));
+    assert_eq!(meta.indexed_at, 1_717_200_000, "索引时间应为提交时间");
+
+    // 提交日期可用于过滤
+    assert_eq!(
+        kb.search_documents("connect before:2024-01-01", 10)
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(kb
+        .search_documents("restart before:2024-01-01", 10)
+        .unwrap()
+        .is_empty());
+
+    let summary = kb.add_git_history(&repo, None, false).unwrap();
+    assert_eq!(summary.commits, 0, "没有新提交时不应重复索引");
+
+    commit_file(
+        &repo,
+        "server.rs",
+        "pub fn serve() {}\n",
+        "Add server",
+        "2024-07-01T00:00:00Z",
+    );
+    let summary = kb.add_git_history(&repo, None, false).unwrap();
+    assert_eq!(summary.commits, 1, "应只索引上次之后的新提交");
+    assert_eq!(kb.document_count(), 3);
+
+    let summary = kb.add_git_history(&repo, Some(2), true).unwrap();
+    assert_eq!(summary.commits, 2, "重新索引时应遵守提交数量上限");
+    assert_eq!(kb.document_count(), 3);
+}
+
+#[test]
+fn test_delete_commits_keeps_other_repositories_incremental() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let repo = sample_repo(&temp_dir.path().join("repo"));
+    let other = temp_dir.path().join("other");
+    fs::create_dir_all(&other).unwrap();
+    let other = fs::canonicalize(other).unwrap();
+    git(&other, &["init", "-q"], "2023-01-01T00:00:00Z");
+    commit_file(
+        &other,
+        "server.rs",
+        "pub fn serve() {}\n",
+        "Add server",
+        "2023-02-01T00:00:00Z",
+    );
+
+    let mut kb = open_knowledge_base(temp_config(&temp_dir.path().join("kb"), EXTENSIONS));
+    kb.set_collection("client").unwrap();
+    assert_eq!(kb.add_git_history(&repo, None, false).unwrap().commits, 2);
+    kb.set_collection("server").unwrap();
+    assert_eq!(kb.add_git_history(&other, None, false).unwrap().commits, 1);
+
+    // 只有被删除提交所在仓库的索引进度被清除
+    assert_eq!(kb.delete_collection("client").unwrap(), 2);
+    assert_eq!(kb.add_git_history(&other, None, false).unwrap().commits, 0);
+    assert_eq!(kb.add_git_history(&repo, None, false).unwrap().commits, 2);
+}
+
+#[test]
+fn test_blame_and_context() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let repo = sample_repo(&temp_dir.path().join("repo"));
+    let client = repo.join("client.rs");
+
+    let commit = blame_lines(&client, 1, 1).unwrap();
+    assert_eq!(commit.summary, "Add client");
+    let commit = blame_lines(&client, 1, 5).unwrap();
+    assert_eq!(
+        commit.summary, "Add retry logic to connect",
+        "应返回范围内最新的提交"
+    );
+    assert_eq!(commit.author, "Alice");
+    assert!(commit.describe().contains("drops the first connection"));
+
+    let repository = GitRepository::open(&repo).unwrap();
+    assert_eq!(
+        repository.blame(&client, None).unwrap().unwrap().hash,
+        commit.hash
+    );
+
+    // 未提交的修改不计入
+    fs::write(repo.join("draft.rs"), "fn draft() {}\n").unwrap();
+    assert!(blame_lines(&repo.join("draft.rs"), 1, 1).is_none());
+    assert!(blame_lines(&temp_dir.path().join("outside.rs"), 1, 1).is_none());
+
+    let mut kb = open_knowledge_base(temp_config(&temp_dir.path().join("kb"), EXTENSIONS));
+    kb.add_files(&[client.to_string_lossy().to_string()], false)
+        .unwrap();
+    let kb: Arc<dyn KnowledgeBase> = Arc::new(kb);
+    let items = ContextCollector::new()
+        .with_knowledge_base(kb)
+        .collect_related_code("connect");
+    assert_eq!(items.len(), 1);
+    assert!(
+        items[0].content.contains("Add retry logic to connect"),
+        "代码片段应附加最近的提交说明"
+    );
+    assert!(items[0]
+        .tags
+        .contains(&format!("commit:{}", commit.short_hash())));
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use codex::knowledge::health::{HealthIssue, VerifyReport};
+use common::{open_knowledge_base, temp_config};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建包含源文件和文档的项目目录，返回规范化后的目录
+fn sample_project(dir: &Path) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(
+        dir.join("lib.rs"),
+        "fn load_config() {}\n\nfn save_config() {}\n\nfn reset_config() {}\n",
+    )
+    .unwrap();
+    fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+    fs::write(dir.join("README.md"), "# Sample\n").unwrap();
+    fs::canonicalize(dir).unwrap()
+}
+
+#[test]
+fn test_stats_counts_documents_and_symbols() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"));
+    let mut kb = open_knowledge_base(temp_config(&temp_dir.path().join("kb"), EXTENSIONS));
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    kb.add_web_page("https://example.com/guide", "Guide", "# Guide\n")
+        .unwrap();
+
+    let stats = kb.stats().unwrap();
+    assert_eq!(stats.documents, 4);
+    assert_eq!(stats.by_source.get("file"), Some(&3));
+    assert_eq!(stats.by_source.get("web"), Some(&1));
+    assert_eq!(stats.by_language.get("rust"), Some(&2));
+    assert_eq!(stats.by_collection.get("default"), Some(&4));
+    assert_eq!(stats.symbols, 5);
+    assert_eq!(stats.symbols_by_kind.get("function"), Some(&4));
+    assert_eq!(stats.symbols_by_kind.get("section"), Some(&1));
+    assert_eq!(stats.cached_files, 3, "有解析器的文件写入索引缓存");
+    assert!(stats.index_size > 0);
+    assert!(stats.metadata_size > 0);
+    assert!(stats.last_updated.is_some());
+}
+
+#[test]
+fn test_verify_and_repair_inconsistencies() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"));
+    let kb_dir = temp_dir.path().join("kb");
+    let mut kb = open_knowledge_base(temp_config(&kb_dir, EXTENSIONS));
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    assert!(kb.verify().unwrap().is_healthy(), "新建的知识库应当一致");
+    drop(kb);
+
+    // 删除一个已索引的文件，并让索引缓存与符号索引不一致
+    fs::remove_file(project.join("main.rs")).unwrap();
+    let lib = project.join("lib.rs").to_string_lossy().to_string();
+    let cache_path = kb_dir.join("metadata").join("index_cache.json");
+    let mut cache: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    let files = cache["files"].as_object_mut().unwrap();
+    let item = files.remove(&lib).unwrap();
+    files.insert("/no/such/file.rs".to_string(), item);
+    fs::write(&cache_path, serde_json::to_string(&cache).unwrap()).unwrap();
+
+    let mut kb = open_knowledge_base(temp_config(&kb_dir, EXTENSIONS));
+    let report = kb.verify().unwrap();
+    assert_eq!(report.checked, 3);
+    let main = project.join("main.rs").to_string_lossy().to_string();
+    assert!(report.issues.contains(&HealthIssue::MissingFile(main)));
+    assert!(report.issues.contains(&HealthIssue::CacheMismatch {
+        path: lib.clone(),
+        cached: 0,
+        indexed: 3,
+    }));
+    assert!(report.issues.contains(&HealthIssue::StaleCacheEntry(
+        "/no/such/file.rs".to_string()
+    )));
+    assert_eq!(
+        report.issues.len(),
+        3,
+        "不应报告其他问题: {:?}",
+        report.issues
+    );
+
+    let summary = kb.repair(&report).unwrap();
+    assert_eq!(summary.removed, 1);
+    assert_eq!(summary.reindexed, 1);
+    assert_eq!(summary.cleaned, 1);
+    assert!(kb.verify().unwrap().is_healthy(), "修复后应当一致");
+    assert_eq!(kb.document_count(), 2);
+    assert_eq!(kb.search_symbols("load_config", 5).unwrap().len(), 1);
+    assert!(kb.search_symbols("main", 5).unwrap().is_empty());
+
+    // 同一文件的多个问题只重新索引一次
+    let report = VerifyReport {
+        checked: 2,
+        issues: vec![
+            HealthIssue::MissingContent(lib.clone()),
+            HealthIssue::OrphanedXrefs("/no/such/file.rs".to_string()),
+            HealthIssue::CacheMismatch {
+                path: lib,
+                cached: 0,
+                indexed: 3,
+            },
+        ],
+    };
+    assert_eq!(kb.repair(&report).unwrap().reindexed, 1);
+}
+
+#[test]
+fn test_compact_merges_segments() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"));
+    let mut kb = open_knowledge_base(temp_config(&temp_dir.path().join("kb"), EXTENSIONS));
+    for i in 0..3 {
+        let path = project.join(format!("extra_{}.rs", i));
+        fs::write(&path, format!("fn extra_{}() {{}}\n", i)).unwrap();
+        kb.add_files(&[path.to_string_lossy().to_string()], false)
+            .unwrap();
+    }
+    assert!(kb.stats().unwrap().segments > 2, "每次提交都会产生新的段");
+
+    let summary = kb.compact().unwrap();
+    assert!(summary.segments_before > summary.segments_after);
+    assert_eq!(summary.segments_after, 2, "两个索引应各合并为一个段");
+    assert_eq!(kb.search_symbols("extra_1", 5).unwrap().len(), 1);
+    assert!(kb.verify().unwrap().is_healthy());
+}
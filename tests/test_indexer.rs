@@ -0,0 +1,113 @@
+mod common;
+
+use codex::knowledge::base::CodeFile;
+use codex::knowledge::indexer::CodeIndexer;
+use codex::knowledge::searcher::CodeSearcher;
+use codex::parsers::CodeElementType;
+use common::temp_config;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs"];
+
+/// 写入文件并构造CodeFile
+fn write_code_file(path: &Path, content: &str) -> CodeFile {
+    fs::write(path, content).unwrap();
+    CodeFile {
+        path: path.to_path_buf(),
+        content: content.to_string(),
+        language: "rs".to_string(),
+        size: content.len() as u64,
+        modified_at: 0,
+    }
+}
+
+#[test]
+fn test_index_file_and_search() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut indexer = CodeIndexer::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+
+    let source = dir.path().join("lib.rs");
+    let file = write_code_file(
+        &source,
+        "fn parse_config(path: &str) -> Config {\n    todo!()\n}\n\nfn load_config() {}\n\nfn unrelated() {}\n",
+    );
+    indexer.index_file(file).unwrap();
+
+    let results = indexer.search("parse_config").unwrap();
+    assert!(!results.is_empty(), "应搜索到已索引的函数");
+    let first = &results[0];
+    assert_eq!(first.name, "parse_config");
+    assert_eq!(first.element_type, CodeElementType::Function);
+    assert_eq!(first.definition.file_path, source.to_str().unwrap());
+    assert_eq!(first.definition.line, 1);
+
+    let scored = indexer.search_symbols("parse_config", None, 10).unwrap();
+    assert_eq!(scored[0].signature, "fn parse_config(path: &str) -> Config");
+    assert!(
+        scored.windows(2).all(|w| w[0].score >= w[1].score),
+        "结果应按评分排序"
+    );
+
+    assert!(indexer.search("nonexistent").unwrap().is_empty());
+}
+
+#[test]
+fn test_reindex_replaces_old_symbols() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut indexer = CodeIndexer::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+
+    let source = dir.path().join("main.rs");
+    indexer
+        .index_file(write_code_file(&source, "fn old_name() {}\n"))
+        .unwrap();
+    assert_eq!(indexer.search("old_name").unwrap().len(), 1);
+
+    // 修改文件内容后重新索引，旧符号应被删除
+    indexer
+        .index_file(write_code_file(
+            &source,
+            "fn new_name() {}\nfn other() {}\n",
+        ))
+        .unwrap();
+    assert!(
+        indexer.search("old_name").unwrap().is_empty(),
+        "旧符号应被删除"
+    );
+    assert_eq!(indexer.search("new_name").unwrap().len(), 1);
+    assert_eq!(indexer.total_elements(), 2);
+
+    indexer.remove_file(&source).unwrap();
+    assert!(indexer.search("new_name").unwrap().is_empty());
+    assert_eq!(indexer.file_count(), 0);
+}
+
+#[test]
+fn test_searcher_filters_by_type() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut indexer = CodeIndexer::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    indexer
+        .index_file(write_code_file(
+            &dir.path().join("lib.rs"),
+            "fn render() {}\nfn render_page() {}\n",
+        ))
+        .unwrap();
+
+    let searcher = CodeSearcher::new(Arc::new(RwLock::new(indexer)));
+    let results = searcher.search("render").unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].name, "render", "完全匹配的名称应排在前面");
+    assert_eq!(
+        searcher
+            .search_by_type("render", &CodeElementType::Function)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(searcher
+        .search_by_type("render", &CodeElementType::Struct)
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use codex::config::app::KnowledgeConfig;
+use codex::knowledge::base::{DocumentSource, KnowledgeBase};
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::migrate::{find_legacy_store, migrate_legacy_store, LEGACY_DB_DIR};
+use common::temp_config;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建排除构建输出目录的配置
+fn project_config(dir: &Path) -> KnowledgeConfig {
+    KnowledgeConfig {
+        exclude_patterns: vec!["target".to_string()],
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建包含源文件和文档的临时项目
+fn sample_project(dir: &Path) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    fs::create_dir_all(project.join("target")).unwrap();
+    fs::write(
+        project.join("src/lib.rs"),
+        "/// 解析配置\nfn parse_config() {}\n",
+    )
+    .unwrap();
+    fs::write(
+        project.join("README.md"),
+        "# Demo\n\nConfiguration guide for the demo project.\n",
+    )
+    .unwrap();
+    fs::write(project.join("target/build.rs"), "fn ignored() {}\n").unwrap();
+    project
+}
+
+#[test]
+fn test_add_search_and_remove() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+    let mut kb = LocalKnowledgeBase::new(project_config(dir.path())).unwrap();
+    kb.init().unwrap();
+
+    let added = kb
+        .add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    assert_eq!(added, 2, "排除目录中的文件不应被添加");
+    assert_eq!(kb.list_files().unwrap().len(), 2);
+
+    // 全文搜索
+    let hits = kb.search_documents("configuration", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].path.ends_with("README.md"));
+    assert_eq!(hits[0].language, "markdown");
+    assert!(hits[0].snippet.contains("Configuration"));
+
+    // 符号搜索
+    let symbols = kb.search("parse_config").unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert!(symbols[0].definition.file_path.ends_with("lib.rs"));
+
+    // 删除文件后文档和符号都应被移除
+    kb.remove_file(&project.join("src/lib.rs")).unwrap();
+    assert!(kb.search("parse_config").unwrap().is_empty());
+    assert_eq!(kb.list_files().unwrap().len(), 1);
+
+    kb.clear().unwrap();
+    assert!(kb.list().unwrap().is_empty());
+    assert!(kb.search_documents("configuration", 10).unwrap().is_empty());
+}
+
+#[test]
+fn test_data_persists_in_configured_dirs() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+    {
+        let mut kb = LocalKnowledgeBase::new(project_config(dir.path())).unwrap();
+        kb.add_files(
+            &[project.join("README.md").to_string_lossy().to_string()],
+            false,
+        )
+        .unwrap();
+        kb.add_web_page(
+            "https://example.com/guide",
+            "Guide",
+            "Web configuration tips",
+        )
+        .unwrap();
+        kb.commit().unwrap();
+    }
+
+    assert!(dir.path().join("index/documents").is_dir());
+    assert!(dir.path().join("metadata").is_dir());
+
+    // 重新打开后数据仍然存在
+    let kb = LocalKnowledgeBase::new(project_config(dir.path())).unwrap();
+    assert_eq!(kb.document_count(), 2);
+    let hits = kb.search_documents("configuration", 10).unwrap();
+    assert_eq!(hits.len(), 2);
+    let web = kb.get("https://example.com/guide").unwrap().unwrap();
+    assert_eq!(web.source, DocumentSource::Web);
+    assert_eq!(web.title, "Guide");
+}
+
+#[test]
+fn test_migrate_legacy_store() {
+    let dir = tempfile::tempdir().unwrap();
+    let legacy_dir = dir.path().join("legacy");
+    fs::create_dir_all(&legacy_dir).unwrap();
+    fs::write(legacy_dir.join("main.rs"), "fn legacy_main() {}\n").unwrap();
+
+    // 构造旧版知识库：键为路径或URL，值为内容
+    {
+        let db = sled::open(legacy_dir.join(LEGACY_DB_DIR)).unwrap();
+        db.insert("main.rs", "fn legacy_main() {}\n").unwrap();
+        db.insert("/removed/notes.md", "Legacy notes about deployment")
+            .unwrap();
+        db.insert("https://example.com/docs", "Scraped deployment docs")
+            .unwrap();
+        db.flush().unwrap();
+    }
+    assert!(find_legacy_store(&legacy_dir).is_some());
+
+    let mut kb = LocalKnowledgeBase::new(project_config(dir.path())).unwrap();
+    let report = migrate_legacy_store(&mut kb, &legacy_dir).unwrap();
+    assert_eq!(report.files, 2);
+    assert_eq!(report.pages, 1);
+
+    assert_eq!(kb.document_count(), 3);
+    assert_eq!(kb.search_documents("deployment", 10).unwrap().len(), 2);
+    assert_eq!(
+        kb.search("legacy_main").unwrap().len(),
+        1,
+        "仍存在的文件应建立符号索引"
+    );
+
+    // 迁移后旧目录被重命名，不会重复迁移
+    assert!(find_legacy_store(&legacy_dir).is_none());
+    assert!(legacy_dir
+        .join(format!("{}.migrated", LEGACY_DB_DIR))
+        .is_dir());
+}
@@ -0,0 +1,99 @@
+use codex::context::{CompressionStrategy, ContextManager};
+use codex::mentions::{MentionKind, MentionResolver};
+use std::fs;
+use std::path::PathBuf;
+
+/// 创建包含若干源文件的临时项目
+fn sample_project() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+    let lines: Vec<String> = (1..=50).map(|i| format!("// line {}", i)).collect();
+    fs::write(dir.path().join("src/main.rs"), lines.join("\n")).unwrap();
+    fs::write(dir.path().join("src/lib.rs"), "pub fn helper() {}\n").unwrap();
+    fs::write(dir.path().join("src/nested/util.rs"), "pub fn util() {}\n").unwrap();
+    fs::write(dir.path().join("README.md"), "# demo\n").unwrap();
+    dir
+}
+
+#[test]
+fn test_parse_mentions() {
+    let project = sample_project();
+    let resolver = MentionResolver::new(project.path());
+
+    let mentions = resolver
+        .parse("看看 @src/main.rs:10-12 和 @src/*.rs，还有 @README.md。以及 @Config 邮箱 a@b");
+    let kinds: Vec<MentionKind> = mentions.into_iter().map(|m| m.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            MentionKind::LineRange(PathBuf::from("src/main.rs"), 10, 12),
+            MentionKind::Glob("src/*.rs".to_string()),
+            MentionKind::File(PathBuf::from("README.md")),
+            MentionKind::Symbol("Config".to_string()),
+        ],
+        "应识别行范围、通配符、文件和符号引用"
+    );
+
+    // 目录引用展开为递归通配符
+    let mentions = resolver.parse("@src/nested");
+    assert_eq!(
+        mentions[0].kind,
+        MentionKind::Glob("src/nested/**/*".to_string())
+    );
+}
+
+#[test]
+fn test_resolve_line_range_and_glob() {
+    let project = sample_project();
+    let resolver = MentionResolver::new(project.path());
+
+    let (resolved, errors) = resolver.resolve_all("@src/main.rs:10-12");
+    assert!(errors.is_empty(), "不应有解析错误: {:?}", errors);
+    assert_eq!(resolved.len(), 1);
+    assert_eq!(resolved[0].label, "src/main.rs:10-12");
+    assert!(resolved[0].content.contains("// line 10"));
+    assert!(resolved[0].content.contains("// line 12"));
+    assert!(!resolved[0].content.contains("// line 13"));
+
+    // 单层通配符不应匹配子目录中的文件
+    let (resolved, _) = resolver.resolve_all("@src/*.rs");
+    let mut labels: Vec<String> = resolved.into_iter().map(|m| m.label).collect();
+    labels.sort();
+    assert_eq!(labels, vec!["src/lib.rs", "src/main.rs"]);
+
+    let (resolved, _) = resolver.resolve_all("@src");
+    assert_eq!(resolved.len(), 3, "目录引用应包含所有子文件");
+
+    let (resolved, errors) = resolver.resolve_all("@src/*.py");
+    assert!(resolved.is_empty());
+    assert_eq!(errors.len(), 1, "没有匹配的通配符应报告错误");
+}
+
+#[test]
+fn test_pinned_mentions_survive_compression() {
+    let project = sample_project();
+    let resolver = MentionResolver::new(project.path());
+    let mut context = ContextManager::new(200, CompressionStrategy::RecentFirst);
+
+    let (resolved, _) = resolver.resolve_all("@src/main.rs:1-5");
+    let attached = MentionResolver::pin_into(&resolved, &mut context);
+    assert_eq!(attached.len(), 1);
+    assert_eq!(attached[0].0, "src/main.rs:1-5");
+    assert!(attached[0].1 > 0, "应统计固定项的令牌数");
+
+    // 添加大量消息触发压缩
+    for i in 0..50 {
+        context.add_user_message(&format!(
+            "这是第 {} 条比较长的用户消息，用于触发上下文压缩",
+            i
+        ));
+    }
+
+    let pinned = context.pinned_items();
+    assert_eq!(pinned.len(), 1, "固定项不应被压缩移除");
+    assert_eq!(pinned[0].source(), Some("src/main.rs:1-5"));
+    assert!(context.len() < 51, "未固定的消息应被压缩");
+
+    context.unpin(&pinned[0].id);
+    assert!(context.pinned_items().is_empty());
+}
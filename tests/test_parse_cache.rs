@@ -0,0 +1,221 @@
+mod common;
+
+use codex::knowledge::base::CodeFile;
+use codex::knowledge::indexer::CodeIndexer;
+use codex::knowledge::pipeline::{IndexSink, ParsedFile};
+use codex::parse_cache::{diff_elements, ParseCache, TextEdit};
+use codex::parsers::{initialize_parsers, CodeElement};
+use common::temp_config;
+use std::fs;
+use std::path::Path;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs"];
+
+/// 获取元素名称
+fn names(elements: &[CodeElement]) -> Vec<&str> {
+    elements.iter().map(|e| e.name.as_str()).collect()
+}
+
+#[test]
+fn test_edits_reparse_incrementally() {
+    initialize_parsers().unwrap();
+    let mut cache = ParseCache::new();
+    let source = "fn alpha() {}\n\nfn beta() {}\n\nfn gamma() {}\n";
+    let elements = cache.open("src/lib.rs", source).unwrap().unwrap();
+    assert_eq!(names(elements), vec!["alpha", "beta", "gamma"]);
+    assert!(cache.open("notes.unknown", "text").unwrap().is_none());
+    assert!(cache.elements("notes.unknown").is_none());
+
+    // 重命名函数只影响该函数，之后的函数因位置移动也会变化
+    let at = source.find("beta").unwrap();
+    let update = cache
+        .edit("src/lib.rs", &[TextEdit::replace(at, at + 4, "delta")])
+        .unwrap();
+    assert_eq!(names(&update.elements.removed), vec!["beta", "gamma"]);
+    assert_eq!(names(&update.elements.changed), vec!["delta", "gamma"]);
+    assert_eq!(update.changed_ranges.len(), 1);
+    assert_eq!(update.changed_ranges[0].start_line, 3);
+    assert_eq!(update.changed_ranges[0].end_line, 3);
+    assert_eq!(
+        names(cache.elements("src/lib.rs").unwrap()),
+        vec!["alpha", "delta", "gamma"]
+    );
+
+    // 多个编辑依次应用，只修改函数体时元素不变
+    let content = "fn alpha() {}\n\nfn delta() {}\n\nfn gamma() {}\n";
+    let body = content.find("{}").unwrap() + 1;
+    let update = cache
+        .edit(
+            "src/lib.rs",
+            &[
+                TextEdit::replace(body, body, " 1 "),
+                TextEdit::replace(body + 1, body + 2, ""),
+            ],
+        )
+        .unwrap();
+    let before = content.replacen("{}", "{  }", 1);
+    assert_eq!(update.changed_ranges.len(), 1);
+    assert_eq!(update.changed_ranges[0].start_line, 1);
+    assert!(!update.elements.changed.is_empty(), "alpha 的结束位置变化");
+    assert_eq!(names(&update.elements.changed)[0], "alpha");
+
+    // 无效的编辑范围返回错误，缓存保持不变
+    assert!(cache
+        .edit("src/lib.rs", &[TextEdit::replace(5, before.len() + 1, "")])
+        .is_err());
+    assert!(cache.edit("src/other.rs", &[]).is_err());
+
+    // 按新内容更新：相同内容没有变化，未缓存的文件所有元素都视为变化
+    let update = cache.update("src/lib.rs", &before).unwrap().unwrap();
+    assert!(update.changed_ranges.is_empty());
+    assert!(update.elements.changed.is_empty() && update.elements.removed.is_empty());
+    let update = cache
+        .update(
+            "src/lib.rs",
+            &before.replace("fn gamma() {}\n", "/// 说明\nfn gamma() {}\n"),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(names(&update.elements.changed), vec!["gamma"]);
+    assert_eq!(update.changed_ranges[0].start_line, 5);
+    let update = cache
+        .update("app.py", "def main():\n    pass\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(names(&update.elements.changed), vec!["main"]);
+    assert!(update.elements.removed.is_empty());
+    assert_eq!(update.changed_ranges[0].end_line, 2);
+
+    // 多字节字符边界上的修改
+    let update = cache
+        .update("src/lib.rs", &before.replace("alpha", "阿尔法"))
+        .unwrap()
+        .unwrap();
+    assert_eq!(names(&update.elements.changed)[0], "阿尔法");
+    assert_eq!(names(&update.elements.removed)[0], "alpha");
+    assert_eq!(update.changed_ranges[0].start_byte, 3);
+
+    assert!(cache.remove("app.py"));
+    assert!(!cache.remove("app.py"));
+    assert!(cache.elements("src/lib.rs").is_some());
+}
+
+#[test]
+fn test_cache_evicts_least_recently_parsed() {
+    initialize_parsers().unwrap();
+    let mut cache = ParseCache::with_capacity(2);
+    cache.open("a.rs", "fn a() {}\n").unwrap();
+    cache.open("b.rs", "fn b() {}\n").unwrap();
+    // 重新解析 a.rs 后，最久未解析的是 b.rs
+    cache.update("a.rs", "fn a() {}\n").unwrap();
+    cache.open("c.rs", "fn c() {}\n").unwrap();
+    assert!(cache.elements("b.rs").is_none());
+    assert!(cache.elements("a.rs").is_some());
+    assert!(cache.elements("c.rs").is_some());
+
+    // 被淘汰的文件再次更新时完整解析
+    let update = cache.update("b.rs", "fn b() {}\n").unwrap().unwrap();
+    assert_eq!(names(&update.elements.changed), vec!["b"]);
+    assert!(cache.elements("a.rs").is_none());
+}
+
+#[test]
+fn test_diff_elements_matches_duplicates() {
+    initialize_parsers().unwrap();
+    let mut cache = ParseCache::new();
+    let old = cache
+        .open("a.rs", "fn a() {}\nfn b() {}\n")
+        .unwrap()
+        .unwrap()
+        .to_vec();
+    let mut new = old.clone();
+    new.push(old[0].clone());
+    let changes = diff_elements(&old, &new);
+    assert_eq!(names(&changes.changed), vec!["a"]);
+    assert!(changes.removed.is_empty());
+    let unchanged = diff_elements(&old, &old);
+    assert!(unchanged.changed.is_empty() && unchanged.removed.is_empty());
+}
+
+/// 写入文件并构造CodeFile
+fn write_code_file(path: &Path, content: &str) -> CodeFile {
+    fs::write(path, content).unwrap();
+    CodeFile {
+        path: path.to_path_buf(),
+        content: content.to_string(),
+        language: "rs".to_string(),
+        size: content.len() as u64,
+        modified_at: 0,
+    }
+}
+
+#[test]
+fn test_indexer_updates_changed_symbols() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut indexer = CodeIndexer::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    let source = dir.path().join("lib.rs");
+    let key = source.to_string_lossy().to_string();
+
+    indexer
+        .index_file(write_code_file(
+            &source,
+            "fn keep() {}\n\nfn rename_me() {}\n\nfn tail() {}\n",
+        ))
+        .unwrap();
+    indexer
+        .index_file(write_code_file(
+            &source,
+            "fn keep() {}\n\nfn renamed() {}\n\nfn tail() {}\n",
+        ))
+        .unwrap();
+    assert!(indexer.search("rename_me").unwrap().is_empty());
+    assert_eq!(indexer.search("renamed").unwrap().len(), 1);
+    assert_eq!(indexer.search("keep").unwrap().len(), 1);
+    let tail = indexer.search("tail").unwrap();
+    assert_eq!(tail.len(), 1);
+    assert_eq!(tail[0].definition.start_byte, 31);
+    assert_eq!(indexer.symbol_counts().unwrap().by_file[&key], 3);
+
+    // 未提交的修改不可见时回退为替换全部符号
+    for content in ["fn keep() {}\n", "fn keep() {}\nfn added() {}\n"] {
+        let file = write_code_file(&source, content);
+        let elements = CodeIndexer::parse_elements(&key, content).unwrap().unwrap();
+        indexer.store_elements(&file, &elements, None).unwrap();
+    }
+    indexer.commit().unwrap();
+    assert_eq!(indexer.symbol_counts().unwrap().by_file[&key], 2);
+    assert!(indexer.search("tail").unwrap().is_empty());
+    assert_eq!(indexer.search("added").unwrap().len(), 1);
+}
+
+#[test]
+fn test_indexer_applies_incremental_parse_changes() {
+    initialize_parsers().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let mut indexer = CodeIndexer::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    let source = dir.path().join("lib.rs");
+    let key = source.to_string_lossy().to_string();
+    let mut cache = ParseCache::new();
+
+    // 首次解析没有可用的差异，之后的解析只报告变化的元素
+    for (content, changed) in [
+        ("fn keep() {}\n\nfn old_name() {}\n", None),
+        ("fn keep() {}\n\nfn new_name() {}\n", Some(vec!["new_name"])),
+    ] {
+        fs::write(&source, content).unwrap();
+        let parsed = ParsedFile::parse_cached(&source, content.to_string(), &mut cache).unwrap();
+        assert_eq!(
+            parsed
+                .changes
+                .as_ref()
+                .map(|changes| names(&changes.changed)),
+            changed
+        );
+        indexer.write_parsed(parsed).unwrap();
+        indexer.commit().unwrap();
+    }
+    assert!(indexer.search("old_name").unwrap().is_empty());
+    assert_eq!(indexer.search("new_name").unwrap().len(), 1);
+    assert_eq!(indexer.symbol_counts().unwrap().by_file[&key], 2);
+}
@@ -1,4 +1,7 @@
-use codex::parsers::{initialize_parsers, PARSER_REGISTRY};
+use codex::parsers::{
+    check_file_syntax, initialize_parsers, CodeElement, CodeElementType, SyntaxDiagnosticKind,
+    Visibility, PARSER_REGISTRY,
+};
 
 #[test]
 fn test_parser_registry_initialize() {
@@ -15,6 +18,14 @@ fn test_parser_registry_initialize() {
     assert!(supported_languages.contains(&"python".to_string()));
     assert!(supported_languages.contains(&"javascript".to_string()));
     assert!(supported_languages.contains(&"typescript".to_string()));
+    assert!(supported_languages.contains(&"go".to_string()));
+    assert!(supported_languages.contains(&"java".to_string()));
+    assert!(supported_languages.contains(&"c".to_string()));
+    assert!(supported_languages.contains(&"cpp".to_string()));
+    assert!(supported_languages.contains(&"markdown".to_string()));
+    assert!(supported_languages.contains(&"toml".to_string()));
+    assert!(supported_languages.contains(&"yaml".to_string()));
+    assert!(supported_languages.contains(&"json".to_string()));
 
     // 检查支持的扩展名
     let supported_extensions = registry.supported_extensions();
@@ -22,6 +33,12 @@ fn test_parser_registry_initialize() {
     assert!(supported_extensions.contains(&"py".to_string()));
     assert!(supported_extensions.contains(&"js".to_string()));
     assert!(supported_extensions.contains(&"ts".to_string()));
+    assert!(supported_extensions.contains(&"go".to_string()));
+    assert!(supported_extensions.contains(&"java".to_string()));
+    assert!(supported_extensions.contains(&"h".to_string()));
+    assert!(supported_extensions.contains(&"hpp".to_string()));
+    assert!(supported_extensions.contains(&"md".to_string()));
+    assert!(supported_extensions.contains(&"yml".to_string()));
 }
 
 #[test]
@@ -90,12 +107,20 @@ class Point:
     assert!(result.is_ok(), "Python代码解析失败");
 
     let elements = result.unwrap();
-    // 应该至少解析出3个函数（main, add, __init__）
+    // 应该至少解析出3个函数（main, add, __init__），类中的__init__为方法
     let functions = elements
         .iter()
-        .filter(|e| e.element_type == codex::parsers::CodeElementType::Function)
+        .filter(|e| {
+            matches!(
+                e.element_type,
+                CodeElementType::Function | CodeElementType::Method
+            )
+        })
         .collect::<Vec<_>>();
     assert!(functions.len() >= 3, "应该解析出至少3个函数");
+    let init = functions.iter().find(|f| f.name == "__init__").unwrap();
+    assert_eq!(init.element_type, CodeElementType::Method);
+    assert_eq!(init.scope.as_deref(), Some("Point"));
 
     // 检查函数名是否正确
     let function_names: Vec<_> = functions.iter().map(|f| &f.name[..]).collect();
@@ -253,3 +278,633 @@ fn test_parser_by_filename() {
         "应该根据component.ts文件名获取到TypeScript解析器"
     );
 }
+
+/// 检查是否解析出指定类型和名称的代码元素
+fn has_element(elements: &[CodeElement], element_type: CodeElementType, name: &str) -> bool {
+    elements
+        .iter()
+        .any(|e| e.element_type == element_type && e.name == name)
+}
+
+#[test]
+fn test_go_parser() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+
+    // 获取Go解析器
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let go_parser = registry.get_parser_by_language("go").unwrap();
+
+    // 测试Go代码解析
+    let go_code = r#"package shapes
+
+type Shape interface {
+    Area() float64
+}
+
+type Rect struct {
+    Width, Height float64
+}
+
+type Meters = float64
+
+func (r Rect) Area() float64 {
+    return r.Width * r.Height
+}
+
+func NewRect(w, h float64) Rect {
+    return Rect{w, h}
+}
+"#;
+
+    let elements = go_parser.parse_file("shapes.go", go_code).unwrap();
+    assert!(
+        has_element(&elements, CodeElementType::Module, "shapes"),
+        "应该包含shapes包"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Interface, "Shape"),
+        "应该包含Shape接口"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Struct, "Rect"),
+        "应该包含Rect结构体"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::TypeAlias, "Meters"),
+        "应该包含Meters类型别名"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Method, "Area"),
+        "应该包含Area方法"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Function, "NewRect"),
+        "应该包含NewRect函数"
+    );
+
+    // 检查位置信息
+    let new_rect = elements.iter().find(|e| e.name == "NewRect").unwrap();
+    assert_eq!(new_rect.definition.line, 17);
+    assert_eq!(new_rect.definition.file_path, "shapes.go");
+    assert_eq!(new_rect.language, "go");
+}
+
+#[test]
+fn test_java_parser() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+
+    // 获取Java解析器
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let java_parser = registry.get_parser_by_language("java").unwrap();
+
+    // 测试Java代码解析
+    let java_code = r#"package com.example.demo;
+
+public interface Greeter {
+    String greet(String name);
+}
+
+public enum Color { RED, GREEN }
+
+public class HelloController implements Greeter {
+    public HelloController() {}
+
+    @Override
+    public String greet(String name) {
+        return "Hello " + name;
+    }
+
+    static class Inner {}
+}
+"#;
+
+    let elements = java_parser.parse_snippet(java_code).unwrap();
+    assert!(
+        has_element(&elements, CodeElementType::Module, "com.example.demo"),
+        "应该包含包名"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Interface, "Greeter"),
+        "应该包含Greeter接口"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Enum, "Color"),
+        "应该包含Color枚举"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Class, "HelloController"),
+        "应该包含HelloController类"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Class, "Inner"),
+        "应该包含嵌套类"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Method, "greet"),
+        "应该包含greet方法"
+    );
+
+    // 构造函数和接口方法声明都按方法处理
+    let greets = elements.iter().filter(|e| e.name == "greet").count();
+    assert_eq!(greets, 2, "接口和类中的greet方法都应该被解析");
+    assert_eq!(
+        elements
+            .iter()
+            .filter(|e| e.name == "HelloController")
+            .count(),
+        2
+    );
+}
+
+#[test]
+fn test_c_parser() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+
+    // 获取C解析器
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let c_parser = registry.get_parser_by_filename("list.h").unwrap();
+    assert_eq!(c_parser.language_name(), "c");
+
+    // 测试C代码解析
+    let c_code = r#"struct node {
+    int value;
+    struct node *next;
+};
+
+typedef struct {
+    int x, y;
+} Point;
+
+typedef unsigned long size_type;
+
+enum color { RED, GREEN };
+
+static struct node *list_push(struct node *head, int value) {
+    return head;
+}
+
+int main(void) {
+    struct node n;
+    return 0;
+}
+"#;
+
+    let elements = c_parser.parse_snippet(c_code).unwrap();
+    assert!(
+        has_element(&elements, CodeElementType::Struct, "node"),
+        "应该包含node结构体"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Struct, "Point"),
+        "匿名结构体的类型定义应按结构体处理"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::TypeAlias, "size_type"),
+        "应该包含size_type类型定义"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Enum, "color"),
+        "应该包含color枚举"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Function, "list_push"),
+        "应该包含返回指针的函数"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Function, "main"),
+        "应该包含main函数"
+    );
+    assert_eq!(
+        elements.iter().filter(|e| e.name == "node").count(),
+        1,
+        "结构体的引用不应被识别为定义"
+    );
+}
+
+#[test]
+fn test_cpp_parser() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+
+    // 获取C++解析器
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let cpp_parser = registry.get_parser_by_language("cpp").unwrap();
+
+    // 测试C++代码解析
+    let cpp_code = r#"namespace geometry {
+
+using Scalar = double;
+
+class Shape {
+public:
+    virtual ~Shape() {}
+    virtual Scalar area() const = 0;
+};
+
+struct Circle : public Shape {
+    Scalar radius;
+    Scalar area() const override { return 3.14 * radius * radius; }
+};
+
+template <typename T>
+T clamp(T value, T low, T high) {
+    return value < low ? low : value;
+}
+
+}
+
+geometry::Scalar Square::area() const {
+    return side * side;
+}
+"#;
+
+    let elements = cpp_parser.parse_snippet(cpp_code).unwrap();
+    assert!(
+        has_element(&elements, CodeElementType::Module, "geometry"),
+        "应该包含geometry命名空间"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::TypeAlias, "Scalar"),
+        "应该包含Scalar类型别名"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Class, "Shape"),
+        "应该包含Shape类"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Struct, "Circle"),
+        "应该包含Circle结构体"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Method, "~Shape"),
+        "应该包含析构函数"
+    );
+    assert!(
+        has_element(&elements, CodeElementType::Function, "clamp"),
+        "应该包含模板函数"
+    );
+    assert_eq!(
+        elements.iter().filter(|e| e.name == "area").count(),
+        2,
+        "类内定义和类外定义的方法都应该被解析，纯虚函数声明除外"
+    );
+}
+
+/// 按名称查找代码元素
+fn find_element<'a>(elements: &'a [CodeElement], name: &str) -> &'a CodeElement {
+    elements
+        .iter()
+        .find(|e| e.name == name)
+        .unwrap_or_else(|| panic!("应该包含{}", name))
+}
+
+#[test]
+fn test_rust_element_details() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+
+    // 获取Rust解析器
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let rust_parser = registry.get_parser_by_language("rust").unwrap();
+
+    let rust_code = r#"pub mod geometry {
+    #[derive(Debug, Clone)]
+    pub struct Point<T> {
+        x: T,
+        y: T,
+    }
+
+    impl<T: Copy> Point<T> {
+        pub fn new(x: T, y: T) -> Self {
+            Self { x, y }
+        }
+
+        fn swap(&self) -> Self {
+            Self { x: self.y, y: self.x }
+        }
+    }
+
+    pub(crate) trait Shape {
+        fn area(&self) -> f64;
+    }
+}
+"#;
+
+    let elements = rust_parser.parse_file("geometry.rs", rust_code).unwrap();
+
+    // 完整的定义范围
+    let point = find_element(&elements, "Point");
+    assert_eq!(point.element_type, CodeElementType::Struct);
+    assert_eq!(point.definition.line, 3);
+    assert_eq!(point.definition.end_line, 6);
+    assert_eq!(
+        &rust_code[point.definition.start_byte..point.definition.end_byte],
+        "pub struct Point<T> {\n        x: T,\n        y: T,\n    }"
+    );
+
+    // 签名、泛型、属性和可见性
+    assert_eq!(point.signature.as_deref(), Some("pub struct Point<T>"));
+    assert_eq!(point.generics.as_deref(), Some("<T>"));
+    assert_eq!(point.attributes, vec!["#[derive(Debug, Clone)]"]);
+    assert_eq!(point.visibility, Some(Visibility::Public));
+    assert_eq!(
+        find_element(&elements, "Shape").visibility,
+        Some(Visibility::Internal)
+    );
+
+    // impl 中的函数为方法，作用域包含外层模块
+    let new = find_element(&elements, "new");
+    assert_eq!(new.element_type, CodeElementType::Method);
+    assert_eq!(
+        new.signature.as_deref(),
+        Some("pub fn new(x: T, y: T) -> Self")
+    );
+    assert_eq!(new.scope.as_deref(), Some("geometry::Point"));
+    assert_eq!(new.qualified_name(), "geometry::Point::new");
+    let swap = find_element(&elements, "swap");
+    assert_eq!(swap.visibility, Some(Visibility::Private));
+    let area = find_element(&elements, "area");
+    assert_eq!(area.element_type, CodeElementType::Method);
+    assert_eq!(area.visibility, Some(Visibility::Public));
+    assert_eq!(area.signature.as_deref(), Some("fn area(&self) -> f64"));
+
+    // 父元素和子元素
+    let implementation = elements
+        .iter()
+        .find(|e| e.element_type == CodeElementType::Implementation)
+        .unwrap();
+    assert_eq!(implementation.name, "Point");
+    assert_eq!(implementation.generics.as_deref(), Some("<T: Copy>"));
+    let methods: Vec<_> = implementation
+        .children
+        .iter()
+        .map(|c| c.name.as_str())
+        .collect();
+    assert_eq!(methods, vec!["new", "swap"]);
+    assert_eq!(
+        new.parent.as_ref().map(|p| p.element_type.clone()),
+        Some(CodeElementType::Implementation)
+    );
+    let module = find_element(&elements, "geometry");
+    assert_eq!(module.children.len(), 3, "模块应包含结构体、实现块和trait");
+    assert!(module.parent.is_none());
+}
+
+#[test]
+fn test_element_details_across_languages() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+    let registry = PARSER_REGISTRY.read().unwrap();
+
+    // Python的装饰器和命名约定
+    let python_code = r#"class Cache:
+    @property
+    def size(self):
+        return 0
+
+    def _evict(self):
+        pass
+
+    def __reset(self):
+        pass
+"#;
+    let python_parser = registry.get_parser_by_language("python").unwrap();
+    let elements = python_parser.parse_snippet(python_code).unwrap();
+    let size = find_element(&elements, "size");
+    assert_eq!(size.attributes, vec!["@property"]);
+    assert_eq!(size.signature.as_deref(), Some("def size(self)"));
+    assert_eq!(size.visibility, Some(Visibility::Public));
+    assert_eq!(
+        find_element(&elements, "_evict").visibility,
+        Some(Visibility::Internal)
+    );
+    assert_eq!(
+        find_element(&elements, "__reset").visibility,
+        Some(Visibility::Private)
+    );
+
+    // TypeScript的导出和访问修饰符
+    let ts_code = r#"export class Service {
+    private load(id: string): void {}
+    protected save(): void {}
+}
+
+function helper() {}
+"#;
+    let ts_parser = registry.get_parser_by_language("typescript").unwrap();
+    let elements = ts_parser.parse_snippet(ts_code).unwrap();
+    assert_eq!(
+        find_element(&elements, "Service").visibility,
+        Some(Visibility::Public)
+    );
+    let load = find_element(&elements, "load");
+    assert_eq!(load.element_type, CodeElementType::Method);
+    assert_eq!(load.visibility, Some(Visibility::Private));
+    assert_eq!(load.qualified_name(), "Service.load");
+    assert_eq!(
+        find_element(&elements, "save").visibility,
+        Some(Visibility::Protected)
+    );
+    assert_eq!(
+        find_element(&elements, "helper").visibility,
+        Some(Visibility::Internal)
+    );
+
+    // Go方法的接收者类型作为作用域
+    let go_code = "package shapes\n\nfunc (r *Rect) area() float64 {\n    return 0\n}\n";
+    let go_parser = registry.get_parser_by_language("go").unwrap();
+    let elements = go_parser.parse_snippet(go_code).unwrap();
+    let area = find_element(&elements, "area");
+    assert_eq!(area.element_type, CodeElementType::Method);
+    assert_eq!(area.scope.as_deref(), Some("Rect"));
+    assert_eq!(area.visibility, Some(Visibility::Internal));
+
+    // Java的注解和访问修饰符
+    let java_code = r#"public class Controller {
+    @Override
+    protected String handle(String path) {
+        return path;
+    }
+}
+"#;
+    let java_parser = registry.get_parser_by_language("java").unwrap();
+    let elements = java_parser.parse_snippet(java_code).unwrap();
+    let handle = find_element(&elements, "handle");
+    assert_eq!(handle.attributes, vec!["@Override"]);
+    assert_eq!(handle.visibility, Some(Visibility::Protected));
+    assert_eq!(
+        handle.signature.as_deref(),
+        Some("@Override protected String handle(String path)")
+    );
+
+    // C++类成员的访问说明符和类外定义的方法
+    let cpp_code = r#"class Square {
+    int side;
+public:
+    int area() const { return side * side; }
+};
+
+int Square::perimeter() const {
+    return 4 * side;
+}
+"#;
+    let cpp_parser = registry.get_parser_by_language("cpp").unwrap();
+    let elements = cpp_parser.parse_snippet(cpp_code).unwrap();
+    let area = find_element(&elements, "area");
+    assert_eq!(area.visibility, Some(Visibility::Public));
+    assert_eq!(area.qualified_name(), "Square::area");
+    let perimeter = find_element(&elements, "perimeter");
+    assert_eq!(perimeter.element_type, CodeElementType::Method);
+    assert_eq!(perimeter.scope.as_deref(), Some("Square"));
+    assert!(perimeter.parent.is_none());
+}
+
+#[test]
+fn test_parse_imports() {
+    // 初始化解析器
+    initialize_parsers().unwrap();
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let modules = |language: &str, code: &str| -> Vec<String> {
+        let parser = registry.get_parser_by_language(language).unwrap();
+        parser
+            .parse_imports(code)
+            .unwrap()
+            .into_iter()
+            .map(|import| import.module)
+            .collect()
+    };
+
+    // Rust的 use 树展开为完整路径，`mod name;` 按 `self::name` 处理
+    let rust_code = "use crate::a::{b::C, d as e, f::*};\nuse std::io;\nmod foo;\nmod inline {}\n";
+    assert_eq!(
+        modules("rust", rust_code),
+        vec![
+            "crate::a::b::C",
+            "crate::a::d",
+            "crate::a::f",
+            "std::io",
+            "self::foo"
+        ]
+    );
+
+    // Python的相对导入保留前导的点，导入的名称单独记录
+    let python_code = "import os.path, sys as s\nfrom ..pkg.mod import (x, y as z)\n";
+    let parser = registry.get_parser_by_language("python").unwrap();
+    let imports = parser.parse_imports(python_code).unwrap();
+    assert_eq!(imports.len(), 3);
+    assert_eq!(imports[0].module, "os.path");
+    assert_eq!(imports[1].module, "sys");
+    assert_eq!(imports[2].module, "..pkg.mod");
+    assert_eq!(imports[2].names, vec!["x", "y"]);
+    assert_eq!(imports[2].line, 2);
+
+    // JavaScript的 import、export from 和 require
+    let js_code = "import d, { a as b } from './utils';\nexport { q } from './q';\nconst x = require('lib');\n";
+    assert_eq!(
+        modules("javascript", js_code),
+        vec!["./utils", "./q", "lib"]
+    );
+
+    // Go、Java和C++
+    let go_code = "package p\n\nimport (\n    \"fmt\"\n    m \"example.com/mod/pkg\"\n)\n";
+    assert_eq!(modules("go", go_code), vec!["fmt", "example.com/mod/pkg"]);
+    let java_code = "import java.util.List;\nimport com.example.*;\n";
+    assert_eq!(
+        modules("java", java_code),
+        vec!["java.util.List", "com.example"]
+    );
+    let cpp_code = "#include <vector>\n#include \"shape.hpp\"\n";
+    assert_eq!(modules("cpp", cpp_code), vec!["<vector>", "shape.hpp"]);
+}
+
+#[test]
+fn test_document_parsers() {
+    initialize_parsers().unwrap();
+    let registry = PARSER_REGISTRY.read().unwrap();
+
+    // Markdown的标题按级别嵌套为章节，围栏代码块以语言命名
+    let markdown = "# Guide\n\nIntro.\n\n## Install\n\n```bash\ncargo install codex\n```\n\n## Usage\n\nRun it.\n";
+    let parser = registry.get_parser_by_filename("README.md").unwrap();
+    let elements = parser.parse_file("README.md", markdown).unwrap();
+    let install = find_element(&elements, "Install");
+    assert_eq!(install.element_type, CodeElementType::Section);
+    assert_eq!(install.definition.line, 5);
+    assert_eq!(install.definition.end_line, 10);
+    assert_eq!(install.qualified_name(), "Guide > Install");
+    assert_eq!(install.signature.as_deref(), Some("## Install"));
+    let block = find_element(&elements, "bash");
+    assert_eq!(block.element_type, CodeElementType::CodeBlock);
+    assert_eq!(block.scope.as_deref(), Some("Guide > Install"));
+    assert_eq!(find_element(&elements, "Guide").children.len(), 2);
+
+    // TOML的表以点分键命名，表中的键以表为作用域
+    let toml = "name = \"codex\"\n\n[profile.release]\nlto = true\n\n[[bin]]\nname = \"codex\"\n";
+    let parser = registry.get_parser_by_filename("Cargo.toml").unwrap();
+    let elements = parser.parse_file("Cargo.toml", toml).unwrap();
+    let release = find_element(&elements, "profile.release");
+    assert_eq!(release.element_type, CodeElementType::Section);
+    assert_eq!(release.definition.line, 3);
+    assert_eq!(
+        find_element(&elements, "lto").qualified_name(),
+        "profile.release.lto"
+    );
+    assert!(has_element(&elements, CodeElementType::Section, "bin"));
+    assert_eq!(
+        find_element(&elements, "name").element_type,
+        CodeElementType::Key
+    );
+
+    // YAML和JSON只提取顶层键
+    let yaml = "name: app\nservices:\n  web:\n    image: nginx\n";
+    let parser = registry.get_parser_by_filename("compose.yml").unwrap();
+    let elements = parser.parse_file("compose.yml", yaml).unwrap();
+    let names: Vec<&str> = elements.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["name", "services"]);
+    assert_eq!(elements[1].definition.end_line, 4);
+
+    let json = "{\n  \"name\": \"app\",\n  \"scripts\": { \"build\": \"tsc\" }\n}\n";
+    let parser = registry.get_parser_by_filename("package.json").unwrap();
+    let elements = parser.parse_file("package.json", json).unwrap();
+    let names: Vec<&str> = elements.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, vec!["name", "scripts"]);
+    assert!(parser.parse_imports(json).unwrap().is_empty());
+}
+
+#[test]
+fn test_check_syntax() {
+    initialize_parsers().unwrap();
+
+    // 正确的代码没有诊断
+    let valid = "fn main() {\n    println!(\"hi\");\n}\n";
+    assert_eq!(
+        check_file_syntax("main.rs", valid).unwrap(),
+        Some(Vec::new())
+    );
+
+    // 缺少的记号报告为 MISSING，包含位置和所在行
+    let diagnostics = check_file_syntax("f.py", "def f(:\n    pass\n")
+        .unwrap()
+        .unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].kind, SyntaxDiagnosticKind::Missing);
+    assert_eq!(diagnostics[0].message, "缺少 `)`");
+    assert_eq!((diagnostics[0].line, diagnostics[0].column), (1, 7));
+    assert_eq!(diagnostics[0].context, "def f(:");
+
+    // 无法解析的代码报告为 ERROR，信息中引用出错的代码
+    let diagnostics = check_file_syntax("data.json", "{\n  \"a\": 1,,\n}\n")
+        .unwrap()
+        .unwrap();
+    assert!(!diagnostics.is_empty());
+    assert_eq!(diagnostics[0].kind, SyntaxDiagnosticKind::Error);
+    assert_eq!(diagnostics[0].line, 2);
+    assert!(diagnostics[0].to_string().starts_with("2:"));
+
+    // 没有解析器的文件不检查
+    assert!(check_file_syntax("notes.txt", "anything {")
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,182 @@
+mod common;
+
+use codex::config::app::KnowledgeConfig;
+use codex::error::{AppError, AppResult};
+use codex::knowledge::indexer::CodeIndexer;
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::pipeline::{FileStatus, IndexEvent, IndexPipeline, IndexSink, ParsedFile};
+use common::{open_knowledge_base, temp_config};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建使用多个线程并行索引的配置
+fn pipeline_config(dir: &Path) -> KnowledgeConfig {
+    KnowledgeConfig {
+        index_workers: 4,
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建包含多个源文件的项目目录，返回规范化后的目录
+fn sample_project(dir: &Path, count: usize) -> PathBuf {
+    fs::create_dir_all(dir).unwrap();
+    for i in 0..count {
+        fs::write(
+            dir.join(format!("module_{}.rs", i)),
+            format!("fn handler_{}() {{}}\n", i),
+        )
+        .unwrap();
+    }
+    fs::write(dir.join("README.md"), "# Sample\n").unwrap();
+    // 同一秒内修改的文档无法判断是否变化，将修改时间设为过去
+    let modified = SystemTime::now() - Duration::from_secs(3600);
+    fs::File::options()
+        .write(true)
+        .open(dir.join("README.md"))
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    fs::canonicalize(dir).unwrap()
+}
+
+/// 写入指定数量的文件后模拟中断的索引目标
+struct InterruptedSink<'a> {
+    kb: &'a mut LocalKnowledgeBase,
+    remaining: usize,
+}
+
+impl IndexSink for InterruptedSink<'_> {
+    fn write_parsed(&mut self, parsed: ParsedFile) -> AppResult<usize> {
+        if self.remaining == 0 {
+            return Err(AppError::knowledge("索引被中断"));
+        }
+        self.remaining -= 1;
+        self.kb.write_parsed(parsed)
+    }
+
+    fn checkpoint(&mut self) -> AppResult<()> {
+        self.kb.checkpoint()
+    }
+}
+
+#[test]
+fn test_parallel_add_reports_progress() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"), 30);
+    let mut kb = open_knowledge_base(pipeline_config(&temp_dir.path().join("kb")));
+
+    let mut events = Vec::new();
+    let stats = kb
+        .add_files_with_progress(
+            &[project.to_string_lossy().to_string()],
+            true,
+            &mut |event| events.push(event),
+        )
+        .unwrap();
+    assert_eq!(stats.discovered, 31);
+    assert_eq!(stats.indexed, 31);
+    assert_eq!(stats.elements, 31, "README 的标题作为章节元素");
+    assert!(stats.timings.total >= stats.timings.walk);
+
+    assert_eq!(events.first(), Some(&IndexEvent::Started { total: 31 }));
+    let done: Vec<usize> = events
+        .iter()
+        .filter_map(|event| match event {
+            IndexEvent::File { done, .. } => Some(*done),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(done, (1..=31).collect::<Vec<_>>(), "进度应逐个递增");
+    assert!(matches!(events.last(), Some(IndexEvent::Finished(_))));
+
+    assert_eq!(kb.document_count(), 31);
+    let symbols = kb.search_symbols("handler_17", 5).unwrap();
+    assert_eq!(symbols[0].element.name, "handler_17");
+
+    // 再次添加时所有文件都未修改
+    let stats = kb
+        .add_files_with_progress(&[project.to_string_lossy().to_string()], true, &mut |_| {})
+        .unwrap();
+    assert_eq!(stats.unchanged, 31);
+    assert_eq!(stats.indexed, 0);
+
+    fs::write(project.join("module_3.rs"), "fn handler_three() {}\n").unwrap();
+    let stats = kb
+        .add_files_with_progress(&[project.to_string_lossy().to_string()], true, &mut |_| {})
+        .unwrap();
+    assert_eq!(stats.indexed, 1, "只应重新索引修改过的文件");
+    assert_eq!(kb.search_symbols("handler_three", 5).unwrap().len(), 1);
+}
+
+#[test]
+fn test_interrupted_index_resumes_from_checkpoint() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"), 20);
+    let kb_dir = temp_dir.path().join("kb");
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&project)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    let mut kb = open_knowledge_base(pipeline_config(&kb_dir));
+    let mut sink = InterruptedSink {
+        kb: &mut kb,
+        remaining: 10,
+    };
+    let result = IndexPipeline::new(4).checkpoint_interval(5).run(
+        || Ok(files),
+        &|_, _| false,
+        &mut sink,
+        &mut |_| {},
+    );
+    assert!(result.is_err(), "写入失败时应停止索引");
+    drop(kb);
+
+    let mut kb = open_knowledge_base(pipeline_config(&kb_dir));
+    let mut failed = Vec::new();
+    let stats = kb
+        .add_files_with_progress(
+            &[project.to_string_lossy().to_string()],
+            true,
+            &mut |event| {
+                if let IndexEvent::File {
+                    path,
+                    status: FileStatus::Failed(_),
+                    ..
+                } = event
+                {
+                    failed.push(path);
+                }
+            },
+        )
+        .unwrap();
+    assert!(failed.is_empty());
+    assert_eq!(stats.unchanged, 10, "检查点之前写入的文件应被跳过");
+    assert_eq!(stats.indexed, 11);
+    assert_eq!(kb.document_count(), 21);
+}
+
+#[test]
+fn test_code_indexer_index_directory() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = sample_project(&temp_dir.path().join("project"), 8);
+    let mut indexer = CodeIndexer::new(pipeline_config(&temp_dir.path().join("kb"))).unwrap();
+    indexer.set_quiet(true);
+
+    let stats = indexer
+        .index_directory_with_progress(&project, &mut |_| {})
+        .unwrap();
+    assert_eq!(stats.elements, 9);
+    assert_eq!(indexer.file_count(), 9, "有解析器的文档也写入索引缓存");
+    assert!(!indexer.find_symbol("handler_5").is_empty());
+
+    let stats = indexer
+        .index_directory_with_progress(&project, &mut |_| {})
+        .unwrap();
+    assert_eq!(stats.unchanged, 9);
+}
@@ -0,0 +1,96 @@
+use codex::parsers::{
+    element_query_source, initialize_parsers, reload_queries, set_query_dirs, CodeElement,
+    CodeElementType, ELEMENT_QUERY_FILE, PARSER_REGISTRY,
+};
+use std::fs;
+use std::path::Path;
+
+/// 在查询目录中写入语言的元素查询文件
+fn write_query(dir: &Path, language: &str, query: &str) {
+    let path = dir.join(language).join(ELEMENT_QUERY_FILE);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, query).unwrap();
+}
+
+/// 使用指定语言的解析器提取代码元素
+fn parse(language: &str, code: &str) -> Vec<CodeElement> {
+    let registry = PARSER_REGISTRY.read().unwrap();
+    let parser = registry.get_parser_by_language(language).unwrap();
+    parser.parse_snippet(code).unwrap()
+}
+
+/// 获取指定类型元素的名称
+fn names(elements: &[CodeElement], element_type: CodeElementType) -> Vec<&str> {
+    elements
+        .iter()
+        .filter(|e| e.element_type == element_type)
+        .map(|e| e.name.as_str())
+        .collect()
+}
+
+#[test]
+fn test_query_files_extend_and_override_bundled_queries() {
+    initialize_parsers().unwrap();
+    let temp_dir = tempfile::tempdir().unwrap();
+    let user_dir = temp_dir.path().join("user");
+    let project_dir = temp_dir.path().join("project");
+    set_query_dirs(vec![user_dir.clone(), project_dir.clone()]);
+
+    // 所有内置查询都能用对应的语法编译
+    {
+        let registry = PARSER_REGISTRY.read().unwrap();
+        for language in registry.supported_languages() {
+            let parser = registry.get_parser_by_language(&language).unwrap();
+            let source = element_query_source(&language).unwrap();
+            assert!(
+                tree_sitter::Query::new(parser.tree_sitter_language(), &source).is_ok(),
+                "{} 的内置查询无效",
+                language
+            );
+        }
+    }
+
+    // 以 `; extends` 开头的查询追加到内置查询之后
+    let rust_code = "fn main() {\n    let total = 1;\n}\n";
+    assert!(names(&parse("rust", rust_code), CodeElementType::Variable).is_empty());
+    write_query(
+        &user_dir,
+        "rust",
+        "; extends\n(let_declaration pattern: (identifier) @name) @definition.variable\n",
+    );
+    reload_queries();
+    let elements = parse("rust", rust_code);
+    assert_eq!(names(&elements, CodeElementType::Function), vec!["main"]);
+    assert_eq!(names(&elements, CodeElementType::Variable), vec!["total"]);
+
+    // 其他查询替换内置查询，项目目录的查询优先于用户目录
+    let python_code =
+        "class Suite:\n    pass\n\ndef helper():\n    pass\n\ndef test_add():\n    pass\n";
+    write_query(
+        &user_dir,
+        "python",
+        "(class_definition name: (_) @name) @definition.class\n",
+    );
+    write_query(
+        &project_dir,
+        "python",
+        "((function_definition name: (_) @name) @definition.function\n  (#match? @name \"^test_\"))\n",
+    );
+    reload_queries();
+    let elements = parse("python", python_code);
+    assert_eq!(elements.len(), 1);
+    assert_eq!(elements[0].name, "test_add");
+
+    // 无效的查询文件不影响解析，回退到内置查询
+    write_query(&project_dir, "go", "(no_such_node) @definition.function\n");
+    write_query(
+        &user_dir,
+        "java",
+        "(class_declaration name: (_) @name) @definition.widget\n",
+    );
+    reload_queries();
+    let go_elements = parse("go", "package main\n\nfunc main() {}\n");
+    assert_eq!(names(&go_elements, CodeElementType::Function), vec!["main"]);
+    let java_elements = parse("java", "class Shape {}\n");
+    assert_eq!(names(&java_elements, CodeElementType::Class), vec!["Shape"]);
+}
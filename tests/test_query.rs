@@ -0,0 +1,243 @@
+mod common;
+
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::query::{HighlightedLine, QueryTerm, SearchQuery};
+use codex::parsers::CodeElementType;
+use common::temp_config;
+use std::fs;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "py", "md"];
+
+#[test]
+fn test_parse_filters_and_terms() {
+    let query = SearchQuery::parse(
+        r#"lang:rs path:src/ai/** kind:fn name:Generate_* "reply text" /gen.*/ replly~ conect~2 client"#,
+    )
+    .unwrap();
+    assert_eq!(query.languages, vec!["rust"], "语言别名应被规范化");
+    assert_eq!(query.paths, vec!["src/ai/**"]);
+    assert_eq!(query.kinds, vec![CodeElementType::Function]);
+    assert_eq!(query.names, vec!["generate_*"], "名称过滤不区分大小写");
+    assert_eq!(
+        query.terms,
+        vec![
+            QueryTerm::Phrase("reply text".to_string()),
+            QueryTerm::Regex("gen.*".to_string()),
+            QueryTerm::Fuzzy {
+                term: "replly".to_string(),
+                distance: 1
+            },
+            QueryTerm::Fuzzy {
+                term: "conect".to_string(),
+                distance: 2
+            },
+            QueryTerm::Word("client".to_string()),
+        ]
+    );
+    assert!(query.has_symbol_filters());
+    assert!(!query.has_date_filters());
+    assert_eq!(query.text(), "\"reply text\" client");
+
+    // 未知的键按普通关键词处理
+    let query = SearchQuery::parse("http://example.com").unwrap();
+    assert_eq!(
+        query.terms,
+        vec![QueryTerm::Word("http://example.com".to_string())]
+    );
+}
+
+#[test]
+fn test_parse_dates_and_errors() {
+    let query = SearchQuery::parse("after:2024-01-02 before:7d").unwrap();
+    assert_eq!(query.after, Some(1_704_153_600));
+    let week_ago = chrono::Utc::now().timestamp() - 7 * 24 * 3600;
+    assert!((query.before.unwrap() - week_ago).abs() < 60);
+    assert!(query.has_date_filters());
+
+    assert!(
+        SearchQuery::parse("after:yesterday").is_err(),
+        "无效日期应报错"
+    );
+    assert!(
+        SearchQuery::parse("kind:widget").is_err(),
+        "未知元素类型应报错"
+    );
+    assert!(SearchQuery::parse("lang:").is_err(), "过滤条件缺少值应报错");
+    assert!(SearchQuery::parse("/(unclosed/").is_err(), "无效正则应报错");
+    assert!(
+        SearchQuery::parse("term~3").is_err(),
+        "编辑距离超过上限应报错"
+    );
+}
+
+#[test]
+fn test_path_globs() {
+    let query = SearchQuery::parse("path:src/ai/**").unwrap();
+    assert!(query.matches_path("/work/project/src/ai/adapter.rs"));
+    assert!(query.matches_path("src/ai/providers/openai.rs"));
+    assert!(!query.matches_path("src/cli.rs"));
+
+    let query = SearchQuery::parse("path:*.md").unwrap();
+    assert!(query.matches_path("/work/project/README.md"));
+    assert!(!query.matches_path("/work/project/src/main.rs"));
+
+    // 没有通配符时匹配目录下的所有文件
+    let query = SearchQuery::parse("path:src/knowledge").unwrap();
+    assert!(query.matches_path("/work/src/knowledge/query.rs"));
+    assert!(!query.matches_path("/work/src/knowledge_base.rs"));
+}
+
+#[test]
+fn test_highlighter() {
+    let query = SearchQuery::parse(r#"connect "the server" /cli.*/ servr~"#).unwrap();
+    let highlighter = query.highlighter();
+
+    let text = "Client connects to THE SERVER";
+    let ranges = highlighter.ranges(text);
+    let matched: Vec<&str> = ranges.iter().map(|&(s, e)| &text[s..e]).collect();
+    assert_eq!(matched, vec!["Client", "THE SERVER"]);
+    assert_eq!(
+        highlighter.ranges("fn start_the_server()"),
+        vec![(9, 19)],
+        "短语中的词之间允许任意分隔符"
+    );
+
+    let lines = highlighter.highlight("fn main() {}\n\nlet x = connect();\nservr();\n", 10);
+    assert_eq!(
+        lines.iter().map(|line| line.line).collect::<Vec<_>>(),
+        vec![12, 13],
+        "高亮行应带有从起始行开始的行号"
+    );
+    assert_eq!(
+        lines[0].segments(),
+        vec![("let x = ", false), ("connect", true), ("();", false)]
+    );
+
+    // 没有匹配时返回第一个非空行
+    let lines = highlighter.highlight("\nnothing here\n", 1);
+    assert_eq!(
+        lines,
+        vec![HighlightedLine {
+            line: 2,
+            text: "nothing here".to_string(),
+            ranges: Vec::new(),
+        }]
+    );
+}
+
+#[test]
+fn test_search_with_filters() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(project.join("src/ai")).unwrap();
+    fs::write(
+        project.join("src/ai/generate.rs"),
+        "/// Builds a reply\npub fn generate_reply() -> String {\n    String::new()\n}\n\npub struct ReplyCache;\n",
+    )
+    .unwrap();
+    fs::write(
+        project.join("tools.py"),
+        "def generate_reply():\n    return 'reply'\n",
+    )
+    .unwrap();
+    fs::write(project.join("NOTES.md"), "# Notes\n\nThe reply format.\n").unwrap();
+
+    let mut kb =
+        LocalKnowledgeBase::new(temp_config(&temp_dir.path().join("kb"), EXTENSIONS)).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+
+    let symbols = kb
+        .search_symbols("lang:rust kind:fn name:generate_*", 10)
+        .unwrap();
+    assert_eq!(symbols.len(), 1, "应只返回 Rust 中的函数");
+    assert_eq!(symbols[0].element.name, "generate_reply");
+
+    let symbols = kb.search_symbols("name:generate_*", 10).unwrap();
+    assert_eq!(symbols.len(), 2, "名称过滤应匹配所有语言");
+    let symbols = kb.search_symbols("name:generate_* path:*.py", 10).unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].element.language, "python");
+
+    let hits = kb.search_documents("reply", 10).unwrap();
+    assert_eq!(hits.len(), 3);
+    let hits = kb.search_documents("reply path:src/ai/**", 10).unwrap();
+    assert_eq!(hits.len(), 1);
+    assert!(hits[0].path.ends_with("src/ai/generate.rs"));
+    let highlight = &hits[0].highlights[0];
+    assert!(highlight.line >= 1, "高亮行应带有行号");
+    assert!(highlight.segments().iter().any(|(_, matched)| *matched));
+
+    let hits = kb.search_documents("lang:python replly~", 10).unwrap();
+    assert_eq!(hits.len(), 1, "模糊词应匹配相近的词");
+    assert!(hits[0].path.ends_with("tools.py"));
+
+    assert_eq!(
+        kb.search_documents("reply after:2000-01-01", 10)
+            .unwrap()
+            .len(),
+        3
+    );
+    assert!(kb
+        .search_documents("reply before:2000-01-01", 10)
+        .unwrap()
+        .is_empty());
+    assert!(
+        kb.search_symbols("reply after:2000-01-01", 10)
+            .unwrap()
+            .is_empty(),
+        "时间条件不适用于符号"
+    );
+    assert!(
+        kb.search_documents("kind:fn reply", 10).unwrap().is_empty(),
+        "类型条件不适用于文档"
+    );
+}
+
+#[test]
+fn test_kind_fn_matches_methods() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let project = temp_dir.path().join("project");
+    fs::create_dir_all(project.join("src/ai")).unwrap();
+    fs::write(
+        project.join("src/ai/adapter.rs"),
+        "pub struct AIClient;\n\nimpl AIClient {\n    pub fn generate_response(&self) {}\n}\n",
+    )
+    .unwrap();
+
+    let mut kb =
+        LocalKnowledgeBase::new(temp_config(&temp_dir.path().join("kb"), EXTENSIONS)).unwrap();
+    kb.init().unwrap();
+    kb.set_quiet(true);
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+
+    // impl 中的函数记录为方法，kind:fn 同时匹配方法，kind:method 只匹配方法
+    let symbols = kb
+        .search_symbols("lang:rust path:src/ai/** kind:fn name:generate_*", 10)
+        .unwrap();
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].element.element_type, CodeElementType::Method);
+    assert_eq!(
+        kb.search_symbols("kind:method name:generate_*", 10)
+            .unwrap()
+            .len(),
+        1
+    );
+    assert!(kb
+        .search_symbols("kind:struct name:generate_*", 10)
+        .unwrap()
+        .is_empty());
+    assert_eq!(
+        CodeElementType::Function.filter_kinds(),
+        [CodeElementType::Function, CodeElementType::Method]
+    );
+    assert_eq!(
+        CodeElementType::Method.filter_kinds(),
+        [CodeElementType::Method]
+    );
+}
@@ -0,0 +1,202 @@
+use codex::parsers::initialize_parsers;
+use codex::refactor::{
+    extract_variable, find_selection, inline_variable, rename_symbol, FileChange,
+};
+use std::fs;
+use std::path::Path;
+
+/// 获取单个文件重构后的内容
+fn modified(path: &str, content: &str, name: &str, line: Option<u32>) -> String {
+    let refactoring = inline_variable(Path::new(path), content, name, line).unwrap();
+    assert_eq!(refactoring.changes.len(), 1);
+    refactoring.changes[0].modified.clone()
+}
+
+#[test]
+fn test_rename_symbol_across_files() {
+    initialize_parsers().unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("lib.rs"),
+        "pub fn area(w: u32) -> u32 {\n    w * w\n}\n\n// area 的说明\nconst NAME: &str = \"area\";\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("main.py"),
+        "from lib import area\n\nprint(area(2))\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("notes.txt"), "area\n").unwrap();
+
+    let refactoring = rename_symbol(dir.path(), "area", "surface").unwrap();
+    assert_eq!(refactoring.changes.len(), 2);
+    let rust = refactoring
+        .changes
+        .iter()
+        .find(|change| change.path.ends_with("lib.rs"))
+        .unwrap();
+    // 注释和字符串中的同名文本保持不变
+    assert!(rust.modified.starts_with("pub fn surface(w: u32)"));
+    assert!(rust.modified.contains("// area 的说明"));
+    assert!(rust.modified.contains("\"area\""));
+
+    let diff = refactoring.diff();
+    assert!(diff.contains("-pub fn area(w: u32) -> u32 {"));
+    assert!(diff.contains("+print(surface(2))"));
+    // 当前目录之外的文件使用不带 a/、b/ 前缀的绝对路径
+    let main = dir.path().join("main.py");
+    assert!(diff.contains(&format!("--- {}\n+++ {}\n", main.display(), main.display())));
+    assert!(!diff.contains("a//") && !diff.contains("b//"));
+
+    refactoring.apply().unwrap();
+    assert!(fs::read_to_string(dir.path().join("main.py"))
+        .unwrap()
+        .contains("import surface"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+        "area\n"
+    );
+
+    // 新名称已被使用或不是合法标识符时拒绝
+    fs::write(
+        dir.path().join("other.rs"),
+        "fn taken() {}\nfn surface2() {}\n",
+    )
+    .unwrap();
+    assert!(rename_symbol(dir.path(), "taken", "surface2").is_err());
+    // 新名称只在未引用旧名称的文件中定义时同样拒绝
+    fs::write(dir.path().join("shape.rs"), "fn perimeter() {}\n").unwrap();
+    assert!(rename_symbol(dir.path(), "perimeter", "taken").is_err());
+    assert!(rename_symbol(dir.path(), "taken", "not valid").is_err());
+    assert!(rename_symbol(dir.path(), "missing", "found")
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_extract_and_inline_variable() {
+    initialize_parsers().unwrap();
+    let path = Path::new("src/lib.rs");
+    let source = "fn total(items: &[u32]) -> u32 {\n    let sum: u32 = items.iter().sum();\n    sum * items.len() as u32\n}\n";
+
+    let selection = find_selection(source, "items.len() as u32", None).unwrap();
+    let refactoring = extract_variable(path, source, selection, "count").unwrap();
+    let extracted = &refactoring.changes[0].modified;
+    assert!(refactoring
+        .diff()
+        .starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+    assert_eq!(
+        extracted,
+        "fn total(items: &[u32]) -> u32 {\n    let sum: u32 = items.iter().sum();\n    let count = items.len() as u32;\n    sum * count\n}\n"
+    );
+
+    // 当前目录下的绝对路径转换为相对路径
+    let change = FileChange {
+        path: std::env::current_dir().unwrap().join("src/lib.rs"),
+        ..refactoring.changes[0].clone()
+    };
+    assert!(change
+        .diff()
+        .starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+
+    // 内联刚提取的变量，复合表达式加上括号
+    assert_eq!(
+        modified("src/lib.rs", extracted, "count", None),
+        source.replace("items.len() as u32", "(items.len() as u32)")
+    );
+
+    // 表达式不完整、名称冲突或为方法调用的被调用部分时拒绝
+    assert!(find_selection(source, "missing", None).is_err());
+    assert!(find_selection(source, "items", None).is_err());
+    assert!(find_selection(source, "items", Some(3)).is_ok());
+    let partial = source.find("iter()").unwrap()..source.find("sum()").unwrap();
+    assert!(extract_variable(path, source, partial, "x").is_err());
+    let callee = find_selection(source, "items.len", None).unwrap();
+    assert!(extract_variable(path, source, callee, "x").is_err());
+    let selection = find_selection(source, "items.len() as u32", None).unwrap();
+    assert!(extract_variable(path, source, selection, "sum").is_err());
+
+    // 可变变量、格式字符串中的引用和有副作用的多次引用不能内联
+    let rejected = "fn main() {\n    let mut count = 0;\n    count += 1;\n    let a = 1;\n    println!(\"{a}\");\n    let x = next();\n    use_it(x, x);\n}\n";
+    for name in ["count", "a", "x"] {
+        assert!(
+            inline_variable(path, rejected, name, None).is_err(),
+            "{}",
+            name
+        );
+    }
+
+    // 循环条件中的表达式不能提取到循环之前
+    let looping = "fn main() {\n    let mut total = 0;\n    while total > 10 {\n        total += 1;\n    }\n}\n";
+    let selection = find_selection(looping, "total > 10", None).unwrap();
+    assert!(extract_variable(path, looping, selection, "done").is_err());
+}
+
+#[test]
+fn test_inline_variable_shorthand_and_precedence() {
+    initialize_parsers().unwrap();
+    assert_eq!(
+        modified(
+            "app.ts",
+            "function f(a: number, b: number) {\n    const sum = a + b;\n    return { sum, double: sum * 2 };\n}\n",
+            "sum",
+            None,
+        ),
+        "function f(a: number, b: number) {\n    return { sum: a + b, double: (a + b) * 2 };\n}\n"
+    );
+    assert_eq!(
+        modified(
+            "app.py",
+            "def f(a, b):\n    total = a + b\n    return total * 2\n",
+            "total",
+            Some(2),
+        ),
+        "def f(a, b):\n    return (a + b) * 2\n"
+    );
+    assert!(inline_variable(Path::new("Main.java"), "class A {}", "a", None).is_err());
+}
+
+#[test]
+fn test_conditional_evaluation_is_preserved() {
+    initialize_parsers().unwrap();
+    let extract = |path: &str, source: &str, expression: &str| {
+        let selection = find_selection(source, expression, None).unwrap();
+        extract_variable(Path::new(path), source, selection, "v")
+    };
+
+    // 短路运算符的右侧不一定求值，不能提取到语句之前；左侧总是先求值
+    let rust = "fn main() {\n    if a() && b() {}\n}\n";
+    assert!(extract("src/main.rs", rust, "b()").is_err());
+    assert!(extract("src/main.rs", rust, "a()").is_ok());
+    let js = "function f(a) {\n    return a && b();\n}\n";
+    assert!(extract("app.js", js, "b()").is_err());
+    assert!(extract("app.ts", "function f(a) {\n    a ??= b();\n}\n", "b()").is_err());
+    assert!(extract("app.js", "function f(c) {\n    if (c) g(b());\n}\n", "b()").is_err());
+
+    // 有副作用的初始值不能内联到条件分支或短路运算符的右侧
+    let path = Path::new("src/main.rs");
+    for source in [
+        "fn main() {\n    let x = f();\n    if c {\n        g(x);\n    }\n}\n",
+        "fn main() {\n    let x = f();\n    c || g(x);\n}\n",
+    ] {
+        assert!(
+            inline_variable(path, source, "x", None).is_err(),
+            "{}",
+            source
+        );
+    }
+    assert!(inline_variable(
+        Path::new("app.js"),
+        "function f(c) {\n    const x = g();\n    return c ?? x;\n}\n",
+        "x",
+        None
+    )
+    .is_err());
+    assert!(inline_variable(
+        path,
+        "fn main() {\n    let x = f();\n    g(x) && c;\n}\n",
+        "x",
+        None
+    )
+    .is_ok());
+}
@@ -0,0 +1,116 @@
+mod common;
+
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::search_result_optimizer::{
+    RuleBasedSearchResultOptimizer, SearchCandidate, SearchIntent, SearchResultOptimizer,
+    SearchResultOptimizerConfig,
+};
+use codex::parsers::CodeElementType;
+use common::{open_knowledge_base, temp_config};
+use std::fs;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs"];
+
+/// 创建评分相同的候选结果
+fn candidate(id: &str, name: &str, element_type: Option<CodeElementType>) -> SearchCandidate {
+    SearchCandidate {
+        id: id.to_string(),
+        name: name.to_string(),
+        element_type,
+        path: id.to_string(),
+        engine_score: 1.0,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_intent_boosts_element_types() {
+    let optimizer = RuleBasedSearchResultOptimizer::new(SearchResultOptimizerConfig::default());
+    let candidates = || {
+        vec![
+            candidate("a.rs:1", "parser", Some(CodeElementType::Function)),
+            candidate("b.rs:1", "parser", Some(CodeElementType::Struct)),
+        ]
+    };
+
+    let results = optimizer
+        .optimize_results("parser struct", candidates())
+        .unwrap();
+    assert_eq!(results[0].id, "b.rs:1", "查找类型时结构体应排在前面");
+    assert_eq!(results[0].intent, SearchIntent::ClassDefinition);
+    assert!(results[0].relevance_explanation.contains("符合搜索意图"));
+
+    let results = optimizer
+        .optimize_results("parser fn", candidates())
+        .unwrap();
+    assert_eq!(results[0].id, "a.rs:1", "查找函数时函数应排在前面");
+
+    assert_eq!(
+        optimizer.understand_intent("default config").intent,
+        SearchIntent::FeatureSearch,
+        "意图关键词应按完整的词匹配"
+    );
+}
+
+#[test]
+fn test_recent_results_rank_higher() {
+    let optimizer = RuleBasedSearchResultOptimizer::new(SearchResultOptimizerConfig::default());
+    let now = chrono::Utc::now().timestamp();
+    let mut old = candidate("old.md", "release notes", None);
+    old.modified_at = Some(now - 365 * 86_400);
+    let mut recent = candidate("recent.md", "release notes", None);
+    recent.modified_at = Some(now);
+
+    let results = optimizer
+        .optimize_results("release notes", vec![old, recent])
+        .unwrap();
+    assert_eq!(results[0].id, "recent.md", "最近修改的结果应排在前面");
+    assert!(results[0].score.time > results[1].score.time);
+    assert_eq!(results[0].result_type, "document");
+}
+
+#[test]
+fn test_search_history_survives_restart() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let source = temp_dir.path().join("src");
+    fs::create_dir_all(&source).unwrap();
+    fs::write(source.join("client.rs"), "fn connect_client() {}\n").unwrap();
+    fs::write(source.join("server.rs"), "fn connect_server() {}\n").unwrap();
+
+    let kb_dir = temp_dir.path().join("kb");
+    let mut kb = open_knowledge_base(temp_config(&kb_dir, EXTENSIONS));
+    kb.add_files(&[source.to_string_lossy().to_string()], true)
+        .unwrap();
+    let symbols = kb.search_symbols("connect", 10).unwrap();
+    assert_eq!(symbols.len(), 2);
+    assert!(
+        symbols.iter().all(|symbol| !symbol.explanation.is_empty()),
+        "每个结果都应有排序说明"
+    );
+
+    // 选择排在后面的结果
+    let selected = &symbols[1].element;
+    let id = format!(
+        "{}:{}",
+        selected.definition.file_path, selected.definition.line
+    );
+    let name = selected.name.clone();
+    kb.record_search("connect").unwrap();
+    kb.record_selection("connect", &id).unwrap();
+    drop(kb);
+
+    let kb = open_knowledge_base(temp_config(&kb_dir, EXTENSIONS));
+    let symbols = kb.search_symbols("connect", 10).unwrap();
+    assert_eq!(
+        symbols[0].element.name, name,
+        "重启后仍应优先显示选择过的结果"
+    );
+    assert!(symbols[0].explanation.contains("曾在相似搜索中选择"));
+
+    let hits = kb.search_documents("connect", 10).unwrap();
+    assert_eq!(
+        hits[0].path, selected.definition.file_path,
+        "选择过的文件在文档搜索中也应提升"
+    );
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use codex::config::app::{EmbeddingConfig, KnowledgeConfig};
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::embedding::{
+    EmbeddingProvider, LocalEmbeddingProvider, OllamaEmbeddingProvider,
+};
+use codex::knowledge::hybrid::{reciprocal_rank_fusion, RRF_K};
+use codex::knowledge::local::LocalKnowledgeBase;
+use common::temp_config;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建使用指定嵌入模型的配置
+fn embedding_config(dir: &Path, embedding: EmbeddingConfig) -> KnowledgeConfig {
+    KnowledgeConfig {
+        exclude_patterns: vec!["target".to_string()],
+        embedding,
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建包含HTTP重试逻辑和无关代码的临时项目
+fn sample_project(dir: &Path) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(
+        project.join("client.rs"),
+        "/// Send the request again with exponential backoff when the server fails\n\
+         fn send_with_backoff(req: HttpRequest, attempts: u32) -> Response {\n    \
+         for attempt in 0..attempts {\n        sleep(backoff(attempt));\n    }\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        project.join("render.rs"),
+        "/// Draw the sidebar widgets\nfn render_sidebar(frame: &mut Frame) {}\n",
+    )
+    .unwrap();
+    project
+}
+
+/// 启动 Ollama 兼容的嵌入服务替身：包含 retry 或 backoff 的文本映射到第一维，其余映射到第二维
+fn spawn_ollama_stand_in() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut content_length = 0;
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            assert!(request_line.starts_with("POST /api/embed "));
+            let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(request["model"], "stand-in");
+            let embeddings: Vec<Vec<f32>> = request["input"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|text| {
+                    let text = text.as_str().unwrap().to_lowercase();
+                    if text.contains("retry") || text.contains("backoff") {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect();
+
+            let response = serde_json::json!({ "embeddings": embeddings }).to_string();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .unwrap();
+        }
+    });
+    format!("http://{}", address)
+}
+
+#[test]
+fn test_reciprocal_rank_fusion() {
+    let keyword = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let semantic = vec!["c".to_string(), "d".to_string(), "a".to_string()];
+    let fused = reciprocal_rank_fusion(&[keyword, semantic], RRF_K);
+
+    let keys: Vec<&str> = fused.iter().map(|(key, _)| key.as_str()).collect();
+    assert_eq!(keys, vec!["a", "c", "b", "d"], "两路都命中的结果应排在前面");
+    assert!((fused[0].1 - (1.0 / 61.0 + 1.0 / 63.0)).abs() < 1e-6);
+}
+
+#[test]
+fn test_hybrid_search_with_ollama_stand_in() {
+    let base_url = spawn_ollama_stand_in();
+    let provider = OllamaEmbeddingProvider::new(&base_url, "stand-in");
+    assert_eq!(provider.id(), "ollama:stand-in");
+    let vectors = provider
+        .embed(&["retry later".to_string(), "draw".to_string()])
+        .unwrap();
+    assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+    let config = embedding_config(
+        dir.path(),
+        EmbeddingConfig {
+            provider: "ollama".to_string(),
+            model: "stand-in".to_string(),
+            base_url,
+            dimensions: 0,
+        },
+    );
+    let mut kb = LocalKnowledgeBase::new(config.clone()).unwrap();
+    kb.init().unwrap();
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    assert_eq!(kb.vector_count(), 2);
+
+    // 查询词与代码没有共同的关键词，只能通过语义检索命中
+    let hits = kb.search_hybrid("where do we retry", 5).unwrap();
+    assert!(!hits.is_empty(), "语义检索应命中重试逻辑");
+    assert!(hits[0].path.ends_with("client.rs"));
+    assert_eq!(hits[0].lines, Some((1, 6)));
+    assert!(hits[0].snippet.contains("send_with_backoff"));
+
+    // 向量随索引持久化
+    drop(kb);
+    let mut kb = LocalKnowledgeBase::new(config).unwrap();
+    assert_eq!(kb.vector_count(), 2);
+    kb.remove_file(&project.join("client.rs")).unwrap();
+    assert_eq!(kb.vector_count(), 1, "删除文件时应同时删除其向量");
+}
+
+#[test]
+fn test_hybrid_search_with_local_embeddings() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+    let config = embedding_config(
+        dir.path(),
+        EmbeddingConfig {
+            provider: "local".to_string(),
+            ..Default::default()
+        },
+    );
+    let mut kb = LocalKnowledgeBase::new(config).unwrap();
+    kb.init().unwrap();
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+
+    let hits = kb
+        .search_hybrid("where do we resend HTTP requests", 5)
+        .unwrap();
+    assert!(hits[0].path.ends_with("client.rs"));
+
+    // 未配置嵌入模型时语义检索返回错误
+    let plain = LocalKnowledgeBase::new(embedding_config(
+        &dir.path().join("plain"),
+        EmbeddingConfig::default(),
+    ))
+    .unwrap();
+    assert!(plain.search_hybrid("retry", 5).is_err());
+}
+
+#[test]
+fn test_local_embedding_is_stable() {
+    // 向量会被保存和导入复用，哈希结果必须固定
+    let provider = LocalEmbeddingProvider::new(8);
+    assert_eq!(provider.id(), "local-v2:8");
+    let vector = provider.embed(&["retry".to_string()]).unwrap().remove(0);
+    let expected = [0.0, 2.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0].map(|v: f32| v / 5f32.sqrt());
+    for (actual, expected) in vector.iter().zip(expected) {
+        assert!((actual - expected).abs() < 1e-6, "{:?}", vector);
+    }
+}
+
+#[tokio::test]
+async fn test_embed_inside_current_thread_runtime() {
+    let provider = OllamaEmbeddingProvider::new(&spawn_ollama_stand_in(), "stand-in");
+    let vectors = provider.embed(&["backoff".to_string()]).unwrap();
+    assert_eq!(vectors, vec![vec![1.0, 0.0]]);
+}
@@ -0,0 +1,168 @@
+use codex::context::{ContextExportFormat, ContextManager};
+use codex::session::{SessionMeta, SessionStore};
+use std::path::Path;
+
+/// 构造一段包含两轮对话的上下文
+fn sample_context() -> ContextManager {
+    let mut context = ContextManager::default();
+    context.add_user_message("如何在 Rust 中读取文件？");
+    context.add_ai_message("使用 std::fs::read_to_string。");
+    context.add_user_message("如果文件不是 UTF-8 呢？");
+    context.add_ai_message("使用 std::fs::read 读取字节。");
+    context
+}
+
+#[test]
+fn test_session_save_and_load() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(data_dir.path());
+
+    let mut meta = SessionMeta::new(Path::new("/tmp/project"));
+    store.save(&mut meta, &sample_context()).unwrap();
+
+    // 标题应取自第一条用户消息
+    assert_eq!(meta.title, "如何在 Rust 中读取文件？");
+    assert_eq!(meta.message_count, 4);
+
+    // 使用ID前缀加载会话
+    let (loaded, context) = store.load(meta.short_id()).unwrap();
+    assert_eq!(loaded.id, meta.id);
+    assert_eq!(context.len(), 4);
+    assert_eq!(
+        context.get_context()[1].content,
+        "使用 std::fs::read_to_string。"
+    );
+}
+
+#[test]
+fn test_session_list_rename_delete() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(data_dir.path());
+    assert!(store.list().unwrap().is_empty());
+
+    let mut first = SessionMeta::new(Path::new("/tmp/a"));
+    store.save(&mut first, &sample_context()).unwrap();
+    let mut second = SessionMeta::new(Path::new("/tmp/b"));
+    store.save(&mut second, &sample_context()).unwrap();
+    assert_eq!(store.list().unwrap().len(), 2);
+
+    // 按目录查找最近的会话
+    let latest = store.latest_for_dir(Path::new("/tmp/b")).unwrap().unwrap();
+    assert_eq!(latest.id, second.id);
+    assert!(store.latest_for_dir(Path::new("/tmp/c")).unwrap().is_none());
+
+    let renamed = store.rename(&first.id, "文件读取").unwrap();
+    assert_eq!(renamed.title, "文件读取");
+    assert_eq!(store.get(&first.id).unwrap().title, "文件读取");
+
+    store.delete(&first.id).unwrap();
+    let remaining = store.list().unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, second.id);
+    assert!(store.get(&first.id).is_err());
+
+    // 拒绝指向会话目录之外的ID
+    let sessions = data_dir.path().join("sessions");
+    let outside = data_dir.path().join("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::copy(
+        sessions.join(&second.id).join("meta.json"),
+        outside.join("meta.json"),
+    )
+    .unwrap();
+    for id in ["../outside", "..", ""] {
+        assert!(store.delete(id).is_err(), "{}", id);
+    }
+    assert!(outside.join("meta.json").exists());
+}
+
+#[test]
+fn test_session_fork() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(data_dir.path());
+
+    let mut meta = SessionMeta::new(Path::new("/tmp/project"));
+    store.save(&mut meta, &sample_context()).unwrap();
+
+    // 在第2条消息处分叉，丢弃之后的对话
+    let forked = store.fork(&meta.id, 2).unwrap();
+    assert_ne!(forked.id, meta.id);
+    assert_eq!(forked.forked_from.as_deref(), Some(meta.id.as_str()));
+    assert_eq!(forked.forked_at, Some(2));
+    assert_eq!(forked.cwd, meta.cwd);
+
+    let (_, context) = store.load(&forked.id).unwrap();
+    assert_eq!(context.len(), 2);
+
+    // 原会话保持不变
+    let (_, original) = store.load(&meta.id).unwrap();
+    assert_eq!(original.len(), 4);
+
+    // 越界的分叉位置应报错
+    assert!(store.fork(&meta.id, 0).is_err());
+    assert!(store.fork(&meta.id, 5).is_err());
+}
+
+#[test]
+fn test_session_export_transcript() {
+    let data_dir = tempfile::tempdir().unwrap();
+    let store = SessionStore::new(data_dir.path());
+
+    let mut context = ContextManager::default();
+    context.add_user_message("如何读取文件？");
+    context
+        .add_ai_message("示例如下：\n\n```rust\nlet s = std::fs::read_to_string(\"a.txt\")?;\n```");
+    context.add_code_snippet("fn main() {}", "rust");
+    context.add_tool_result("read_file", "<html>内容</html>");
+
+    let mut meta = SessionMeta::new(Path::new("/tmp/project"));
+    store.save(&mut meta, &context).unwrap();
+
+    let markdown = store
+        .export(&meta.id, ContextExportFormat::Markdown)
+        .unwrap();
+    assert!(
+        markdown.starts_with("# 如何读取文件？"),
+        "Markdown应以会话标题开头"
+    );
+    assert!(markdown.contains("## User · "));
+    assert!(markdown.contains("## Assistant · "));
+    assert!(markdown.contains("```rust\nlet s = std::fs::read_to_string(\"a.txt\")?;\n```"));
+    assert!(
+        markdown.contains("```rust\nfn main() {}\n```"),
+        "代码片段应带语言标签"
+    );
+    assert!(markdown.contains("## Tool call: read_file"));
+    assert!(
+        markdown.contains("```text\n<html>内容</html>\n```"),
+        "工具调用的输出应作为代码块导出"
+    );
+    assert!(markdown.contains("**Total:** 4 messages"));
+
+    let html = store.export(&meta.id, ContextExportFormat::Html).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<code class=\"language-rust\">fn main() {}</code>"));
+    assert!(
+        html.contains("&lt;html&gt;内容&lt;/html&gt;"),
+        "HTML内容应被转义"
+    );
+    assert!(html.contains("Total: 4 messages"));
+
+    assert_eq!(
+        "md".parse::<ContextExportFormat>().unwrap(),
+        ContextExportFormat::Markdown
+    );
+    assert!("pdf".parse::<ContextExportFormat>().is_err());
+}
+
+#[test]
+fn test_markdown_export_nests_code_fences() {
+    let mut context = ContextManager::default();
+    context.add_ai_message("示例文档：\n\n````markdown\n```rust\nfn main() {}\n```\n````");
+    context.add_tool_result("grep", "README.md: 使用 ``` 包围代码");
+
+    // 代码块中已有的围栏不能提前结束外层代码块
+    let markdown = context.render_markdown("会话");
+    assert!(markdown.contains("````markdown\n```rust\nfn main() {}\n```\n````"));
+    assert!(markdown.contains("````text\nREADME.md: 使用 ``` 包围代码\n````"));
+}
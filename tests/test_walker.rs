@@ -0,0 +1,158 @@
+mod common;
+
+use codex::config::app::KnowledgeConfig;
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::walker::{is_binary, read_text_file, IgnoreMatcher, ProjectWalker};
+use common::temp_config;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md", "bin"];
+
+/// 创建排除生成文件的配置
+fn walker_config(dir: &Path) -> KnowledgeConfig {
+    KnowledgeConfig {
+        exclude_patterns: vec!["*.generated.rs".to_string()],
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建包含忽略文件、构建产物和二进制文件的临时项目
+fn sample_project(dir: &Path) -> PathBuf {
+    let project = dir.join("project");
+    for sub in ["src", "target/debug", "vendor", "docs"] {
+        fs::create_dir_all(project.join(sub)).unwrap();
+    }
+    fs::write(project.join(".gitignore"), "target/\n*.log\n").unwrap();
+    fs::write(project.join(".codexignore"), "vendor/\n").unwrap();
+    fs::write(project.join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(project.join("src/api.generated.rs"), "fn generated() {}\n").unwrap();
+    fs::write(project.join("target/debug/build.rs"), "fn build() {}\n").unwrap();
+    fs::write(project.join("vendor/lib.rs"), "fn vendored() {}\n").unwrap();
+    fs::write(project.join("docs/guide.md"), "# Guide\n").unwrap();
+    fs::write(project.join("debug.log"), "log line\n").unwrap();
+    fs::write(project.join("data.bin"), [0x7f, b'E', b'L', b'F', 0, 0, 1]).unwrap();
+    project
+}
+
+/// 转换为相对项目目录的路径字符串
+fn relative(project: &Path, files: &[PathBuf]) -> Vec<String> {
+    files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(project)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect()
+}
+
+#[test]
+fn test_walker_honors_ignore_files_and_excludes() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+
+    let config = walker_config(dir.path());
+    let files = ProjectWalker::from_config(&config).walk(&project).unwrap();
+    assert_eq!(
+        relative(&project, &files),
+        vec!["data.bin", "docs/guide.md", "src/main.rs"],
+        "应遵循 .gitignore、.codexignore 和排除模式"
+    );
+
+    // 不限制扩展名时仍然遵循忽略规则
+    let files = ProjectWalker::new()
+        .max_depth(Some(1))
+        .walk(&project)
+        .unwrap();
+    assert_eq!(
+        relative(&project, &files),
+        vec![".codexignore", ".gitignore", "data.bin"]
+    );
+
+    // 监听模式使用的匹配器与遍历结果一致
+    let matcher = IgnoreMatcher::new(&project, &config.exclude_patterns).unwrap();
+    assert!(matcher.is_ignored(&project.join("target/debug/build.rs"), false));
+    assert!(matcher.is_ignored(&project.join("vendor/lib.rs"), false));
+    assert!(matcher.is_ignored(&project.join("src/api.generated.rs"), false));
+    assert!(matcher.is_ignored(&project.join(".git/HEAD"), false));
+    assert!(!matcher.is_ignored(&project.join("src/main.rs"), false));
+    assert!(!matcher.is_ignored(Path::new("/elsewhere/main.rs"), false));
+}
+
+#[test]
+fn test_read_text_file_skips_binary_and_large_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+
+    assert!(is_binary(&[b'a', 0, b'b']));
+    assert!(!is_binary("纯文本".as_bytes()));
+    assert!(read_text_file(&project.join("data.bin"), 1024)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        read_text_file(&project.join("src/main.rs"), 1024)
+            .unwrap()
+            .as_deref(),
+        Some("fn main() {}\n")
+    );
+    assert!(
+        read_text_file(&project.join("src/main.rs"), 4)
+            .unwrap()
+            .is_none(),
+        "超过大小上限的文件应被跳过"
+    );
+
+    // 遍历时同样按大小上限过滤
+    let files = ProjectWalker::new()
+        .max_file_size(10)
+        .walk(&project.join("src"))
+        .unwrap();
+    assert!(files.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_walker_symlink_policy() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let outside = dir.path().join("outside");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("shared.rs"), "fn shared() {}\n").unwrap();
+    std::os::unix::fs::symlink(&outside, project.join("linked")).unwrap();
+    std::os::unix::fs::symlink(&project, project.join("loop")).unwrap();
+
+    let files = ProjectWalker::new().walk(&project).unwrap();
+    assert!(files.is_empty(), "默认不跟随符号链接");
+
+    let files = ProjectWalker::new()
+        .follow_symlinks(true)
+        .walk(&project)
+        .unwrap();
+    assert_eq!(
+        relative(&project, &files),
+        vec!["linked/shared.rs"],
+        "跟随符号链接时应检测并跳过循环"
+    );
+}
+
+#[test]
+fn test_add_files_skips_ignored_and_binary_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = sample_project(dir.path());
+
+    let mut kb = LocalKnowledgeBase::new(walker_config(dir.path())).unwrap();
+    kb.init().unwrap();
+    let added = kb
+        .add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    assert_eq!(added, 2, "只应添加 main.rs 和 guide.md");
+
+    let docs = kb.list().unwrap();
+    assert!(docs.iter().all(|doc| !doc.path.contains("target")));
+    assert!(docs.iter().all(|doc| !doc.path.ends_with("data.bin")));
+}
@@ -0,0 +1,175 @@
+mod common;
+
+use codex::config::app::KnowledgeConfig;
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::indexer::CodeIndexer;
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::watcher::{Debouncer, KnowledgeWatcher, WatchEvent};
+use common::temp_config;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "md"];
+
+/// 创建排除构建输出目录的配置
+fn project_config(dir: &Path) -> KnowledgeConfig {
+    KnowledgeConfig {
+        exclude_patterns: vec!["target".to_string()],
+        ..temp_config(dir, EXTENSIONS)
+    }
+}
+
+/// 创建空的项目目录，返回规范化后的路径
+fn project_dir(dir: &Path) -> PathBuf {
+    let project = dir.join("project");
+    fs::create_dir_all(project.join("src")).unwrap();
+    project.canonicalize().unwrap()
+}
+
+#[test]
+fn test_debouncer_coalesces_changes() {
+    let mut debouncer = Debouncer::new(Duration::from_millis(100));
+    let start = Instant::now();
+    let path = PathBuf::from("src/lib.rs");
+
+    debouncer.push(path.clone(), start);
+    debouncer.push(path.clone(), start + Duration::from_millis(50));
+    assert!(
+        debouncer
+            .drain_ready(start + Duration::from_millis(120))
+            .is_empty(),
+        "静默期内再次变化应重新计时"
+    );
+
+    let ready = debouncer.drain_ready(start + Duration::from_millis(150));
+    assert_eq!(ready, vec![path], "多次变化只应处理一次");
+    assert!(debouncer.is_empty());
+}
+
+#[test]
+fn test_should_index_filters_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = project_dir(dir.path());
+    let config = project_config(dir.path());
+    let index_dir = config.index_dir.clone();
+    let watcher = KnowledgeWatcher::new(config, &project, Duration::from_millis(10)).unwrap();
+
+    assert!(watcher.should_index(&project.join("src/lib.rs")));
+    assert!(watcher.should_index(&project.join("README.md")));
+    assert!(
+        !watcher.should_index(&project.join("image.png")),
+        "不支持的扩展名"
+    );
+    assert!(
+        !watcher.should_index(&project.join("target/build.rs")),
+        "排除目录"
+    );
+    assert!(
+        !watcher.should_index(&index_dir.join("meta.rs")),
+        "知识库存储目录"
+    );
+}
+
+#[test]
+fn test_apply_reindexes_and_removes_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = project_dir(dir.path());
+    let config = project_config(dir.path());
+    let watcher =
+        KnowledgeWatcher::new(config.clone(), &project, Duration::from_millis(10)).unwrap();
+    let file = project.join("src/lib.rs");
+    let mut events = Vec::new();
+
+    // 新建文件后重新索引
+    fs::write(&file, "fn parse_config() {}\n").unwrap();
+    watcher
+        .apply(std::slice::from_ref(&file), &mut |event| events.push(event))
+        .unwrap();
+    assert!(events.contains(&WatchEvent::Indexed(file.clone())));
+    {
+        let mut kb = LocalKnowledgeBase::new(config.clone()).unwrap();
+        kb.init().unwrap();
+        assert_eq!(kb.search("parse_config").unwrap().len(), 1);
+        assert_eq!(kb.document_count(), 1);
+    }
+
+    // 修改文件后只保留新的符号
+    fs::write(&file, "fn load_settings() {}\n").unwrap();
+    watcher
+        .apply(std::slice::from_ref(&file), &mut |_| {})
+        .unwrap();
+    {
+        let mut kb = LocalKnowledgeBase::new(config.clone()).unwrap();
+        kb.init().unwrap();
+        assert!(
+            kb.search("parse_config").unwrap().is_empty(),
+            "旧符号应被替换"
+        );
+        assert_eq!(kb.search("load_settings").unwrap().len(), 1);
+    }
+
+    // 删除文件后从索引和缓存中移除
+    fs::remove_file(&file).unwrap();
+    events.clear();
+    watcher
+        .apply(std::slice::from_ref(&file), &mut |event| events.push(event))
+        .unwrap();
+    assert!(events.contains(&WatchEvent::Removed(file.clone())));
+    assert!(matches!(
+        events.last(),
+        Some(WatchEvent::BatchDone {
+            indexed: 0,
+            removed: 1,
+            ..
+        })
+    ));
+
+    let mut kb = LocalKnowledgeBase::new(config.clone()).unwrap();
+    kb.init().unwrap();
+    assert!(kb.search("load_settings").unwrap().is_empty());
+    assert_eq!(kb.document_count(), 0);
+    drop(kb);
+
+    let mut indexer = CodeIndexer::new(config).unwrap();
+    indexer.load_cache().unwrap();
+    assert_eq!(indexer.file_count(), 0, "索引缓存中不应保留已删除的文件");
+}
+
+#[test]
+fn test_spawned_watcher_indexes_new_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = project_dir(dir.path());
+    let config = project_config(dir.path());
+    let watcher =
+        KnowledgeWatcher::new(config.clone(), &project, Duration::from_millis(50)).unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let mut handle = watcher.spawn(sender);
+    assert!(matches!(
+        receiver.recv_timeout(Duration::from_secs(5)),
+        Ok(WatchEvent::Started(_))
+    ));
+
+    fs::write(project.join("src/main.rs"), "fn watched_symbol() {}\n").unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    let mut indexed = false;
+    while Instant::now() < deadline {
+        if let Ok(WatchEvent::BatchDone { indexed: n, .. }) =
+            receiver.recv_timeout(Duration::from_millis(200))
+        {
+            if n > 0 {
+                indexed = true;
+                break;
+            }
+        }
+    }
+    handle.stop();
+    assert!(indexed, "监听器应在文件创建后重新索引");
+
+    let mut kb = LocalKnowledgeBase::new(config).unwrap();
+    kb.init().unwrap();
+    assert_eq!(kb.search("watched_symbol").unwrap().len(), 1);
+}
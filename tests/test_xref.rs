@@ -0,0 +1,174 @@
+mod common;
+
+use codex::context::ContextCollector;
+use codex::knowledge::base::KnowledgeBase;
+use codex::knowledge::local::LocalKnowledgeBase;
+use codex::knowledge::xref::{extract_xrefs, ReferenceKind};
+use codex::parsers::initialize_parsers;
+use common::temp_config;
+use std::fs;
+use std::sync::Arc;
+
+/// 测试中索引的文件类型
+const EXTENSIONS: &[&str] = &["rs", "py", "js"];
+
+const RUST_SOURCE: &str = r#"use std::fmt::Display;
+
+struct AIClient;
+
+impl AIClient {
+    fn new() -> Self {
+        AIClient
+    }
+
+    fn generate_response(&self, prompt: &str) -> String {
+        self.build_prompt(prompt)
+    }
+
+    fn build_prompt(&self, prompt: &str) -> String {
+        format!("> {}", prompt)
+    }
+}
+
+fn main() {
+    let client = AIClient::new();
+    let answer = client.generate_response("hi");
+    println!("{}", answer);
+}
+"#;
+
+#[test]
+fn test_extract_rust_xrefs() {
+    initialize_parsers().unwrap();
+    let xrefs = extract_xrefs("src/main.rs", RUST_SOURCE).unwrap().unwrap();
+
+    let names: Vec<&str> = xrefs
+        .definitions
+        .iter()
+        .map(|def| def.qualified_name.as_str())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "AIClient",
+            "AIClient::new",
+            "AIClient::generate_response",
+            "AIClient::build_prompt",
+            "main"
+        ]
+    );
+
+    let call = xrefs
+        .references
+        .iter()
+        .find(|r| r.kind == ReferenceKind::Call && r.name == "generate_response")
+        .expect("应记录方法调用");
+    assert_eq!(call.caller.as_deref(), Some("main"));
+    assert_eq!((call.line, call.column), (21, 25));
+
+    let constructor = xrefs
+        .references
+        .iter()
+        .find(|r| r.kind == ReferenceKind::Call && r.name == "new")
+        .unwrap();
+    assert_eq!(constructor.qualifier.as_deref(), Some("AIClient"));
+
+    let self_call = xrefs
+        .references
+        .iter()
+        .find(|r| r.name == "build_prompt" && r.kind == ReferenceKind::Call)
+        .unwrap();
+    assert_eq!(
+        self_call.qualifier.as_deref(),
+        Some("AIClient"),
+        "self 调用应限定为当前类型"
+    );
+    assert_eq!(
+        self_call.caller.as_deref(),
+        Some("AIClient::generate_response")
+    );
+
+    assert!(xrefs
+        .references
+        .iter()
+        .any(|r| r.kind == ReferenceKind::Import && r.name == "Display"));
+}
+
+#[test]
+fn test_extract_python_and_javascript_xrefs() {
+    initialize_parsers().unwrap();
+
+    let python = "import os\n\nclass Client:\n    def send(self):\n        return self.retry(3)\n\n    def retry(self, n):\n        return os.getenv('X')\n";
+    let xrefs = extract_xrefs("client.py", python).unwrap().unwrap();
+    assert!(xrefs
+        .definitions
+        .iter()
+        .any(|def| def.qualified_name == "Client.retry"));
+    let call = xrefs.references.iter().find(|r| r.name == "retry").unwrap();
+    assert_eq!(call.kind, ReferenceKind::Call);
+    assert_eq!(call.caller.as_deref(), Some("Client.send"));
+    assert!(xrefs
+        .references
+        .iter()
+        .any(|r| r.kind == ReferenceKind::Import && r.name == "os"));
+
+    let javascript = "import { fetchJson } from './http';\n\nconst load = async () => fetchJson('/api');\n\nclass Store {\n  refresh() { return load(); }\n}\n";
+    let xrefs = extract_xrefs("store.js", javascript).unwrap().unwrap();
+    let names: Vec<&str> = xrefs
+        .definitions
+        .iter()
+        .map(|def| def.qualified_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["load", "Store", "Store.refresh"]);
+    let call = xrefs
+        .references
+        .iter()
+        .find(|r| r.name == "load" && r.kind == ReferenceKind::Call)
+        .unwrap();
+    assert_eq!(call.caller.as_deref(), Some("Store.refresh"));
+
+    assert!(extract_xrefs("notes.txt", "plain").unwrap().is_none());
+}
+
+#[test]
+fn test_knowledge_base_xref_queries() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    fs::create_dir_all(&project).unwrap();
+    fs::write(project.join("main.rs"), RUST_SOURCE).unwrap();
+
+    let mut kb = LocalKnowledgeBase::new(temp_config(dir.path(), EXTENSIONS)).unwrap();
+    kb.init().unwrap();
+    kb.add_files(&[project.to_string_lossy().to_string()], true)
+        .unwrap();
+    let xref = kb.xref().unwrap();
+
+    let definitions = xref.definitions("AIClient::generate_response").unwrap();
+    assert_eq!(definitions.len(), 1);
+    assert_eq!(definitions[0].item.line, 10);
+    assert!(xref
+        .definitions("Other::generate_response")
+        .unwrap()
+        .is_empty());
+
+    let callers = xref.callers("AIClient::generate_response").unwrap();
+    assert_eq!(callers.len(), 1);
+    assert_eq!(callers[0].item.caller.as_deref(), Some("main"));
+
+    let callees: Vec<String> = xref
+        .callees("main")
+        .unwrap()
+        .into_iter()
+        .map(|location| location.item.name)
+        .collect();
+    assert_eq!(callees, vec!["new", "generate_response", "println"]);
+
+    // 上下文收集器取出调用者所在的整个函数
+    let kb: Arc<dyn KnowledgeBase> = Arc::new(kb);
+    let items = ContextCollector::new()
+        .with_knowledge_base(kb.clone())
+        .collect_callers(&["AIClient::generate_response"]);
+    assert_eq!(items.len(), 1);
+    assert!(items[0].content.starts_with("fn main()"));
+    assert!(items[0].source().unwrap().ends_with("main.rs:19-23"));
+}